@@ -9,6 +9,7 @@ use std::path::Path;
 use git2::Repository;
 use polykit_core::adapter::{LangMetadata, LanguageAdapter};
 use polykit_core::error::{Error, Result};
+use polykit_core::package::Task;
 use semver::Version;
 
 /// Adapter for Go packages using Git tag-based versioning.
@@ -42,7 +43,36 @@ impl LanguageAdapter for GoAdapter {
             message: format!("Failed to read Git tags: {}", e),
         })?;
 
-        Ok(LangMetadata { version })
+        Ok(LangMetadata {
+            version,
+            name: read_module_path(path),
+            tasks: vec![
+                Task {
+                    name: "build".to_string(),
+                    command: "go build ./...".to_string(),
+                    depends_on: Vec::new(),
+                },
+                Task {
+                    name: "test".to_string(),
+                    command: "go test ./...".to_string(),
+                    depends_on: Vec::new(),
+                },
+            ],
+            private: false,
+        })
+    }
+
+    fn read_dependencies(&self, path: &Path) -> Result<Vec<String>> {
+        let go_mod_path = path.join("go.mod");
+        let content = std::fs::read_to_string(&go_mod_path).map_err(|e| Error::Adapter {
+            package: path.display().to_string(),
+            message: format!("Failed to read go.mod at {}: {}", go_mod_path.display(), e),
+        })?;
+
+        let mut deps = parse_go_mod_dependencies(&content);
+        deps.sort_unstable();
+        deps.dedup();
+        Ok(deps)
     }
 
     fn bump_version(&self, path: &Path, new_version: &str) -> Result<()> {
@@ -93,6 +123,50 @@ impl LanguageAdapter for GoAdapter {
     }
 }
 
+/// Reads the module path from the `module` directive in go.mod.
+fn read_module_path(path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(path.join("go.mod")).ok()?;
+    content.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("module")
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+            .map(|rest| rest.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Reads the module paths of `require` directives and `replace` sources.
+fn parse_go_mod_dependencies(content: &str) -> Vec<String> {
+    let mut deps = Vec::new();
+    let mut block: Option<&str> = None;
+
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(directive) = block {
+            if line == ")" {
+                block = None;
+            } else if let Some(module) = line.split_whitespace().next() {
+                if directive == "require" || directive == "replace" {
+                    deps.push(module.to_string());
+                }
+            }
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let directive = parts.next().unwrap_or("");
+        match (directive, parts.next()) {
+            ("require" | "replace" | "exclude" | "retract", Some("(")) => block = Some(directive),
+            ("require" | "replace", Some(module)) => deps.push(module.to_string()),
+            _ => {}
+        }
+    }
+
+    deps
+}
+
 /// Find Git repository by walking up from the given path.
 fn find_repo(path: &Path) -> std::result::Result<Repository, git2::Error> {
     let mut current = path;
@@ -259,4 +333,29 @@ mod tests {
             assert!(err_msg.contains("Invalid version format"));
         }
     }
+
+    #[test]
+    fn test_parse_go_mod_dependencies() {
+        let content = r#"
+module github.com/acme/api
+
+go 1.22
+
+require github.com/acme/proto v0.0.0
+
+require (
+    github.com/google/uuid v1.6.0 // indirect
+)
+
+replace github.com/acme/shared => ../shared
+"#;
+        assert_eq!(
+            parse_go_mod_dependencies(content),
+            vec![
+                "github.com/acme/proto",
+                "github.com/google/uuid",
+                "github.com/acme/shared"
+            ]
+        );
+    }
 }
@@ -3,10 +3,34 @@ use std::path::Path;
 
 use polykit_core::adapter::{LangMetadata, LanguageAdapter};
 use polykit_core::error::{Error, Result};
+use polykit_core::package::Task;
 use serde_json::Value;
 
 pub struct JsAdapter;
 
+const DEPENDENCY_FIELDS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// Picks the package manager from the nearest lockfile or workspace file.
+fn package_manager(path: &Path) -> &'static str {
+    for dir in path.ancestors() {
+        if dir.join("pnpm-lock.yaml").exists() || dir.join("pnpm-workspace.yaml").exists() {
+            return "pnpm";
+        }
+        if dir.join("yarn.lock").exists() {
+            return "yarn";
+        }
+        if dir.join("package-lock.json").exists() || dir.join(".git").exists() {
+            break;
+        }
+    }
+    "npm"
+}
+
 impl LanguageAdapter for JsAdapter {
     fn language(&self) -> &'static str {
         "js"
@@ -47,8 +71,66 @@ impl LanguageAdapter for JsAdapter {
             .get("version")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
+        let name = json
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let private = json
+            .get("private")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
-        Ok(LangMetadata { version })
+        let package_manager = package_manager(path);
+        let tasks = json
+            .get("scripts")
+            .and_then(|v| v.as_object())
+            .map(|scripts| {
+                scripts
+                    .keys()
+                    .map(|script| Task {
+                        name: script.clone(),
+                        command: format!("{} run {}", package_manager, script),
+                        depends_on: Vec::new(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(LangMetadata {
+            version,
+            name,
+            tasks,
+            private,
+        })
+    }
+
+    fn read_dependencies(&self, path: &Path) -> Result<Vec<String>> {
+        let package_json_path = path.join("package.json");
+        let content = fs::read_to_string(&package_json_path).map_err(|e| Error::Adapter {
+            package: path.display().to_string(),
+            message: format!(
+                "Failed to read package.json at {}: {}",
+                package_json_path.display(),
+                e
+            ),
+        })?;
+        let json: Value = serde_json::from_str(&content).map_err(|e| Error::Adapter {
+            package: path.display().to_string(),
+            message: format!(
+                "Failed to parse package.json at {}: {}. File may be malformed JSON.",
+                package_json_path.display(),
+                e
+            ),
+        })?;
+
+        let mut deps: Vec<String> = DEPENDENCY_FIELDS
+            .iter()
+            .filter_map(|field| json.get(field).and_then(|v| v.as_object()))
+            .flat_map(|deps| deps.keys().cloned())
+            .collect();
+        deps.sort_unstable();
+        deps.dedup();
+        Ok(deps)
     }
 
     fn bump_version(&self, path: &Path, new_version: &str) -> Result<()> {
@@ -116,3 +198,29 @@ impl LanguageAdapter for JsAdapter {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_read_metadata_scripts_use_package_manager() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        fs::write(temp_dir.path().join("pnpm-workspace.yaml"), "packages: []").unwrap();
+        let package_dir = temp_dir.path().join("web");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(
+            package_dir.join("package.json"),
+            r#"{ "name": "web", "private": true, "scripts": { "build": "vite build" } }"#,
+        )
+        .unwrap();
+
+        let metadata = JsAdapter.read_metadata(&package_dir).unwrap();
+        assert_eq!(metadata.name.as_deref(), Some("web"));
+        assert!(metadata.private);
+        assert_eq!(metadata.tasks.len(), 1);
+        assert_eq!(metadata.tasks[0].command, "pnpm run build");
+    }
+}
@@ -62,8 +62,80 @@ impl LanguageAdapter for PythonAdapter {
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string())
             });
+        let name = toml
+            .get("project")
+            .and_then(|p| p.get("name"))
+            .or_else(|| {
+                toml.get("tool")
+                    .and_then(|t| t.get("poetry"))
+                    .and_then(|p| p.get("name"))
+            })
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
 
-        Ok(LangMetadata { version })
+        Ok(LangMetadata {
+            version,
+            name,
+            ..Default::default()
+        })
+    }
+
+    fn read_dependencies(&self, path: &Path) -> Result<Vec<String>> {
+        let pyproject_path = path.join("pyproject.toml");
+        let content = fs::read_to_string(&pyproject_path).map_err(|e| Error::Adapter {
+            package: path.display().to_string(),
+            message: format!(
+                "Failed to read pyproject.toml at {}: {}",
+                pyproject_path.display(),
+                e
+            ),
+        })?;
+        let toml: Value = content.parse().map_err(|e| Error::Adapter {
+            package: path.display().to_string(),
+            message: format!(
+                "Failed to parse pyproject.toml at {}: {}. File may be malformed.",
+                pyproject_path.display(),
+                e
+            ),
+        })?;
+
+        let project = toml.get("project");
+        let requirements = project
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_array())
+            .into_iter()
+            .chain(
+                project
+                    .and_then(|p| p.get("optional-dependencies"))
+                    .and_then(|o| o.as_table())
+                    .into_iter()
+                    .flat_map(|groups| groups.values().filter_map(|g| g.as_array())),
+            )
+            .flatten()
+            .filter_map(|req| req.as_str())
+            .map(requirement_name);
+
+        let poetry = toml.get("tool").and_then(|t| t.get("poetry"));
+        let poetry_deps = poetry
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_table())
+            .into_iter()
+            .chain(
+                poetry
+                    .and_then(|p| p.get("group"))
+                    .and_then(|g| g.as_table())
+                    .into_iter()
+                    .flat_map(|groups| groups.values())
+                    .filter_map(|group| group.get("dependencies").and_then(|d| d.as_table())),
+            )
+            .flat_map(|deps| deps.keys())
+            .filter(|name| *name != "python")
+            .cloned();
+
+        let mut deps: Vec<String> = requirements.chain(poetry_deps).collect();
+        deps.sort_unstable();
+        deps.dedup();
+        Ok(deps)
     }
 
     fn bump_version(&self, path: &Path, new_version: &str) -> Result<()> {
@@ -147,6 +219,15 @@ impl LanguageAdapter for PythonAdapter {
     }
 }
 
+/// Extracts the distribution name from a PEP 508 requirement string.
+fn requirement_name(requirement: &str) -> String {
+    requirement
+        .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .next()
+        .unwrap_or(requirement)
+        .to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,4 +384,28 @@ requires = ["setuptools"]
             assert!(err_msg.contains("Could not find 'project.version'"));
         }
     }
+
+    #[test]
+    fn test_read_dependencies() {
+        let temp_dir = TempDir::new().unwrap();
+        let package_dir = temp_dir.path().join("test-python");
+        fs::create_dir_all(&package_dir).unwrap();
+
+        let content = r#"
+[project]
+name = "api"
+dependencies = ["requests>=2.0", "ml-core[gpu] ; python_version > '3.9'"]
+
+[project.optional-dependencies]
+dev = ["pytest"]
+
+[tool.poetry.group.test.dependencies]
+python = "^3.11"
+hypothesis = "*"
+"#;
+        fs::write(package_dir.join("pyproject.toml"), content).unwrap();
+
+        let deps = PythonAdapter.read_dependencies(&package_dir).unwrap();
+        assert_eq!(deps, vec!["hypothesis", "ml-core", "pytest", "requests"]);
+    }
 }
@@ -3,10 +3,13 @@ use std::path::Path;
 
 use polykit_core::adapter::{LangMetadata, LanguageAdapter};
 use polykit_core::error::{Error, Result};
+use polykit_core::package::Task;
 use toml::Value;
 
 pub struct RustAdapter;
 
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
 impl LanguageAdapter for RustAdapter {
     fn language(&self) -> &'static str {
         "rust"
@@ -43,13 +46,82 @@ impl LanguageAdapter for RustAdapter {
             ),
         })?;
 
-        let version = toml
-            .get("package")
+        let package = toml.get("package");
+        let version = package
             .and_then(|p| p.get("version"))
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
+        let name = package
+            .and_then(|p| p.get("name"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let private = package
+            .and_then(|p| p.get("publish"))
+            .and_then(|v| v.as_bool())
+            .is_some_and(|publish| !publish);
+
+        Ok(LangMetadata {
+            version,
+            name,
+            tasks: vec![
+                Task {
+                    name: "build".to_string(),
+                    command: "cargo build".to_string(),
+                    depends_on: Vec::new(),
+                },
+                Task {
+                    name: "test".to_string(),
+                    command: "cargo test".to_string(),
+                    depends_on: Vec::new(),
+                },
+            ],
+            private,
+        })
+    }
+
+    fn read_dependencies(&self, path: &Path) -> Result<Vec<String>> {
+        let cargo_toml_path = path.join("Cargo.toml");
+        let content = fs::read_to_string(&cargo_toml_path).map_err(|e| Error::Adapter {
+            package: path.display().to_string(),
+            message: format!(
+                "Failed to read Cargo.toml at {}: {}",
+                cargo_toml_path.display(),
+                e
+            ),
+        })?;
+        let toml: Value = content.parse().map_err(|e| Error::Adapter {
+            package: path.display().to_string(),
+            message: format!(
+                "Failed to parse Cargo.toml at {}: {}. File may be malformed.",
+                cargo_toml_path.display(),
+                e
+            ),
+        })?;
 
-        Ok(LangMetadata { version })
+        let target_tables = toml
+            .get("target")
+            .and_then(|t| t.as_table())
+            .into_iter()
+            .flat_map(|targets| targets.values());
+        let mut deps: Vec<String> = std::iter::once(&toml)
+            .chain(target_tables)
+            .flat_map(|table| {
+                DEPENDENCY_TABLES
+                    .iter()
+                    .filter_map(|name| table.get(name).and_then(|d| d.as_table()))
+            })
+            .flat_map(|deps| deps.iter())
+            .map(|(name, spec)| {
+                // `package` renames the dependency, e.g. `core2 = { package = "core" }`
+                spec.get("package")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or(name)
+                    .to_string()
+            })
+            .collect();
+        deps.sort_unstable();
+        deps.dedup();
+        Ok(deps)
     }
 
     fn bump_version(&self, path: &Path, new_version: &str) -> Result<()> {
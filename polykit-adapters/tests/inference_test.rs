@@ -0,0 +1,140 @@
+use std::fs;
+use std::path::PathBuf;
+
+use polykit_adapters::get_adapter;
+use polykit_core::native_deps::NativeDependencies;
+use polykit_core::package::Language;
+use polykit_core::scanner::Scanner;
+use tempfile::TempDir;
+
+fn write(path: PathBuf, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_infer_packages_from_native_workspaces() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+
+    write(
+        root.join("package.json"),
+        r#"{ "private": true, "workspaces": ["apps/*", "!apps/scratch"] }"#,
+    );
+    write(
+        root.join("apps/web/package.json"),
+        r#"{ "name": "@acme/web", "version": "1.4.0", "scripts": { "build": "vite build", "lint": "eslint ." } }"#,
+    );
+    write(root.join("apps/web/tsconfig.json"), "{}");
+    write(
+        root.join("apps/scratch/package.json"),
+        r#"{ "name": "scratch" }"#,
+    );
+
+    write(
+        root.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"crates/*\"]\n",
+    );
+    write(
+        root.join("crates/core/Cargo.toml"),
+        "[package]\nname = \"core\"\nversion = \"0.3.0\"\npublish = false\n",
+    );
+    write(
+        root.join("crates/core/polykit.toml"),
+        "name = \"rust-core\"\n\n[tasks]\ntest = \"cargo nextest run\"\n",
+    );
+
+    write(
+        root.join("services/ml/pyproject.toml"),
+        "[project]\nname = \"ml\"\nversion = \"2.0.0\"\n",
+    );
+
+    let mut scanner = Scanner::new(root.join("packages")).with_inference(get_adapter);
+    assert!(scanner.infers_packages());
+    assert_eq!(scanner.packages_root(), root);
+
+    let map = scanner.scan_as_map().unwrap();
+    let mut names: Vec<&str> = map.keys().map(|k| k.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["ml", "rust-core", "web"]);
+
+    let web = &map["web"];
+    assert_eq!(web.language, Language::Ts);
+    assert_eq!(web.version.as_deref(), Some("1.4.0"));
+    assert_eq!(web.path, PathBuf::from("apps/web"));
+    assert_eq!(web.get_task("lint").unwrap().command, "npm run lint");
+
+    let core = &map["rust-core"];
+    assert!(!core.public);
+    assert_eq!(core.get_task("build").unwrap().command, "cargo build");
+    assert_eq!(core.get_task("test").unwrap().command, "cargo nextest run");
+
+    assert_eq!(map["ml"].language, Language::Python);
+}
+
+#[test]
+fn test_polykit_toml_packages_skip_inference() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+    write(
+        root.join("packages/a/polykit.toml"),
+        "name = \"a\"\nlanguage = \"rust\"\npublic = true\n",
+    );
+    write(
+        root.join("services/ml/pyproject.toml"),
+        "[project]\nname = \"ml\"\n",
+    );
+
+    let mut scanner = Scanner::new(root.join("packages")).with_inference(get_adapter);
+    assert!(!scanner.infers_packages());
+
+    let packages = scanner.scan().unwrap();
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].name, "a");
+}
+
+#[test]
+fn test_inferred_graph_uses_manifest_dependencies() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+
+    write(
+        root.join("package.json"),
+        r#"{ "workspaces": ["packages/*"] }"#,
+    );
+    write(
+        root.join("packages/ui/package.json"),
+        r#"{ "name": "@acme/ui", "dependencies": { "react": "^18" } }"#,
+    );
+    write(
+        root.join("packages/web/package.json"),
+        r#"{ "name": "@acme/web", "dependencies": { "@acme/ui": "workspace:*" } }"#,
+    );
+    write(
+        root.join("packages/docs/package.json"),
+        r#"{ "name": "@acme/docs", "devDependencies": { "@acme/ui": "workspace:*" } }"#,
+    );
+    write(
+        root.join("packages/docs/polykit.toml"),
+        "[deps]\ninternal = [\"web\"]\n",
+    );
+
+    let mut scanner = Scanner::new(root.join("missing")).with_inference(get_adapter);
+    let packages = scanner.scan().unwrap();
+    let by_name = |name: &str| packages.iter().find(|p| p.name == name).unwrap();
+
+    assert_eq!(by_name("web").deps.as_slice(), ["ui".to_string()]);
+    assert!(by_name("ui").deps.is_empty());
+    assert_eq!(by_name("docs").deps.as_slice(), ["web".to_string()]);
+
+    let drift = NativeDependencies::read(root, &packages, get_adapter)
+        .unwrap()
+        .drift(&packages);
+    assert_eq!(drift.len(), 1);
+    assert_eq!(drift[0].package, "docs");
+    assert_eq!(drift[0].missing, vec!["ui".to_string()]);
+    assert_eq!(drift[0].stale, vec!["web".to_string()]);
+}
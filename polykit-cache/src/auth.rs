@@ -0,0 +1,245 @@
+//! Bearer token authentication with scopes and namespace restrictions.
+
+use axum::http::{header, HeaderMap};
+use polykit_core::error::{Error, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Access level granted by a token.
+///
+/// Each scope includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Download artifacts and query their existence.
+    Read,
+    /// Upload artifacts.
+    Write,
+    /// Delete artifacts.
+    Admin,
+}
+
+/// A token entry in the server configuration.
+///
+/// Exactly one of `token` and `token_env` must be set.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenConfig {
+    /// Token value.
+    pub token: Option<String>,
+    /// Environment variable holding the token value.
+    pub token_env: Option<String>,
+    /// Access level granted by the token.
+    pub scope: Scope,
+    /// Namespaces the token may access. Empty means all namespaces.
+    #[serde(default)]
+    pub namespaces: Vec<String>,
+}
+
+/// Reason a request was not authorized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// No token or an unknown token was presented.
+    Unauthenticated,
+    /// The token does not grant the scope or namespace.
+    Forbidden(String),
+}
+
+struct Token {
+    digest: [u8; 32],
+    scope: Scope,
+    namespaces: Vec<String>,
+}
+
+impl Token {
+    fn allows_namespace(&self, namespace: Option<&str>) -> bool {
+        match namespace {
+            _ if self.namespaces.is_empty() => true,
+            Some(namespace) => self.namespaces.iter().any(|n| n == namespace),
+            None => false,
+        }
+    }
+}
+
+/// Set of tokens accepted by the server.
+///
+/// When empty, authentication is disabled and every request is allowed.
+#[derive(Default)]
+pub struct Tokens {
+    tokens: Vec<Token>,
+}
+
+impl Tokens {
+    /// Creates an empty token set, which disables authentication.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolves token values from the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entry has no or several token sources, or its
+    /// environment variable is unset or empty.
+    pub fn from_configs(configs: &[TokenConfig]) -> Result<Self> {
+        let mut tokens = Vec::with_capacity(configs.len());
+
+        for config in configs {
+            let value = match (&config.token, &config.token_env) {
+                (Some(token), None) => token.clone(),
+                (None, Some(var)) => std::env::var(var).map_err(|_| Error::Adapter {
+                    package: "auth".to_string(),
+                    message: format!("Token environment variable {} is not set", var),
+                })?,
+                _ => {
+                    return Err(Error::Adapter {
+                        package: "auth".to_string(),
+                        message: "Each token needs exactly one of 'token' or 'token_env'".to_string(),
+                    })
+                }
+            };
+
+            if value.is_empty() {
+                return Err(Error::Adapter {
+                    package: "auth".to_string(),
+                    message: "Tokens must not be empty".to_string(),
+                });
+            }
+
+            tokens.push(Token {
+                digest: Sha256::digest(value.as_bytes()).into(),
+                scope: config.scope,
+                namespaces: config.namespaces.clone(),
+            });
+        }
+
+        Ok(Self { tokens })
+    }
+
+    /// Returns `true` if authentication is disabled.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns the number of configured tokens.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Checks that the request's bearer token grants `scope` on `namespace`.
+    ///
+    /// A `namespace` of `None` stands for server-wide access and is only
+    /// granted to tokens without namespace restrictions.
+    pub fn authorize(
+        &self,
+        headers: &HeaderMap,
+        scope: Scope,
+        namespace: Option<&str>,
+    ) -> std::result::Result<(), AuthError> {
+        if self.tokens.is_empty() {
+            return Ok(());
+        }
+
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AuthError::Unauthenticated)?;
+
+        // Comparing digests keeps the comparison time independent of the token
+        let digest: [u8; 32] = Sha256::digest(presented.trim().as_bytes()).into();
+        let token = self
+            .tokens
+            .iter()
+            .find(|token| token.digest == digest)
+            .ok_or(AuthError::Unauthenticated)?;
+
+        if token.scope < scope {
+            return Err(AuthError::Forbidden(format!(
+                "Token does not grant {:?} access",
+                scope
+            )));
+        }
+        if !token.allows_namespace(namespace) {
+            return Err(AuthError::Forbidden(match namespace {
+                Some(namespace) => format!("Token does not grant access to namespace {}", namespace),
+                None => "Token is restricted to specific namespaces".to_string(),
+            }));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+        );
+        headers
+    }
+
+    fn token(value: &str, scope: Scope, namespaces: &[&str]) -> TokenConfig {
+        TokenConfig {
+            token: Some(value.to_string()),
+            token_env: None,
+            scope,
+            namespaces: namespaces.iter().map(|n| n.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_empty_tokens_allow_everything() {
+        let tokens = Tokens::new();
+        assert!(tokens.authorize(&HeaderMap::new(), Scope::Admin, None).is_ok());
+    }
+
+    #[test]
+    fn test_scopes_and_namespaces() {
+        let tokens = Tokens::from_configs(&[
+            token("reader", Scope::Read, &[]),
+            token("web-ci", Scope::Write, &["web"]),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            tokens.authorize(&HeaderMap::new(), Scope::Read, Some("web")),
+            Err(AuthError::Unauthenticated)
+        );
+        assert_eq!(
+            tokens.authorize(&headers("wrong"), Scope::Read, Some("web")),
+            Err(AuthError::Unauthenticated)
+        );
+
+        assert!(tokens.authorize(&headers("reader"), Scope::Read, Some("web")).is_ok());
+        assert!(tokens.authorize(&headers("reader"), Scope::Read, None).is_ok());
+        assert!(matches!(
+            tokens.authorize(&headers("reader"), Scope::Write, Some("web")),
+            Err(AuthError::Forbidden(_))
+        ));
+
+        assert!(tokens.authorize(&headers("web-ci"), Scope::Write, Some("web")).is_ok());
+        assert!(tokens.authorize(&headers("web-ci"), Scope::Read, Some("web")).is_ok());
+        assert!(matches!(
+            tokens.authorize(&headers("web-ci"), Scope::Read, Some("mobile")),
+            Err(AuthError::Forbidden(_))
+        ));
+        assert!(matches!(
+            tokens.authorize(&headers("web-ci"), Scope::Read, None),
+            Err(AuthError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_token_needs_exactly_one_source() {
+        let mut config = token("value", Scope::Read, &[]);
+        config.token_env = Some("POLYKIT_CACHE_TEST_TOKEN".to_string());
+        assert!(Tokens::from_configs(&[config]).is_err());
+    }
+}
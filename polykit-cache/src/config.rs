@@ -1,6 +1,18 @@
 //! Server configuration.
 
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use polykit_core::error::{Error, Result};
+use serde::Deserialize;
+
+use crate::auth::TokenConfig;
+use crate::gc::GcPolicy;
+use crate::limits::RequestLimits;
+use crate::namespace::{validate_namespace, NamespaceSettings};
+use crate::upstream::UpstreamConfig;
 
 /// Server configuration.
 #[derive(Debug, Clone)]
@@ -13,6 +25,32 @@ pub struct ServerConfig {
     pub bind_address: String,
     /// Port number.
     pub port: u16,
+    /// Maximum total size of stored artifacts before LRU eviction.
+    pub max_total_size: Option<u64>,
+    /// Time since last access after which artifacts expire.
+    pub artifact_ttl: Option<Duration>,
+    /// Interval between background garbage collection runs.
+    pub gc_interval: Option<Duration>,
+    /// TOML file listing keys trusted to sign uploads.
+    pub trusted_keys_file: Option<PathBuf>,
+    /// Reject uploads without a valid signature from a trusted key.
+    pub require_signatures: bool,
+    /// zstd dictionaries needed to read uploads compressed with them.
+    pub dictionaries: Vec<PathBuf>,
+    /// TOML file with per-namespace quota and retention settings.
+    pub namespace_config_file: Option<PathBuf>,
+    /// Per-namespace quota and retention settings.
+    pub namespaces: BTreeMap<String, NamespaceSettings>,
+    /// Tokens accepted by the server. Authentication is disabled when empty.
+    pub tokens: Vec<TokenConfig>,
+    /// PEM certificate chain for TLS termination.
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key for TLS termination.
+    pub tls_key: Option<PathBuf>,
+    /// Concurrency and timeout limits for incoming requests.
+    pub limits: RequestLimits,
+    /// Upstream server to pull misses from and replicate uploads to.
+    pub upstream: Option<UpstreamConfig>,
 }
 
 impl Default for ServerConfig {
@@ -22,6 +60,19 @@ impl Default for ServerConfig {
             max_artifact_size: 1024 * 1024 * 1024, // 1GB
             bind_address: "127.0.0.1".to_string(),
             port: 8080,
+            max_total_size: None,
+            artifact_ttl: None,
+            gc_interval: Some(Duration::from_secs(3600)),
+            trusted_keys_file: None,
+            require_signatures: false,
+            dictionaries: Vec::new(),
+            namespace_config_file: None,
+            namespaces: BTreeMap::new(),
+            tokens: Vec::new(),
+            tls_cert: None,
+            tls_key: None,
+            limits: RequestLimits::default(),
+            upstream: None,
         }
     }
 }
@@ -32,6 +83,25 @@ impl ServerConfig {
         Self::default()
     }
 
+    /// Loads a configuration file, using defaults for unset values.
+    ///
+    /// Relative paths in the file are resolved against the file's directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or contains an
+    /// invalid namespace name.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(Error::Io)?;
+        let file: ConfigFile = toml::from_str(&content).map_err(|e| Error::Toml {
+            error: e,
+            context: path.display().to_string(),
+        })?;
+
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        file.into_config(base_dir)
+    }
+
     /// Sets the storage directory.
     pub fn with_storage_dir(mut self, dir: impl Into<PathBuf>) -> Self {
         self.storage_dir = dir.into();
@@ -56,8 +126,281 @@ impl ServerConfig {
         self
     }
 
+    /// Sets the maximum total storage size.
+    pub fn with_max_total_size(mut self, size: Option<u64>) -> Self {
+        self.max_total_size = size;
+        self
+    }
+
+    /// Sets the artifact time-to-live since last access.
+    pub fn with_artifact_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.artifact_ttl = ttl;
+        self
+    }
+
+    /// Sets the background garbage collection interval.
+    pub fn with_gc_interval(mut self, interval: Option<Duration>) -> Self {
+        self.gc_interval = interval;
+        self
+    }
+
+    /// Sets the trusted keys file.
+    pub fn with_trusted_keys_file(mut self, path: Option<PathBuf>) -> Self {
+        self.trusted_keys_file = path;
+        self
+    }
+
+    /// Sets whether unsigned uploads are rejected.
+    pub fn with_require_signatures(mut self, require: bool) -> Self {
+        self.require_signatures = require;
+        self
+    }
+
+    /// Sets the zstd dictionaries accepted on uploads.
+    pub fn with_dictionaries(mut self, paths: Vec<PathBuf>) -> Self {
+        self.dictionaries = paths;
+        self
+    }
+
+    /// Sets the per-namespace settings file.
+    pub fn with_namespace_config_file(mut self, path: Option<PathBuf>) -> Self {
+        self.namespace_config_file = path;
+        self
+    }
+
+    /// Sets the TLS certificate chain and private key.
+    pub fn with_tls(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        self.tls_cert = Some(cert.into());
+        self.tls_key = Some(key.into());
+        self
+    }
+
+    /// Sets the maximum number of in-flight requests per client.
+    pub fn with_max_concurrent_per_client(mut self, max: Option<usize>) -> Self {
+        self.limits.max_concurrent_per_client = max;
+        self
+    }
+
+    /// Sets the request timeout.
+    pub fn with_request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.limits.request_timeout = timeout;
+        self
+    }
+
+    /// Sets the upstream server.
+    pub fn with_upstream(mut self, upstream: Option<UpstreamConfig>) -> Self {
+        self.upstream = upstream;
+        self
+    }
+
+    /// Returns the TLS certificate and key paths if TLS is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if only one of the certificate and key is set.
+    pub fn tls_paths(&self) -> Result<Option<(&Path, &Path)>> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Ok(Some((cert, key))),
+            (None, None) => Ok(None),
+            _ => Err(Error::Adapter {
+                package: "config".to_string(),
+                message: "TLS needs both a certificate and a private key".to_string(),
+            }),
+        }
+    }
+
+    /// Returns the default retention policy for garbage collection.
+    pub fn gc_policy(&self) -> GcPolicy {
+        GcPolicy {
+            max_total_size: self.max_total_size,
+            ttl: self.artifact_ttl,
+        }
+    }
+
     /// Returns the bind address as a string.
     pub fn bind_addr(&self) -> String {
         format!("{}:{}", self.bind_address, self.port)
     }
 }
+
+/// Layout of the `--config` TOML file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    storage_dir: Option<PathBuf>,
+    max_artifact_size: Option<u64>,
+    bind: Option<String>,
+    port: Option<u16>,
+    trusted_keys: Option<PathBuf>,
+    require_signatures: Option<bool>,
+    #[serde(default)]
+    dictionaries: Vec<PathBuf>,
+    #[serde(default)]
+    gc: GcSection,
+    tls: Option<TlsSection>,
+    #[serde(default)]
+    limits: LimitsSection,
+    upstream: Option<UpstreamConfig>,
+    #[serde(default)]
+    tokens: Vec<TokenConfig>,
+    #[serde(default)]
+    namespaces: BTreeMap<String, NamespaceSettings>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GcSection {
+    max_total_size: Option<u64>,
+    /// Seconds since last access.
+    ttl: Option<u64>,
+    /// Seconds between runs, `0` disables background collection.
+    interval: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TlsSection {
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LimitsSection {
+    max_concurrent_per_client: Option<usize>,
+    /// Seconds.
+    request_timeout: Option<u64>,
+}
+
+impl ConfigFile {
+    fn into_config(self, base_dir: &Path) -> Result<ServerConfig> {
+        for name in self.namespaces.keys() {
+            validate_namespace(name)?;
+        }
+
+        let resolve = |path: PathBuf| {
+            if path.is_absolute() {
+                path
+            } else {
+                base_dir.join(path)
+            }
+        };
+
+        let mut config = ServerConfig::default();
+        if let Some(dir) = self.storage_dir {
+            config.storage_dir = resolve(dir);
+        }
+        if let Some(size) = self.max_artifact_size {
+            config.max_artifact_size = size;
+        }
+        if let Some(bind) = self.bind {
+            config.bind_address = bind;
+        }
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        config.trusted_keys_file = self.trusted_keys.map(resolve);
+        config.require_signatures = self.require_signatures.unwrap_or(false);
+        config.dictionaries = self.dictionaries.into_iter().map(resolve).collect();
+        config.max_total_size = self.gc.max_total_size;
+        config.artifact_ttl = self.gc.ttl.map(Duration::from_secs);
+        if let Some(interval) = self.gc.interval {
+            config.gc_interval = (interval > 0).then(|| Duration::from_secs(interval));
+        }
+        if let Some(tls) = self.tls {
+            config.tls_cert = Some(resolve(tls.cert));
+            config.tls_key = Some(resolve(tls.key));
+        }
+        config.limits = RequestLimits {
+            max_concurrent_per_client: self.limits.max_concurrent_per_client,
+            request_timeout: self.limits.request_timeout.map(Duration::from_secs),
+        };
+        config.upstream = self.upstream;
+        config.tokens = self.tokens;
+        config.namespaces = self.namespaces;
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Scope;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("cache.toml");
+        fs::write(
+            &path,
+            r#"
+storage_dir = "data"
+port = 9443
+dictionaries = ["outputs.dict"]
+
+[gc]
+ttl = 3600
+interval = 0
+
+[tls]
+cert = "/etc/polykit/cert.pem"
+key = "key.pem"
+
+[limits]
+max_concurrent_per_client = 8
+request_timeout = 120
+
+[upstream]
+url = "https://cache.example.com"
+token_env = "POLYKIT_CACHE_UPSTREAM_TOKEN"
+
+[[tokens]]
+token = "secret"
+scope = "write"
+namespaces = ["web"]
+
+[namespaces.web]
+quota = 1000
+"#,
+        )
+        .unwrap();
+
+        let config = ServerConfig::from_file(&path).unwrap();
+        assert_eq!(config.storage_dir, temp_dir.path().join("data"));
+        assert_eq!(config.port, 9443);
+        assert_eq!(config.bind_address, "127.0.0.1");
+        assert_eq!(config.dictionaries, vec![temp_dir.path().join("outputs.dict")]);
+        assert_eq!(config.artifact_ttl, Some(Duration::from_secs(3600)));
+        assert_eq!(config.gc_interval, None);
+        assert_eq!(config.tls_cert, Some(PathBuf::from("/etc/polykit/cert.pem")));
+        assert_eq!(config.tls_key, Some(temp_dir.path().join("key.pem")));
+        assert_eq!(config.limits.max_concurrent_per_client, Some(8));
+        assert_eq!(config.limits.request_timeout, Some(Duration::from_secs(120)));
+        let upstream = config.upstream.unwrap();
+        assert_eq!(upstream.url, "https://cache.example.com");
+        assert!(!upstream.read_only);
+        assert_eq!(config.tokens.len(), 1);
+        assert_eq!(config.tokens[0].scope, Scope::Write);
+        assert_eq!(config.namespaces["web"].quota, Some(1000));
+    }
+
+    #[test]
+    fn test_from_file_rejects_unknown_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("cache.toml");
+        fs::write(&path, "storage-dir = \"data\"\n").unwrap();
+
+        assert!(ServerConfig::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_tls_needs_cert_and_key() {
+        let mut config = ServerConfig::new();
+        assert!(config.tls_paths().unwrap().is_none());
+
+        config.tls_cert = Some(PathBuf::from("cert.pem"));
+        assert!(config.tls_paths().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Garbage collection and retention for stored artifacts.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use polykit_core::error::Result;
+use tracing::{info, warn};
+
+use crate::namespace::Namespaces;
+use crate::storage::{now_secs, Storage, StorageEntry};
+
+/// Retention policy applied by the garbage collector.
+#[derive(Debug, Clone, Default)]
+pub struct GcPolicy {
+    /// Maximum total size of all stored artifacts in bytes.
+    ///
+    /// When exceeded, least recently used artifacts are evicted first.
+    pub max_total_size: Option<u64>,
+    /// Maximum time since an artifact was last downloaded (or uploaded).
+    pub ttl: Option<Duration>,
+}
+
+impl GcPolicy {
+    /// Returns `true` if the policy would ever evict anything.
+    pub fn is_enabled(&self) -> bool {
+        self.max_total_size.is_some() || self.ttl.is_some()
+    }
+}
+
+/// Summary of a garbage collection run.
+#[derive(Debug, Clone, Default)]
+pub struct GcReport {
+    /// Number of artifacts examined.
+    pub scanned: usize,
+    /// Artifacts removed because they exceeded the TTL.
+    pub expired: usize,
+    /// Artifacts removed to bring the store under the size limit.
+    pub evicted: usize,
+    /// Artifacts removed because their metadata was missing.
+    pub orphaned: usize,
+    /// Total bytes freed.
+    pub bytes_freed: u64,
+    /// Total bytes still stored after the run.
+    pub bytes_remaining: u64,
+}
+
+impl GcReport {
+    /// Returns the total number of artifacts removed.
+    pub fn removed(&self) -> usize {
+        self.expired + self.evicted + self.orphaned
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reason {
+    Expired,
+    Evicted,
+    Orphaned,
+}
+
+struct Candidate {
+    cache_key: String,
+    last_accessed: Option<u64>,
+    reason: Reason,
+}
+
+/// Runs one garbage collection pass over the storage.
+///
+/// Candidates are chosen from an unlocked snapshot of the store, then removed
+/// while holding the storage lock exclusively. Any candidate that was touched
+/// by a download or finished uploading in the meantime is kept.
+///
+/// # Errors
+///
+/// Returns an error if the storage cannot be listed or a file cannot be removed.
+pub async fn collect_garbage(storage: &Storage, policy: &GcPolicy) -> Result<GcReport> {
+    let entries = storage.list_entries()?;
+    let mut report = GcReport {
+        scanned: entries.len(),
+        ..Default::default()
+    };
+
+    let candidates = plan(entries, policy, now_secs(), &mut report.bytes_remaining);
+
+    let _guard = storage.lock_exclusive().await;
+
+    for candidate in candidates {
+        let current = storage
+            .read_metadata_sync(&candidate.cache_key)
+            .map(|m| m.last_accessed_at());
+        if current != candidate.last_accessed {
+            continue;
+        }
+
+        let freed = storage.remove_artifact(&candidate.cache_key)?;
+        report.bytes_freed += freed;
+        report.bytes_remaining = report.bytes_remaining.saturating_sub(freed);
+        match candidate.reason {
+            Reason::Expired => report.expired += 1,
+            Reason::Evicted => report.evicted += 1,
+            Reason::Orphaned => report.orphaned += 1,
+        }
+    }
+
+    // No upload is in flight while the lock is held, so every temp file is stale
+    storage.cleanup_temp_files()?;
+
+    Ok(report)
+}
+
+/// Selects artifacts to remove: orphans, then expired entries, then the least
+/// recently used entries until the remaining total fits the size limit.
+fn plan(
+    entries: Vec<StorageEntry>,
+    policy: &GcPolicy,
+    now: u64,
+    total_size: &mut u64,
+) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    let mut survivors = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        *total_size += entry.size;
+
+        let Some(metadata) = entry.metadata else {
+            candidates.push(Candidate {
+                cache_key: entry.cache_key,
+                last_accessed: None,
+                reason: Reason::Orphaned,
+            });
+            continue;
+        };
+
+        let last_accessed = metadata.last_accessed_at();
+        let expired = policy
+            .ttl
+            .is_some_and(|ttl| now.saturating_sub(last_accessed) >= ttl.as_secs());
+
+        if expired {
+            candidates.push(Candidate {
+                cache_key: entry.cache_key,
+                last_accessed: Some(last_accessed),
+                reason: Reason::Expired,
+            });
+        } else {
+            survivors.push((entry.cache_key, entry.size, last_accessed, metadata.created_at));
+        }
+    }
+
+    if let Some(max_total_size) = policy.max_total_size {
+        let mut remaining: u64 = survivors.iter().map(|(_, size, _, _)| size).sum();
+
+        survivors.sort_by(|a, b| (a.2, a.3, &a.0).cmp(&(b.2, b.3, &b.0)));
+
+        for (cache_key, size, last_accessed, _) in survivors {
+            if remaining <= max_total_size {
+                break;
+            }
+            remaining -= size;
+            candidates.push(Candidate {
+                cache_key,
+                last_accessed: Some(last_accessed),
+                reason: Reason::Evicted,
+            });
+        }
+    }
+
+    candidates
+}
+
+/// Runs one garbage collection pass over every namespace with a retention policy.
+///
+/// Returns the report of each collected namespace. A failure in one namespace
+/// is logged and does not stop collection of the others.
+pub async fn collect_namespaces(namespaces: &Namespaces) -> Vec<(String, GcReport)> {
+    let mut reports = Vec::new();
+
+    for (name, storage) in namespaces.list() {
+        let policy = namespaces.policy(&name);
+        if !policy.is_enabled() {
+            continue;
+        }
+
+        match collect_garbage(&storage, &policy).await {
+            Ok(report) => reports.push((name, report)),
+            Err(e) => warn!("Garbage collection of namespace {} failed: {}", name, e),
+        }
+    }
+
+    reports
+}
+
+/// Spawns a background task that runs garbage collection periodically.
+pub fn spawn_gc_task(namespaces: Arc<Namespaces>, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            for (name, report) in collect_namespaces(&namespaces).await {
+                if report.removed() > 0 {
+                    info!(
+                        "Garbage collection of namespace {} removed {} artifacts ({} expired, {} evicted, {} orphaned), freed {} bytes, {} bytes remaining",
+                        name,
+                        report.removed(),
+                        report.expired,
+                        report.evicted,
+                        report.orphaned,
+                        report.bytes_freed,
+                        report.bytes_remaining
+                    );
+                }
+            }
+        }
+    })
+}
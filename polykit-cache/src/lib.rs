@@ -1,6 +1,13 @@
 //! Reference HTTP cache server library.
 
+pub mod auth;
 pub mod config;
+pub mod gc;
+pub mod limits;
+pub mod metrics;
+pub mod namespace;
 pub mod server;
 pub mod storage;
+pub mod tls;
+pub mod upstream;
 pub mod verification;
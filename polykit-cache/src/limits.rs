@@ -0,0 +1,184 @@
+//! Per-client request concurrency limits.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::body::{Body, Bytes, HttpBody};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http_body::{Frame, SizeHint};
+
+/// Limits applied to incoming requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestLimits {
+    /// Maximum number of requests a single client may have in flight.
+    pub max_concurrent_per_client: Option<usize>,
+    /// Maximum time to handle a request, including reading the body.
+    pub request_timeout: Option<Duration>,
+}
+
+/// Tracks in-flight requests per client address.
+///
+/// Requests without connection info (e.g. when the router is served without
+/// `into_make_service_with_connect_info`) share a single slot pool.
+pub struct ClientLimiter {
+    max_concurrent: usize,
+    in_flight: Mutex<HashMap<Option<IpAddr>, usize>>,
+}
+
+impl ClientLimiter {
+    /// Creates a limiter allowing `max_concurrent` requests per client.
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent,
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserves a slot for the client, or returns `None` if it is at its limit.
+    pub fn try_acquire(self: &Arc<Self>, client: Option<IpAddr>) -> Option<ClientPermit> {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        let count = in_flight.entry(client).or_default();
+        if *count >= self.max_concurrent {
+            return None;
+        }
+        *count += 1;
+
+        Some(ClientPermit {
+            limiter: Arc::clone(self),
+            client,
+        })
+    }
+
+    fn release(&self, client: Option<IpAddr>) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = in_flight.get_mut(&client) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&client);
+            }
+        }
+    }
+}
+
+/// A reserved request slot, released on drop.
+pub struct ClientPermit {
+    limiter: Arc<ClientLimiter>,
+    client: Option<IpAddr>,
+}
+
+impl Drop for ClientPermit {
+    fn drop(&mut self) {
+        self.limiter.release(self.client);
+    }
+}
+
+/// A response body that keeps its request's [`ClientPermit`] until the body
+/// has been sent or dropped, so slow downloads count against the limit.
+struct PermitBody {
+    inner: Body,
+    permit: Option<ClientPermit>,
+}
+
+impl HttpBody for PermitBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = Pin::new(&mut self.inner).poll_frame(cx);
+        if matches!(frame, Poll::Ready(None)) {
+            self.permit = None;
+        }
+        frame
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Middleware that rejects requests from clients over their concurrency limit.
+///
+/// The slot is held until the response body has been sent.
+pub async fn limit_per_client(
+    State(limiter): State<Arc<ClientLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    let Some(permit) = limiter.try_acquire(client) else {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, "1")],
+            axum::Json(serde_json::json!({ "error": "Too many concurrent requests" })),
+        )
+            .into_response();
+    };
+
+    next.run(request).await.map(|inner| {
+        Body::new(PermitBody {
+            inner,
+            permit: Some(permit),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limiter_is_per_client() {
+        let limiter = Arc::new(ClientLimiter::new(2));
+        let a = Some(IpAddr::from([10, 0, 0, 1]));
+        let b = Some(IpAddr::from([10, 0, 0, 2]));
+
+        let first = limiter.try_acquire(a).unwrap();
+        let _second = limiter.try_acquire(a).unwrap();
+        assert!(limiter.try_acquire(a).is_none());
+        assert!(limiter.try_acquire(b).is_some());
+
+        drop(first);
+        assert!(limiter.try_acquire(a).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_permit_is_held_until_body_is_sent() {
+        use tower::Service;
+
+        let limiter = Arc::new(ClientLimiter::new(1));
+        let mut router = axum::Router::new()
+            .route("/", axum::routing::get(|| async { "artifact" }))
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::clone(&limiter),
+                limit_per_client,
+            ));
+
+        let response = router.call(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(limiter.try_acquire(None).is_none());
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"artifact");
+        assert!(limiter.try_acquire(None).is_some());
+    }
+}
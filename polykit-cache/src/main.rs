@@ -1,45 +1,215 @@
 //! Reference HTTP cache server for Polykit.
 
+mod auth;
 mod config;
+mod gc;
+mod limits;
+mod metrics;
+mod namespace;
 mod server;
 mod storage;
+mod tls;
+mod upstream;
 mod verification;
 
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use polykit_core::remote_cache::{Dictionaries, TrustedKeys};
 use tokio::signal;
-use tracing::{info, Level};
+use tracing::{info, warn, Level};
 
+use auth::Tokens;
 use config::ServerConfig;
+use gc::{collect_namespaces, spawn_gc_task};
+use namespace::{load_namespace_settings, Namespaces};
 use server::{create_router, AppState};
-use storage::Storage;
+use tls::{load_tls_config, spawn_reload_on_sighup};
+use upstream::{Upstream, UpstreamConfig};
 use verification::Verifier;
 
+/// Command-line options.
+///
+/// Every option can also be set through its environment variable and
+/// overrides the `--config` file.
 #[derive(Parser)]
 #[command(name = "polykit-cache")]
 #[command(about = "Reference HTTP cache server for Polykit")]
 struct Cli {
-    /// Storage directory for artifacts
-    #[arg(long, default_value = "./cache")]
-    storage_dir: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// TOML configuration file
+    #[arg(long, global = true, env = "POLYKIT_CACHE_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Storage directory for artifacts [default: ./cache]
+    #[arg(long, global = true, env = "POLYKIT_CACHE_STORAGE_DIR")]
+    storage_dir: Option<PathBuf>,
+
+    /// Maximum artifact size in bytes [default: 1073741824]
+    #[arg(long, env = "POLYKIT_CACHE_MAX_SIZE")]
+    max_size: Option<u64>,
+
+    /// Bind address [default: 127.0.0.1]
+    #[arg(long, env = "POLYKIT_CACHE_BIND")]
+    bind: Option<String>,
+
+    /// Port number [default: 8080]
+    #[arg(long, env = "POLYKIT_CACHE_PORT")]
+    port: Option<u16>,
+
+    /// Maximum total storage size in bytes before LRU eviction
+    #[arg(long, global = true, env = "POLYKIT_CACHE_MAX_TOTAL_SIZE")]
+    max_total_size: Option<u64>,
+
+    /// Evict artifacts not downloaded for this many seconds
+    #[arg(long, global = true, env = "POLYKIT_CACHE_TTL")]
+    ttl: Option<u64>,
+
+    /// TOML file with `[namespaces.<name>]` quota and retention settings
+    #[arg(long, global = true, env = "POLYKIT_CACHE_NAMESPACE_CONFIG")]
+    namespace_config: Option<PathBuf>,
+
+    /// Seconds between background garbage collection runs, 0 disables [default: 3600]
+    #[arg(long, env = "POLYKIT_CACHE_GC_INTERVAL")]
+    gc_interval: Option<u64>,
+
+    /// TOML file with `[[keys]]` trusted to sign uploads
+    #[arg(long, env = "POLYKIT_CACHE_TRUSTED_KEYS")]
+    trusted_keys: Option<PathBuf>,
+
+    /// Reject uploads without a valid signature from a trusted key
+    #[arg(long, action, env = "POLYKIT_CACHE_REQUIRE_SIGNATURES")]
+    require_signatures: bool,
+
+    /// zstd dictionary clients compress uploads with (repeatable)
+    #[arg(long = "dictionary", env = "POLYKIT_CACHE_DICTIONARIES", value_delimiter = ',')]
+    dictionaries: Vec<PathBuf>,
 
-    /// Maximum artifact size in bytes
-    #[arg(long, default_value = "1073741824")] // 1GB
-    max_size: u64,
+    /// PEM certificate chain; enables TLS together with --tls-key
+    #[arg(long, env = "POLYKIT_CACHE_TLS_CERT")]
+    tls_cert: Option<PathBuf>,
 
-    /// Bind address
-    #[arg(long, default_value = "127.0.0.1")]
-    bind: String,
+    /// PEM private key; enables TLS together with --tls-cert
+    #[arg(long, env = "POLYKIT_CACHE_TLS_KEY")]
+    tls_key: Option<PathBuf>,
 
-    /// Port number
-    #[arg(long, default_value = "8080")]
-    port: u16,
+    /// Maximum number of in-flight requests per client address
+    #[arg(long, env = "POLYKIT_CACHE_MAX_CONCURRENT_PER_CLIENT")]
+    max_concurrent_per_client: Option<usize>,
+
+    /// Seconds after which a request is aborted
+    #[arg(long, env = "POLYKIT_CACHE_REQUEST_TIMEOUT")]
+    request_timeout: Option<u64>,
+
+    /// Upstream cache server to pull misses from and replicate uploads to
+    #[arg(long, env = "POLYKIT_CACHE_UPSTREAM")]
+    upstream: Option<String>,
+
+    /// Token for the upstream server
+    #[arg(long, env = "POLYKIT_CACHE_UPSTREAM_TOKEN", hide_env_values = true)]
+    upstream_token: Option<String>,
+
+    /// Only pull from the upstream server, don't replicate uploads to it
+    #[arg(long, action, env = "POLYKIT_CACHE_UPSTREAM_READ_ONLY")]
+    upstream_read_only: bool,
 
     /// Log level
-    #[arg(long, default_value = "info")]
+    #[arg(long, global = true, env = "POLYKIT_CACHE_LOG_LEVEL", default_value = "info")]
     log_level: String,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Run garbage collection once and exit
+    Gc,
+}
+
+impl Cli {
+    /// Builds the server configuration from the config file and overrides.
+    fn server_config(&self) -> Result<ServerConfig> {
+        let mut config = match self.config {
+            Some(ref path) => ServerConfig::from_file(path)?,
+            None => ServerConfig::new(),
+        };
+
+        if let Some(ref dir) = self.storage_dir {
+            config = config.with_storage_dir(dir);
+        }
+        if let Some(size) = self.max_size {
+            config = config.with_max_artifact_size(size);
+        }
+        if let Some(ref bind) = self.bind {
+            config = config.with_bind_address(bind);
+        }
+        if let Some(port) = self.port {
+            config = config.with_port(port);
+        }
+        if self.max_total_size.is_some() {
+            config = config.with_max_total_size(self.max_total_size);
+        }
+        if let Some(ttl) = self.ttl {
+            config = config.with_artifact_ttl(Some(Duration::from_secs(ttl)));
+        }
+        if let Some(interval) = self.gc_interval {
+            config = config.with_gc_interval((interval > 0).then(|| Duration::from_secs(interval)));
+        }
+        if self.namespace_config.is_some() {
+            config = config.with_namespace_config_file(self.namespace_config.clone());
+        }
+        if self.trusted_keys.is_some() {
+            config = config.with_trusted_keys_file(self.trusted_keys.clone());
+        }
+        if self.require_signatures {
+            config = config.with_require_signatures(true);
+        }
+        if !self.dictionaries.is_empty() {
+            config = config.with_dictionaries(self.dictionaries.clone());
+        }
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => config = config.with_tls(cert, key),
+            (None, None) => {}
+            _ => anyhow::bail!("--tls-cert and --tls-key must be set together"),
+        }
+        if self.max_concurrent_per_client.is_some() {
+            config = config.with_max_concurrent_per_client(self.max_concurrent_per_client);
+        }
+        if let Some(timeout) = self.request_timeout {
+            config = config.with_request_timeout(Some(Duration::from_secs(timeout)));
+        }
+        if let Some(ref url) = self.upstream {
+            // Keep the token and mode from the config file, only the URL changes
+            let upstream = match config.upstream.take() {
+                Some(upstream) => UpstreamConfig {
+                    url: url.clone(),
+                    ..upstream
+                },
+                None => UpstreamConfig {
+                    url: url.clone(),
+                    token: None,
+                    token_env: None,
+                    read_only: false,
+                },
+            };
+            config = config.with_upstream(Some(upstream));
+        }
+        if let Some(ref mut upstream) = config.upstream {
+            if self.upstream_token.is_some() {
+                upstream.token = self.upstream_token.clone();
+            }
+            if self.upstream_read_only {
+                upstream.read_only = true;
+            }
+        }
+
+        Ok(config)
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -59,11 +229,11 @@ async fn main() -> Result<()> {
         .init();
 
     // Create configuration
-    let config = ServerConfig::new()
-        .with_storage_dir(&cli.storage_dir)
-        .with_max_artifact_size(cli.max_size)
-        .with_bind_address(&cli.bind)
-        .with_port(cli.port);
+    let config = cli.server_config()?;
+
+    if let Some(Command::Gc) = cli.command {
+        return run_gc_once(&config).await;
+    }
 
     info!("Starting polykit-cache server");
     info!("Storage directory: {}", config.storage_dir.display());
@@ -71,30 +241,147 @@ async fn main() -> Result<()> {
     info!("Listening on {}", config.bind_addr());
 
     // Create storage and verifier
-    let storage = Storage::new(&config.storage_dir, config.max_artifact_size)?;
-    let verifier = Verifier::new(config.max_artifact_size);
+    let namespaces = open_namespaces(&config)?;
+    info!("Serving {} namespaces", namespaces.list().len());
+    let trusted_keys = match config.trusted_keys_file {
+        Some(ref path) => TrustedKeys::from_file(path)?,
+        None => TrustedKeys::new(),
+    };
+    if config.require_signatures && trusted_keys.is_empty() {
+        anyhow::bail!("--require-signatures needs at least one key in --trusted-keys");
+    }
+    if !trusted_keys.is_empty() {
+        info!(
+            "Verifying upload signatures against {} trusted keys{}",
+            trusted_keys.len(),
+            if config.require_signatures { ", unsigned uploads rejected" } else { "" }
+        );
+    }
+    let dictionaries = Dictionaries::load(&config.dictionaries)?;
+    if !dictionaries.is_empty() {
+        info!("Accepting uploads compressed with {} dictionaries", config.dictionaries.len());
+    }
+    let verifier = Verifier::new(config.max_artifact_size)
+        .with_trusted_keys(trusted_keys)
+        .with_require_signatures(config.require_signatures)
+        .with_dictionaries(dictionaries);
 
     // Clean up any stale temp files
-    storage.cleanup_temp_files()?;
+    for (_, storage) in namespaces.list() {
+        storage.cleanup_temp_files()?;
+    }
+
+    let tokens = Tokens::from_configs(&config.tokens)?;
+    if tokens.is_empty() {
+        warn!("No tokens configured, authentication is disabled");
+    } else {
+        info!("Accepting {} tokens", tokens.len());
+    }
 
     // Create app state
-    let state = AppState::new(storage, verifier);
+    let has_retention = namespaces.has_retention();
+    let mut state = AppState::new(namespaces, verifier)
+        .with_tokens(tokens)
+        .with_limits(config.limits);
 
-    // Create router
-    let app = create_router(state);
+    if let Some(ref upstream_config) = config.upstream {
+        let upstream = Upstream::from_config(upstream_config)?;
+        info!(
+            "Pulling misses from upstream {}{}",
+            upstream.url(),
+            if upstream.replicates() { ", replicating uploads" } else { "" }
+        );
+        state = state.with_upstream(upstream);
+    }
+
+    // Start background garbage collection
+    if has_retention {
+        match config.gc_interval {
+            Some(interval) => {
+                info!("Garbage collection every {} seconds", interval.as_secs());
+                spawn_gc_task(state.namespaces(), interval);
+            }
+            None => warn!("Retention limits are set but background garbage collection is disabled"),
+        }
+    }
+
+    // Create router, keeping client addresses for per-client limits
+    let app = create_router(state).into_make_service_with_connect_info::<SocketAddr>();
 
     // Create server
     let listener = tokio::net::TcpListener::bind(&config.bind_addr()).await?;
 
     // Start server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    match config.tls_paths()? {
+        Some((cert, key)) => {
+            let tls_config = load_tls_config(cert, key).await?;
+            spawn_reload_on_sighup(tls_config.clone(), cert.to_path_buf(), key.to_path_buf())?;
+            info!("TLS enabled, send SIGHUP to reload the certificate");
+
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown_signal().await;
+                shutdown_handle.graceful_shutdown(None);
+            });
+
+            axum_server::from_tcp_rustls(listener.into_std()?, tls_config)
+                .handle(handle)
+                .serve(app)
+                .await?;
+        }
+        None => {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown_signal())
+                .await?;
+        }
+    }
 
     info!("Server stopped");
     Ok(())
 }
 
+/// Opens all namespaces with their quota and retention settings.
+fn open_namespaces(config: &ServerConfig) -> Result<Namespaces> {
+    let mut settings = config.namespaces.clone();
+    if let Some(ref path) = config.namespace_config_file {
+        settings.extend(load_namespace_settings(path)?);
+    }
+
+    let namespaces = Namespaces::open(&config.storage_dir, config.max_artifact_size)?
+        .with_default_policy(config.gc_policy())
+        .with_settings(settings);
+
+    Ok(namespaces)
+}
+
+async fn run_gc_once(config: &ServerConfig) -> Result<()> {
+    let namespaces = open_namespaces(config)?;
+    if !namespaces.has_retention() {
+        anyhow::bail!(
+            "No retention policy configured. Set --max-total-size and/or --ttl, or configure namespaces in --namespace-config."
+        );
+    }
+
+    for (name, report) in collect_namespaces(&namespaces).await {
+        info!(
+            "Namespace {}: scanned {} artifacts, removed {} ({} expired, {} evicted, {} orphaned)",
+            name,
+            report.scanned,
+            report.removed(),
+            report.expired,
+            report.evicted,
+            report.orphaned
+        );
+        info!(
+            "Namespace {}: freed {} bytes, {} bytes remaining",
+            name, report.bytes_freed, report.bytes_remaining
+        );
+    }
+
+    Ok(())
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         signal::ctrl_c()
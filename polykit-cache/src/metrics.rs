@@ -0,0 +1,319 @@
+//! Request metrics and cache statistics.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::Serialize;
+
+use crate::storage::StorageUsage;
+
+/// Namespace used by the unprefixed `/v1/artifacts` routes.
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Upper bounds (in seconds) of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Cache activity counters for a single namespace.
+#[derive(Debug, Default)]
+pub struct NamespaceCounters {
+    uploads: AtomicU64,
+    downloads: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    upstream_hits: AtomicU64,
+    replications: AtomicU64,
+    replication_failures: AtomicU64,
+    replications_dropped: AtomicU64,
+}
+
+impl NamespaceCounters {
+    /// Records a successful upload.
+    pub fn record_upload(&self) {
+        self.uploads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a successful download.
+    pub fn record_download(&self) {
+        self.downloads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a lookup that found the artifact.
+    pub fn record_hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a lookup that did not find the artifact.
+    pub fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records an artifact pulled from the upstream server after a local miss.
+    pub fn record_upstream_hit(&self) {
+        self.upstream_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records an upload replicated to the upstream server.
+    pub fn record_replication(&self) {
+        self.replications.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records an upload that could not be replicated to the upstream server.
+    pub fn record_replication_failure(&self) {
+        self.replication_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records an upload not replicated because the replication backlog was full.
+    pub fn record_replication_dropped(&self) {
+        self.replications_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, usage: StorageUsage) -> NamespaceStats {
+        NamespaceStats {
+            artifacts: usage.artifacts,
+            bytes: usage.bytes,
+            uploads: self.uploads.load(Ordering::Relaxed),
+            downloads: self.downloads.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            upstream_hits: self.upstream_hits.load(Ordering::Relaxed),
+            replications: self.replications.load(Ordering::Relaxed),
+            replication_failures: self.replication_failures.load(Ordering::Relaxed),
+            replications_dropped: self.replications_dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Point-in-time statistics for a namespace.
+#[derive(Debug, Clone, Serialize)]
+pub struct NamespaceStats {
+    /// Number of stored artifacts.
+    pub artifacts: u64,
+    /// Total size of stored artifacts in bytes.
+    pub bytes: u64,
+    /// Successful uploads since startup.
+    pub uploads: u64,
+    /// Successful downloads since startup.
+    pub downloads: u64,
+    /// Lookups (GET, HEAD or batch query) that found the artifact.
+    pub hits: u64,
+    /// Lookups (GET, HEAD or batch query) that did not find the artifact.
+    pub misses: u64,
+    /// Artifacts pulled from the upstream server after a local miss.
+    pub upstream_hits: u64,
+    /// Uploads replicated to the upstream server.
+    pub replications: u64,
+    /// Uploads that could not be replicated to the upstream server.
+    pub replication_failures: u64,
+    /// Uploads not replicated because the replication backlog was full.
+    pub replications_dropped: u64,
+}
+
+/// Point-in-time statistics for the whole server.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerStats {
+    /// Number of stored artifacts across all namespaces.
+    pub artifacts: u64,
+    /// Total size of stored artifacts in bytes across all namespaces.
+    pub bytes: u64,
+    /// Per-namespace statistics.
+    pub namespaces: BTreeMap<String, NamespaceStats>,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+type RequestLabels = (String, String);
+
+/// Name, Prometheus type, help text and accessor of a per-namespace metric.
+type NamespaceMetric = (&'static str, &'static str, &'static str, fn(&NamespaceStats) -> u64);
+
+/// Collects HTTP request metrics and per-namespace cache counters.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    latencies: Mutex<BTreeMap<RequestLabels, Histogram>>,
+    namespaces: Mutex<BTreeMap<String, Arc<NamespaceCounters>>>,
+}
+
+impl Metrics {
+    /// Creates an empty metrics registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the counters for a namespace, creating them on first use.
+    pub fn namespace(&self, namespace: &str) -> Arc<NamespaceCounters> {
+        let mut namespaces = self.namespaces.lock().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(namespaces.entry(namespace.to_string()).or_default())
+    }
+
+    /// Records a completed HTTP request.
+    pub fn record_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        if let Ok(mut requests) = self.requests.lock() {
+            *requests
+                .entry((method.to_string(), route.to_string(), status))
+                .or_default() += 1;
+        }
+        if let Ok(mut latencies) = self.latencies.lock() {
+            latencies
+                .entry((method.to_string(), route.to_string()))
+                .or_default()
+                .observe(elapsed.as_secs_f64());
+        }
+    }
+
+    /// Returns a statistics snapshot.
+    ///
+    /// `usage` is called for each namespace to obtain its storage usage.
+    pub fn stats(&self, usage: impl Fn(&str) -> StorageUsage) -> ServerStats {
+        let namespaces: BTreeMap<String, NamespaceStats> = self
+            .namespaces
+            .lock()
+            .map(|namespaces| {
+                namespaces
+                    .iter()
+                    .map(|(name, counters)| (name.clone(), counters.snapshot(usage(name))))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ServerStats {
+            artifacts: namespaces.values().map(|s| s.artifacts).sum(),
+            bytes: namespaces.values().map(|s| s.bytes).sum(),
+            namespaces,
+        }
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn render_prometheus(&self, stats: &ServerStats) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP polykit_cache_http_requests_total Total number of HTTP requests.\n");
+        out.push_str("# TYPE polykit_cache_http_requests_total counter\n");
+        if let Ok(requests) = self.requests.lock() {
+            for ((method, route, status), count) in requests.iter() {
+                let _ = writeln!(
+                    out,
+                    "polykit_cache_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                    method,
+                    escape_label(route),
+                    status,
+                    count
+                );
+            }
+        }
+
+        out.push_str("# HELP polykit_cache_http_request_duration_seconds HTTP request latency.\n");
+        out.push_str("# TYPE polykit_cache_http_request_duration_seconds histogram\n");
+        if let Ok(latencies) = self.latencies.lock() {
+            for ((method, route), histogram) in latencies.iter() {
+                let labels = format!("method=\"{}\",route=\"{}\"", method, escape_label(route));
+                for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                    let _ = writeln!(
+                        out,
+                        "polykit_cache_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                        labels, bound, count
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "polykit_cache_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                    labels, histogram.count
+                );
+                let _ = writeln!(
+                    out,
+                    "polykit_cache_http_request_duration_seconds_sum{{{}}} {}",
+                    labels, histogram.sum
+                );
+                let _ = writeln!(
+                    out,
+                    "polykit_cache_http_request_duration_seconds_count{{{}}} {}",
+                    labels, histogram.count
+                );
+            }
+        }
+
+        let namespace_metrics: [NamespaceMetric; 10] = [
+            ("artifacts", "gauge", "Number of stored artifacts.", |s| s.artifacts),
+            ("storage_bytes", "gauge", "Total size of stored artifacts in bytes.", |s| s.bytes),
+            ("uploads_total", "counter", "Successful artifact uploads.", |s| s.uploads),
+            ("downloads_total", "counter", "Successful artifact downloads.", |s| s.downloads),
+            ("hits_total", "counter", "Artifact lookups that found the artifact.", |s| s.hits),
+            ("misses_total", "counter", "Artifact lookups that missed.", |s| s.misses),
+            ("upstream_hits_total", "counter", "Artifacts pulled from upstream after a local miss.", |s| s.upstream_hits),
+            ("replications_total", "counter", "Uploads replicated upstream.", |s| s.replications),
+            ("replication_failures_total", "counter", "Uploads that failed to replicate upstream.", |s| s.replication_failures),
+            ("replications_dropped_total", "counter", "Uploads not replicated because the replication backlog was full.", |s| s.replications_dropped),
+        ];
+
+        for (name, kind, help, value) in namespace_metrics {
+            let _ = writeln!(out, "# HELP polykit_cache_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE polykit_cache_{} {}", name, kind);
+            for (namespace, namespace_stats) in &stats.namespaces {
+                let _ = writeln!(
+                    out,
+                    "polykit_cache_{}{{namespace=\"{}\"}} {}",
+                    name,
+                    escape_label(namespace),
+                    value(namespace_stats)
+                );
+            }
+        }
+
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Middleware that records request counts and latencies.
+///
+/// Requests are labelled with their route template rather than the concrete
+/// path, so cache keys don't blow up label cardinality.
+pub async fn track_requests(
+    State(metrics): State<Arc<Metrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    metrics.record_request(&method, &route, response.status().as_u16(), start.elapsed());
+    response
+}
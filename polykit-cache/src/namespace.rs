@@ -0,0 +1,281 @@
+//! Namespaces that keep artifacts, quotas and statistics of tenants apart.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use polykit_core::error::{Error, Result};
+use serde::Deserialize;
+
+use crate::gc::GcPolicy;
+use crate::metrics::DEFAULT_NAMESPACE;
+use crate::storage::Storage;
+
+/// Directory under the storage root that holds non-default namespaces.
+const NAMESPACES_DIR: &str = "ns";
+
+/// Maximum length of a namespace name.
+const MAX_NAMESPACE_LEN: usize = 64;
+
+/// Per-namespace retention settings.
+///
+/// Unset fields fall back to the server-wide defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamespaceSettings {
+    /// Hard limit on the namespace size in bytes; uploads beyond it are rejected.
+    pub quota: Option<u64>,
+    /// Maximum total size in bytes before LRU eviction.
+    pub max_total_size: Option<u64>,
+    /// Seconds since last access after which artifacts expire.
+    pub ttl: Option<u64>,
+}
+
+impl NamespaceSettings {
+    /// Returns the retention policy, filling unset fields from `defaults`.
+    pub fn policy(&self, defaults: &GcPolicy) -> GcPolicy {
+        GcPolicy {
+            max_total_size: self.max_total_size.or(defaults.max_total_size),
+            ttl: self.ttl.map(Duration::from_secs).or(defaults.ttl),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NamespacesFile {
+    #[serde(default)]
+    namespaces: BTreeMap<String, NamespaceSettings>,
+}
+
+/// Loads per-namespace settings from a TOML file with `[namespaces.<name>]` tables.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or parsed, or a namespace name
+/// is invalid.
+pub fn load_namespace_settings(path: &Path) -> Result<BTreeMap<String, NamespaceSettings>> {
+    let content = fs::read_to_string(path).map_err(Error::Io)?;
+    let file: NamespacesFile = toml::from_str(&content).map_err(|e| Error::Toml {
+        error: e,
+        context: path.display().to_string(),
+    })?;
+
+    for name in file.namespaces.keys() {
+        validate_namespace(name)?;
+    }
+
+    Ok(file.namespaces)
+}
+
+/// Checks that a namespace name is safe to use as a directory name.
+///
+/// Names are 1-64 characters of lowercase ASCII letters, digits, `-` and `_`.
+///
+/// # Errors
+///
+/// Returns an error if the name is invalid.
+pub fn validate_namespace(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAMESPACE_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Adapter {
+            package: "namespace".to_string(),
+            message: format!(
+                "Invalid namespace '{}': use 1-{} lowercase letters, digits, '-' or '_'",
+                name, MAX_NAMESPACE_LEN
+            ),
+        })
+    }
+}
+
+/// Registry of per-namespace storages.
+///
+/// The default namespace is stored directly in the storage root, so stores
+/// created before namespaces existed keep working. Other namespaces live in
+/// `<root>/ns/<name>/` and are created on first upload.
+pub struct Namespaces {
+    root: PathBuf,
+    max_artifact_size: u64,
+    default_policy: GcPolicy,
+    settings: BTreeMap<String, NamespaceSettings>,
+    storages: RwLock<BTreeMap<String, Arc<Storage>>>,
+}
+
+impl Namespaces {
+    /// Opens the storage root and every namespace already on disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a storage directory cannot be created or read.
+    pub fn open(root: impl AsRef<Path>, max_artifact_size: u64) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        let mut storages = BTreeMap::new();
+        storages.insert(
+            DEFAULT_NAMESPACE.to_string(),
+            Arc::new(Storage::new(&root, max_artifact_size)?),
+        );
+
+        let namespaces_dir = root.join(NAMESPACES_DIR);
+        if namespaces_dir.is_dir() {
+            for entry in fs::read_dir(&namespaces_dir).map_err(Error::Io)? {
+                let path = entry.map_err(Error::Io)?.path();
+                let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if !path.is_dir() || validate_namespace(name).is_err() || name == DEFAULT_NAMESPACE {
+                    continue;
+                }
+                storages.insert(
+                    name.to_string(),
+                    Arc::new(Storage::new(&path, max_artifact_size)?),
+                );
+            }
+        }
+
+        Ok(Self {
+            root,
+            max_artifact_size,
+            default_policy: GcPolicy::default(),
+            settings: BTreeMap::new(),
+            storages: RwLock::new(storages),
+        })
+    }
+
+    /// Sets the retention policy used by namespaces without their own settings.
+    pub fn with_default_policy(mut self, policy: GcPolicy) -> Self {
+        self.default_policy = policy;
+        self
+    }
+
+    /// Sets per-namespace retention settings.
+    pub fn with_settings(mut self, settings: BTreeMap<String, NamespaceSettings>) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Returns the storage of an existing namespace.
+    pub fn get(&self, name: &str) -> Option<Arc<Storage>> {
+        let storages = self.storages.read().unwrap_or_else(|e| e.into_inner());
+        storages.get(name).cloned()
+    }
+
+    /// Returns the storage of a namespace, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or the directory cannot be created.
+    pub fn get_or_create(&self, name: &str) -> Result<Arc<Storage>> {
+        if let Some(storage) = self.get(name) {
+            return Ok(storage);
+        }
+        validate_namespace(name)?;
+
+        let mut storages = self.storages.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(storage) = storages.get(name) {
+            return Ok(Arc::clone(storage));
+        }
+
+        let path = self.root.join(NAMESPACES_DIR).join(name);
+        let storage = Arc::new(Storage::new(&path, self.max_artifact_size)?);
+        storages.insert(name.to_string(), Arc::clone(&storage));
+        Ok(storage)
+    }
+
+    /// Returns all namespaces with their storages, sorted by name.
+    pub fn list(&self) -> Vec<(String, Arc<Storage>)> {
+        let storages = self.storages.read().unwrap_or_else(|e| e.into_inner());
+        storages
+            .iter()
+            .map(|(name, storage)| (name.clone(), Arc::clone(storage)))
+            .collect()
+    }
+
+    /// Returns the retention policy of a namespace.
+    pub fn policy(&self, name: &str) -> GcPolicy {
+        match self.settings.get(name) {
+            Some(settings) => settings.policy(&self.default_policy),
+            None => self.default_policy.clone(),
+        }
+    }
+
+    /// Returns the upload quota of a namespace in bytes, if any.
+    pub fn quota(&self, name: &str) -> Option<u64> {
+        self.settings.get(name).and_then(|settings| settings.quota)
+    }
+
+    /// Returns `true` if any namespace has a retention policy that can evict.
+    pub fn has_retention(&self) -> bool {
+        self.default_policy.is_enabled()
+            || self
+                .settings
+                .values()
+                .any(|settings| settings.policy(&self.default_policy).is_enabled())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validate_namespace() {
+        assert!(validate_namespace("team-a").is_ok());
+        assert!(validate_namespace("repo_1").is_ok());
+        assert!(validate_namespace("").is_err());
+        assert!(validate_namespace("../etc").is_err());
+        assert!(validate_namespace("Team").is_err());
+        assert!(validate_namespace(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_namespaces_are_reopened() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let namespaces = Namespaces::open(temp_dir.path(), 1024).unwrap();
+        namespaces.get_or_create("team-a").unwrap();
+        assert!(temp_dir.path().join("ns").join("team-a").is_dir());
+
+        let reopened = Namespaces::open(temp_dir.path(), 1024).unwrap();
+        let names: Vec<String> = reopened.list().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["default".to_string(), "team-a".to_string()]);
+    }
+
+    #[test]
+    fn test_namespace_policy_falls_back_to_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut settings = BTreeMap::new();
+        settings.insert(
+            "team-a".to_string(),
+            NamespaceSettings {
+                quota: Some(500),
+                max_total_size: Some(100),
+                ttl: None,
+            },
+        );
+
+        let namespaces = Namespaces::open(temp_dir.path(), 1024)
+            .unwrap()
+            .with_default_policy(GcPolicy {
+                max_total_size: Some(1000),
+                ttl: Some(Duration::from_secs(60)),
+            })
+            .with_settings(settings);
+
+        let policy = namespaces.policy("team-a");
+        assert_eq!(policy.max_total_size, Some(100));
+        assert_eq!(policy.ttl, Some(Duration::from_secs(60)));
+        assert_eq!(namespaces.policy("other").max_total_size, Some(1000));
+        assert_eq!(namespaces.quota("team-a"), Some(500));
+        assert_eq!(namespaces.quota("other"), None);
+    }
+}
@@ -3,62 +3,278 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware,
     response::{IntoResponse, Response},
-    routing::{get, head, put},
-    Router,
+    routing::{delete, get, head, post, put},
+    Json, Router,
 };
+use polykit_core::remote_cache::{ArtifactFilter, ArtifactList, StoredArtifact, StoredManifest};
+use serde::{Deserialize, Serialize};
+use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::TraceLayer;
+use tracing::{info, warn};
 
-use crate::storage::Storage;
+use crate::auth::{AuthError, Scope, Tokens};
+use crate::limits::{limit_per_client, ClientLimiter, RequestLimits};
+use crate::metrics::{track_requests, Metrics, ServerStats, DEFAULT_NAMESPACE};
+use crate::namespace::{validate_namespace, Namespaces};
+use crate::storage::{Storage, StorageError};
+use crate::upstream::Upstream;
 use crate::verification::Verifier;
 
+/// Maximum number of keys accepted by a single batch query.
+const MAX_QUERY_KEYS: usize = 10_000;
+
+/// Number of artifacts listed when the request sets no limit.
+const DEFAULT_LIST_LIMIT: usize = 1000;
+
 /// Server state shared across handlers.
 #[derive(Clone)]
 pub struct AppState {
-    storage: Arc<Storage>,
+    namespaces: Arc<Namespaces>,
     verifier: Arc<Verifier>,
+    metrics: Arc<Metrics>,
+    tokens: Arc<Tokens>,
+    limits: RequestLimits,
+    upstream: Option<Arc<Upstream>>,
 }
 
 impl AppState {
     /// Creates new app state.
-    pub fn new(storage: Storage, verifier: Verifier) -> Self {
+    pub fn new(namespaces: Namespaces, verifier: Verifier) -> Self {
         Self {
-            storage: Arc::new(storage),
+            namespaces: Arc::new(namespaces),
             verifier: Arc::new(verifier),
+            metrics: Arc::new(Metrics::new()),
+            tokens: Arc::new(Tokens::new()),
+            limits: RequestLimits::default(),
+            upstream: None,
+        }
+    }
+
+    /// Requires requests to present one of these tokens.
+    pub fn with_tokens(mut self, tokens: Tokens) -> Self {
+        self.tokens = Arc::new(tokens);
+        self
+    }
+
+    /// Sets the concurrency and timeout limits.
+    pub fn with_limits(mut self, limits: RequestLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Pulls local misses from `upstream` and replicates uploads to it.
+    pub fn with_upstream(mut self, upstream: Upstream) -> Self {
+        self.upstream = Some(Arc::new(upstream));
+        self
+    }
+
+    /// Returns the shared namespace registry.
+    pub fn namespaces(&self) -> Arc<Namespaces> {
+        Arc::clone(&self.namespaces)
+    }
+
+    /// Returns a statistics snapshot.
+    pub fn stats(&self) -> ServerStats {
+        // Make sure every namespace is reported before it sees any traffic
+        for (name, _) in self.namespaces.list() {
+            self.metrics.namespace(&name);
+        }
+        self.metrics.stats(|name| {
+            self.namespaces
+                .get(name)
+                .map(|storage| storage.usage())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Checks that the request may access `namespace` (or the whole server) with `scope`.
+    fn authorize(
+        &self,
+        headers: &HeaderMap,
+        scope: Scope,
+        namespace: Option<&str>,
+    ) -> Result<(), ServerError> {
+        self.tokens
+            .authorize(headers, scope, namespace)
+            .map_err(|e| match e {
+                AuthError::Unauthenticated => ServerError::Unauthorized,
+                AuthError::Forbidden(msg) => ServerError::Forbidden(msg),
+            })
+    }
+
+    /// Returns the storage of an existing namespace, or `None` if nothing was
+    /// ever uploaded to it.
+    fn storage(&self, namespace: &str) -> Result<Option<Arc<Storage>>, ServerError> {
+        validate_namespace(namespace).map_err(|e| ServerError::BadRequest(e.to_string()))?;
+        Ok(self.namespaces.get(namespace))
+    }
+
+    /// Fetches a locally missing artifact from upstream, verifies it and
+    /// stores it locally.
+    ///
+    /// Returns the compressed data and its hash, or `None` if there is no
+    /// upstream or it does not have a valid artifact.
+    async fn pull_from_upstream(&self, namespace: &str, cache_key: &str) -> Option<(Vec<u8>, String)> {
+        let upstream = self.upstream.as_ref()?;
+        let data = upstream.fetch(namespace, cache_key).await?;
+
+        let (artifact, hash) = match self.verifier.verify_upload(&data, cache_key) {
+            Ok(verified) => verified,
+            Err(e) => {
+                warn!("Rejected artifact {} from upstream: {}", cache_key, e);
+                return None;
+            }
+        };
+
+        // The artifact is served even if it cannot be kept locally
+        match self.namespaces.get_or_create(namespace) {
+            Ok(storage) => {
+                // Held until the artifact is stored so concurrent stores
+                // cannot overrun the quota together
+                let reservation = self
+                    .namespaces
+                    .quota(namespace)
+                    .map(|quota| storage.reserve(data.len() as u64, quota));
+                if matches!(reservation, Some(None)) {
+                    warn!("Not storing {} from upstream, namespace {} is over its quota", cache_key, namespace);
+                } else if let Err(e) = storage
+                    .store_artifact(cache_key, data.clone(), hash.clone(), &artifact)
+                    .await
+                {
+                    // A concurrent request may have stored it first
+                    if !matches!(e, StorageError::AlreadyExists(_)) {
+                        warn!("Failed to store {} from upstream: {}", cache_key, e);
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to open namespace {}: {}", namespace, e),
         }
+
+        self.metrics.namespace(namespace).record_upstream_hit();
+        Some((data, hash))
     }
 }
 
+/// Path parameters of the artifact routes.
+///
+/// The unprefixed `/v1/artifacts` routes have no namespace parameter and use
+/// the default namespace.
+#[derive(Debug, Deserialize)]
+struct ArtifactPath {
+    #[serde(default = "default_namespace")]
+    namespace: String,
+    cache_key: String,
+}
+
+fn default_namespace() -> String {
+    DEFAULT_NAMESPACE.to_string()
+}
+
 /// Creates the HTTP router.
 pub fn create_router(state: AppState) -> Router {
-    Router::new()
+    let metrics = Arc::clone(&state.metrics);
+    let limits = state.limits;
+
+    let mut router = Router::new()
+        .route("/healthz", get(health))
+        .route("/metrics", get(prometheus_metrics))
+        .route("/v1/stats", get(stats))
+        .route("/v1/artifacts", get(list_artifacts))
+        .route("/v1/artifacts/query", post(query_artifacts))
         .route("/v1/artifacts/:cache_key", put(upload_artifact))
         .route("/v1/artifacts/:cache_key", get(download_artifact))
         .route("/v1/artifacts/:cache_key", head(check_artifact))
+        .route("/v1/artifacts/:cache_key", delete(delete_artifact))
+        .route("/v1/artifacts/:cache_key/manifest", get(artifact_manifest))
+        .route("/v1/ns/:namespace/artifacts", get(list_artifacts))
+        .route("/v1/ns/:namespace/artifacts/query", post(query_artifacts))
+        .route("/v1/ns/:namespace/artifacts/:cache_key", put(upload_artifact))
+        .route("/v1/ns/:namespace/artifacts/:cache_key", get(download_artifact))
+        .route("/v1/ns/:namespace/artifacts/:cache_key", head(check_artifact))
+        .route("/v1/ns/:namespace/artifacts/:cache_key", delete(delete_artifact))
+        .route("/v1/ns/:namespace/artifacts/:cache_key/manifest", get(artifact_manifest));
+
+    if let Some(timeout) = limits.request_timeout {
+        router = router.layer(TimeoutLayer::new(timeout));
+    }
+    if let Some(max) = limits.max_concurrent_per_client {
+        let limiter = Arc::new(ClientLimiter::new(max));
+        router = router.layer(middleware::from_fn_with_state(limiter, limit_per_client));
+    }
+
+    router
+        .layer(middleware::from_fn_with_state(metrics, track_requests))
         .layer(TraceLayer::new_for_http())
         .with_state(state)
 }
 
+/// Reports whether the server can serve requests.
+///
+/// GET /healthz
+async fn health(State(state): State<AppState>) -> Response {
+    if state.namespaces.list().iter().all(|(_, storage)| storage.is_healthy()) {
+        (StatusCode::OK, Json(serde_json::json!({ "status": "ok" }))).into_response()
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "status": "unavailable", "reason": "storage directory is not accessible" })),
+        )
+            .into_response()
+    }
+}
+
+/// Returns storage and traffic statistics.
+///
+/// GET /v1/stats
+async fn stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ServerStats>, ServerError> {
+    state.authorize(&headers, Scope::Read, None)?;
+    Ok(Json(state.stats()))
+}
+
+/// Returns metrics in the Prometheus text format.
+///
+/// GET /metrics
+async fn prometheus_metrics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ServerError> {
+    state.authorize(&headers, Scope::Read, None)?;
+    let body = state.metrics.render_prometheus(&state.stats());
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    )
+        .into_response())
+}
+
 /// Uploads an artifact.
 ///
 /// PUT /v1/artifacts/{cache_key}
+/// PUT /v1/ns/{namespace}/artifacts/{cache_key}
 async fn upload_artifact(
     State(state): State<AppState>,
-    Path(cache_key): Path<String>,
+    Path(ArtifactPath { namespace, cache_key }): Path<ArtifactPath>,
+    headers: HeaderMap,
     body: axum::body::Body,
 ) -> Result<Response, ServerError> {
-    // Validate cache key format
-    if !cache_key.chars().all(|c| c.is_ascii_hexdigit()) || cache_key.len() < 32 {
-        return Err(ServerError::BadRequest(format!(
-            "Invalid cache key format: {}",
-            cache_key
-        )));
-    }
+    state.authorize(&headers, Scope::Write, Some(&namespace))?;
+    validate_cache_key(&cache_key)?;
+    validate_namespace(&namespace).map_err(|e| ServerError::BadRequest(e.to_string()))?;
+
+    let storage = state
+        .namespaces
+        .get_or_create(&namespace)
+        .map_err(|e| ServerError::Internal(format!("Failed to open namespace: {}", e)))?;
 
     // Stream body to bytes with size limit
-    let max_size = state.storage.max_artifact_size() as usize;
+    let max_size = storage.max_artifact_size() as usize;
     let bytes = axum::body::to_bytes(body, max_size)
         .await
         .map_err(|e| {
@@ -78,108 +294,372 @@ async fn upload_artifact(
         .verify_upload(&bytes, &cache_key)
         .map_err(|e| ServerError::UnprocessableEntity(e.to_string()))?;
 
+    // Reserve the bytes up front so concurrent uploads cannot overrun the
+    // quota together; the reservation is released once the upload returns
+    let _reservation = match state.namespaces.quota(&namespace) {
+        Some(quota) => Some(storage.reserve(bytes.len() as u64, quota).ok_or_else(|| {
+            ServerError::InsufficientStorage(format!(
+                "Namespace {} is over its quota of {} bytes",
+                namespace, quota
+            ))
+        })?),
+        None => None,
+    };
+
     // Store artifact
-    state
-        .storage
+    storage
         .store_artifact(&cache_key, bytes.to_vec(), hash, &artifact)
         .await
-        .map_err(|e| {
-            if e.to_string().contains("already exists") {
-                ServerError::Conflict(format!("Artifact {} already exists", cache_key))
-            } else {
-                ServerError::Internal(format!("Failed to store artifact: {}", e))
-            }
+        .map_err(|e| match e {
+            StorageError::AlreadyExists(_) => ServerError::Conflict(e.to_string()),
+            e => ServerError::Internal(format!("Failed to store artifact: {}", e)),
         })?;
 
+    state.metrics.namespace(&namespace).record_upload();
+
+    if let Some(ref upstream) = state.upstream {
+        upstream.replicate(namespace, cache_key, &bytes, Arc::clone(&state.metrics));
+    }
+
     Ok(StatusCode::CREATED.into_response())
 }
 
 /// Downloads an artifact.
 ///
 /// GET /v1/artifacts/{cache_key}
+/// GET /v1/ns/{namespace}/artifacts/{cache_key}
 async fn download_artifact(
     State(state): State<AppState>,
-    Path(cache_key): Path<String>,
+    Path(ArtifactPath { namespace, cache_key }): Path<ArtifactPath>,
+    headers: HeaderMap,
 ) -> Result<Response, ServerError> {
-    // Validate cache key format
-    if !cache_key.chars().all(|c| c.is_ascii_hexdigit()) || cache_key.len() < 32 {
-        return Err(ServerError::BadRequest(format!(
-            "Invalid cache key format: {}",
-            cache_key
-        )));
-    }
+    state.authorize(&headers, Scope::Read, Some(&namespace))?;
+    validate_cache_key(&cache_key)?;
 
-    // Check if artifact exists
-    if !state.storage.has_artifact(&cache_key) {
-        return Err(ServerError::NotFound);
-    }
+    let storage = state.storage(&namespace)?;
+    let known_namespace = storage.is_some();
+    let Some(storage) = storage.filter(|storage| storage.has_artifact(&cache_key)) else {
+        let Some((data, hash)) = state.pull_from_upstream(&namespace, &cache_key).await else {
+            // Unknown namespaces have no counters, so arbitrary names cannot grow the metrics
+            if known_namespace {
+                state.metrics.namespace(&namespace).record_miss();
+            }
+            return Err(ServerError::NotFound);
+        };
 
-    // Read artifact
-    let data = state
-        .storage
-        .read_artifact(&cache_key)
-        .await
-        .map_err(|e| ServerError::Internal(format!("Failed to read artifact: {}", e)))?;
+        let counters = state.metrics.namespace(&namespace);
+        counters.record_hit();
+        counters.record_download();
+        return artifact_response(data.len() as u64, &hash, axum::body::Body::from(data));
+    };
+    let counters = state.metrics.namespace(&namespace);
+
+    // Read artifact (it may have been garbage collected since the check above)
+    let data = storage.read_artifact(&cache_key).await.map_err(|e| match e {
+        StorageError::NotFound(_) => {
+            counters.record_miss();
+            ServerError::NotFound
+        }
+        e => ServerError::Internal(format!("Failed to read artifact: {}", e)),
+    })?;
 
     // Read metadata for headers
-    let metadata = state
-        .storage
-        .read_metadata(&cache_key)
-        .await
-        .map_err(|e| ServerError::Internal(format!("Failed to read metadata: {}", e)))?;
+    let metadata = storage.read_metadata(&cache_key).await.map_err(|e| match e {
+        StorageError::NotFound(_) => {
+            counters.record_miss();
+            ServerError::NotFound
+        }
+        e => ServerError::Internal(format!("Failed to read metadata: {}", e)),
+    })?;
+
+    counters.record_hit();
+    counters.record_download();
 
-    // Create response with proper headers
-    let response = Response::builder()
+    artifact_response(data.len() as u64, &metadata.hash, axum::body::Body::from(data))
+}
+
+/// Builds an artifact response with size and hash headers.
+fn artifact_response(
+    size: u64,
+    hash: &str,
+    body: axum::body::Body,
+) -> Result<Response, ServerError> {
+    Response::builder()
         .status(StatusCode::OK)
         .header("Content-Type", "application/zstd")
-        .header("Content-Length", data.len())
-        .header("X-Artifact-Hash", &metadata.hash)
-        .body(axum::body::Body::from(data))
-        .map_err(|e| ServerError::Internal(format!("Failed to create response: {}", e)))?;
-
-    Ok(response)
+        .header("Content-Length", size)
+        .header("X-Artifact-Hash", hash)
+        .body(body)
+        .map_err(|e| ServerError::Internal(format!("Failed to create response: {}", e)))
 }
 
 /// Checks if an artifact exists.
 ///
 /// HEAD /v1/artifacts/{cache_key}
+/// HEAD /v1/ns/{namespace}/artifacts/{cache_key}
 async fn check_artifact(
     State(state): State<AppState>,
-    Path(cache_key): Path<String>,
+    Path(ArtifactPath { namespace, cache_key }): Path<ArtifactPath>,
+    headers: HeaderMap,
 ) -> Result<Response, ServerError> {
-    // Validate cache key format
-    if !cache_key.chars().all(|c| c.is_ascii_hexdigit()) || cache_key.len() < 32 {
+    state.authorize(&headers, Scope::Read, Some(&namespace))?;
+    validate_cache_key(&cache_key)?;
+
+    let storage = state.storage(&namespace)?;
+
+    if let Some(ref storage) = storage {
+        if storage.has_artifact(&cache_key) {
+            // Read metadata for headers
+            if let Ok(metadata) = storage.read_metadata(&cache_key).await {
+                state.metrics.namespace(&namespace).record_hit();
+                return artifact_response(metadata.size, &metadata.hash, axum::body::Body::empty());
+            }
+        }
+    }
+
+    // Pulling the artifact now makes the download that usually follows local
+    if let Some((data, hash)) = state.pull_from_upstream(&namespace, &cache_key).await {
+        state.metrics.namespace(&namespace).record_hit();
+        return artifact_response(data.len() as u64, &hash, axum::body::Body::empty());
+    }
+
+    // Unknown namespaces have no counters, so arbitrary names cannot grow the metrics
+    if storage.is_some() {
+        state.metrics.namespace(&namespace).record_miss();
+    }
+    Err(ServerError::NotFound)
+}
+
+/// Lists stored artifacts, newest first.
+///
+/// GET /v1/artifacts?package=&task=&since=&limit=
+/// GET /v1/ns/{namespace}/artifacts?package=&task=&since=&limit=
+async fn list_artifacts(
+    State(state): State<AppState>,
+    namespace: Option<Path<String>>,
+    Query(filter): Query<ArtifactFilter>,
+    headers: HeaderMap,
+) -> Result<Json<ArtifactList>, ServerError> {
+    let namespace = namespace.map_or_else(default_namespace, |Path(namespace)| namespace);
+    state.authorize(&headers, Scope::Read, Some(&namespace))?;
+
+    let Some(storage) = state.storage(&namespace)? else {
+        return Ok(Json(ArtifactList { artifacts: Vec::new() }));
+    };
+
+    // The walk reads every metadata file, keep it off the async workers
+    let entries = {
+        let filter = filter.clone();
+        tokio::task::spawn_blocking(move || {
+            storage.list_entries_where(|metadata| {
+                metadata.is_some_and(|metadata| filter.matches(metadata.artifact.as_ref()))
+            })
+        })
+        .await
+        .map_err(|e| ServerError::Internal(format!("Failed to list artifacts: {}", e)))?
+        .map_err(|e| ServerError::Internal(format!("Failed to list artifacts: {}", e)))?
+    };
+
+    let mut artifacts: Vec<StoredArtifact> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let metadata = entry.metadata?;
+            Some(StoredArtifact {
+                cache_key: entry.cache_key,
+                size: entry.size,
+                last_accessed: metadata.last_accessed_at(),
+                hash: metadata.hash,
+                metadata: metadata.artifact,
+            })
+        })
+        .collect();
+
+    artifacts.sort_by(|a, b| {
+        let created = |artifact: &StoredArtifact| artifact.metadata.as_ref().map(|m| m.created_at);
+        created(b).cmp(&created(a)).then_with(|| a.cache_key.cmp(&b.cache_key))
+    });
+    artifacts.truncate(filter.limit.unwrap_or(DEFAULT_LIST_LIMIT));
+
+    Ok(Json(ArtifactList { artifacts }))
+}
+
+/// Returns the metadata and file list of an artifact without its payload.
+///
+/// GET /v1/artifacts/{cache_key}/manifest
+/// GET /v1/ns/{namespace}/artifacts/{cache_key}/manifest
+async fn artifact_manifest(
+    State(state): State<AppState>,
+    Path(ArtifactPath { namespace, cache_key }): Path<ArtifactPath>,
+    headers: HeaderMap,
+) -> Result<Json<StoredManifest>, ServerError> {
+    state.authorize(&headers, Scope::Read, Some(&namespace))?;
+    validate_cache_key(&cache_key)?;
+
+    let Some(storage) = state.storage(&namespace)? else {
+        return Err(ServerError::NotFound);
+    };
+
+    let not_found_or = |context: &str, e: StorageError| match e {
+        StorageError::NotFound(_) => ServerError::NotFound,
+        e => ServerError::Internal(format!("Failed to read {}: {}", context, e)),
+    };
+
+    let metadata = storage
+        .read_metadata(&cache_key)
+        .await
+        .map_err(|e| not_found_or("metadata", e))?;
+    let manifest = storage
+        .read_manifest(&cache_key)
+        .await
+        .map_err(|e| not_found_or("manifest", e))?;
+
+    let metadata = match metadata.artifact {
+        Some(metadata) => metadata,
+        // Stored by an older version, recover the metadata from the artifact itself
+        None => {
+            let data = storage
+                .read_artifact(&cache_key)
+                .await
+                .map_err(|e| not_found_or("artifact", e))?;
+            polykit_core::remote_cache::Artifact::from_compressed(data)
+                .map_err(|e| ServerError::Internal(format!("Failed to read artifact: {}", e)))?
+                .metadata()
+                .clone()
+        }
+    };
+
+    Ok(Json(StoredManifest {
+        cache_key,
+        metadata,
+        manifest,
+    }))
+}
+
+/// Deletes an artifact, e.g. to remove a poisoned entry.
+///
+/// DELETE /v1/artifacts/{cache_key}
+/// DELETE /v1/ns/{namespace}/artifacts/{cache_key}
+async fn delete_artifact(
+    State(state): State<AppState>,
+    Path(ArtifactPath { namespace, cache_key }): Path<ArtifactPath>,
+    headers: HeaderMap,
+) -> Result<Response, ServerError> {
+    state.authorize(&headers, Scope::Admin, Some(&namespace))?;
+    validate_cache_key(&cache_key)?;
+
+    let Some(storage) = state.storage(&namespace)? else {
+        return Err(ServerError::NotFound);
+    };
+
+    let deleted = storage
+        .delete_artifact(&cache_key)
+        .await
+        .map_err(|e| ServerError::Internal(format!("Failed to delete artifact: {}", e)))?;
+
+    if !deleted {
+        return Err(ServerError::NotFound);
+    }
+
+    info!("Deleted artifact {} from namespace {}", cache_key, namespace);
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Request body of a batch existence query.
+#[derive(Debug, Deserialize)]
+struct QueryRequest {
+    keys: Vec<String>,
+}
+
+/// Response body of a batch existence query.
+#[derive(Debug, Serialize)]
+struct QueryResponse {
+    found: Vec<String>,
+    missing: Vec<String>,
+}
+
+/// Checks which of several artifacts exist.
+///
+/// POST /v1/artifacts/query
+/// POST /v1/ns/{namespace}/artifacts/query
+async fn query_artifacts(
+    State(state): State<AppState>,
+    namespace: Option<Path<String>>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<Json<QueryResponse>, ServerError> {
+    let namespace = namespace.map_or_else(default_namespace, |Path(namespace)| namespace);
+    state.authorize(&headers, Scope::Read, Some(&namespace))?;
+    let request: QueryRequest = serde_json::from_slice(&body)
+        .map_err(|e| ServerError::BadRequest(format!("Invalid query body: {}", e)))?;
+
+    if request.keys.len() > MAX_QUERY_KEYS {
         return Err(ServerError::BadRequest(format!(
-            "Invalid cache key format: {}",
-            cache_key
+            "Too many keys in query: {} (maximum {})",
+            request.keys.len(),
+            MAX_QUERY_KEYS
         )));
     }
+    for cache_key in &request.keys {
+        validate_cache_key(cache_key)?;
+    }
 
-    if state.storage.has_artifact(&cache_key) {
-        // Read metadata for headers
-        if let Ok(metadata) = state.storage.read_metadata(&cache_key).await {
-            let response = Response::builder()
-                .status(StatusCode::OK)
-                .header("Content-Type", "application/zstd")
-                .header("Content-Length", metadata.size)
-                .header("X-Artifact-Hash", &metadata.hash)
-                .body(axum::body::Body::empty())
-                .map_err(|e| ServerError::Internal(format!("Failed to create response: {}", e)))?;
+    let storage = state.storage(&namespace)?;
+    let (mut found, mut missing): (Vec<String>, Vec<String>) = match storage {
+        Some(ref storage) => request
+            .keys
+            .into_iter()
+            .partition(|cache_key| storage.has_artifact(cache_key)),
+        None => (Vec::new(), request.keys),
+    };
 
-            return Ok(response);
+    // Report upstream hits as found, the download then pulls them through
+    if let Some(ref upstream) = state.upstream {
+        if !missing.is_empty() {
+            let upstream_found = upstream.has_keys(&namespace, &missing).await;
+            let (pulled, still_missing): (Vec<_>, Vec<_>) = missing
+                .into_iter()
+                .zip(upstream_found)
+                .partition(|(_, found)| *found);
+            found.extend(pulled.into_iter().map(|(cache_key, _)| cache_key));
+            missing = still_missing.into_iter().map(|(cache_key, _)| cache_key).collect();
         }
     }
 
-    Err(ServerError::NotFound)
+    // Unknown namespaces have no counters, so arbitrary names cannot grow the metrics
+    if storage.is_some() {
+        let counters = state.metrics.namespace(&namespace);
+        for _ in &found {
+            counters.record_hit();
+        }
+        for _ in &missing {
+            counters.record_miss();
+        }
+    }
+
+    Ok(Json(QueryResponse { found, missing }))
+}
+
+/// Validates the cache key format.
+fn validate_cache_key(cache_key: &str) -> Result<(), ServerError> {
+    if !cache_key.chars().all(|c| c.is_ascii_hexdigit()) || cache_key.len() < 32 {
+        return Err(ServerError::BadRequest(format!(
+            "Invalid cache key format: {}",
+            cache_key
+        )));
+    }
+    Ok(())
 }
 
 /// Server error types.
 #[derive(Debug)]
 pub enum ServerError {
     BadRequest(String),
+    Unauthorized,
+    Forbidden(String),
     Conflict(String),
     PayloadTooLarge(String),
     UnprocessableEntity(String),
+    InsufficientStorage(String),
     NotFound,
     Internal(String),
 }
@@ -188,9 +668,20 @@ impl IntoResponse for ServerError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
             ServerError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            ServerError::Unauthorized => {
+                let body = axum::Json(serde_json::json!({ "error": "Missing or invalid token" }));
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    [(header::WWW_AUTHENTICATE, "Bearer")],
+                    body,
+                )
+                    .into_response();
+            }
+            ServerError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
             ServerError::Conflict(msg) => (StatusCode::CONFLICT, msg),
             ServerError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
             ServerError::UnprocessableEntity(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg),
+            ServerError::InsufficientStorage(msg) => (StatusCode::INSUFFICIENT_STORAGE, msg),
             ServerError::NotFound => (StatusCode::NOT_FOUND, "Artifact not found".to_string()),
             ServerError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
         };
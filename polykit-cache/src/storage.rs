@@ -2,9 +2,54 @@
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use polykit_core::error::{Error, Result};
-use polykit_core::remote_cache::Artifact;
+use polykit_core::remote_cache::{Artifact, ArtifactManifest, ArtifactMetadata};
+use tokio::sync::{RwLock, RwLockWriteGuard};
+
+/// Minimum number of seconds between two access-time updates of the same artifact.
+///
+/// Keeps downloads from rewriting metadata on every request.
+const ACCESS_TIME_RESOLUTION_SECS: u64 = 60;
+
+/// Errors returned by artifact reads and writes.
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    /// The artifact, or the named file of it, does not exist.
+    #[error("{0} not found")]
+    NotFound(String),
+    /// An artifact with the same cache key is already stored.
+    #[error("Artifact {0} already exists")]
+    AlreadyExists(String),
+    #[error(transparent)]
+    Other(#[from] Error),
+}
+
+/// Result of an artifact read or write.
+pub type StorageResult<T> = std::result::Result<T, StorageError>;
+
+impl From<StorageError> for Error {
+    fn from(error: StorageError) -> Self {
+        match error {
+            StorageError::Other(error) => error,
+            error => Error::Adapter {
+                package: "storage".to_string(),
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+/// Maps a failed read of `what` to [`StorageError::NotFound`] if the file is gone.
+fn read_error(what: String, error: std::io::Error) -> StorageError {
+    if error.kind() == std::io::ErrorKind::NotFound {
+        StorageError::NotFound(what)
+    } else {
+        StorageError::Other(Error::Io(error))
+    }
+}
 
 /// Storage metadata for an artifact.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -17,12 +62,70 @@ pub struct StorageMetadata {
     pub created_at: u64,
     /// Cache key hash.
     pub cache_key_hash: String,
+    /// Timestamp when artifact was last downloaded (Unix epoch seconds).
+    ///
+    /// Metadata written before access tracking existed falls back to `created_at`.
+    #[serde(default)]
+    pub last_accessed: u64,
+    /// Metadata of the stored artifact, `None` for artifacts stored by older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<ArtifactMetadata>,
+}
+
+impl StorageMetadata {
+    /// Returns the last access time, falling back to the creation time.
+    pub fn last_accessed_at(&self) -> u64 {
+        self.last_accessed.max(self.created_at)
+    }
+}
+
+/// A stored artifact as seen by a storage listing.
+#[derive(Debug, Clone)]
+pub struct StorageEntry {
+    /// Cache key of the artifact.
+    pub cache_key: String,
+    /// Size of the artifact file on disk in bytes.
+    pub size: u64,
+    /// Stored metadata, or `None` if the metadata file is missing or unreadable.
+    pub metadata: Option<StorageMetadata>,
+}
+
+/// Number and total size of stored artifacts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct StorageUsage {
+    /// Number of stored artifacts.
+    pub artifacts: u64,
+    /// Total size of stored artifacts in bytes.
+    pub bytes: u64,
 }
 
 /// Manages artifact storage with directory sharding.
+///
+/// Uploads and downloads hold a shared lock while touching the filesystem;
+/// garbage collection takes the lock exclusively while it deletes files.
 pub struct Storage {
     storage_root: PathBuf,
     max_artifact_size: u64,
+    lock: RwLock<()>,
+    artifact_count: AtomicU64,
+    total_bytes: AtomicU64,
+    reserved_bytes: AtomicU64,
+}
+
+/// Bytes set aside by [`Storage::reserve`] for an upload in progress.
+///
+/// The reservation is released when dropped, whether or not the upload was
+/// stored.
+#[derive(Debug)]
+pub struct Reservation<'a> {
+    reserved_bytes: &'a AtomicU64,
+    bytes: u64,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        self.reserved_bytes.fetch_sub(self.bytes, Ordering::SeqCst);
+    }
 }
 
 impl Storage {
@@ -39,10 +142,25 @@ impl Storage {
         let tmp_dir = storage_root.join("tmp");
         fs::create_dir_all(&tmp_dir).map_err(Error::Io)?;
 
-        Ok(Self {
+        let mut storage = Self {
             storage_root,
             max_artifact_size,
-        })
+            lock: RwLock::new(()),
+            artifact_count: AtomicU64::new(0),
+            total_bytes: AtomicU64::new(0),
+            reserved_bytes: AtomicU64::new(0),
+        };
+
+        // Seed usage counters from whatever is already on disk
+        let (mut artifacts, mut bytes) = (0, 0);
+        storage.walk_artifacts(|_, size| {
+            artifacts += 1;
+            bytes += size;
+        })?;
+        *storage.artifact_count.get_mut() = artifacts;
+        *storage.total_bytes.get_mut() = bytes;
+
+        Ok(storage)
     }
 
     /// Gets the shard directory path for a cache key.
@@ -72,6 +190,11 @@ impl Storage {
         self.shard_path(cache_key).join(format!("{}.json", cache_key))
     }
 
+    /// Gets the manifest file path for a cache key.
+    fn manifest_path(&self, cache_key: &str) -> PathBuf {
+        self.shard_path(cache_key).join(format!("{}.manifest.json", cache_key))
+    }
+
     /// Checks if an artifact exists.
     pub fn has_artifact(&self, cache_key: &str) -> bool {
         self.artifact_path(cache_key).exists()
@@ -101,13 +224,14 @@ impl Storage {
         data: Vec<u8>,
         hash: String,
         artifact: &Artifact,
-    ) -> Result<()> {
+    ) -> StorageResult<()> {
         // Validate cache key format (should be hex string)
         if !cache_key.chars().all(|c| c.is_ascii_hexdigit()) {
             return Err(Error::Adapter {
                 package: "storage".to_string(),
                 message: format!("Invalid cache key format: {}", cache_key),
-            });
+            }
+            .into());
         }
 
         // Check size limit
@@ -119,15 +243,15 @@ impl Storage {
                     data.len(),
                     self.max_artifact_size
                 ),
-            });
+            }
+            .into());
         }
 
+        let _guard = self.lock.read().await;
+
         // Check if artifact already exists (immutable)
         if self.has_artifact(cache_key) {
-            return Err(Error::Adapter {
-                package: "storage".to_string(),
-                message: format!("Artifact {} already exists", cache_key),
-            });
+            return Err(StorageError::AlreadyExists(cache_key.to_string()));
         }
 
         // Write to temporary file
@@ -138,13 +262,18 @@ impl Storage {
         let shard_dir = self.shard_path(cache_key);
         fs::create_dir_all(&shard_dir).map_err(Error::Io)?;
 
-        // Atomic rename
+        // Publish without replacing, so of concurrent uploads of the same key
+        // only the one that created the file is counted
         let artifact_path = self.artifact_path(cache_key);
-        fs::rename(&temp_path, &artifact_path).map_err(|e| {
-            // Clean up temp file on error
-            let _ = fs::remove_file(&temp_path);
-            Error::Io(e)
-        })?;
+        let published = fs::hard_link(&temp_path, &artifact_path);
+        let _ = fs::remove_file(&temp_path);
+        match published {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                return Err(StorageError::AlreadyExists(cache_key.to_string()));
+            }
+            Err(e) => return Err(Error::Io(e).into()),
+        }
 
         // Write metadata
         let artifact_metadata = artifact.metadata();
@@ -153,17 +282,37 @@ impl Storage {
             size: data.len() as u64,
             created_at: artifact_metadata.created_at,
             cache_key_hash: artifact_metadata.cache_key_hash.clone(),
+            last_accessed: now_secs(),
+            artifact: Some(artifact_metadata.clone()),
         };
 
-        let metadata_json = serde_json::to_string(&storage_metadata).map_err(|e| Error::Adapter {
+        self.artifact_count.fetch_add(1, Ordering::Relaxed);
+        self.total_bytes.fetch_add(data.len() as u64, Ordering::SeqCst);
+
+        // Kept next to the artifact so browsing does not decompress payloads
+        self.write_json(&self.manifest_path(cache_key), artifact.manifest())?;
+        Ok(self.write_metadata(cache_key, &storage_metadata)?)
+    }
+
+    /// Writes artifact metadata atomically via a temporary file.
+    fn write_metadata(&self, cache_key: &str, metadata: &StorageMetadata) -> Result<()> {
+        self.write_json(&self.metadata_path(cache_key), metadata)
+    }
+
+    /// Writes a JSON file atomically via a temporary file.
+    fn write_json(&self, path: &Path, value: &impl serde::Serialize) -> Result<()> {
+        let json = serde_json::to_string(value).map_err(|e| Error::Adapter {
             package: "storage".to_string(),
-            message: format!("Failed to serialize metadata: {}", e),
+            message: format!("Failed to serialize {}: {}", path.display(), e),
         })?;
 
-        let metadata_path = self.metadata_path(cache_key);
-        fs::write(&metadata_path, metadata_json).map_err(Error::Io)?;
+        let temp_path = self.temp_path();
+        fs::write(&temp_path, json).map_err(Error::Io)?;
 
-        Ok(())
+        fs::rename(&temp_path, path).map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            Error::Io(e)
+        })
     }
 
     /// Reads an artifact.
@@ -171,17 +320,29 @@ impl Storage {
     /// # Errors
     ///
     /// Returns an error if the artifact doesn't exist or cannot be read.
-    pub async fn read_artifact(&self, cache_key: &str) -> Result<Vec<u8>> {
-        let artifact_path = self.artifact_path(cache_key);
+    pub async fn read_artifact(&self, cache_key: &str) -> StorageResult<Vec<u8>> {
+        let _guard = self.lock.read().await;
+        let data = tokio::fs::read(self.artifact_path(cache_key))
+            .await
+            .map_err(|e| read_error(format!("Artifact {}", cache_key), e))?;
 
-        if !artifact_path.exists() {
-            return Err(Error::Adapter {
-                package: "storage".to_string(),
-                message: format!("Artifact {} not found", cache_key),
-            });
+        // Access tracking is best-effort and must never fail a download
+        let _ = self.touch(cache_key).await;
+
+        Ok(data)
+    }
+
+    /// Records a download of an artifact for TTL and LRU eviction.
+    async fn touch(&self, cache_key: &str) -> StorageResult<()> {
+        let mut metadata = self.read_metadata_unlocked(cache_key).await?;
+        let now = now_secs();
+
+        if now.saturating_sub(metadata.last_accessed) < ACCESS_TIME_RESOLUTION_SECS {
+            return Ok(());
         }
 
-        tokio::fs::read(&artifact_path).await.map_err(Error::Io)
+        metadata.last_accessed = now;
+        Ok(self.write_metadata(cache_key, &metadata)?)
     }
 
     /// Reads artifact metadata.
@@ -189,24 +350,64 @@ impl Storage {
     /// # Errors
     ///
     /// Returns an error if metadata doesn't exist or cannot be read.
-    pub async fn read_metadata(&self, cache_key: &str) -> Result<StorageMetadata> {
-        let metadata_path = self.metadata_path(cache_key);
+    pub async fn read_metadata(&self, cache_key: &str) -> StorageResult<StorageMetadata> {
+        let _guard = self.lock.read().await;
+        self.read_metadata_unlocked(cache_key).await
+    }
 
-        if !metadata_path.exists() {
-            return Err(Error::Adapter {
+    async fn read_metadata_unlocked(
+        &self,
+        cache_key: &str,
+    ) -> StorageResult<StorageMetadata> {
+        let content = tokio::fs::read_to_string(self.metadata_path(cache_key))
+            .await
+            .map_err(|e| read_error(format!("Metadata for {}", cache_key), e))?;
+
+        serde_json::from_str(&content).map_err(|e| {
+            StorageError::Other(Error::Adapter {
                 package: "storage".to_string(),
-                message: format!("Metadata for {} not found", cache_key),
-            });
+                message: format!("Failed to parse metadata: {}", e),
+            })
+        })
+    }
+
+    /// Reads the file manifest of an artifact.
+    ///
+    /// Artifacts stored before manifests were kept separately are decompressed
+    /// to recover it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the artifact doesn't exist or cannot be read.
+    pub async fn read_manifest(&self, cache_key: &str) -> StorageResult<ArtifactManifest> {
+        let _guard = self.lock.read().await;
+
+        if let Ok(content) = tokio::fs::read_to_string(self.manifest_path(cache_key)).await {
+            if let Ok(manifest) = serde_json::from_str(&content) {
+                return Ok(manifest);
+            }
         }
 
-        let content = tokio::fs::read_to_string(&metadata_path)
+        let data = tokio::fs::read(self.artifact_path(cache_key))
             .await
-            .map_err(Error::Io)?;
+            .map_err(|e| read_error(format!("Artifact {}", cache_key), e))?;
+        Ok(Artifact::from_compressed(data)?.manifest().clone())
+    }
 
-        serde_json::from_str(&content).map_err(|e| Error::Adapter {
-            package: "storage".to_string(),
-            message: format!("Failed to parse metadata: {}", e),
-        })
+    /// Deletes an artifact, waiting for in-flight uploads and downloads.
+    ///
+    /// Returns `false` if the artifact did not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files exist but cannot be removed.
+    pub async fn delete_artifact(&self, cache_key: &str) -> Result<bool> {
+        let _guard = self.lock_exclusive().await;
+        if !self.has_artifact(cache_key) {
+            return Ok(false);
+        }
+        self.remove_artifact(cache_key)?;
+        Ok(true)
     }
 
     /// Returns the maximum artifact size.
@@ -214,6 +415,170 @@ impl Storage {
         self.max_artifact_size
     }
 
+    /// Lists all stored artifacts.
+    ///
+    /// Walks the shard directories without taking the storage lock, so the
+    /// result is a snapshot that may be stale by the time it is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a shard directory cannot be read.
+    pub fn list_entries(&self) -> Result<Vec<StorageEntry>> {
+        self.list_entries_where(|_| true)
+    }
+
+    /// Lists the stored artifacts whose metadata passes `keep`.
+    ///
+    /// Each metadata file is read and filtered during the walk, so entries
+    /// that do not pass are never collected. Like [`Storage::list_entries`],
+    /// this blocks on the filesystem.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a shard directory cannot be read.
+    pub fn list_entries_where(
+        &self,
+        keep: impl Fn(Option<&StorageMetadata>) -> bool,
+    ) -> Result<Vec<StorageEntry>> {
+        let mut entries = Vec::new();
+        self.walk_artifacts(|cache_key, size| {
+            let metadata = self.read_metadata_sync(&cache_key);
+            if keep(metadata.as_ref()) {
+                entries.push(StorageEntry {
+                    cache_key,
+                    size,
+                    metadata,
+                });
+            }
+        })?;
+        Ok(entries)
+    }
+
+    /// Walks the shard directories, calling `visit` with each artifact's
+    /// cache key and size.
+    fn walk_artifacts(&self, mut visit: impl FnMut(String, u64)) -> Result<()> {
+        for shard1 in fs::read_dir(&self.storage_root).map_err(Error::Io)? {
+            let shard1 = shard1.map_err(Error::Io)?.path();
+            // Skips `tmp` and the `ns` directory holding other namespaces
+            let is_shard = shard1
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.len() == 2 && n.chars().all(|c| c.is_ascii_hexdigit()));
+            if !shard1.is_dir() || !is_shard {
+                continue;
+            }
+
+            for shard2 in fs::read_dir(&shard1).map_err(Error::Io)? {
+                let shard2 = shard2.map_err(Error::Io)?.path();
+                if !shard2.is_dir() {
+                    continue;
+                }
+
+                for file in fs::read_dir(&shard2).map_err(Error::Io)? {
+                    let path = file.map_err(Error::Io)?.path();
+                    if path.extension().and_then(|s| s.to_str()) != Some("zst") {
+                        continue;
+                    }
+                    let Some(cache_key) = path.file_stem().and_then(|s| s.to_str()) else {
+                        continue;
+                    };
+
+                    // The artifact may have been removed since the directory was read
+                    let Ok(file_metadata) = fs::metadata(&path) else {
+                        continue;
+                    };
+
+                    visit(cache_key.to_string(), file_metadata.len());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number and total size of stored artifacts.
+    pub fn usage(&self) -> StorageUsage {
+        StorageUsage {
+            artifacts: self.artifact_count.load(Ordering::Relaxed),
+            bytes: self.total_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reserves `bytes` for an upload if stored and reserved bytes stay
+    /// within `quota`.
+    ///
+    /// Concurrent uploads each reserve their size before writing, so they
+    /// cannot overrun the quota together. Hold the reservation until
+    /// [`Storage::store_artifact`] returns. Returns `None` if the quota would
+    /// be exceeded.
+    pub fn reserve(&self, bytes: u64, quota: u64) -> Option<Reservation<'_>> {
+        self.reserved_bytes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |reserved| {
+                // Stored bytes are read after reserved bytes: an upload adds
+                // to the stored total before releasing its reservation, so
+                // it is never missed from both.
+                let stored = self.total_bytes.load(Ordering::SeqCst);
+                let reserved_after = reserved.checked_add(bytes)?;
+                (stored.saturating_add(reserved_after) <= quota).then_some(reserved_after)
+            })
+            .ok()?;
+
+        Some(Reservation {
+            reserved_bytes: &self.reserved_bytes,
+            bytes,
+        })
+    }
+
+    /// Checks that the storage directory is still accessible.
+    pub fn is_healthy(&self) -> bool {
+        self.storage_root.join("tmp").is_dir()
+    }
+
+    /// Reads artifact metadata synchronously, returning `None` if it is
+    /// missing or cannot be parsed.
+    pub fn read_metadata_sync(&self, cache_key: &str) -> Option<StorageMetadata> {
+        let content = fs::read_to_string(self.metadata_path(cache_key)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Takes the storage lock exclusively, waiting for in-flight uploads and
+    /// downloads to finish.
+    pub async fn lock_exclusive(&self) -> RwLockWriteGuard<'_, ()> {
+        self.lock.write().await
+    }
+
+    /// Removes an artifact and its metadata, returning the number of bytes freed.
+    ///
+    /// Callers must hold the guard returned by [`Storage::lock_exclusive`] so
+    /// that no upload or download observes a half-removed artifact.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files exist but cannot be removed.
+    pub fn remove_artifact(&self, cache_key: &str) -> Result<u64> {
+        let artifact_path = self.artifact_path(cache_key);
+        let Ok(file_metadata) = fs::metadata(&artifact_path) else {
+            return Ok(0);
+        };
+
+        for path in [
+            artifact_path,
+            self.metadata_path(cache_key),
+            self.manifest_path(cache_key),
+        ] {
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(Error::Io(e)),
+            }
+        }
+
+        self.artifact_count.fetch_sub(1, Ordering::Relaxed);
+        self.total_bytes.fetch_sub(file_metadata.len(), Ordering::Relaxed);
+
+        Ok(file_metadata.len())
+    }
+
     /// Cleans up temporary files older than the specified duration.
     ///
     /// This should be called periodically to clean up failed uploads.
@@ -238,6 +603,14 @@ impl Storage {
     }
 }
 
+/// Returns the current time as Unix epoch seconds.
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
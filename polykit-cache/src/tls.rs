@@ -0,0 +1,78 @@
+//! TLS termination with certificate reloading.
+
+use std::path::{Path, PathBuf};
+
+use axum_server::tls_rustls::RustlsConfig;
+use polykit_core::error::{Error, Result};
+use tracing::{info, warn};
+
+/// Loads the certificate chain and private key from PEM files.
+///
+/// # Errors
+///
+/// Returns an error if either file cannot be read or parsed.
+pub async fn load_tls_config(cert: &Path, key: &Path) -> Result<RustlsConfig> {
+    // Several crates may enable rustls providers, so pick one explicitly
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    RustlsConfig::from_pem_file(cert, key)
+        .await
+        .map_err(|e| Error::Adapter {
+            package: "tls".to_string(),
+            message: format!(
+                "Failed to load TLS certificate {} and key {}: {}",
+                cert.display(),
+                key.display(),
+                e
+            ),
+        })
+}
+
+/// Spawns a task that reloads the certificate and key on SIGHUP.
+///
+/// A failed reload is logged and the previous certificate stays in use.
+#[cfg(unix)]
+pub fn spawn_reload_on_sighup(
+    config: RustlsConfig,
+    cert: PathBuf,
+    key: PathBuf,
+) -> Result<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup()).map_err(Error::Io)?;
+
+    Ok(tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            match config.reload_from_pem_file(&cert, &key).await {
+                Ok(()) => info!("Reloaded TLS certificate from {}", cert.display()),
+                Err(e) => warn!("Failed to reload TLS certificate, keeping the previous one: {}", e),
+            }
+        }
+    }))
+}
+
+/// Certificates are not reloaded on platforms without SIGHUP.
+#[cfg(not(unix))]
+pub fn spawn_reload_on_sighup(
+    _config: RustlsConfig,
+    _cert: PathBuf,
+    _key: PathBuf,
+) -> Result<tokio::task::JoinHandle<()>> {
+    Ok(tokio::spawn(async {}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_load_tls_config_reports_missing_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let cert = temp_dir.path().join("cert.pem");
+        let key = temp_dir.path().join("key.pem");
+
+        let err = load_tls_config(&cert, &key).await.unwrap_err();
+        assert!(err.to_string().contains("cert.pem"));
+    }
+}
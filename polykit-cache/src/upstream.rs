@@ -0,0 +1,256 @@
+//! Pull-through proxying to an upstream cache server.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use polykit_core::error::{Error, Result};
+use polykit_core::remote_cache::{HttpBackend, RemoteCacheConfig};
+use serde::Deserialize;
+use tokio::sync::Semaphore;
+use tracing::{debug, warn};
+
+use crate::metrics::{Metrics, DEFAULT_NAMESPACE};
+
+/// Maximum number of uploads replicated upstream at the same time.
+const REPLICATION_CONCURRENCY: usize = 8;
+
+/// Maximum number of uploads held in memory for replication, including
+/// those being replicated. Further uploads are not replicated.
+const REPLICATION_BACKLOG: usize = 64;
+
+/// Upstream settings in the server configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpstreamConfig {
+    /// Base URL of the upstream cache server.
+    pub url: String,
+    /// Token sent to the upstream server. At most one of `token` and
+    /// `token_env` may be set.
+    pub token: Option<String>,
+    /// Environment variable holding the upstream token.
+    pub token_env: Option<String>,
+    /// Only pull from upstream, never replicate uploads to it.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// Client for the upstream server, with one `HttpBackend` per namespace.
+///
+/// Local namespaces map to the upstream namespace of the same name.
+pub struct Upstream {
+    config: RemoteCacheConfig,
+    read_only: bool,
+    backends: Mutex<HashMap<String, Arc<HttpBackend>>>,
+    replication: Arc<Semaphore>,
+    backlog: Arc<Semaphore>,
+}
+
+impl Upstream {
+    /// Creates an upstream client from the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is not HTTP(S), both token sources are
+    /// set, or the token environment variable is unset.
+    pub fn from_config(config: &UpstreamConfig) -> Result<Self> {
+        let mut remote = RemoteCacheConfig::new(&config.url);
+        if !remote.is_http() {
+            return Err(Error::Adapter {
+                package: "upstream".to_string(),
+                message: format!("Upstream URL must be http(s), got '{}'", config.url),
+            });
+        }
+
+        let token = match (&config.token, &config.token_env) {
+            (Some(_), Some(_)) => {
+                return Err(Error::Adapter {
+                    package: "upstream".to_string(),
+                    message: "Upstream needs at most one of 'token' or 'token_env'".to_string(),
+                })
+            }
+            (Some(token), None) => Some(token.clone()),
+            (None, Some(var)) => Some(std::env::var(var).map_err(|_| Error::Adapter {
+                package: "upstream".to_string(),
+                message: format!("Upstream token environment variable {} is not set", var),
+            })?),
+            (None, None) => None,
+        };
+        if let Some(token) = token {
+            remote = remote.with_token(token);
+        }
+
+        Ok(Self {
+            config: remote,
+            read_only: config.read_only,
+            backends: Mutex::new(HashMap::new()),
+            replication: Arc::new(Semaphore::new(REPLICATION_CONCURRENCY)),
+            backlog: Arc::new(Semaphore::new(REPLICATION_BACKLOG)),
+        })
+    }
+
+    /// Returns the upstream base URL.
+    pub fn url(&self) -> &str {
+        &self.config.url
+    }
+
+    /// Returns `true` if uploads are replicated upstream.
+    pub fn replicates(&self) -> bool {
+        !self.read_only
+    }
+
+    fn backend(&self, namespace: &str) -> Result<Arc<HttpBackend>> {
+        let mut backends = self.backends.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(backend) = backends.get(namespace) {
+            return Ok(Arc::clone(backend));
+        }
+
+        let config = if namespace == DEFAULT_NAMESPACE {
+            self.config.clone()
+        } else {
+            self.config.clone().with_namespace(namespace)
+        };
+        let backend = Arc::new(HttpBackend::new(&config)?);
+        backends.insert(namespace.to_string(), Arc::clone(&backend));
+        Ok(backend)
+    }
+
+    /// Downloads an artifact from upstream.
+    ///
+    /// Upstream errors are logged and reported as a miss, so an unreachable
+    /// upstream degrades to a plain local cache.
+    pub async fn fetch(&self, namespace: &str, cache_key: &str) -> Option<Vec<u8>> {
+        let result = match self.backend(namespace) {
+            Ok(backend) => backend.fetch_compressed(cache_key).await,
+            Err(e) => Err(e),
+        };
+
+        result.unwrap_or_else(|e| {
+            warn!("Upstream fetch of {} failed: {}", cache_key, e);
+            None
+        })
+    }
+
+    /// Checks which artifacts exist upstream, treating errors as misses.
+    pub async fn has_keys(&self, namespace: &str, cache_keys: &[String]) -> Vec<bool> {
+        let result = match self.backend(namespace) {
+            Ok(backend) => backend.has_keys(cache_keys).await,
+            Err(e) => Err(e),
+        };
+
+        result.unwrap_or_else(|e| {
+            warn!("Upstream query failed: {}", e);
+            vec![false; cache_keys.len()]
+        })
+    }
+
+    /// Replicates an uploaded artifact upstream in the background.
+    ///
+    /// Replication is best effort: failures are logged and counted, and
+    /// uploads still queued at shutdown are not replicated. When
+    /// `REPLICATION_BACKLOG` uploads are already pending, the upload is
+    /// dropped without copying it and counted as such.
+    pub fn replicate(
+        self: &Arc<Self>,
+        namespace: String,
+        cache_key: String,
+        data: &[u8],
+        metrics: Arc<Metrics>,
+    ) {
+        if self.read_only {
+            return;
+        }
+
+        let counters = metrics.namespace(&namespace);
+        let Ok(pending) = Arc::clone(&self.backlog).try_acquire_owned() else {
+            warn!(
+                "Replication backlog is full, not replicating {} to upstream",
+                cache_key
+            );
+            counters.record_replication_dropped();
+            return;
+        };
+
+        let data = data.to_vec();
+        let upstream = Arc::clone(self);
+        tokio::spawn(async move {
+            let _pending = pending;
+            let Ok(_permit) = upstream.replication.acquire().await else {
+                return;
+            };
+
+            let result = async {
+                let backend = upstream.backend(&namespace)?;
+                // Other proxies may have replicated the same artifact already
+                if backend.has_key(&cache_key).await? {
+                    return Ok(false);
+                }
+                backend.upload_compressed(&cache_key, &data).await?;
+                Ok::<_, Error>(true)
+            }
+            .await;
+
+            match result {
+                Ok(true) => {
+                    debug!("Replicated {} to upstream", cache_key);
+                    counters.record_replication();
+                }
+                Ok(false) => debug!("{} already exists upstream", cache_key),
+                Err(e) => {
+                    warn!("Failed to replicate {} to upstream: {}", cache_key, e);
+                    counters.record_replication_failure();
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(url: &str) -> UpstreamConfig {
+        UpstreamConfig {
+            url: url.to_string(),
+            token: None,
+            token_env: None,
+            read_only: false,
+        }
+    }
+
+    #[test]
+    fn test_upstream_requires_http_url() {
+        assert!(Upstream::from_config(&config("/mnt/cache")).is_err());
+
+        let upstream = Upstream::from_config(&config("https://cache.example.com")).unwrap();
+        assert_eq!(upstream.url(), "https://cache.example.com");
+        assert!(upstream.replicates());
+    }
+
+    #[test]
+    fn test_upstream_token_env_must_be_set() {
+        let mut config = config("https://cache.example.com");
+        config.token_env = Some("POLYKIT_CACHE_TEST_UNSET_UPSTREAM_TOKEN".to_string());
+        assert!(Upstream::from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replicate_drops_uploads_when_backlog_is_full() {
+        let upstream = Arc::new(Upstream::from_config(&config("http://127.0.0.1:9")).unwrap());
+        let _backlog = Arc::clone(&upstream.backlog)
+            .try_acquire_many_owned(REPLICATION_BACKLOG as u32)
+            .unwrap();
+        let metrics = Arc::new(Metrics::new());
+
+        upstream.replicate(
+            DEFAULT_NAMESPACE.to_string(),
+            "aabbccdd".to_string(),
+            b"artifact",
+            Arc::clone(&metrics),
+        );
+
+        let stats = metrics.stats(|_| Default::default());
+        let counters = &stats.namespaces[DEFAULT_NAMESPACE];
+        assert_eq!(counters.replications_dropped, 1);
+        assert_eq!(counters.replication_failures, 0);
+    }
+}
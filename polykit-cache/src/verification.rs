@@ -1,12 +1,15 @@
 //! Integrity verification for uploaded artifacts.
 
 use polykit_core::error::{Error, Result};
-use polykit_core::remote_cache::Artifact;
+use polykit_core::remote_cache::{Artifact, Dictionaries, TrustedKeys};
 use sha2::{Digest, Sha256};
 
 /// Verifies an uploaded artifact before storage.
 pub struct Verifier {
     max_artifact_size: u64,
+    trusted_keys: TrustedKeys,
+    require_signatures: bool,
+    dictionaries: Dictionaries,
 }
 
 impl Verifier {
@@ -14,9 +17,33 @@ impl Verifier {
     pub fn new(max_artifact_size: u64) -> Self {
         Self {
             max_artifact_size,
+            trusted_keys: TrustedKeys::new(),
+            require_signatures: false,
+            dictionaries: Dictionaries::new(),
         }
     }
 
+    /// Sets the keys whose signatures are accepted.
+    ///
+    /// Signed uploads are rejected unless the signature verifies against
+    /// one of these keys.
+    pub fn with_trusted_keys(mut self, keys: TrustedKeys) -> Self {
+        self.trusted_keys = keys;
+        self
+    }
+
+    /// Sets whether unsigned uploads are rejected.
+    pub fn with_require_signatures(mut self, require: bool) -> Self {
+        self.require_signatures = require;
+        self
+    }
+
+    /// Sets the dictionaries needed to read uploads compressed with them.
+    pub fn with_dictionaries(mut self, dictionaries: Dictionaries) -> Self {
+        self.dictionaries = dictionaries;
+        self
+    }
+
     /// Verifies an uploaded artifact.
     ///
     /// # Arguments
@@ -54,7 +81,7 @@ impl Verifier {
         let computed_hash = format!("{:x}", hasher.finalize());
 
         // Parse artifact
-        let artifact = Artifact::from_compressed(data.to_vec())?;
+        let artifact = Artifact::from_compressed_with(data.to_vec(), &self.dictionaries)?;
 
         // Verify artifact integrity
         polykit_core::remote_cache::ArtifactVerifier::verify(&artifact, Some(&computed_hash))?;
@@ -71,6 +98,13 @@ impl Verifier {
             });
         }
 
+        // Verify signature
+        if self.require_signatures
+            || (artifact.signature().is_some() && !self.trusted_keys.is_empty())
+        {
+            self.trusted_keys.verify(&artifact)?;
+        }
+
         // Verify manifest integrity (already done by ArtifactVerifier, but double-check)
         let manifest = artifact.manifest();
         if manifest.total_size == 0 && !manifest.files.is_empty() {
@@ -138,6 +172,82 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_verify_rejects_unsigned_when_required() {
+        use polykit_core::remote_cache::SigningKey;
+
+        let key = SigningKey::hmac("ci", b"secret");
+        let mut trusted = TrustedKeys::new();
+        trusted.add(key.trusted_key());
+        let verifier = Verifier::new(1024 * 1024)
+            .with_trusted_keys(trusted)
+            .with_require_signatures(true);
+
+        let mut output_files = BTreeMap::new();
+        output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+
+        let cache_key = "aabbccdd11223344556677889900aabbccddeeff";
+        let artifact = Artifact::new(
+            "test".to_string(),
+            "build".to_string(),
+            "echo".to_string(),
+            cache_key.to_string(),
+            output_files,
+        )
+        .unwrap();
+
+        assert!(verifier
+            .verify_upload(artifact.compressed_data(), cache_key)
+            .is_err());
+
+        let signed = artifact.signed(&key).unwrap();
+        assert!(verifier
+            .verify_upload(signed.compressed_data(), cache_key)
+            .is_ok());
+
+        let forged = artifact.signed(&SigningKey::hmac("ci", b"guess")).unwrap();
+        assert!(verifier
+            .verify_upload(forged.compressed_data(), cache_key)
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_needs_dictionary() {
+        use polykit_core::remote_cache::{Compressor, Dictionaries, Dictionary};
+
+        let samples: Vec<Vec<u8>> = (0..200)
+            .map(|i| format!("{{\"file\":\"dist/chunk-{}.js\",\"size\":{}}}", i, i * 31).into_bytes())
+            .collect();
+        let dictionary = Dictionary::train(&samples, 4096).unwrap();
+
+        let mut output_files = BTreeMap::new();
+        output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+
+        let cache_key = "aabbccdd11223344556677889900aabbccddeeff";
+        let artifact = Artifact::new(
+            "test".to_string(),
+            "build".to_string(),
+            "echo".to_string(),
+            cache_key.to_string(),
+            output_files,
+        )
+        .unwrap()
+        .compressed_with(&Compressor::default().with_dictionary(dictionary.clone()))
+        .unwrap();
+
+        let verifier = Verifier::new(1024 * 1024);
+        assert!(verifier
+            .verify_upload(artifact.compressed_data(), cache_key)
+            .is_err());
+
+        let mut dictionaries = Dictionaries::new();
+        dictionaries.add(dictionary);
+        let verifier = Verifier::new(1024 * 1024).with_dictionaries(dictionaries);
+        assert!(verifier
+            .verify_upload(artifact.compressed_data(), cache_key)
+            .is_ok());
+    }
+
     #[test]
     fn test_verify_size_limit() {
         let verifier = Verifier::new(100); // Very small limit
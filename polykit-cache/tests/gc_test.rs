@@ -0,0 +1,138 @@
+//! Tests for garbage collection and retention.
+
+use polykit_cache::gc::{collect_garbage, GcPolicy};
+use polykit_cache::storage::Storage;
+use polykit_core::remote_cache::Artifact;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+async fn store(storage: &Storage, cache_key: &str, size: usize) {
+    let mut output_files = BTreeMap::new();
+    output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+
+    let artifact = Artifact::new(
+        "test".to_string(),
+        "build".to_string(),
+        "echo".to_string(),
+        cache_key.to_string(),
+        output_files,
+    )
+    .unwrap();
+
+    storage
+        .store_artifact(cache_key, vec![0u8; size], "hash".to_string(), &artifact)
+        .await
+        .unwrap();
+}
+
+fn metadata_path(root: &Path, cache_key: &str) -> PathBuf {
+    root.join(&cache_key[..2])
+        .join(&cache_key[2..4])
+        .join(format!("{}.json", cache_key))
+}
+
+fn set_last_accessed(root: &Path, cache_key: &str, timestamp: u64) {
+    let path = metadata_path(root, cache_key);
+    let mut metadata: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    metadata["created_at"] = timestamp.into();
+    metadata["last_accessed"] = timestamp.into();
+    std::fs::write(&path, metadata.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn test_gc_evicts_expired_artifacts() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = Storage::new(temp_dir.path(), 1024 * 1024).unwrap();
+
+    let old_key = "aabbccdd11223344556677889900aabbccddee01";
+    let fresh_key = "aabbccdd11223344556677889900aabbccddee02";
+    store(&storage, old_key, 100).await;
+    store(&storage, fresh_key, 100).await;
+    set_last_accessed(temp_dir.path(), old_key, 1_000);
+
+    let policy = GcPolicy {
+        max_total_size: None,
+        ttl: Some(Duration::from_secs(24 * 3600)),
+    };
+    let report = collect_garbage(&storage, &policy).await.unwrap();
+
+    assert_eq!(report.scanned, 2);
+    assert_eq!(report.expired, 1);
+    assert_eq!(report.bytes_freed, 100);
+    assert!(!storage.has_artifact(old_key));
+    assert!(storage.has_artifact(fresh_key));
+}
+
+#[tokio::test]
+async fn test_gc_evicts_least_recently_used_over_size_limit() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = Storage::new(temp_dir.path(), 1024 * 1024).unwrap();
+
+    let keys = [
+        "aabbccdd11223344556677889900aabbccddee01",
+        "aabbccdd11223344556677889900aabbccddee02",
+        "aabbccdd11223344556677889900aabbccddee03",
+    ];
+    for key in &keys {
+        store(&storage, key, 100).await;
+    }
+    // Oldest access first: keys[1], keys[0], keys[2]
+    set_last_accessed(temp_dir.path(), keys[0], 2_000);
+    set_last_accessed(temp_dir.path(), keys[1], 1_000);
+    set_last_accessed(temp_dir.path(), keys[2], 3_000);
+
+    let policy = GcPolicy {
+        max_total_size: Some(150),
+        ttl: None,
+    };
+    let report = collect_garbage(&storage, &policy).await.unwrap();
+
+    assert_eq!(report.evicted, 2);
+    assert_eq!(report.bytes_remaining, 100);
+    assert!(!storage.has_artifact(keys[0]));
+    assert!(!storage.has_artifact(keys[1]));
+    assert!(storage.has_artifact(keys[2]));
+}
+
+#[tokio::test]
+async fn test_gc_removes_orphaned_artifacts() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = Storage::new(temp_dir.path(), 1024 * 1024).unwrap();
+
+    let cache_key = "aabbccdd11223344556677889900aabbccddee01";
+    store(&storage, cache_key, 100).await;
+    std::fs::remove_file(metadata_path(temp_dir.path(), cache_key)).unwrap();
+
+    let policy = GcPolicy {
+        max_total_size: Some(u64::MAX),
+        ttl: None,
+    };
+    let report = collect_garbage(&storage, &policy).await.unwrap();
+
+    assert_eq!(report.orphaned, 1);
+    assert!(!storage.has_artifact(cache_key));
+}
+
+#[tokio::test]
+async fn test_download_refreshes_last_access() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = Storage::new(temp_dir.path(), 1024 * 1024).unwrap();
+
+    let cache_key = "aabbccdd11223344556677889900aabbccddee01";
+    store(&storage, cache_key, 100).await;
+    set_last_accessed(temp_dir.path(), cache_key, 1_000);
+
+    storage.read_artifact(cache_key).await.unwrap();
+
+    let policy = GcPolicy {
+        max_total_size: None,
+        ttl: Some(Duration::from_secs(24 * 3600)),
+    };
+    let report = collect_garbage(&storage, &policy).await.unwrap();
+
+    assert_eq!(report.removed(), 0);
+    assert!(storage.has_artifact(cache_key));
+}
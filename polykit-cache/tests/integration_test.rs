@@ -1,7 +1,7 @@
 //! End-to-end integration tests for the cache server.
 
+use polykit_cache::namespace::{NamespaceSettings, Namespaces};
 use polykit_cache::server::{create_router, AppState};
-use polykit_cache::storage::Storage;
 use polykit_cache::verification::Verifier;
 use polykit_core::remote_cache::{Artifact, CacheKey, HttpBackend, RemoteCacheBackend, RemoteCacheConfig};
 use std::collections::BTreeMap;
@@ -12,9 +12,13 @@ use tokio::task;
 use tokio::time::Duration;
 
 async fn start_test_server(temp_dir: &TempDir) -> String {
-    let storage = Storage::new(temp_dir.path(), 1024 * 1024 * 1024).unwrap();
+    let namespaces = Namespaces::open(temp_dir.path(), 1024 * 1024 * 1024).unwrap();
+    start_server_with(namespaces).await
+}
+
+async fn start_server_with(namespaces: Namespaces) -> String {
     let verifier = Verifier::new(1024 * 1024 * 1024);
-    let state = AppState::new(storage, verifier);
+    let state = AppState::new(namespaces, verifier);
     let app = create_router(state);
 
     // Find an available port
@@ -223,3 +227,430 @@ async fn test_e2e_not_found() {
     // HEAD should return false
     assert!(!backend.has_artifact(&cache_key).await.unwrap());
 }
+
+#[tokio::test]
+async fn test_e2e_healthz() {
+    let temp_dir = TempDir::new().unwrap();
+    let server_url = start_test_server(&temp_dir).await;
+
+    let response = reqwest::get(format!("{}/healthz", server_url)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let body: serde_json::Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(body["status"], "ok");
+}
+
+#[tokio::test]
+async fn test_e2e_stats_and_metrics() {
+    let temp_dir = TempDir::new().unwrap();
+    let server_url = start_test_server(&temp_dir).await;
+
+    let cache_key = CacheKey::builder()
+        .package_id("stats-package")
+        .task_name("build")
+        .command("echo")
+        .dependency_graph_hash("abc")
+        .toolchain_version("node-v20")
+        .build()
+        .unwrap();
+
+    let mut output_files = BTreeMap::new();
+    output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+    let artifact = Artifact::new(
+        "stats-package".to_string(),
+        "build".to_string(),
+        "echo".to_string(),
+        cache_key.as_string(),
+        output_files,
+    )
+    .unwrap();
+
+    let config = RemoteCacheConfig::new(&server_url);
+    let backend = HttpBackend::new(&config).unwrap();
+
+    let missing_key = CacheKey::builder()
+        .package_id("missing")
+        .task_name("build")
+        .command("echo")
+        .dependency_graph_hash("abc")
+        .toolchain_version("node-v20")
+        .build()
+        .unwrap();
+
+    backend.upload_artifact(&cache_key, &artifact).await.unwrap();
+    assert!(backend.fetch_artifact(&cache_key).await.unwrap().is_some());
+    assert!(backend.fetch_artifact(&missing_key).await.unwrap().is_none());
+
+    let stats = reqwest::get(format!("{}/v1/stats", server_url))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+
+    assert_eq!(stats["artifacts"], 1);
+    assert_eq!(stats["bytes"], artifact.compressed_data().len());
+    let default_ns = &stats["namespaces"]["default"];
+    assert_eq!(default_ns["uploads"], 1);
+    assert_eq!(default_ns["downloads"], 1);
+    assert_eq!(default_ns["hits"], 1);
+    assert_eq!(default_ns["misses"], 1);
+
+    let metrics = reqwest::get(format!("{}/metrics", server_url))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert!(metrics.contains(
+        "polykit_cache_http_requests_total{method=\"PUT\",route=\"/v1/artifacts/:cache_key\",status=\"201\"} 1"
+    ));
+    assert!(metrics.contains("# TYPE polykit_cache_http_request_duration_seconds histogram"));
+    assert!(metrics.contains("polykit_cache_hits_total{namespace=\"default\"} 1"));
+    assert!(metrics.contains("polykit_cache_artifacts{namespace=\"default\"} 1"));
+}
+
+#[tokio::test]
+async fn test_e2e_batch_query_and_prefetch() {
+    use polykit_core::remote_cache::RemoteCache;
+
+    let temp_dir = TempDir::new().unwrap();
+    let server_url = start_test_server(&temp_dir).await;
+
+    let cache_key = CacheKey::builder()
+        .package_id("query-package")
+        .task_name("build")
+        .command("echo")
+        .dependency_graph_hash("abc")
+        .toolchain_version("node-v20")
+        .build()
+        .unwrap();
+
+    let missing_key = CacheKey::builder()
+        .package_id("missing")
+        .task_name("build")
+        .command("echo")
+        .dependency_graph_hash("abc")
+        .toolchain_version("node-v20")
+        .build()
+        .unwrap();
+
+    let mut output_files = BTreeMap::new();
+    output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+    let artifact = Artifact::new(
+        "query-package".to_string(),
+        "build".to_string(),
+        "echo".to_string(),
+        cache_key.as_string(),
+        output_files,
+    )
+    .unwrap();
+
+    let config = RemoteCacheConfig::new(&server_url);
+    let backend = HttpBackend::new(&config).unwrap();
+    backend.upload_artifact(&cache_key, &artifact).await.unwrap();
+
+    let keys = vec![cache_key.clone(), missing_key.clone()];
+    assert_eq!(backend.has_artifacts(&keys).await.unwrap(), vec![true, false]);
+
+    let remote_cache = RemoteCache::from_config(config).unwrap();
+    assert_eq!(remote_cache.prefetch(&keys).await.unwrap(), 1);
+
+    // Served by the prefetch download, without downloading again
+    let fetched = remote_cache.fetch_artifact(&cache_key).await.unwrap().unwrap();
+    assert_eq!(fetched.metadata().package_name, "query-package");
+    assert!(remote_cache.fetch_artifact(&missing_key).await.unwrap().is_none());
+
+    let stats = reqwest::get(format!("{}/v1/stats", server_url))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+    assert_eq!(stats["namespaces"]["default"]["downloads"], 1);
+}
+
+#[tokio::test]
+async fn test_e2e_batch_query_rejects_invalid_keys() {
+    let temp_dir = TempDir::new().unwrap();
+    let server_url = start_test_server(&temp_dir).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/artifacts/query", server_url))
+        .header("Content-Type", "application/json")
+        .body(r#"{"keys":["not-a-key"]}"#)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+fn namespace_test_artifact(package: &str) -> (CacheKey, Artifact) {
+    let cache_key = CacheKey::builder()
+        .package_id(package)
+        .task_name("build")
+        .command("echo")
+        .dependency_graph_hash("abc")
+        .toolchain_version("node-v20")
+        .build()
+        .unwrap();
+
+    let mut output_files = BTreeMap::new();
+    output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+    let artifact = Artifact::new(
+        package.to_string(),
+        "build".to_string(),
+        "echo".to_string(),
+        cache_key.as_string(),
+        output_files,
+    )
+    .unwrap();
+
+    (cache_key, artifact)
+}
+
+#[tokio::test]
+async fn test_e2e_namespaces_are_isolated() {
+    let temp_dir = TempDir::new().unwrap();
+    let server_url = start_test_server(&temp_dir).await;
+
+    let (cache_key, artifact) = namespace_test_artifact("ns-package");
+
+    let team_a = HttpBackend::new(&RemoteCacheConfig::new(&server_url).with_namespace("team-a")).unwrap();
+    let team_b = HttpBackend::new(&RemoteCacheConfig::new(&server_url).with_namespace("team-b")).unwrap();
+    let default = HttpBackend::new(&RemoteCacheConfig::new(&server_url)).unwrap();
+
+    team_a.upload_artifact(&cache_key, &artifact).await.unwrap();
+
+    assert!(team_a.has_artifact(&cache_key).await.unwrap());
+    assert!(team_a.fetch_artifact(&cache_key).await.unwrap().is_some());
+    assert!(!team_b.has_artifact(&cache_key).await.unwrap());
+    assert!(team_b.fetch_artifact(&cache_key).await.unwrap().is_none());
+    assert!(!default.has_artifact(&cache_key).await.unwrap());
+    assert_eq!(
+        team_b.has_artifacts(std::slice::from_ref(&cache_key)).await.unwrap(),
+        vec![false]
+    );
+
+    // The same key can be uploaded to another namespace without a conflict
+    default.upload_artifact(&cache_key, &artifact).await.unwrap();
+    assert!(temp_dir.path().join("ns").join("team-a").is_dir());
+    assert!(temp_dir.path().join(&cache_key.as_string()[..2]).is_dir());
+
+    let stats = reqwest::get(format!("{}/v1/stats", server_url))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+
+    assert_eq!(stats["artifacts"], 2);
+    assert_eq!(stats["namespaces"]["team-a"]["uploads"], 1);
+    assert_eq!(stats["namespaces"]["team-a"]["artifacts"], 1);
+    assert_eq!(stats["namespaces"]["default"]["uploads"], 1);
+    assert!(stats["namespaces"].get("team-b").is_none());
+}
+
+#[tokio::test]
+async fn test_e2e_namespace_quota_and_validation() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut settings = BTreeMap::new();
+    settings.insert(
+        "tiny".to_string(),
+        NamespaceSettings {
+            quota: Some(16),
+            ..Default::default()
+        },
+    );
+    let namespaces = Namespaces::open(temp_dir.path(), 1024 * 1024 * 1024)
+        .unwrap()
+        .with_settings(settings);
+    let server_url = start_server_with(namespaces).await;
+
+    let (cache_key, artifact) = namespace_test_artifact("quota-package");
+
+    let response = reqwest::Client::new()
+        .put(format!("{}/v1/ns/tiny/artifacts/{}", server_url, cache_key.as_string()))
+        .body(artifact.compressed_data().to_vec())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::INSUFFICIENT_STORAGE);
+
+    let response = reqwest::Client::new()
+        .put(format!("{}/v1/ns/Not_Valid/artifacts/{}", server_url, cache_key.as_string()))
+        .body(artifact.compressed_data().to_vec())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    assert!(!temp_dir.path().join("ns").join("Not_Valid").exists());
+}
+
+#[tokio::test]
+async fn test_e2e_token_authentication() {
+    use polykit_cache::auth::{Scope, TokenConfig, Tokens};
+
+    let temp_dir = TempDir::new().unwrap();
+    let tokens = Tokens::from_configs(&[
+        TokenConfig {
+            token: Some("web-writer".to_string()),
+            token_env: None,
+            scope: Scope::Write,
+            namespaces: vec!["web".to_string()],
+        },
+        TokenConfig {
+            token: Some("reader".to_string()),
+            token_env: None,
+            scope: Scope::Read,
+            namespaces: Vec::new(),
+        },
+    ])
+    .unwrap();
+
+    let namespaces = Namespaces::open(temp_dir.path(), 1024 * 1024 * 1024).unwrap();
+    let state = AppState::new(namespaces, Verifier::new(1024 * 1024 * 1024)).with_tokens(tokens);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(state)).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (cache_key, artifact) = namespace_test_artifact("auth-package");
+
+    let anonymous = HttpBackend::new(&RemoteCacheConfig::new(&server_url).with_namespace("web")).unwrap();
+    assert!(anonymous.upload_artifact(&cache_key, &artifact).await.is_err());
+
+    let writer = HttpBackend::new(
+        &RemoteCacheConfig::new(&server_url)
+            .with_namespace("web")
+            .with_token("web-writer"),
+    )
+    .unwrap();
+    writer.upload_artifact(&cache_key, &artifact).await.unwrap();
+    assert!(writer.fetch_artifact(&cache_key).await.unwrap().is_some());
+
+    let reader = HttpBackend::new(
+        &RemoteCacheConfig::new(&server_url)
+            .with_namespace("web")
+            .with_token("reader"),
+    )
+    .unwrap();
+    assert!(reader.fetch_artifact(&cache_key).await.unwrap().is_some());
+    assert!(reader.upload_artifact(&cache_key, &artifact).await.is_err());
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!("{}/v1/ns/web/artifacts/{}", server_url, cache_key.as_string()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+    let response = client
+        .get(format!("{}/v1/ns/mobile/artifacts/{}", server_url, cache_key.as_string()))
+        .bearer_auth("web-writer")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let response = client
+        .get(format!("{}/v1/stats", server_url))
+        .bearer_auth("web-writer")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let response = client.get(format!("{}/healthz", server_url)).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_e2e_browse_and_delete() {
+    use polykit_cache::auth::{Scope, TokenConfig, Tokens};
+    use polykit_core::remote_cache::ArtifactFilter;
+
+    let temp_dir = TempDir::new().unwrap();
+    let tokens = Tokens::from_configs(&[
+        TokenConfig {
+            token: Some("writer".to_string()),
+            token_env: None,
+            scope: Scope::Write,
+            namespaces: Vec::new(),
+        },
+        TokenConfig {
+            token: Some("admin".to_string()),
+            token_env: None,
+            scope: Scope::Admin,
+            namespaces: Vec::new(),
+        },
+    ])
+    .unwrap();
+
+    let namespaces = Namespaces::open(temp_dir.path(), 1024 * 1024 * 1024).unwrap();
+    let state = AppState::new(namespaces, Verifier::new(1024 * 1024 * 1024)).with_tokens(tokens);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(state)).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let writer = HttpBackend::new(&RemoteCacheConfig::new(&server_url).with_token("writer")).unwrap();
+    let admin = HttpBackend::new(&RemoteCacheConfig::new(&server_url).with_token("admin")).unwrap();
+
+    let (web_key, web_artifact) = namespace_test_artifact("web");
+    let (api_key, api_artifact) = namespace_test_artifact("api");
+    writer.upload_artifact(&web_key, &web_artifact).await.unwrap();
+    writer.upload_artifact(&api_key, &api_artifact).await.unwrap();
+
+    let all = writer.list_artifacts(&ArtifactFilter::default()).await.unwrap();
+    assert_eq!(all.len(), 2);
+    assert!(all.iter().all(|a| a.metadata.is_some()));
+
+    let web_only = writer
+        .list_artifacts(&ArtifactFilter {
+            package: Some("web".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(web_only.len(), 1);
+    assert_eq!(web_only[0].cache_key, web_key.as_string());
+
+    let limited = writer
+        .list_artifacts(&ArtifactFilter {
+            limit: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(limited.len(), 1);
+
+    let manifest = writer
+        .fetch_manifest(&web_key.as_string())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(manifest.metadata.package_name, "web");
+    assert!(manifest.manifest.files.contains_key(&PathBuf::from("file.txt")));
+    assert!(writer.fetch_manifest(&"0".repeat(64)).await.unwrap().is_none());
+
+    // Deleting needs the admin scope
+    assert!(writer.delete_artifact(&web_key.as_string()).await.is_err());
+    assert!(admin.delete_artifact(&web_key.as_string()).await.unwrap());
+    assert!(!admin.delete_artifact(&web_key.as_string()).await.unwrap());
+
+    assert!(!writer.has_artifact(&web_key).await.unwrap());
+    assert!(writer.has_artifact(&api_key).await.unwrap());
+    assert_eq!(writer.list_artifacts(&ArtifactFilter::default()).await.unwrap().len(), 1);
+}
@@ -1,6 +1,6 @@
 //! Tests for storage layer.
 
-use polykit_cache::storage::Storage;
+use polykit_cache::storage::{Storage, StorageError};
 use polykit_core::remote_cache::Artifact;
 use std::collections::BTreeMap;
 use std::path::PathBuf;
@@ -116,7 +116,67 @@ async fn test_storage_immutable() {
         .await;
 
     assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("already exists"));
+    let error = result.unwrap_err();
+    assert!(error.to_string().contains("already exists"));
+    assert!(matches!(error, StorageError::AlreadyExists(_)));
+}
+
+#[tokio::test]
+async fn test_storage_missing_artifact_is_not_found() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = Storage::new(temp_dir.path(), 1024 * 1024).unwrap();
+    let cache_key = "aabbccdd11223344556677889900aabbccddeeff";
+
+    assert!(matches!(
+        storage.read_artifact(cache_key).await,
+        Err(StorageError::NotFound(_))
+    ));
+    assert!(matches!(
+        storage.read_metadata(cache_key).await,
+        Err(StorageError::NotFound(_))
+    ));
+    assert!(matches!(
+        storage.read_manifest(cache_key).await,
+        Err(StorageError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_storage_list_entries_where_filters_while_walking() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = Storage::new(temp_dir.path(), 1024 * 1024).unwrap();
+
+    for (cache_key, package) in [
+        ("aabbccdd11223344556677889900aabbccddeeff", "web"),
+        ("bbccddee11223344556677889900aabbccddeeff", "api"),
+    ] {
+        let mut output_files = BTreeMap::new();
+        output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+        let artifact = Artifact::new(
+            package.to_string(),
+            "build".to_string(),
+            "echo".to_string(),
+            cache_key.to_string(),
+            output_files,
+        )
+        .unwrap();
+        storage
+            .store_artifact(cache_key, artifact.compressed_data().to_vec(), "hash".to_string(), &artifact)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(storage.list_entries().unwrap().len(), 2);
+
+    let web = storage
+        .list_entries_where(|metadata| {
+            metadata
+                .and_then(|metadata| metadata.artifact.as_ref())
+                .is_some_and(|artifact| artifact.package_name == "web")
+        })
+        .unwrap();
+    assert_eq!(web.len(), 1);
+    assert_eq!(web[0].cache_key, "aabbccdd11223344556677889900aabbccddeeff");
 }
 
 #[tokio::test]
@@ -166,6 +226,105 @@ async fn test_storage_concurrent_uploads() {
     }
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_storage_concurrent_uploads_of_same_key() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = std::sync::Arc::new(Storage::new(temp_dir.path(), 1024 * 1024).unwrap());
+    let cache_key = "aabbccdd11223344556677889900aabbccddeeff";
+
+    let mut output_files = BTreeMap::new();
+    output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+    let artifact = std::sync::Arc::new(
+        Artifact::new(
+            "test".to_string(),
+            "build".to_string(),
+            "echo".to_string(),
+            cache_key.to_string(),
+            output_files,
+        )
+        .unwrap(),
+    );
+
+    let handles: Vec<_> = (0..16)
+        .map(|_| {
+            let (storage, artifact) = (storage.clone(), artifact.clone());
+            tokio::spawn(async move {
+                storage
+                    .store_artifact(cache_key, artifact.compressed_data().to_vec(), "hash".to_string(), &artifact)
+                    .await
+            })
+        })
+        .collect();
+
+    let mut stored = 0;
+    for handle in handles {
+        match handle.await.unwrap() {
+            Ok(()) => stored += 1,
+            Err(error) => assert!(matches!(error, StorageError::AlreadyExists(_))),
+        }
+    }
+
+    assert_eq!(stored, 1);
+    let usage = storage.usage();
+    assert_eq!(usage.artifacts, 1);
+    assert_eq!(usage.bytes, artifact.compressed_data().len() as u64);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_storage_concurrent_uploads_within_quota() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = std::sync::Arc::new(Storage::new(temp_dir.path(), 1024 * 1024).unwrap());
+
+    let artifacts: Vec<_> = (0..16)
+        .map(|i| {
+            let cache_key = format!("aabbccdd11223344556677889900aabbccdd{:04x}", i);
+            let mut output_files = BTreeMap::new();
+            output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+            let artifact = Artifact::new(
+                "test".to_string(),
+                "build".to_string(),
+                "echo".to_string(),
+                cache_key.clone(),
+                output_files,
+            )
+            .unwrap();
+            (cache_key, artifact)
+        })
+        .collect();
+    let size = artifacts[0].1.compressed_data().len() as u64;
+    let quota = size * 3;
+
+    let handles: Vec<_> = artifacts
+        .into_iter()
+        .map(|(cache_key, artifact)| {
+            let storage = storage.clone();
+            tokio::spawn(async move {
+                let data = artifact.compressed_data().to_vec();
+                let Some(_reservation) = storage.reserve(data.len() as u64, quota) else {
+                    return false;
+                };
+                storage
+                    .store_artifact(&cache_key, data, "hash".to_string(), &artifact)
+                    .await
+                    .unwrap();
+                true
+            })
+        })
+        .collect();
+
+    let mut stored = 0;
+    for handle in handles {
+        if handle.await.unwrap() {
+            stored += 1;
+        }
+    }
+
+    assert!(stored >= 1);
+    let usage = storage.usage();
+    assert_eq!(usage.artifacts, stored);
+    assert!(usage.bytes <= quota, "{} bytes stored over a quota of {}", usage.bytes, quota);
+}
+
 #[tokio::test]
 async fn test_storage_size_limit() {
     let temp_dir = TempDir::new().unwrap();
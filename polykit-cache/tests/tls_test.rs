@@ -0,0 +1,62 @@
+//! TLS termination tests.
+
+use polykit_cache::namespace::Namespaces;
+use polykit_cache::server::{create_router, AppState};
+use polykit_cache::tls::load_tls_config;
+use polykit_cache::verification::Verifier;
+use std::path::Path;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::time::Duration;
+
+/// Writes a fresh self-signed certificate for `localhost` and returns its PEM.
+fn write_certificate(dir: &Path) -> String {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_pem = certified.cert.pem();
+    std::fs::write(dir.join("cert.pem"), &cert_pem).unwrap();
+    std::fs::write(dir.join("key.pem"), certified.signing_key.serialize_pem()).unwrap();
+    cert_pem
+}
+
+fn client_trusting(cert_pem: &str) -> reqwest::Client {
+    reqwest::Client::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(cert_pem.as_bytes()).unwrap())
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_tls_serves_and_reloads_certificate() {
+    let temp_dir = TempDir::new().unwrap();
+    let tls_dir = TempDir::new().unwrap();
+    let cert = tls_dir.path().join("cert.pem");
+    let key = tls_dir.path().join("key.pem");
+
+    let first_pem = write_certificate(tls_dir.path());
+    let tls_config = load_tls_config(&cert, &key).await.unwrap();
+
+    let namespaces = Namespaces::open(temp_dir.path(), 1024 * 1024).unwrap();
+    let app = create_router(AppState::new(namespaces, Verifier::new(1024 * 1024)));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = axum_server::from_tcp_rustls(listener.into_std().unwrap(), tls_config.clone());
+    tokio::spawn(async move {
+        server.serve(app.into_make_service()).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let url = format!("https://localhost:{}/healthz", port);
+    let response = client_trusting(&first_pem).get(&url).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // Plain HTTP is not served
+    assert!(reqwest::get(format!("http://localhost:{}/healthz", port)).await.is_err());
+
+    let second_pem = write_certificate(tls_dir.path());
+    tls_config.reload_from_pem_file(&cert, &key).await.unwrap();
+
+    assert!(client_trusting(&first_pem).get(&url).send().await.is_err());
+    let response = client_trusting(&second_pem).get(&url).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
@@ -0,0 +1,167 @@
+//! Pull-through proxy tests.
+
+use polykit_cache::namespace::Namespaces;
+use polykit_cache::server::{create_router, AppState};
+use polykit_cache::upstream::{Upstream, UpstreamConfig};
+use polykit_cache::verification::Verifier;
+use polykit_core::remote_cache::{
+    Artifact, CacheKey, HttpBackend, RemoteCacheBackend, RemoteCacheConfig, SigningKey, TrustedKeys,
+};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::time::Duration;
+
+async fn serve(state: AppState) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(state)).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    url
+}
+
+async fn start_server(dir: &TempDir) -> String {
+    let namespaces = Namespaces::open(dir.path(), 1024 * 1024).unwrap();
+    serve(AppState::new(namespaces, Verifier::new(1024 * 1024))).await
+}
+
+async fn start_proxy(dir: &TempDir, upstream_url: &str, verifier: Verifier) -> String {
+    let namespaces = Namespaces::open(dir.path(), 1024 * 1024).unwrap();
+    let upstream = Upstream::from_config(&UpstreamConfig {
+        url: upstream_url.to_string(),
+        token: None,
+        token_env: None,
+        read_only: false,
+    })
+    .unwrap();
+    serve(AppState::new(namespaces, verifier).with_upstream(upstream)).await
+}
+
+fn test_artifact(package: &str) -> (CacheKey, Artifact) {
+    let cache_key = CacheKey::builder()
+        .package_id(package)
+        .task_name("build")
+        .command("echo")
+        .dependency_graph_hash("abc")
+        .toolchain_version("node-v20")
+        .build()
+        .unwrap();
+
+    let mut output_files = BTreeMap::new();
+    output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+    let artifact = Artifact::new(
+        package.to_string(),
+        "build".to_string(),
+        "echo".to_string(),
+        cache_key.as_string(),
+        output_files,
+    )
+    .unwrap();
+
+    (cache_key, artifact)
+}
+
+#[tokio::test]
+async fn test_proxy_pulls_misses_from_upstream() {
+    let upstream_dir = TempDir::new().unwrap();
+    let proxy_dir = TempDir::new().unwrap();
+    let upstream_url = start_server(&upstream_dir).await;
+    let proxy_url = start_proxy(&proxy_dir, &upstream_url, Verifier::new(1024 * 1024)).await;
+
+    let (cache_key, artifact) = test_artifact("pulled");
+    let central =
+        HttpBackend::new(&RemoteCacheConfig::new(&upstream_url).with_namespace("web")).unwrap();
+    central
+        .upload_artifact(&cache_key, &artifact)
+        .await
+        .unwrap();
+
+    let proxy =
+        HttpBackend::new(&RemoteCacheConfig::new(&proxy_url).with_namespace("web")).unwrap();
+    assert_eq!(
+        proxy
+            .has_artifacts(std::slice::from_ref(&cache_key))
+            .await
+            .unwrap(),
+        vec![true]
+    );
+
+    let fetched = proxy.fetch_artifact(&cache_key).await.unwrap().unwrap();
+    assert_eq!(fetched.metadata().package_name, "pulled");
+
+    // The artifact is now stored locally in the same namespace
+    let key = cache_key.as_string();
+    let local = proxy_dir
+        .path()
+        .join("ns")
+        .join("web")
+        .join(&key[..2])
+        .join(&key[2..4])
+        .join(format!("{}.zst", key));
+    assert!(local.is_file());
+
+    let (missing_key, _) = test_artifact("nowhere");
+    assert!(proxy.fetch_artifact(&missing_key).await.unwrap().is_none());
+    assert!(!proxy.has_artifact(&missing_key).await.unwrap());
+
+    let stats = reqwest::get(format!("{}/v1/stats", proxy_url))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+    assert_eq!(stats["namespaces"]["web"]["upstream_hits"], 1);
+}
+
+#[tokio::test]
+async fn test_proxy_replicates_uploads() {
+    let upstream_dir = TempDir::new().unwrap();
+    let proxy_dir = TempDir::new().unwrap();
+    let upstream_url = start_server(&upstream_dir).await;
+    let proxy_url = start_proxy(&proxy_dir, &upstream_url, Verifier::new(1024 * 1024)).await;
+
+    let (cache_key, artifact) = test_artifact("replicated");
+    let proxy = HttpBackend::new(&RemoteCacheConfig::new(&proxy_url)).unwrap();
+    proxy.upload_artifact(&cache_key, &artifact).await.unwrap();
+
+    let central = HttpBackend::new(&RemoteCacheConfig::new(&upstream_url)).unwrap();
+    let mut replicated = false;
+    for _ in 0..50 {
+        if central.has_artifact(&cache_key).await.unwrap() {
+            replicated = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(replicated, "upload was not replicated upstream");
+}
+
+#[tokio::test]
+async fn test_proxy_verifies_upstream_artifacts() {
+    let upstream_dir = TempDir::new().unwrap();
+    let proxy_dir = TempDir::new().unwrap();
+    let upstream_url = start_server(&upstream_dir).await;
+
+    let mut trusted_keys = TrustedKeys::new();
+    trusted_keys.add(SigningKey::hmac("ci", b"secret").trusted_key());
+    let verifier = Verifier::new(1024 * 1024)
+        .with_trusted_keys(trusted_keys)
+        .with_require_signatures(true);
+    let proxy_url = start_proxy(&proxy_dir, &upstream_url, verifier).await;
+
+    // Unsigned artifacts from upstream are not served or stored
+    let (cache_key, artifact) = test_artifact("unsigned");
+    let central = HttpBackend::new(&RemoteCacheConfig::new(&upstream_url)).unwrap();
+    central
+        .upload_artifact(&cache_key, &artifact)
+        .await
+        .unwrap();
+
+    let proxy = HttpBackend::new(&RemoteCacheConfig::new(&proxy_url)).unwrap();
+    assert!(proxy.fetch_artifact(&cache_key).await.unwrap().is_none());
+    assert!(!proxy_dir.path().join(&cache_key.as_string()[..2]).exists());
+}
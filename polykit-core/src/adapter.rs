@@ -3,11 +3,19 @@
 use std::path::Path;
 
 use crate::error::Result;
+use crate::package::Task;
 
 /// Language-specific metadata extracted from package files.
+#[derive(Debug, Default)]
 pub struct LangMetadata {
     /// Package version, if available.
     pub version: Option<String>,
+    /// Package name from the native manifest, if available.
+    pub name: Option<String>,
+    /// Default tasks, such as `package.json` scripts.
+    pub tasks: Vec<Task>,
+    /// Whether the manifest marks the package as unpublished.
+    pub private: bool,
 }
 
 /// Trait for language-specific package metadata operations.
@@ -19,4 +27,13 @@ pub trait LanguageAdapter: Send + Sync {
     fn detect(&self, path: &Path) -> bool;
     fn read_metadata(&self, path: &Path) -> Result<LangMetadata>;
     fn bump_version(&self, path: &Path, new_version: &str) -> Result<()>;
+
+    /// Reads the names of the dependencies declared in the native manifest.
+    ///
+    /// Names are returned as the ecosystem spells them, such as npm package
+    /// names or Go module paths. External dependencies are included; callers
+    /// match the names against the packages in the workspace.
+    fn read_dependencies(&self, _path: &Path) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
 }
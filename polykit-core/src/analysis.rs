@@ -0,0 +1,304 @@
+//! Critical-path and bottleneck analysis over estimated task durations.
+//!
+//! Durations come from [`RunHistory::estimates`](crate::history::RunHistory::estimates).
+//! Packages without an estimate count as instant.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::time::Duration;
+
+use rustc_hash::FxHashMap;
+
+use crate::graph::DependencyGraph;
+
+/// Timing of one package in an unlimited-parallelism run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageTiming {
+    pub package: String,
+    pub duration: Duration,
+    /// When the package can start, once all its dependencies are done.
+    pub earliest_start: Duration,
+    /// How long the package can be delayed without delaying the run.
+    pub slack: Duration,
+}
+
+/// Critical path and per-package slack of a dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// The longest chain of packages, dependencies first.
+    pub critical_path: Vec<String>,
+    /// Length of the critical path: the fastest possible run.
+    pub duration: Duration,
+    /// Sum of every package's duration: a run with one job.
+    pub total_work: Duration,
+    /// Every package, in topological order.
+    pub packages: Vec<PackageTiming>,
+}
+
+impl Analysis {
+    pub fn new(graph: &DependencyGraph, estimates: &FxHashMap<String, Duration>) -> Self {
+        let order = graph.topological_order();
+        let duration_of = |name: &str| estimates.get(name).copied().unwrap_or_default();
+
+        let mut finish: FxHashMap<&str, Duration> = FxHashMap::default();
+        let mut start: FxHashMap<&str, Duration> = FxHashMap::default();
+        for name in &order {
+            let earliest = graph
+                .dependencies(name)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|dep| finish.get(dep.as_str()).copied())
+                .max()
+                .unwrap_or_default();
+            start.insert(name, earliest);
+            finish.insert(name, earliest + duration_of(name));
+        }
+        let duration = finish.values().copied().max().unwrap_or_default();
+
+        let mut latest_start: FxHashMap<&str, Duration> = FxHashMap::default();
+        for name in order.iter().rev() {
+            let latest_finish = graph
+                .dependents(name)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|dependent| latest_start.get(dependent.as_str()).copied())
+                .min()
+                .unwrap_or(duration);
+            latest_start.insert(name, latest_finish - duration_of(name));
+        }
+
+        let packages = order
+            .iter()
+            .map(|name| PackageTiming {
+                package: name.clone(),
+                duration: duration_of(name),
+                earliest_start: start[name.as_str()],
+                slack: latest_start[name.as_str()] - start[name.as_str()],
+            })
+            .collect();
+
+        // Walk back from the last package to finish through the dependency
+        // that finished last.
+        let mut critical_path = Vec::new();
+        let mut current = order
+            .iter()
+            .filter(|name| finish[name.as_str()] == duration)
+            .min();
+        while let Some(name) = current {
+            critical_path.push(name.clone());
+            let mut deps = graph.dependencies(name).unwrap_or_default();
+            deps.sort_unstable();
+            current = deps
+                .into_iter()
+                .filter(|dep| finish[dep.as_str()] == start[name.as_str()])
+                .min()
+                .and_then(|dep| order.iter().find(|name| **name == dep));
+        }
+        critical_path.reverse();
+
+        Self {
+            critical_path,
+            duration,
+            total_work: order.iter().map(|name| duration_of(name)).sum(),
+            packages,
+        }
+    }
+}
+
+/// Returns, for each package, the longest time from its start to the end of
+/// the run: its own duration plus the longest chain of dependents.
+///
+/// Starting packages with higher priority first shortens the critical path.
+pub fn priorities(
+    graph: &DependencyGraph,
+    estimates: &FxHashMap<String, Duration>,
+) -> FxHashMap<String, Duration> {
+    let mut priorities: FxHashMap<String, Duration> = FxHashMap::default();
+    for name in graph.topological_order().into_iter().rev() {
+        let tail = graph
+            .dependents(&name)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|dependent| priorities.get(dependent).copied())
+            .max()
+            .unwrap_or_default();
+        let duration = estimates.get(&name).copied().unwrap_or_default();
+        priorities.insert(name, duration + tail);
+    }
+    priorities
+}
+
+/// A span of a simulated run with a constant number of running packages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    pub start: Duration,
+    pub end: Duration,
+    pub running: usize,
+}
+
+/// A simulated run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub jobs: usize,
+    pub duration: Duration,
+    /// Running packages over time, covering the whole run.
+    pub parallelism: Vec<Segment>,
+}
+
+/// Simulates a run with `jobs` workers, scheduled the way
+/// [`TaskRunner`](crate::runner::TaskRunner) does: one dependency level at a
+/// time, highest priority first within a level.
+pub fn simulate(
+    graph: &DependencyGraph,
+    estimates: &FxHashMap<String, Duration>,
+    jobs: usize,
+) -> Schedule {
+    let jobs = jobs.max(1);
+    let priorities = priorities(graph, estimates);
+    let mut intervals: Vec<(Duration, Duration)> = Vec::new();
+    let mut level_start = Duration::ZERO;
+
+    for mut level in graph.dependency_levels() {
+        level.sort_by_key(|name| Reverse(priorities.get(name).copied().unwrap_or_default()));
+        let mut workers: BinaryHeap<Reverse<Duration>> = (0..jobs.min(level.len()))
+            .map(|_| Reverse(level_start))
+            .collect();
+        let mut level_end = level_start;
+        for name in &level {
+            let Some(Reverse(start)) = workers.pop() else {
+                break;
+            };
+            let end = start + estimates.get(name).copied().unwrap_or_default();
+            intervals.push((start, end));
+            workers.push(Reverse(end));
+            level_end = level_end.max(end);
+        }
+        level_start = level_end;
+    }
+
+    Schedule {
+        jobs,
+        duration: level_start,
+        parallelism: segments(&intervals),
+    }
+}
+
+/// Turns intervals into segments of constant overlap, merging neighbours
+/// with the same count and dropping empty spans.
+fn segments(intervals: &[(Duration, Duration)]) -> Vec<Segment> {
+    let mut bounds: Vec<Duration> = intervals.iter().flat_map(|&(s, e)| [s, e]).collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut segments: Vec<Segment> = Vec::new();
+    for window in bounds.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let running = intervals
+            .iter()
+            .filter(|&&(s, e)| s <= start && e >= end)
+            .count();
+        match segments.last_mut() {
+            Some(last) if last.running == running => last.end = end,
+            _ => segments.push(Segment {
+                start,
+                end,
+                running,
+            }),
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::{Language, Package};
+    use std::path::PathBuf;
+
+    fn package(name: &str, deps: &[&str]) -> Package {
+        Package::new(
+            name.to_string(),
+            Language::Rust,
+            true,
+            PathBuf::from(name),
+            deps.iter().map(|d| d.to_string()).collect(),
+            vec![],
+        )
+    }
+
+    fn graph() -> DependencyGraph {
+        DependencyGraph::new(vec![
+            package("core", &[]),
+            package("utils", &["core"]),
+            package("api", &["utils"]),
+            package("docs", &[]),
+            package("web", &["core"]),
+        ])
+        .unwrap()
+    }
+
+    fn estimates() -> FxHashMap<String, Duration> {
+        [
+            ("core", 10),
+            ("utils", 20),
+            ("api", 30),
+            ("docs", 5),
+            ("web", 15),
+        ]
+        .into_iter()
+        .map(|(name, secs)| (name.to_string(), Duration::from_secs(secs)))
+        .collect()
+    }
+
+    fn timing<'a>(analysis: &'a Analysis, name: &str) -> &'a PackageTiming {
+        analysis
+            .packages
+            .iter()
+            .find(|p| p.package == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_critical_path_and_slack() {
+        let analysis = Analysis::new(&graph(), &estimates());
+
+        assert_eq!(analysis.critical_path, ["core", "utils", "api"]);
+        assert_eq!(analysis.duration, Duration::from_secs(60));
+        assert_eq!(analysis.total_work, Duration::from_secs(80));
+        assert_eq!(timing(&analysis, "api").slack, Duration::ZERO);
+        assert_eq!(timing(&analysis, "web").slack, Duration::from_secs(35));
+        assert_eq!(
+            timing(&analysis, "web").earliest_start,
+            Duration::from_secs(10)
+        );
+        assert_eq!(timing(&analysis, "docs").slack, Duration::from_secs(55));
+
+        let priorities = priorities(&graph(), &estimates());
+        assert_eq!(priorities["core"], Duration::from_secs(60));
+        assert_eq!(priorities["web"], Duration::from_secs(15));
+    }
+
+    #[test]
+    fn test_simulated_runs_by_jobs() {
+        let graph = graph();
+        let serial = simulate(&graph, &estimates(), 1);
+        assert_eq!(serial.duration, Duration::from_secs(80));
+        assert!(serial.parallelism.iter().all(|s| s.running == 1));
+
+        // Levels: [core, docs], [utils, web], [api].
+        let parallel = simulate(&graph, &estimates(), 4);
+        assert_eq!(parallel.duration, Duration::from_secs(60));
+        assert_eq!(
+            parallel.parallelism[0],
+            Segment {
+                start: Duration::ZERO,
+                end: Duration::from_secs(5),
+                running: 2,
+            }
+        );
+
+        // Two jobs already cover the widest level.
+        let two = simulate(&graph, &estimates(), 2);
+        assert_eq!(two.duration, Duration::from_secs(60));
+    }
+}
@@ -13,8 +13,9 @@ use xxhash_rust::xxh3::xxh3_64;
 
 use crate::error::{Error, Result};
 use crate::package::Package;
+use crate::workspace::WorkspaceMembers;
 
-const CACHE_VERSION: u32 = 3;
+const CACHE_VERSION: u32 = 5;
 const MAX_SCAN_DEPTH: usize = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,18 +82,109 @@ impl Cache {
         cache_path
     }
 
+    /// Returns the path of the dependency graph snapshot saved next to the
+    /// scan cache for `packages_dir`.
+    pub fn graph_path(&self, packages_dir: &Path) -> PathBuf {
+        let cache_key = self.compute_cache_key(packages_dir);
+        self.cache_dir.join(format!("graph_{}.bin", cache_key))
+    }
+
+    /// Hashes the mtimes recorded in the scan cache for `packages_dir`.
+    ///
+    /// The hash changes whenever a scan finds changed `polykit.toml` files
+    /// and rewrites the cache, so it keys snapshots derived from the scan.
+    /// Returns `None` if there is no scan cache.
+    pub fn fingerprint(&self, packages_dir: &Path) -> Result<Option<u64>> {
+        let cache_path = self.get_cache_path(packages_dir);
+        let Some(entry) = self.read_entry(&cache_path)? else {
+            return Ok(None);
+        };
+
+        let mut mtimes: Vec<(&PathBuf, &u64)> = entry.mtimes.iter().collect();
+        mtimes.sort_unstable();
+        let mut bytes = Vec::new();
+        for (path, mtime) in mtimes {
+            bytes.extend_from_slice(path.as_os_str().as_encoded_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(&mtime.to_le_bytes());
+        }
+        Ok(Some(xxh3_64(&bytes)))
+    }
+
     pub fn load(&mut self, packages_dir: &Path) -> Result<Option<Vec<Package>>> {
         let cache_path = self.get_cache_path(packages_dir);
-        if !cache_path.exists() {
+        let entry = match self.read_entry(&cache_path)? {
+            Some(entry) => entry,
+            None => {
+                self.stats.misses += 1;
+                return Ok(None);
+            }
+        };
+
+        if !self.validate_mtimes(packages_dir, &entry.mtimes, None)? {
+            self.stats.misses += 1;
+            return Ok(None);
+        }
+
+        self.stats.hits += 1;
+        Ok(Some(entry.packages))
+    }
+
+    /// Loads packages discovered through workspace member globs.
+    ///
+    /// The entry is only valid if it was saved for the same globs and the
+    /// same set of `polykit.toml` files, all with unchanged mtimes.
+    pub fn load_members(
+        &mut self,
+        members: &WorkspaceMembers,
+        config_files: &[PathBuf],
+    ) -> Result<Option<Vec<Package>>> {
+        let cache_path = self.get_cache_path(&members_cache_id(members));
+        let entry = match self.read_entry(&cache_path)? {
+            Some(entry) => entry,
+            None => {
+                self.stats.misses += 1;
+                return Ok(None);
+            }
+        };
+
+        let root = members.root();
+        let same_files = config_files.iter().all(|path| {
+            path.strip_prefix(root)
+                .map(|relative| entry.mtimes.contains_key(relative))
+                .unwrap_or(false)
+        });
+        if !same_files
+            || !self.validate_mtimes(root, &entry.mtimes, Some(config_files.len() as u64))?
+        {
             self.stats.misses += 1;
             return Ok(None);
         }
 
-        let file = File::open(&cache_path).map_err(Error::Io)?;
+        self.stats.hits += 1;
+        Ok(Some(entry.packages))
+    }
+
+    /// Saves packages discovered through workspace member globs.
+    pub fn save_members(
+        &self,
+        members: &WorkspaceMembers,
+        config_files: &[PathBuf],
+        packages: &[Package],
+    ) -> Result<()> {
+        let mtimes = self.mtimes_for_files(members.root(), config_files.to_vec());
+        self.write_entry(&members_cache_id(members), packages, mtimes)
+    }
+
+    fn read_entry(&self, cache_path: &Path) -> Result<Option<CacheEntry>> {
+        if !cache_path.exists() {
+            return Ok(None);
+        }
+
+        let file = File::open(cache_path).map_err(Error::Io)?;
         let metadata = file.metadata().map_err(Error::Io)?;
 
         if metadata.len() == 0 {
-            self.stats.misses += 1;
             return Ok(None);
         }
 
@@ -108,29 +200,37 @@ impl Cache {
             message: format!("Failed to decompress cache: {}", e),
         })?;
 
-        let entry: CacheEntry = bincode::deserialize(&content).map_err(|e| Error::Adapter {
+        // Every layout starts with the version, so an entry written by another
+        // version is a miss instead of a parse error
+        let version: u32 = bincode::deserialize(&content).map_err(|e| Error::Adapter {
             package: "cache".to_string(),
             message: format!("Failed to parse cache: {}", e),
         })?;
-
-        if entry.version != CACHE_VERSION {
-            self.stats.misses += 1;
+        if version != CACHE_VERSION {
             return Ok(None);
         }
 
-        if !self.validate_mtimes(packages_dir, &entry.mtimes)? {
-            self.stats.misses += 1;
-            return Ok(None);
-        }
+        let entry: CacheEntry = bincode::deserialize(&content).map_err(|e| Error::Adapter {
+            package: "cache".to_string(),
+            message: format!("Failed to parse cache: {}", e),
+        })?;
 
-        self.stats.hits += 1;
-        Ok(Some(entry.packages))
+        Ok(Some(entry))
     }
 
     pub fn save(&self, packages_dir: &Path, packages: &[Package]) -> Result<()> {
+        let mtimes = self.collect_mtimes(packages_dir)?;
+        self.write_entry(packages_dir, packages, mtimes)
+    }
+
+    fn write_entry(
+        &self,
+        packages_dir: &Path,
+        packages: &[Package],
+        mtimes: FxHashMap<PathBuf, u64>,
+    ) -> Result<()> {
         fs::create_dir_all(&self.cache_dir).map_err(Error::Io)?;
 
-        let mtimes = self.collect_mtimes(packages_dir)?;
         let entry = CacheEntry {
             version: CACHE_VERSION,
             packages: packages.to_vec(),
@@ -196,6 +296,14 @@ impl Cache {
             .map(|e| e.path().to_path_buf())
             .collect();
 
+        Ok(self.mtimes_for_files(&packages_dir, polykit_files))
+    }
+
+    fn mtimes_for_files(
+        &self,
+        packages_dir: &Path,
+        polykit_files: Vec<PathBuf>,
+    ) -> FxHashMap<PathBuf, u64> {
         let mtimes_vec: Vec<(PathBuf, u64)> = polykit_files
             .into_par_iter()
             .flat_map(|path| {
@@ -205,7 +313,7 @@ impl Cache {
                     if let Ok(mtime) = metadata.modified() {
                         if let Ok(duration) = mtime.duration_since(SystemTime::UNIX_EPOCH) {
                             let relative_path = path
-                                .strip_prefix(&packages_dir)
+                                .strip_prefix(packages_dir)
                                 .unwrap_or(&path)
                                 .to_path_buf();
                             results.push((relative_path, duration.as_secs()));
@@ -218,7 +326,7 @@ impl Cache {
                         if let Ok(mtime) = metadata.modified() {
                             if let Ok(duration) = mtime.duration_since(SystemTime::UNIX_EPOCH) {
                                 let relative_dir = package_dir
-                                    .strip_prefix(&packages_dir)
+                                    .strip_prefix(packages_dir)
                                     .unwrap_or(package_dir)
                                     .to_path_buf();
                                 let dir_key = relative_dir.join(".dir");
@@ -247,13 +355,14 @@ impl Cache {
         let package_count_key = PathBuf::from(".package_count");
         mtimes.insert(package_count_key, package_dirs.len() as u64);
 
-        Ok(mtimes)
+        mtimes
     }
 
     fn validate_mtimes(
         &self,
         packages_dir: &Path,
         cached_mtimes: &FxHashMap<PathBuf, u64>,
+        package_count: Option<u64>,
     ) -> Result<bool> {
         if cached_mtimes.is_empty() {
             return Ok(false);
@@ -261,7 +370,10 @@ impl Cache {
 
         let package_count_key = PathBuf::from(".package_count");
         if let Some(cached_count) = cached_mtimes.get(&package_count_key) {
-            let current_count = self.count_packages_fast(packages_dir)?;
+            let current_count = match package_count {
+                Some(count) => count,
+                None => self.count_packages_fast(packages_dir)?,
+            };
             if current_count != *cached_count {
                 return Ok(false);
             }
@@ -322,3 +434,58 @@ impl Cache {
         Ok(())
     }
 }
+
+/// Identifies a members scan by its root and globs, so changing the globs
+/// never reuses packages discovered with the old ones.
+pub(crate) fn members_cache_id(members: &WorkspaceMembers) -> PathBuf {
+    let mut id = members.root().as_os_str().to_os_string();
+    id.push("\0members\0");
+    id.push(members.fingerprint());
+    PathBuf::from(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scan cache entry in a layout from before the current `Package`.
+    #[derive(Serialize)]
+    struct OldCacheEntry {
+        version: u32,
+        packages: Vec<(String, String)>,
+        mtimes: FxHashMap<PathBuf, u64>,
+    }
+
+    fn write_old_entry(cache: &Cache, packages_dir: &Path, version: u32) {
+        let old = OldCacheEntry {
+            version,
+            packages: vec![("web".to_string(), "js".to_string())],
+            mtimes: FxHashMap::default(),
+        };
+        let serialized = bincode::serialize(&old).unwrap();
+        let compressed = zstd::encode_all(&serialized[..], 3).unwrap();
+        fs::write(cache.get_cache_path(packages_dir), compressed).unwrap();
+    }
+
+    #[test]
+    fn test_old_cache_layout_is_a_miss() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let packages_dir = temp_dir.path().join("packages");
+        fs::create_dir_all(&packages_dir).unwrap();
+        fs::create_dir_all(temp_dir.path().join("cache")).unwrap();
+        let mut cache = Cache::new(temp_dir.path().join("cache"));
+
+        // Version 3 predates package tags
+        write_old_entry(&cache, &packages_dir, 3);
+        assert!(cache.load(&packages_dir).unwrap().is_none());
+        assert!(cache.fingerprint(&packages_dir).unwrap().is_none());
+
+        // Version 4 predates `affected_ignore`
+        write_old_entry(&cache, &packages_dir, 4);
+        assert!(cache.load(&packages_dir).unwrap().is_none());
+
+        // The next scan rewrites it in the current layout
+        cache.save(&packages_dir, &[]).unwrap();
+        assert!(cache.load(&packages_dir).unwrap().is_some());
+    }
+}
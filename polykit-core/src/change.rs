@@ -3,11 +3,199 @@
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
-use git2::{DiffOptions, Repository};
+use git2::{Commit, Diff, DiffOptions, Oid, Repository, Tree};
 
 use crate::error::{Error, Result};
 use crate::graph::DependencyGraph;
-use crate::path_utils;
+use crate::path_utils::{self, GlobalInputs, PackagePaths};
+
+/// The git changes that count as changed files.
+///
+/// Like `git diff base...head`, changes are compared against the merge base
+/// of `base` and `head` by default, so commits that landed on `base` after
+/// the branch point do not count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitRange {
+    /// Reference the changes are compared against.
+    pub base: String,
+    /// Reference whose changes are included, or `None` for `HEAD`.
+    pub head: Option<String>,
+    /// Whether staged, unstaged and untracked files also count as changed.
+    /// Only applies when `head` is `None`.
+    pub include_uncommitted: bool,
+    /// Whether to compare against `base` itself rather than the merge base.
+    pub exact_base: bool,
+}
+
+impl GitRange {
+    /// Compares `base` against `HEAD`, committed changes only.
+    pub fn new(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into(),
+            head: None,
+            include_uncommitted: false,
+            exact_base: false,
+        }
+    }
+
+    /// Includes the changes up to `head` instead of `HEAD`.
+    pub fn with_head(mut self, head: impl Into<String>) -> Self {
+        self.head = Some(head.into());
+        self
+    }
+
+    /// Also counts staged, unstaged and untracked files when `head` is unset.
+    /// Off by default; `polykit affected --git` turns it on outside CI.
+    pub fn with_uncommitted(mut self, include: bool) -> Self {
+        self.include_uncommitted = include;
+        self
+    }
+
+    /// Compares against `base` itself instead of its merge base with `head`.
+    pub fn with_exact_base(mut self, exact: bool) -> Self {
+        self.exact_base = exact;
+        self
+    }
+
+    /// Returns the files changed in the range, relative to the repository root.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a reference is invalid or the diff fails.
+    pub fn changed_files(&self, repo: &Repository) -> Result<Vec<PathBuf>> {
+        ChangeDetector::validate_git_ref(&self.base)?;
+        if let Some(ref head) = self.head {
+            ChangeDetector::validate_git_ref(head)?;
+        }
+
+        let head = self.head.as_deref().unwrap_or("HEAD");
+        let base_tree = if self.exact_base {
+            tree(repo, &self.base)?
+        } else {
+            self.merge_base(repo, head)?
+        };
+        let diff = match self.head {
+            Some(ref head) => {
+                let head_tree = tree(repo, head)?;
+                repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), None)
+            }
+            None if self.include_uncommitted => {
+                let mut diff_opts = DiffOptions::new();
+                diff_opts.include_untracked(true);
+                diff_opts.recurse_untracked_dirs(true);
+                repo.diff_tree_to_workdir_with_index(Some(&base_tree), Some(&mut diff_opts))
+            }
+            None => {
+                let head_tree = tree(repo, "HEAD")?;
+                repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), None)
+            }
+        }
+        .map_err(|e| Error::Adapter {
+            package: "change-detection".to_string(),
+            message: format!("Failed to compute git diff: {}", e),
+        })?;
+
+        diff_paths(&diff)
+    }
+
+    /// Returns the tree of the merge base of `base` and `head`.
+    ///
+    /// A shallow clone may not contain the merge base. The error then says
+    /// how much history `head` has, so the fetch can be deepened beyond it.
+    fn merge_base<'repo>(&self, repo: &'repo Repository, head: &str) -> Result<Tree<'repo>> {
+        let base_commit = commit(repo, &self.base)?;
+        let head_commit = commit(repo, head)?;
+        let merge_base = match repo.merge_base(base_commit.id(), head_commit.id()) {
+            Ok(oid) => oid,
+            Err(e) if e.code() == git2::ErrorCode::NotFound && repo.is_shallow() => {
+                let depth = history_depth(repo, head_commit.id())?;
+                return Err(Error::Adapter {
+                    package: "change-detection".to_string(),
+                    message: format!(
+                        "No merge base of '{}' and '{}' in this shallow clone: '{}' has only {} commits of history. \
+                         Fetch more, e.g. `git fetch --deepen={}` or `fetch-depth: 0` with actions/checkout, \
+                         or pass --exact-base to compare against '{}' directly",
+                        self.base, head, head, depth, depth, self.base
+                    ),
+                });
+            }
+            Err(e) => {
+                return Err(Error::Adapter {
+                    package: "change-detection".to_string(),
+                    message: format!(
+                        "No merge base of '{}' and '{}': {}. Pass --exact-base to compare against '{}' directly",
+                        self.base, head, e, self.base
+                    ),
+                });
+            }
+        };
+
+        repo.find_commit(merge_base)
+            .and_then(|commit| commit.tree())
+            .map_err(|e| Error::Adapter {
+                package: "change-detection".to_string(),
+                message: format!("Failed to get tree of merge base: {}", e),
+            })
+    }
+}
+
+fn commit<'repo>(repo: &'repo Repository, git_ref: &str) -> Result<Commit<'repo>> {
+    let object = repo.revparse_single(git_ref).map_err(|e| Error::Adapter {
+        package: "change-detection".to_string(),
+        message: format!("Failed to parse git reference '{}': {}", git_ref, e),
+    })?;
+
+    object.peel_to_commit().map_err(|e| Error::Adapter {
+        package: "change-detection".to_string(),
+        message: format!("Git reference '{}' is not a commit: {}", git_ref, e),
+    })
+}
+
+fn tree<'repo>(repo: &'repo Repository, git_ref: &str) -> Result<Tree<'repo>> {
+    let object = repo.revparse_single(git_ref).map_err(|e| Error::Adapter {
+        package: "change-detection".to_string(),
+        message: format!("Failed to parse git reference '{}': {}", git_ref, e),
+    })?;
+
+    object.peel_to_tree().map_err(|e| Error::Adapter {
+        package: "change-detection".to_string(),
+        message: format!("Failed to get tree from git reference '{}': {}", git_ref, e),
+    })
+}
+
+/// Counts the commits reachable from `head` in the local history.
+fn history_depth(repo: &Repository, head: Oid) -> Result<usize> {
+    let mut walk = repo.revwalk().map_err(|e| Error::Adapter {
+        package: "change-detection".to_string(),
+        message: format!("Failed to walk git history: {}", e),
+    })?;
+    walk.push(head).map_err(|e| Error::Adapter {
+        package: "change-detection".to_string(),
+        message: format!("Failed to walk git history: {}", e),
+    })?;
+    Ok(walk.filter(|oid| oid.is_ok()).count())
+}
+
+fn diff_paths(diff: &Diff<'_>) -> Result<Vec<PathBuf>> {
+    let mut changed_files = Vec::new();
+    diff.foreach(
+        &mut |delta, _| {
+            if let Some(path) = delta.new_file().path() {
+                changed_files.push(path.to_path_buf());
+            }
+            true
+        },
+        None,
+        None,
+        None,
+    )
+    .map_err(|e| Error::Adapter {
+        package: "change-detection".to_string(),
+        message: format!("Failed to iterate over git diff: {}", e),
+    })?;
+
+    Ok(changed_files)
+}
 
 /// Detects packages affected by file changes.
 pub struct ChangeDetector;
@@ -19,28 +207,67 @@ impl ChangeDetector {
         changed_files: &[impl AsRef<Path>],
         packages_dir: impl AsRef<Path>,
     ) -> Result<HashSet<String>> {
-        let packages_dir = packages_dir.as_ref();
-        let mut changed_packages = HashSet::new();
+        Self::detect_with_global_inputs(
+            graph,
+            changed_files,
+            packages_dir,
+            &GlobalInputs::default(),
+        )
+    }
 
-        for file_path in changed_files {
-            let path = file_path.as_ref();
-            if let Some(package_name) = Self::file_to_package(path, packages_dir) {
-                changed_packages.insert(package_name);
-            }
-        }
+    /// Determines which packages are affected by the given changed files,
+    /// counting changes to `global_inputs` against the packages they select.
+    pub fn detect_with_global_inputs(
+        graph: &DependencyGraph,
+        changed_files: &[impl AsRef<Path>],
+        packages_dir: impl AsRef<Path>,
+        global_inputs: &GlobalInputs,
+    ) -> Result<HashSet<String>> {
+        let changed_packages =
+            Self::packages_for_files(graph, changed_files, packages_dir.as_ref(), global_inputs);
 
         graph.affected_packages(&changed_packages.into_iter().collect::<Vec<_>>())
     }
 
+    /// Determines which packages are affected by the changes in `range`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repository cannot be opened, a reference is
+    /// invalid or the diff fails.
     pub fn detect_from_git(
         graph: &DependencyGraph,
         packages_dir: impl AsRef<Path>,
-        base: Option<&str>,
+        range: &GitRange,
+        global_inputs: &GlobalInputs,
     ) -> Result<HashSet<String>> {
-        let base = base.unwrap_or("HEAD");
-        Self::validate_git_ref(base)?;
-        let changed_files = Self::git_diff_with_libgit2(base)?;
-        Self::detect_affected_packages(graph, &changed_files, packages_dir)
+        let changed_files = range.changed_files(&Self::open_repository()?)?;
+        Self::detect_with_global_inputs(graph, &changed_files, packages_dir, global_inputs)
+    }
+
+    /// Returns the packages with files committed on `HEAD` since it branched
+    /// from `base`, counting changes to `global_inputs` against the packages
+    /// they select.
+    ///
+    /// Unlike [`detect_from_git`](Self::detect_from_git), dependents of the
+    /// changed packages are not included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `base` is not a valid reference or the diff fails.
+    pub fn changed_packages(
+        graph: &DependencyGraph,
+        packages_dir: impl AsRef<Path>,
+        base: &str,
+        global_inputs: &GlobalInputs,
+    ) -> Result<HashSet<String>> {
+        let changed_files = GitRange::new(base).changed_files(&Self::open_repository()?)?;
+        Ok(Self::packages_for_files(
+            graph,
+            &changed_files,
+            packages_dir.as_ref(),
+            global_inputs,
+        ))
     }
 
     /// Reads changed files from stdin (one path per line).
@@ -64,8 +291,39 @@ impl ChangeDetector {
         Self::detect_affected_packages(graph, &changed_files, packages_dir)
     }
 
-    fn file_to_package(file_path: &Path, packages_dir: &Path) -> Option<String> {
-        path_utils::file_to_package(file_path, packages_dir)
+    /// Maps changed files to the packages containing them and the packages
+    /// selected by any global inputs they match.
+    fn packages_for_files(
+        graph: &DependencyGraph,
+        changed_files: &[impl AsRef<Path>],
+        packages_dir: &Path,
+        global_inputs: &GlobalInputs,
+    ) -> HashSet<String> {
+        let package_paths = PackagePaths::new(graph.all_packages());
+        let mut changed_packages = HashSet::new();
+
+        for file_path in changed_files {
+            let path = file_path.as_ref();
+            if let Some(package_name) = Self::file_to_package(path, packages_dir, &package_paths) {
+                changed_packages.insert(package_name);
+            }
+            changed_packages.extend(
+                global_inputs
+                    .packages_for(path)
+                    .into_iter()
+                    .map(|name| name.to_string()),
+            );
+        }
+
+        changed_packages
+    }
+
+    fn file_to_package(
+        file_path: &Path,
+        packages_dir: &Path,
+        package_paths: &PackagePaths,
+    ) -> Option<String> {
+        path_utils::file_to_package(file_path, packages_dir, package_paths)
     }
 
     fn validate_git_ref(git_ref: &str) -> Result<()> {
@@ -100,60 +358,10 @@ impl ChangeDetector {
         Ok(())
     }
 
-    fn git_diff_with_libgit2(base: &str) -> Result<Vec<PathBuf>> {
-        let repo = Repository::open_from_env().map_err(|e| Error::Adapter {
+    fn open_repository() -> Result<Repository> {
+        Repository::open_from_env().map_err(|e| Error::Adapter {
             package: "change-detection".to_string(),
             message: format!("Failed to open git repository: {}", e),
-        })?;
-
-        let base_obj = repo.revparse_single(base).map_err(|e| Error::Adapter {
-            package: "change-detection".to_string(),
-            message: format!("Failed to parse git reference '{}': {}", base, e),
-        })?;
-
-        let base_tree = base_obj.peel_to_tree().map_err(|e| Error::Adapter {
-            package: "change-detection".to_string(),
-            message: format!("Failed to get tree from git reference: {}", e),
-        })?;
-
-        let mut diff_opts = DiffOptions::new();
-        diff_opts.include_untracked(false);
-        diff_opts.recurse_untracked_dirs(false);
-
-        let head = repo.head().map_err(|e| Error::Adapter {
-            package: "change-detection".to_string(),
-            message: format!("Failed to get HEAD: {}", e),
-        })?;
-
-        let head_tree = head.peel_to_tree().map_err(|e| Error::Adapter {
-            package: "change-detection".to_string(),
-            message: format!("Failed to get tree from HEAD: {}", e),
-        })?;
-
-        let diff = repo
-            .diff_tree_to_tree(Some(&base_tree), Some(&head_tree), Some(&mut diff_opts))
-            .map_err(|e| Error::Adapter {
-                package: "change-detection".to_string(),
-                message: format!("Failed to compute git diff: {}", e),
-            })?;
-
-        let mut changed_files = Vec::new();
-        diff.foreach(
-            &mut |delta, _| {
-                if let Some(path) = delta.new_file().path() {
-                    changed_files.push(path.to_path_buf());
-                }
-                true
-            },
-            None,
-            None,
-            None,
-        )
-        .map_err(|e| Error::Adapter {
-            package: "change-detection".to_string(),
-            message: format!("Failed to iterate over git diff: {}", e),
-        })?;
-
-        Ok(changed_files)
+        })
     }
 }
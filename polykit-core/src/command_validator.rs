@@ -138,4 +138,27 @@ impl CommandValidator {
 
         Ok(())
     }
+
+    /// Validates a package tag such as `team:payments` or `layer:domain`.
+    ///
+    /// Tags allow the identifier characters plus `:` and `/`.
+    pub fn validate_tag(tag: &str) -> Result<()> {
+        if tag.is_empty() || tag.len() > 255 {
+            return Err(Error::InvalidPackageName(
+                "Tag must be between 1 and 255 characters".to_string(),
+            ));
+        }
+
+        if let Some(ch) = tag
+            .chars()
+            .find(|&ch| !ch.is_alphanumeric() && !"-_.@:/".contains(ch))
+        {
+            return Err(Error::InvalidPackageName(format!(
+                "Tag '{}' contains invalid character: '{}'",
+                tag, ch
+            )));
+        }
+
+        Ok(())
+    }
 }
@@ -23,6 +23,12 @@ pub struct Config {
     pub name: String,
     pub language: String,
     pub public: bool,
+    /// Free-form labels such as `team:payments` or `layer:domain`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Globs of files, relative to the package, whose changes don't affect it.
+    #[serde(default)]
+    pub affected_ignore: Vec<String>,
     #[serde(default)]
     pub deps: Deps,
     #[serde(deserialize_with = "deserialize_tasks")]
@@ -233,6 +239,122 @@ pub struct WorkspaceConfig {
     /// Remote cache configuration.
     #[serde(default)]
     pub remote_cache: Option<RemoteCacheConfig>,
+    /// Globs selecting package directories, relative to the workspace root.
+    #[serde(default)]
+    pub members: Vec<String>,
+    /// Globs removing directories from `members`.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Whether to infer packages from native workspace manifests.
+    ///
+    /// `None` infers only when neither `members` nor the packages directory
+    /// exists.
+    #[serde(default)]
+    pub infer: Option<bool>,
+    /// Files outside package directories whose changes affect packages.
+    #[serde(default)]
+    pub global_inputs: Vec<GlobalInput>,
+    /// Module-boundary rules from the top-level `[constraints]` table.
+    #[serde(skip)]
+    pub constraints: Constraints,
+}
+
+/// A workspace file glob, relative to the workspace root, that affects
+/// packages outside its own directory.
+///
+/// ```toml
+/// [workspace]
+/// global_inputs = [
+///     "Cargo.lock",
+///     ".github/**",
+///     { glob = "tsconfig.base.json", filter = ["lang:ts"] },
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    untagged,
+    deny_unknown_fields,
+    expecting = "a global input glob, or a table with `glob` and optional `filter`"
+)]
+pub enum GlobalInput {
+    /// Affects every package.
+    Glob(String),
+    /// Affects the packages selected by `filter`, or every package if it is
+    /// empty.
+    Filtered {
+        glob: String,
+        #[serde(default)]
+        filter: Vec<String>,
+    },
+}
+
+impl GlobalInput {
+    pub fn glob(&self) -> &str {
+        match self {
+            GlobalInput::Glob(glob) | GlobalInput::Filtered { glob, .. } => glob,
+        }
+    }
+
+    /// Returns the `--filter` selectors; empty selects every package.
+    pub fn filter(&self) -> &[String] {
+        match self {
+            GlobalInput::Glob(_) => &[],
+            GlobalInput::Filtered { filter, .. } => filter,
+        }
+    }
+}
+
+/// Module-boundary rules checked by `polykit validate`.
+///
+/// ```toml
+/// [constraints]
+/// deny_public_to_private = true
+///
+/// [[constraints.rules]]
+/// tag = "layer:ui"
+/// deny = ["layer:infra"]
+///
+/// [[constraints.rules]]
+/// tag = "layer:domain"
+/// allow = ["layer:domain", "layer:shared"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Constraints {
+    /// Public packages may not depend on private ones.
+    #[serde(default)]
+    pub deny_public_to_private: bool,
+    #[serde(default)]
+    pub rules: Vec<ConstraintRule>,
+}
+
+/// Restricts the direct dependencies of packages carrying `tag`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConstraintRule {
+    pub tag: String,
+    /// If set, dependencies must carry at least one of these tags.
+    #[serde(default)]
+    pub allow: Option<Vec<String>>,
+    /// Dependencies may not carry any of these tags.
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Partial package configuration layered over an inferred package.
+///
+/// Every field is optional; set fields replace the values read from the
+/// native manifest and tasks are merged by name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageOverride {
+    pub name: Option<String>,
+    pub language: Option<String>,
+    pub public: Option<bool>,
+    pub tags: Option<Vec<String>>,
+    pub affected_ignore: Option<Vec<String>>,
+    pub deps: Option<Deps>,
+    #[serde(default, deserialize_with = "deserialize_tasks")]
+    pub tasks: FxHashMap<String, TaskValue>,
 }
 
 impl Config {
@@ -243,46 +365,50 @@ impl Config {
     }
 
     pub fn to_tasks(&self) -> Vec<Task> {
-        self.tasks
-            .iter()
-            .map(|(name, task_value)| match task_value {
-                TaskValue::Simple(command) => Task {
-                    name: name.clone(),
-                    command: command.clone(),
-                    depends_on: Vec::new(),
-                },
-                TaskValue::Complex {
-                    command,
-                    depends_on,
-                } => Task {
-                    name: name.clone(),
-                    command: command.clone(),
-                    depends_on: depends_on.clone(),
-                },
-            })
-            .collect()
+        tasks_from_map(&self.tasks)
     }
 }
 
 impl WorkspaceConfig {
     pub fn to_tasks(&self) -> Vec<Task> {
-        self.tasks
-            .iter()
-            .map(|(name, task_value)| match task_value {
-                TaskValue::Simple(command) => Task {
-                    name: name.clone(),
-                    command: command.clone(),
-                    depends_on: Vec::new(),
-                },
-                TaskValue::Complex {
-                    command,
-                    depends_on,
-                } => Task {
-                    name: name.clone(),
-                    command: command.clone(),
-                    depends_on: depends_on.clone(),
-                },
+        tasks_from_map(&self.tasks)
+    }
+}
+
+impl PackageOverride {
+    pub fn parse_language(&self) -> Result<Option<Language>, crate::Error> {
+        self.language
+            .as_deref()
+            .map(|lang| {
+                Language::from_str(lang).ok_or_else(|| crate::Error::InvalidLanguage {
+                    lang: lang.to_string(),
+                })
             })
-            .collect()
+            .transpose()
+    }
+
+    pub fn to_tasks(&self) -> Vec<Task> {
+        tasks_from_map(&self.tasks)
     }
 }
+
+fn tasks_from_map(tasks: &FxHashMap<String, TaskValue>) -> Vec<Task> {
+    tasks
+        .iter()
+        .map(|(name, task_value)| match task_value {
+            TaskValue::Simple(command) => Task {
+                name: name.clone(),
+                command: command.clone(),
+                depends_on: Vec::new(),
+            },
+            TaskValue::Complex {
+                command,
+                depends_on,
+            } => Task {
+                name: name.clone(),
+                command: command.clone(),
+                depends_on: depends_on.clone(),
+            },
+        })
+        .collect()
+}
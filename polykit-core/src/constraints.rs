@@ -0,0 +1,150 @@
+//! Module-boundary checks over the dependency graph.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::config::Constraints;
+use crate::graph::DependencyGraph;
+use crate::package::Package;
+
+/// A dependency edge that breaks a `[constraints]` rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConstraintViolation {
+    pub package: String,
+    pub dependency: String,
+    /// The rule that was broken, in words.
+    pub rule: String,
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}: {}", self.package, self.dependency, self.rule)
+    }
+}
+
+impl Constraints {
+    /// Returns `true` if no rule is configured.
+    pub fn is_empty(&self) -> bool {
+        !self.deny_public_to_private && self.rules.is_empty()
+    }
+
+    /// Checks every direct dependency edge against the rules.
+    ///
+    /// An edge breaking several rules is reported once per rule. Violations
+    /// are in topological order of the depending package.
+    pub fn check(&self, graph: &DependencyGraph) -> Vec<ConstraintViolation> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        let mut violations = Vec::new();
+        for name in graph.topological_order() {
+            let Some(package) = graph.get_package(&name) else {
+                continue;
+            };
+            let mut deps = graph.dependencies(&name).unwrap_or_default();
+            deps.sort_unstable();
+            for dep in deps.iter().filter_map(|dep| graph.get_package(dep)) {
+                for rule in self.broken_rules(package, dep) {
+                    violations.push(ConstraintViolation {
+                        package: package.name.clone(),
+                        dependency: dep.name.clone(),
+                        rule,
+                    });
+                }
+            }
+        }
+        violations
+    }
+
+    fn broken_rules(&self, package: &Package, dep: &Package) -> Vec<String> {
+        let mut broken = Vec::new();
+        if self.deny_public_to_private && package.public && !dep.public {
+            broken.push("public packages may not depend on private packages".to_string());
+        }
+
+        for rule in self.rules.iter().filter(|rule| package.has_tag(&rule.tag)) {
+            if let Some(denied) = rule.deny.iter().find(|tag| dep.has_tag(tag)) {
+                broken.push(format!("{} may not depend on {}", rule.tag, denied));
+            }
+            if let Some(ref allowed) = rule.allow {
+                if !allowed.iter().any(|tag| dep.has_tag(tag)) {
+                    broken.push(format!(
+                        "{} may only depend on {}",
+                        rule.tag,
+                        allowed.join(", ")
+                    ));
+                }
+            }
+        }
+        broken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConstraintRule;
+    use crate::package::Language;
+    use std::path::PathBuf;
+
+    fn package(name: &str, public: bool, tags: &[&str], deps: &[&str]) -> Package {
+        let mut package = Package::new(
+            name.to_string(),
+            Language::Ts,
+            public,
+            PathBuf::from(name),
+            deps.iter().map(|d| d.to_string()).collect(),
+            vec![],
+        );
+        package.tags = tags.iter().map(|t| t.to_string()).collect();
+        package
+    }
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reports_every_violating_edge() {
+        let graph = DependencyGraph::new(vec![
+            package("db", false, &["layer:infra"], &[]),
+            package("model", true, &["layer:domain"], &[]),
+            package("button", true, &["layer:ui"], &["db", "model"]),
+            package("billing", true, &["layer:domain"], &["model", "db"]),
+        ])
+        .unwrap();
+        let constraints = Constraints {
+            deny_public_to_private: true,
+            rules: vec![
+                ConstraintRule {
+                    tag: "layer:ui".to_string(),
+                    allow: None,
+                    deny: tags(&["layer:infra"]),
+                },
+                ConstraintRule {
+                    tag: "layer:domain".to_string(),
+                    allow: Some(tags(&["layer:domain"])),
+                    deny: Vec::new(),
+                },
+            ],
+        };
+
+        let violations: Vec<String> = constraints
+            .check(&graph)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+
+        assert_eq!(violations.len(), 4);
+        assert!(violations.contains(
+            &"button -> db: public packages may not depend on private packages".to_string()
+        ));
+        assert!(violations
+            .contains(&"button -> db: layer:ui may not depend on layer:infra".to_string()));
+        assert!(violations
+            .contains(&"billing -> db: layer:domain may only depend on layer:domain".to_string()));
+        assert!(Constraints::default().check(&graph).is_empty());
+    }
+}
@@ -0,0 +1,289 @@
+//! Structural problems in a set of packages.
+
+use std::collections::hash_map::Entry;
+use std::collections::VecDeque;
+use std::fmt;
+
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+
+use crate::package::Package;
+
+/// A problem that prevents building a dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Diagnostic {
+    /// A dependency cycle, as a path that starts and ends at the same package.
+    Cycle { path: Vec<String> },
+    /// A dependency on a package that does not exist.
+    UnknownDependency {
+        package: String,
+        dependency: String,
+        /// The closest existing package name, if one is close enough.
+        suggestion: Option<String>,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::Cycle { path } => {
+                write!(f, "Circular dependency: {}", path.join(" -> "))
+            }
+            Diagnostic::UnknownDependency {
+                package,
+                dependency,
+                suggestion,
+            } => {
+                write!(
+                    f,
+                    "Package '{}' depends on unknown package '{}'",
+                    package, dependency
+                )?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " (did you mean '{}'?)", suggestion)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Renders diagnostics for an error message, one per line if there are several.
+pub(crate) fn render(diagnostics: &[Diagnostic]) -> String {
+    match diagnostics {
+        [diagnostic] => format!(" {}", diagnostic),
+        _ => diagnostics
+            .iter()
+            .map(|diagnostic| format!("\n  - {}", diagnostic))
+            .collect(),
+    }
+}
+
+/// Checks packages for unknown dependencies and dependency cycles.
+///
+/// Every problem is reported in one pass: unknown dependencies in package
+/// order, then one cycle per strongly connected component. An empty result
+/// means [`DependencyGraph::new`](crate::graph::DependencyGraph::new) will
+/// succeed.
+pub fn diagnose(packages: &[Package]) -> Vec<Diagnostic> {
+    let mut graph: DiGraph<&str, ()> = DiGraph::with_capacity(packages.len(), packages.len() * 2);
+    let nodes: FxHashMap<&str, NodeIndex> = packages
+        .iter()
+        .map(|package| (package.name.as_str(), graph.add_node(package.name.as_str())))
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    for package in packages {
+        for dep in &package.deps {
+            match nodes.get(dep.as_str()) {
+                Some(&to) => {
+                    graph.add_edge(nodes[package.name.as_str()], to, ());
+                }
+                None => diagnostics.push(Diagnostic::UnknownDependency {
+                    package: package.name.clone(),
+                    dependency: dep.clone(),
+                    suggestion: suggest(dep, nodes.keys().copied()),
+                }),
+            }
+        }
+    }
+
+    diagnostics.extend(
+        cycle_paths(&graph, |node| graph[node].to_string())
+            .into_iter()
+            .map(|path| Diagnostic::Cycle { path }),
+    );
+    diagnostics
+}
+
+/// Finds one cycle in every strongly connected component of a graph.
+///
+/// Each cycle is the shortest one through the component's alphabetically
+/// first package, so the output is stable across runs. Cycles are sorted.
+pub(crate) fn cycle_paths<N>(
+    graph: &DiGraph<N, ()>,
+    name: impl Fn(NodeIndex) -> String,
+) -> Vec<Vec<String>> {
+    let mut cycles: Vec<Vec<String>> = tarjan_scc(graph)
+        .into_iter()
+        .filter(|component| component.len() > 1 || graph.contains_edge(component[0], component[0]))
+        .filter_map(|component| {
+            let start = *component.iter().min_by_key(|&&node| name(node))?;
+            let members: FxHashSet<NodeIndex> = component.into_iter().collect();
+            shortest_cycle(graph, start, &members).map(|path| path.into_iter().map(&name).collect())
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+/// Breadth-first search from `start` back to itself within `members`.
+fn shortest_cycle<N>(
+    graph: &DiGraph<N, ()>,
+    start: NodeIndex,
+    members: &FxHashSet<NodeIndex>,
+) -> Option<Vec<NodeIndex>> {
+    let mut parents: FxHashMap<NodeIndex, NodeIndex> = FxHashMap::default();
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(node) = queue.pop_front() {
+        let mut next: Vec<NodeIndex> = graph
+            .neighbors_directed(node, Direction::Outgoing)
+            .filter(|next| members.contains(next))
+            .collect();
+        next.sort_unstable();
+        for next in next {
+            if next == start {
+                let mut path = vec![node];
+                while let Some(&parent) = parents.get(path.last()?) {
+                    path.push(parent);
+                }
+                path.reverse();
+                path.push(start);
+                return Some(path);
+            }
+            if let Entry::Vacant(entry) = parents.entry(next) {
+                entry.insert(node);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the candidate closest to `name`, if it is within a third of the
+/// name's length in edits.
+pub(crate) fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Edit distance, ignoring case, where swapping two adjacent characters
+/// counts as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::Language;
+    use std::path::PathBuf;
+
+    fn package(name: &str, deps: &[&str]) -> Package {
+        Package::new(
+            name.to_string(),
+            Language::Rust,
+            true,
+            PathBuf::from(name),
+            deps.iter().map(|d| d.to_string()).collect(),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("utils", "utils"), 0);
+        assert_eq!(edit_distance("utlis", "utils"), 1);
+        assert_eq!(edit_distance("api", "apis"), 1);
+        assert_eq!(edit_distance("Core", "core"), 0);
+        assert_eq!(edit_distance("", "api"), 3);
+    }
+
+    #[test]
+    fn test_every_cycle_as_ordered_path() {
+        let diagnostics = diagnose(&[
+            package("c", &["a"]),
+            package("a", &["b"]),
+            package("b", &["c"]),
+            package("x", &["y"]),
+            package("y", &["x"]),
+            package("self", &["self"]),
+            package("ok", &["a"]),
+        ]);
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::Cycle {
+                    path: vec!["a", "b", "c", "a"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                },
+                Diagnostic::Cycle {
+                    path: vec!["self", "self"].into_iter().map(String::from).collect(),
+                },
+                Diagnostic::Cycle {
+                    path: vec!["x", "y", "x"].into_iter().map(String::from).collect(),
+                },
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "Circular dependency: a -> b -> c -> a"
+        );
+    }
+
+    #[test]
+    fn test_unknown_dependencies_with_suggestions() {
+        let diagnostics = diagnose(&[
+            package("utils", &[]),
+            package("api", &["utlis", "database"]),
+            package("web", &["api", "Utils"]),
+        ]);
+
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "Package 'api' depends on unknown package 'utlis' (did you mean 'utils'?)"
+        );
+        assert_eq!(
+            diagnostics[1],
+            Diagnostic::UnknownDependency {
+                package: "api".to_string(),
+                dependency: "database".to_string(),
+                suggestion: None,
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&diagnostics[2]).unwrap(),
+            serde_json::json!({
+                "kind": "unknown_dependency",
+                "package": "web",
+                "dependency": "Utils",
+                "suggestion": "utils",
+            })
+        );
+    }
+}
@@ -4,6 +4,8 @@ use std::path::PathBuf;
 
 use thiserror::Error;
 
+use crate::diagnostics::{render, Diagnostic};
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("IO error")]
@@ -31,6 +33,9 @@ pub enum Error {
     #[error("Circular dependency detected: {0}. Use 'polykit graph' to visualize dependencies.")]
     CircularDependency(String),
 
+    #[error("Invalid dependency graph:{}", render(.0))]
+    InvalidGraph(Vec<Diagnostic>),
+
     #[error("Config file not found. Expected 'polykit.toml' in package directory.")]
     ConfigNotFound(PathBuf),
 
@@ -40,6 +45,12 @@ pub enum Error {
     #[error("Graph error: {0}")]
     Graph(String),
 
+    #[error("Invalid query: {0}")]
+    Query(String),
+
+    #[error("Invalid filter '{filter}': {message}")]
+    InvalidFilter { filter: String, message: String },
+
     #[error("Task execution failed for {package}::{task}")]
     TaskExecution {
         package: String,
@@ -4,6 +4,7 @@ use std::collections::HashSet;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crate::command_validator::CommandValidator;
 use crate::error::{Error, Result};
@@ -162,6 +163,7 @@ impl TaskExecutor {
 
         self.command_validator.validate(&task.command)?;
 
+        let start = Instant::now();
         let output = Command::new("sh")
             .arg("-c")
             .arg(&task.command)
@@ -193,6 +195,8 @@ impl TaskExecutor {
             success: output.status.success(),
             stdout,
             stderr,
+            duration: start.elapsed(),
+            cached: false,
         };
 
         // Store in local cache
@@ -303,6 +307,8 @@ impl TaskExecutor {
                 success: true,
                 stdout: String::new(), // Outputs are in files, not stdout
                 stderr: String::new(),
+                duration: Duration::ZERO,
+                cached: true,
             }))
         } else {
             Ok(None)
@@ -310,10 +316,15 @@ impl TaskExecutor {
     }
 
     pub fn execute_task(&self, package: &Package, task_name: &str) -> Result<TaskResult> {
+        let start = Instant::now();
         let results = self.execute_task_with_deps(package, task_name)?;
         results
             .into_iter()
             .find(|r| r.task_name == task_name)
+            .map(|result| TaskResult {
+                duration: start.elapsed(),
+                ..result
+            })
             .ok_or_else(|| Error::TaskExecution {
                 package: package.name.clone(),
                 task: task_name.to_string(),
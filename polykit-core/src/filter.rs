@@ -0,0 +1,413 @@
+//! pnpm-style package selectors for `--filter`.
+//!
+//! | Selector | Selects |
+//! |----------|---------|
+//! | `api` | The package `api` |
+//! | `api-*` | Packages whose name matches a glob |
+//! | `api...` | `api` and everything it depends on |
+//! | `...api` | `api` and everything that depends on it |
+//! | `./apps/*`, `{apps/*}` | Packages whose directory matches a glob |
+//! | `lang:rust` | Packages in a language |
+//! | `tag:team:payments` | Packages with a tag |
+//! | `[origin/main]` | Packages with files changed since a git reference |
+//! | `!api` | Excludes what the rest of the selector matches |
+//!
+//! `...` can surround any selector, so `...[origin/main]` selects the changed
+//! packages and their dependents. Several selectors are combined: packages
+//! matched by any positive selector, minus packages matched by any `!`
+//! selector. If every selector is negative, they exclude from all packages.
+
+use std::path::{Component, Path};
+
+use globset::{GlobBuilder, GlobMatcher};
+use rustc_hash::FxHashSet;
+
+use crate::change::ChangeDetector;
+use crate::command_validator::CommandValidator;
+use crate::diagnostics::suggest;
+use crate::error::{Error, Result};
+use crate::graph::DependencyGraph;
+use crate::package::Language;
+use crate::path_utils::GlobalInputs;
+use crate::query::reachable;
+
+/// What a selector matches before `...` expansion.
+#[derive(Debug, Clone)]
+enum Target {
+    Name(String),
+    NameGlob(GlobMatcher),
+    Path(GlobMatcher),
+    Language(Language),
+    Tag(String),
+    Changed(String),
+}
+
+#[derive(Debug, Clone)]
+struct Selector {
+    filter: String,
+    target: Target,
+    exclude: bool,
+    with_dependencies: bool,
+    with_dependents: bool,
+}
+
+/// A set of `--filter` selectors.
+#[derive(Debug, Clone, Default)]
+pub struct PackageFilter {
+    selectors: Vec<Selector>,
+}
+
+impl PackageFilter {
+    /// Parses selectors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a selector is empty, uses an unknown `kind:`
+    /// prefix or language, or contains an invalid glob.
+    pub fn parse(filters: &[String]) -> Result<Self> {
+        let selectors = filters
+            .iter()
+            .map(|filter| parse_selector(filter))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { selectors })
+    }
+
+    /// Returns `true` if there are no selectors.
+    pub fn is_empty(&self) -> bool {
+        self.selectors.is_empty()
+    }
+
+    /// Resolves the selectors against a graph.
+    ///
+    /// Returns the selected packages in topological order. Paths are matched
+    /// relative to `packages_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a selector names a package that does not exist,
+    /// a name or path glob selects no package, or a git reference cannot be
+    /// diffed.
+    pub fn select(&self, graph: &DependencyGraph, packages_dir: &Path) -> Result<Vec<String>> {
+        self.select_with_global_inputs(graph, packages_dir, &GlobalInputs::default())
+    }
+
+    /// Resolves the selectors against a graph, counting changes to
+    /// `global_inputs` against the packages they select in `[ref]`
+    /// selectors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a selector names a package that does not exist,
+    /// a name or path glob selects no package, or a git reference cannot be
+    /// diffed.
+    pub fn select_with_global_inputs(
+        &self,
+        graph: &DependencyGraph,
+        packages_dir: &Path,
+        global_inputs: &GlobalInputs,
+    ) -> Result<Vec<String>> {
+        let order = graph.topological_order();
+        let mut included: Option<FxHashSet<String>> = None;
+        let mut excluded: FxHashSet<String> = FxHashSet::default();
+
+        for selector in &self.selectors {
+            let matched = selector.resolve(graph, packages_dir, global_inputs, &order)?;
+            if selector.exclude {
+                excluded.extend(matched);
+            } else {
+                included
+                    .get_or_insert_with(FxHashSet::default)
+                    .extend(matched);
+            }
+        }
+
+        Ok(order
+            .into_iter()
+            .filter(|name| {
+                included
+                    .as_ref()
+                    .is_none_or(|included| included.contains(name))
+            })
+            .filter(|name| !excluded.contains(name))
+            .collect())
+    }
+}
+
+impl Selector {
+    fn resolve(
+        &self,
+        graph: &DependencyGraph,
+        packages_dir: &Path,
+        global_inputs: &GlobalInputs,
+        order: &[String],
+    ) -> Result<FxHashSet<String>> {
+        let packages = order.iter().filter_map(|name| graph.get_package(name));
+        let matched: FxHashSet<String> = match &self.target {
+            Target::Name(name) => {
+                if graph.get_package(name).is_none() {
+                    let message = match suggest(name, order.iter().map(|n| n.as_str())) {
+                        Some(suggestion) => {
+                            format!(
+                                "no package named '{}' (did you mean '{}'?)",
+                                name, suggestion
+                            )
+                        }
+                        None => format!("no package named '{}'", name),
+                    };
+                    return Err(filter_error(&self.filter, message));
+                }
+                FxHashSet::from_iter([name.clone()])
+            }
+            Target::NameGlob(matcher) => packages
+                .filter(|package| matcher.is_match(&package.name))
+                .map(|package| package.name.clone())
+                .collect(),
+            Target::Path(matcher) => packages
+                .filter(|package| matcher.is_match(normalize(&package.path)))
+                .map(|package| package.name.clone())
+                .collect(),
+            Target::Language(language) => packages
+                .filter(|package| package.language == *language)
+                .map(|package| package.name.clone())
+                .collect(),
+            Target::Tag(tag) => packages
+                .filter(|package| package.has_tag(tag))
+                .map(|package| package.name.clone())
+                .collect(),
+            Target::Changed(base) => {
+                ChangeDetector::changed_packages(graph, packages_dir, base, global_inputs)?
+                    .into_iter()
+                    .collect()
+            }
+        };
+
+        // A glob that selects nothing is almost always a typo; excluding
+        // nothing is harmless
+        if matched.is_empty() && !self.exclude {
+            let message = match &self.target {
+                Target::NameGlob(_) => Some("no package name matches the glob"),
+                Target::Path(_) => Some("no package directory matches the glob"),
+                _ => None,
+            };
+            if let Some(message) = message {
+                return Err(filter_error(&self.filter, message.to_string()));
+            }
+        }
+
+        let mut result = matched.clone();
+        if self.with_dependencies {
+            result.extend(reachable(graph, matched.clone(), None, false)?);
+        }
+        if self.with_dependents {
+            result.extend(reachable(graph, matched, None, true)?);
+        }
+        Ok(result)
+    }
+}
+
+fn parse_selector(filter: &str) -> Result<Selector> {
+    let mut rest = filter.trim();
+    let exclude = match rest.strip_prefix('!') {
+        Some(stripped) => {
+            rest = stripped;
+            true
+        }
+        None => false,
+    };
+    let with_dependents = match rest.strip_prefix("...") {
+        Some(stripped) => {
+            rest = stripped;
+            true
+        }
+        None => false,
+    };
+    let with_dependencies = match rest.strip_suffix("...") {
+        Some(stripped) => {
+            rest = stripped;
+            true
+        }
+        None => false,
+    };
+    if rest.is_empty() {
+        return Err(filter_error(filter, "selects nothing".to_string()));
+    }
+
+    let target = if let Some(base) = rest.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+        if base.is_empty() {
+            return Err(filter_error(filter, "git reference is empty".to_string()));
+        }
+        Target::Changed(base.to_string())
+    } else if let Some(path) = rest.strip_prefix('{').and_then(|r| r.strip_suffix('}')) {
+        Target::Path(path_glob(filter, path)?)
+    } else if rest == "." || rest.starts_with("./") || rest.starts_with("../") {
+        Target::Path(path_glob(filter, rest)?)
+    } else if let Some(tag) = rest.strip_prefix("tag:") {
+        CommandValidator::validate_tag(tag).map_err(|e| filter_error(filter, e.to_string()))?;
+        Target::Tag(tag.to_string())
+    } else if let Some((kind, value)) = rest.split_once(':') {
+        if kind != "lang" {
+            return Err(filter_error(
+                filter,
+                format!(
+                    "unknown selector '{}:', expected 'lang:<language>' or 'tag:<tag>'",
+                    kind
+                ),
+            ));
+        }
+        let language = Language::from_str(value).ok_or_else(|| {
+            filter_error(
+                filter,
+                format!(
+                    "unknown language '{}', expected js, ts, python, go or rust",
+                    value
+                ),
+            )
+        })?;
+        Target::Language(language)
+    } else if rest.contains(['*', '?', '[', '{']) {
+        Target::NameGlob(glob(filter, rest)?)
+    } else {
+        Target::Name(rest.to_string())
+    };
+
+    Ok(Selector {
+        filter: filter.to_string(),
+        target,
+        exclude,
+        with_dependencies,
+        with_dependents,
+    })
+}
+
+/// Compiles a directory glob, relative to the packages root.
+fn path_glob(filter: &str, pattern: &str) -> Result<GlobMatcher> {
+    let path = Path::new(pattern.trim_end_matches('/'));
+    if path
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(filter_error(
+            filter,
+            "paths must be relative and inside the workspace".to_string(),
+        ));
+    }
+    glob(filter, &normalize(path))
+}
+
+fn glob(filter: &str, pattern: &str) -> Result<GlobMatcher> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| filter_error(filter, e.to_string()))
+}
+
+/// Joins the normal components of a path with `/`, dropping `.`.
+fn normalize(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn filter_error(filter: &str, message: String) -> Error {
+    Error::InvalidFilter {
+        filter: filter.to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::Package;
+    use std::path::PathBuf;
+
+    fn package(name: &str, language: Language, path: &str, deps: &[&str]) -> Package {
+        Package::new(
+            name.to_string(),
+            language,
+            true,
+            PathBuf::from(path),
+            deps.iter().map(|d| d.to_string()).collect(),
+            vec![],
+        )
+    }
+
+    fn graph() -> DependencyGraph {
+        let mut utils = package("utils", Language::Rust, "libs/utils", &["core"]);
+        utils.tags = vec!["team:platform".to_string()];
+        DependencyGraph::new(vec![
+            package("core", Language::Rust, "libs/core", &[]),
+            utils,
+            package("api", Language::Python, "apps/api", &["utils"]),
+            package("web", Language::Ts, "apps/web", &["api"]),
+            package("docs", Language::Js, "docs", &[]),
+        ])
+        .unwrap()
+    }
+
+    fn select(filters: &[&str]) -> Result<Vec<String>> {
+        let filters: Vec<String> = filters.iter().map(|f| f.to_string()).collect();
+        PackageFilter::parse(&filters)?.select(&graph(), Path::new("."))
+    }
+
+    #[test]
+    fn test_dependency_expansion() {
+        assert_eq!(select(&["api..."]).unwrap(), ["core", "utils", "api"]);
+        assert_eq!(select(&["...utils"]).unwrap(), ["utils", "api", "web"]);
+        assert_eq!(
+            select(&["...api..."]).unwrap(),
+            ["core", "utils", "api", "web"]
+        );
+    }
+
+    #[test]
+    fn test_paths_languages_and_globs() {
+        assert_eq!(select(&["./apps/*"]).unwrap(), ["api", "web"]);
+        assert_eq!(select(&["{libs/**}"]).unwrap(), ["core", "utils"]);
+        assert_eq!(
+            select(&["lang:rust", "docs"]).unwrap(),
+            ["core", "utils", "docs"]
+        );
+        assert_eq!(select(&["*s"]).unwrap(), ["utils", "docs"]);
+        assert_eq!(
+            select(&["...tag:team:platform"]).unwrap(),
+            ["utils", "api", "web"]
+        );
+    }
+
+    #[test]
+    fn test_exclusions() {
+        assert_eq!(select(&["web...", "!lang:rust"]).unwrap(), ["api", "web"]);
+        assert_eq!(select(&["!./apps/*", "!docs"]).unwrap(), ["core", "utils"]);
+    }
+
+    #[test]
+    fn test_unknown_selectors_are_errors() {
+        let error = select(&["utlis"]).unwrap_err().to_string();
+        assert!(error.contains("did you mean 'utils'"), "{}", error);
+        assert!(select(&["owner:me"]).is_err());
+        assert!(select(&["lang:cobol"]).is_err());
+        assert!(select(&["..."]).is_err());
+        assert!(select(&["[]"]).is_err());
+        assert!(select(&["../outside"]).is_err());
+    }
+
+    #[test]
+    fn test_globs_matching_nothing_are_errors() {
+        let error = select(&["./services/*"]).unwrap_err().to_string();
+        assert!(error.contains("no package directory matches"), "{}", error);
+        let error = select(&["{apps/*/nested}"]).unwrap_err().to_string();
+        assert!(error.contains("no package directory matches"), "{}", error);
+        let error = select(&["ap*x"]).unwrap_err().to_string();
+        assert!(error.contains("no package name matches"), "{}", error);
+        assert!(select(&["...ap*x..."]).is_err());
+
+        // Excluding nothing is allowed
+        assert_eq!(select(&["./apps/*", "!./services/*"]).unwrap(), ["api", "web"]);
+    }
+}
@@ -1,6 +1,7 @@
 //! Dependency graph management using petgraph.
 
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
@@ -8,11 +9,11 @@ use std::sync::Arc;
 use petgraph::algo::toposort;
 use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::Direction;
-use petgraph::visit::EdgeRef;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use dashmap::DashMap;
 
+use crate::diagnostics::{cycle_paths, diagnose};
 use crate::error::{Error, Result};
 use crate::package::Package;
 use crate::string_interner::intern;
@@ -26,6 +27,13 @@ pub struct GraphChange {
     pub dependency_changes: Vec<(String, Vec<String>)>,
 }
 
+impl GraphChange {
+    /// Returns `true` if no package was added, modified or removed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GraphNode {
     pub package: Package,
@@ -35,6 +43,8 @@ pub struct GraphNode {
 /// Serializable graph data for persistence.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SerializableGraph {
+    /// Identifies the inputs the graph was built from, `0` if unknown.
+    fingerprint: u64,
     packages: Vec<Package>,
     #[serde(serialize_with = "serialize_arc_str_vec")]
     #[serde(deserialize_with = "deserialize_arc_str_vec")]
@@ -95,6 +105,11 @@ where
 ///
 /// Uses compact u32 indices internally for better memory efficiency and cache performance.
 /// Public API maintains String-based interface for compatibility.
+///
+/// The topological order is maintained incrementally: adding a dependency
+/// only reorders the packages between its two ends (Pearce–Kelly), and
+/// removed packages leave tombstones that are compacted once they outnumber
+/// the live packages.
 #[derive(Debug, Clone)]
 pub struct DependencyGraph {
     // Internal compact representation using u32 indices
@@ -102,16 +117,19 @@ pub struct DependencyGraph {
     id_to_name: Vec<Arc<str>>,
     name_to_id: FxHashMap<Arc<str>, u32>,
     
-    // Package data indexed by u32 ID
+    // Package data indexed by u32 ID, `None` nodes are tombstones
     packages: Vec<Package>,
+    id_to_node: Vec<Option<NodeIndex>>,
+    tombstones: usize,
     
-    // Cached computations using u32 indices internally
-    cached_topological_order: Vec<u32>,
-    dependency_levels: Vec<Vec<u32>>,
+    // Topological order as slots (empty for tombstones), each ID's slot,
+    // and each ID's dependency level
+    order: Vec<Option<u32>>,
+    positions: Vec<u32>,
+    levels: Vec<u32>,
     
     // Public API compatibility (kept for fast lookups)
     name_to_node: FxHashMap<String, NodeIndex>,
-    node_to_id: FxHashMap<NodeIndex, u32>,
     
     // Cache for transitive dependencies
     transitive_deps_cache: DashMap<String, Arc<HashSet<String>>>,
@@ -122,15 +140,35 @@ impl DependencyGraph {
     ///
     /// # Errors
     ///
-    /// Returns an error if circular dependencies are detected.
+    /// Returns [`Error::InvalidGraph`] listing every dependency cycle and
+    /// unknown dependency if the packages do not form a valid graph.
     pub fn new(packages: Vec<Package>) -> Result<Self> {
+        let mut graph = Self::with_packages(packages)?;
+
+        // Diagnosis reruns the checks to report every problem, so it only
+        // runs once the graph is known to be invalid.
+        let sorted = toposort(&graph.graph, None)
+            .map_err(|_| Error::InvalidGraph(diagnose(&graph.packages)))?;
+
+        let order: Vec<u32> = sorted
+            .into_iter()
+            .rev()
+            .map(|idx| graph.graph[idx])
+            .collect();
+        graph.set_order(&order);
+        graph.compute_levels();
+
+        Ok(graph)
+    }
+
+    /// Adds the packages and their edges, leaving the order and levels empty.
+    fn with_packages(packages: Vec<Package>) -> Result<Self> {
         let package_count = packages.len();
         let mut graph = DiGraph::with_capacity(package_count, package_count * 2);
         let mut id_to_name = Vec::with_capacity(package_count);
         let mut name_to_id = FxHashMap::with_capacity_and_hasher(package_count, Default::default());
         let mut name_to_node = FxHashMap::with_capacity_and_hasher(package_count, Default::default());
-        let mut node_to_id = FxHashMap::with_capacity_and_hasher(package_count, Default::default());
-        let mut packages_vec = Vec::with_capacity(package_count);
+        let mut id_to_node = Vec::with_capacity(package_count);
 
         // First pass: intern names and assign u32 IDs
         for (idx, package) in packages.iter().enumerate() {
@@ -139,7 +177,6 @@ impl DependencyGraph {
             
             id_to_name.push(Arc::clone(&name_arc));
             name_to_id.insert(Arc::clone(&name_arc), id);
-            packages_vec.push(package.clone());
         }
 
         // Second pass: add nodes to graph with u32 IDs
@@ -147,7 +184,7 @@ impl DependencyGraph {
             let id = idx as u32;
             let node = graph.add_node(id);
             name_to_node.insert(package.name.clone(), node);
-            node_to_id.insert(node, id);
+            id_to_node.push(Some(node));
         }
 
         // Third pass: add edges
@@ -155,88 +192,61 @@ impl DependencyGraph {
             let from_node = name_to_node.get(&package.name).unwrap();
 
             for dep_name in &package.deps {
-                let to_node = name_to_node.get(dep_name).ok_or_else(|| {
-                    let name = dep_name.clone();
-                    Error::PackageNotFound {
-                        name: name.clone(),
-                        available: format!("Dependency '{}' not found", name),
-                    }
-                })?;
+                let Some(to_node) = name_to_node.get(dep_name) else {
+                    return Err(Error::InvalidGraph(diagnose(&packages)));
+                };
 
                 graph.add_edge(*from_node, *to_node, ());
             }
         }
 
-        let sorted = toposort(&graph, None).map_err(|cycle| {
-            let cycle_id = graph[cycle.node_id()];
-            let cycle_name = &id_to_name[cycle_id as usize];
-            Error::CircularDependency(format!("Cycle detected involving: {}", cycle_name))
-        })?;
-
-        let topological_order: Vec<u32> = sorted
-            .into_iter()
-            .rev()
-            .map(|idx| graph[idx])
-            .collect();
-
-        let dependency_levels =
-            Self::compute_dependency_levels_compact(&graph, &node_to_id, &topological_order)?;
-
         Ok(Self {
             graph,
             id_to_name,
             name_to_id,
-            packages: packages_vec,
-            cached_topological_order: topological_order,
-            dependency_levels,
+            packages,
+            id_to_node,
+            tombstones: 0,
+            order: Vec::new(),
+            positions: vec![0; package_count],
+            levels: vec![0; package_count],
             name_to_node,
-            node_to_id,
             transitive_deps_cache: DashMap::new(),
         })
     }
 
-    fn compute_dependency_levels_compact(
-        graph: &DiGraph<u32, ()>,
-        node_to_id: &FxHashMap<NodeIndex, u32>,
-        order: &[u32],
-    ) -> Result<Vec<Vec<u32>>> {
-        let mut levels = Vec::new();
-        let mut level_map = FxHashMap::with_capacity_and_hasher(order.len(), Default::default());
-
-        for &package_id in order {
-            // Find the node index for this ID
-            let node = node_to_id
-                .iter()
-                .find(|(_, &id)| id == package_id)
-                .map(|(node_idx, _)| *node_idx)
-                .ok_or_else(|| Error::PackageNotFound {
-                    name: format!("id-{}", package_id),
-                    available: format!("Package ID {} not found", package_id),
-                })?;
-
-            let dep_ids: Vec<u32> = graph
-                .neighbors_directed(node, Direction::Outgoing)
-                .map(|idx| graph[idx])
-                .collect();
-
-            let level = if dep_ids.is_empty() {
-                0
-            } else {
-                dep_ids.iter()
-                    .filter_map(|dep_id| level_map.get(dep_id))
-                    .max()
-                    .map(|l| l + 1)
-                    .unwrap_or(0)
-            };
+    /// Replaces the order with `order`, which must hold every ID once.
+    fn set_order(&mut self, order: &[u32]) {
+        self.order = order.iter().map(|&id| Some(id)).collect();
+        for (position, &id) in order.iter().enumerate() {
+            self.positions[id as usize] = position as u32;
+        }
+    }
 
-            level_map.insert(package_id, level);
-            while levels.len() <= level {
-                levels.push(Vec::new());
+    /// Computes every package's level in one pass over the order.
+    fn compute_levels(&mut self) {
+        for slot in 0..self.order.len() {
+            if let Some(id) = self.order[slot] {
+                self.levels[id as usize] = self.level_of(id);
             }
-            levels[level].push(package_id);
         }
+    }
+
+    /// Returns one more than the highest level among the package's
+    /// dependencies, or 0 if it has none.
+    fn level_of(&self, id: u32) -> u32 {
+        let node = self.node(id);
+        self.graph
+            .neighbors_directed(node, Direction::Outgoing)
+            .map(|dep| self.levels[self.graph[dep] as usize] + 1)
+            .max()
+            .unwrap_or(0)
+    }
 
-        Ok(levels)
+    /// Returns the node of a live package ID.
+    #[inline]
+    fn node(&self, id: u32) -> NodeIndex {
+        self.id_to_node[id as usize].expect("package ID was removed")
     }
 
     /// Converts a u32 ID to a package name string.
@@ -261,26 +271,42 @@ impl DependencyGraph {
 
     /// Returns packages in topological order (dependencies before dependents).
     ///
-    /// This is cached during graph construction for fast access.
+    /// This is maintained during construction and incremental updates.
     #[inline]
     pub fn topological_order(&self) -> Vec<String> {
-        self.cached_topological_order
-            .iter()
-            .map(|&id| self.id_to_name(id).to_string())
+        self.order_ids()
+            .map(|id| self.id_to_name(id).to_string())
             .collect()
     }
 
     /// Returns dependency levels for parallel execution.
     ///
     /// Each level contains packages that can be executed in parallel.
-    #[inline]
     pub fn dependency_levels(&self) -> Vec<Vec<String>> {
-        self.dependency_levels
-            .iter()
-            .map(|level| level.iter().map(|&id| self.id_to_name(id).to_string()).collect())
+        self.level_ids()
+            .into_iter()
+            .map(|level| level.into_iter().map(|id| self.id_to_name(id).to_string()).collect())
             .collect()
     }
 
+    /// Returns live package IDs in topological order.
+    fn order_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.order.iter().flatten().copied()
+    }
+
+    /// Groups live package IDs by level, each level in topological order.
+    fn level_ids(&self) -> Vec<Vec<u32>> {
+        let mut levels: Vec<Vec<u32>> = Vec::new();
+        for id in self.order_ids() {
+            let level = self.levels[id as usize] as usize;
+            if levels.len() <= level {
+                levels.resize_with(level + 1, Vec::new);
+            }
+            levels[level].push(id);
+        }
+        levels
+    }
+
     /// Returns direct dependencies of a package.
     ///
     /// # Errors
@@ -422,19 +448,39 @@ impl DependencyGraph {
     }
 
     /// Serializes the graph to a file for fast loading.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
     pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
-        // Convert u32 indices back to Arc<str> for serialization
-        let topological_order: Vec<Arc<str>> = self.cached_topological_order
-            .iter()
-            .map(|&id| Arc::clone(&self.id_to_name[id as usize]))
+        self.save_snapshot(path, 0)
+    }
+
+    /// Serializes the graph along with a fingerprint of the inputs it was
+    /// built from, such as the mtimes of the scanned `polykit.toml` files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save_snapshot(&self, path: impl AsRef<Path>, fingerprint: u64) -> Result<()> {
+        // Tombstones are skipped, so only live packages are saved.
+        let topological_order: Vec<Arc<str>> = self
+            .order_ids()
+            .map(|id| Arc::clone(&self.id_to_name[id as usize]))
             .collect();
-        let dependency_levels: Vec<Vec<Arc<str>>> = self.dependency_levels
-            .iter()
-            .map(|level| level.iter().map(|&id| Arc::clone(&self.id_to_name[id as usize])).collect())
+        let dependency_levels: Vec<Vec<Arc<str>>> = self
+            .level_ids()
+            .into_iter()
+            .map(|level| level.into_iter().map(|id| Arc::clone(&self.id_to_name[id as usize])).collect())
+            .collect();
+        let packages: Vec<Package> = self
+            .order_ids()
+            .map(|id| self.packages[id as usize].clone())
             .collect();
 
         let serializable = SerializableGraph {
-            packages: self.packages.clone(),
+            fingerprint,
+            packages,
             topological_order,
             dependency_levels,
         };
@@ -454,7 +500,23 @@ impl DependencyGraph {
     }
 
     /// Loads a graph from a previously saved file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid graph.
     pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_snapshot(path).map(|(graph, _)| graph)
+    }
+
+    /// Loads a graph and the fingerprint it was saved with.
+    ///
+    /// The saved topological order and levels are restored as they are,
+    /// without sorting the graph again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid graph.
+    pub fn load_snapshot(path: impl AsRef<Path>) -> Result<(Self, u64)> {
         let compressed = fs::read(path).map_err(Error::Io)?;
         let serialized = zstd::decode_all(&compressed[..]).map_err(|e| Error::Adapter {
             package: "graph".to_string(),
@@ -467,69 +529,136 @@ impl DependencyGraph {
                 message: format!("Failed to deserialize graph: {}", e),
             })?;
 
-        Self::new(serializable.packages)
+        let mut graph = Self::with_packages(serializable.packages)?;
+        let to_ids = |names: &[Arc<str>]| -> Result<Vec<u32>> {
+            names
+                .iter()
+                .map(|name| {
+                    graph.name_to_id.get(name).copied().ok_or_else(|| Error::Adapter {
+                        package: "graph".to_string(),
+                        message: format!("Saved order names unknown package '{}'", name),
+                    })
+                })
+                .collect()
+        };
+        let order = to_ids(&serializable.topological_order)?;
+        let levels = serializable
+            .dependency_levels
+            .iter()
+            .map(|level| to_ids(level))
+            .collect::<Result<Vec<_>>>()?;
+        if order.len() != graph.packages.len()
+            || levels.iter().map(Vec::len).sum::<usize>() != order.len()
+        {
+            return Err(Error::Adapter {
+                package: "graph".to_string(),
+                message: "Saved order does not cover every package".to_string(),
+            });
+        }
+
+        graph.set_order(&order);
+        for (level, ids) in levels.iter().enumerate() {
+            for &id in ids {
+                graph.levels[id as usize] = level as u32;
+            }
+        }
+        Ok((graph, serializable.fingerprint))
     }
 
     /// Updates the graph incrementally based on detected changes.
     ///
     /// This is much faster than rebuilding the entire graph when only
-    /// a few packages have changed.
+    /// a few packages have changed: the order is only repaired between the
+    /// ends of new dependencies, and levels only change downstream of the
+    /// changed packages.
     ///
     /// # Errors
     ///
-    /// Returns an error if any package operations fail or circular dependencies are detected.
+    /// Returns an error if any package operations fail, a package depends on
+    /// an unknown package, or circular dependencies are detected. The graph
+    /// may be partially updated and should be rebuilt with [`Self::new`].
     pub fn update_incremental(&mut self, changes: GraphChange) -> Result<()> {
-        // 1. Remove deleted packages
+        // 1. Remove deleted packages, remembering what depended on them
+        let mut dependents = Vec::new();
         for package_name in &changes.removed {
+            dependents.extend(self.dependents(package_name)?);
             self.remove_package(package_name)?;
         }
 
-        // 2. Update modified packages
+        // 2. Add new packages, so that any package can depend on them
+        for package in &changes.added {
+            self.add_package(package)?;
+        }
+
+        // 3. Update modified and new packages, dropping all their old edges
+        // first so that a dependency flipped between two of them is never
+        // seen in both directions
         for package in &changes.modified {
             self.update_package(package)?;
         }
+        for package in changes.modified.iter().chain(&changes.added) {
+            self.add_dependencies(package)?;
+        }
 
-        // 3. Add new packages
-        for package in &changes.added {
-            self.add_package(package)?;
+        // Dependents of removed packages must have dropped them
+        for name in &dependents {
+            if let Some(package) = self.get_package(name) {
+                if package.deps.iter().any(|dep| !self.name_to_node.contains_key(dep)) {
+                    return Err(self.invalid_graph());
+                }
+            }
         }
 
         // 4. Recompute only affected cached values
-        self.recompute_affected_levels(&changes)?;
+        self.transitive_deps_cache.clear();
+        let changed: Vec<u32> = changes
+            .added
+            .iter()
+            .chain(&changes.modified)
+            .map(|package| package.name.as_str())
+            .chain(dependents.iter().map(String::as_str))
+            .filter_map(|name| self.name_to_id(name))
+            .collect();
+        self.update_levels(changed);
+
+        if self.tombstones > self.name_to_node.len() {
+            self.compact();
+        }
 
         Ok(())
     }
 
+    /// Describes every problem with the packages currently in the graph.
+    fn invalid_graph(&self) -> Error {
+        let packages: Vec<Package> = self.all_packages().into_iter().cloned().collect();
+        Error::InvalidGraph(diagnose(&packages))
+    }
+
     fn remove_package(&mut self, name: &str) -> Result<()> {
         let package_id = self.name_to_id(name).ok_or_else(|| Error::PackageNotFound {
             name: name.to_string(),
             available: String::new(),
         })?;
-
-        let node = *self.name_to_node.get(name).ok_or_else(|| Error::PackageNotFound {
-            name: name.to_string(),
-            available: String::new(),
-        })?;
+        let node = self.node(package_id);
 
         // Remove node from graph (this removes all edges automatically)
+        let last = NodeIndex::new(self.graph.node_count() - 1);
         self.graph.remove_node(node);
 
-        // Remove from mappings
-        self.name_to_node.remove(name);
-        self.node_to_id.remove(&node);
-        let name_arc = intern(name);
-        self.name_to_id.remove(&name_arc);
-
-        // Remove package from packages vector (mark as removed, don't actually remove to preserve indices)
-        // For now, we'll keep the package but mark it as removed by clearing the name
-        // In a production system, you might want a more sophisticated approach
-        if (package_id as usize) < self.packages.len() {
-            // We can't easily remove from the middle of a Vec without breaking indices
-            // So we'll leave it but it won't be accessible via name lookup
+        // The graph moves its last node into the freed index
+        if last != node {
+            let moved_id = self.graph[node];
+            self.id_to_node[moved_id as usize] = Some(node);
+            self.name_to_node
+                .insert(self.id_to_name(moved_id).to_string(), node);
         }
 
-        // Clear affected cache entries
-        self.transitive_deps_cache.remove(name);
+        // Remove from mappings, leaving a tombstone until the next compaction
+        self.name_to_node.remove(name);
+        self.name_to_id.remove(&intern(name));
+        self.id_to_node[package_id as usize] = None;
+        self.order[self.positions[package_id as usize] as usize] = None;
+        self.tombstones += 1;
 
         Ok(())
     }
@@ -543,31 +672,30 @@ impl DependencyGraph {
         // Update package data
         self.packages[package_id as usize] = package.clone();
 
-        // Update edges - remove old edges, add new ones
-        let node = self.name_to_node.get(&package.name).ok_or_else(|| Error::PackageNotFound {
+        // Remove old edges. Removing an edge moves the last edge into its
+        // index, so edges are removed one by one rather than by collected index.
+        let node = self.node(package_id);
+        while let Some(edge) = self.graph.first_edge(node, Direction::Outgoing) {
+            self.graph.remove_edge(edge);
+        }
+
+        Ok(())
+    }
+
+    /// Adds edges for the dependencies of a package in the graph.
+    fn add_dependencies(&mut self, package: &Package) -> Result<()> {
+        let node = *self.name_to_node.get(&package.name).ok_or_else(|| Error::PackageNotFound {
             name: package.name.clone(),
             available: String::new(),
         })?;
 
-        // Remove all outgoing edges
-        let old_edges: Vec<_> = self.graph
-            .edges_directed(*node, Direction::Outgoing)
-            .map(|e| e.id())
-            .collect();
-        for edge_id in old_edges {
-            let _ = self.graph.remove_edge(edge_id);
-        }
-
-        // Add new edges based on updated dependencies
         for dep_name in &package.deps {
-            if let Some(dep_node) = self.name_to_node.get(dep_name) {
-                self.graph.add_edge(*node, *dep_node, ());
-            }
+            let Some(&dep_node) = self.name_to_node.get(dep_name) else {
+                return Err(self.invalid_graph());
+            };
+            self.add_dependency(node, dep_node)?;
         }
 
-        // Invalidate cache
-        self.transitive_deps_cache.remove(&package.name);
-
         Ok(())
     }
 
@@ -580,58 +708,211 @@ impl DependencyGraph {
         self.name_to_id.insert(name_arc, package_id);
         self.packages.push(package.clone());
 
-        // Add node to graph
+        // Add node to graph, last in the order until its edges are added
         let node = self.graph.add_node(package_id);
         self.name_to_node.insert(package.name.clone(), node);
-        self.node_to_id.insert(node, package_id);
+        self.id_to_node.push(Some(node));
+        self.positions.push(self.order.len() as u32);
+        self.order.push(Some(package_id));
+        self.levels.push(0);
 
-        // Add edges for dependencies
-        for dep_name in &package.deps {
-            if let Some(dep_node) = self.name_to_node.get(dep_name) {
-                self.graph.add_edge(node, *dep_node, ());
-            }
+        Ok(())
+    }
+
+    /// Adds an edge from `from` to its dependency `to`, keeping the order
+    /// topological with the Pearce–Kelly algorithm.
+    ///
+    /// Nothing moves if `to` already comes first. Otherwise only packages
+    /// between the two are visited: the dependents of `from` placed before
+    /// `to`, and the dependencies of `to` placed after `from`. The
+    /// dependencies then take the first of their combined slots.
+    fn add_dependency(&mut self, from: NodeIndex, to: NodeIndex) -> Result<()> {
+        self.graph.add_edge(from, to, ());
+
+        let position = |node: NodeIndex| self.positions[self.graph[node] as usize];
+        let (lower, upper) = (position(from), position(to));
+        if upper < lower {
+            return Ok(());
+        }
+
+        // Reaching `to` from `from` through dependents closes a cycle.
+        let mut forward = self.search(from, Direction::Incoming, |p| p <= upper);
+        if forward.contains(&self.graph[to]) {
+            return Err(self.circular_dependency());
+        }
+        let mut backward = self.search(to, Direction::Outgoing, |p| p > lower);
+
+        forward.sort_unstable_by_key(|&id| self.positions[id as usize]);
+        backward.sort_unstable_by_key(|&id| self.positions[id as usize]);
+
+        let mut slots: Vec<u32> = backward
+            .iter()
+            .chain(&forward)
+            .map(|&id| self.positions[id as usize])
+            .collect();
+        slots.sort_unstable();
+        for (&id, slot) in backward.iter().chain(&forward).zip(slots) {
+            self.positions[id as usize] = slot;
+            self.order[slot as usize] = Some(id);
         }
 
         Ok(())
     }
 
-    fn recompute_affected_levels(&mut self, changes: &GraphChange) -> Result<()> {
-        // Collect all affected package IDs
-        let mut affected_ids = HashSet::new();
-        
-        for pkg in &changes.added {
-            if let Some(id) = self.name_to_id(&pkg.name) {
-                affected_ids.insert(id);
+    /// Collects the IDs reachable from `start` in `direction` through
+    /// packages whose position passes `within`, including `start`.
+    fn search(
+        &self,
+        start: NodeIndex,
+        direction: Direction,
+        within: impl Fn(u32) -> bool,
+    ) -> Vec<u32> {
+        let mut visited = HashSet::new();
+        let mut stack = vec![start];
+        let mut found = Vec::new();
+        while let Some(node) = stack.pop() {
+            if !visited.insert(node) {
+                continue;
             }
-        }
-        for pkg in &changes.modified {
-            if let Some(id) = self.name_to_id(&pkg.name) {
-                affected_ids.insert(id);
+            found.push(self.graph[node]);
+            for next in self.graph.neighbors_directed(node, direction) {
+                if within(self.positions[self.graph[next] as usize]) {
+                    stack.push(next);
+                }
             }
         }
-        for name in &changes.removed {
-            if let Some(id) = self.name_to_id(name) {
-                affected_ids.insert(id);
+        found
+    }
+
+    fn circular_dependency(&self) -> Error {
+        let cycles: Vec<String> = cycle_paths(&self.graph, |node| {
+            self.id_to_name(self.graph[node]).to_string()
+        })
+        .into_iter()
+        .map(|path| path.join(" -> "))
+        .collect();
+        Error::CircularDependency(format!("Cycle detected: {}", cycles.join("; ")))
+    }
+
+    /// Recomputes the levels of `changed` packages and, where a level moved,
+    /// of their dependents, visiting each package once in topological order.
+    fn update_levels(&mut self, changed: Vec<u32>) {
+        let mut queued: HashSet<u32> = changed.into_iter().collect();
+        let mut queue: BinaryHeap<Reverse<(u32, u32)>> = queued
+            .iter()
+            .map(|&id| Reverse((self.positions[id as usize], id)))
+            .collect();
+
+        while let Some(Reverse((_, id))) = queue.pop() {
+            let level = self.level_of(id);
+            if level == self.levels[id as usize] {
+                continue;
+            }
+            self.levels[id as usize] = level;
+            for dependent in self.graph.neighbors_directed(self.node(id), Direction::Incoming) {
+                let dependent = self.graph[dependent];
+                if queued.insert(dependent) {
+                    queue.push(Reverse((self.positions[dependent as usize], dependent)));
+                }
             }
         }
+    }
 
-        // For now, recompute entire topological order and levels
-        // TODO: Optimize to only recompute affected subgraph
-        let sorted = toposort(&self.graph, None).map_err(|cycle| {
-            let cycle_id = self.graph[cycle.node_id()];
-            let cycle_name = self.id_to_name(cycle_id);
-            Error::CircularDependency(format!("Cycle detected involving: {}", cycle_name))
-        })?;
+    /// Drops tombstones, renumbering live packages in topological order.
+    fn compact(&mut self) {
+        let live: Vec<u32> = self.order_ids().collect();
+        let mut packages: Vec<Option<Package>> =
+            std::mem::take(&mut self.packages).into_iter().map(Some).collect();
+
+        let mut id_to_name = Vec::with_capacity(live.len());
+        let mut id_to_node = Vec::with_capacity(live.len());
+        let mut levels = Vec::with_capacity(live.len());
+        self.name_to_id.clear();
+        for (id, &old_id) in live.iter().enumerate() {
+            let node = self.node(old_id);
+            self.graph[node] = id as u32;
+            let name = Arc::clone(&self.id_to_name[old_id as usize]);
+            self.name_to_id.insert(Arc::clone(&name), id as u32);
+            id_to_name.push(name);
+            id_to_node.push(Some(node));
+            levels.push(self.levels[old_id as usize]);
+            self.packages.extend(packages[old_id as usize].take());
+        }
 
-        self.cached_topological_order = sorted
-            .into_iter()
-            .rev()
-            .map(|idx| self.graph[idx])
-            .collect();
+        self.id_to_name = id_to_name;
+        self.id_to_node = id_to_node;
+        self.levels = levels;
+        self.positions = (0..live.len() as u32).collect();
+        self.order = (0..live.len() as u32).map(Some).collect();
+        self.tombstones = 0;
+    }
+}
 
-        self.dependency_levels =
-            Self::compute_dependency_levels_compact(&self.graph, &self.node_to_id, &self.cached_topological_order)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::Language;
+
+    fn package(name: &str, deps: &[&str]) -> Package {
+        Package::new(
+            name.to_string(),
+            Language::Rust,
+            true,
+            name.into(),
+            deps.iter().map(|d| d.to_string()).collect(),
+            vec![],
+        )
+    }
 
-        Ok(())
+    fn change(added: Vec<Package>, removed: Vec<&str>) -> GraphChange {
+        GraphChange {
+            added,
+            modified: Vec::new(),
+            removed: removed.into_iter().map(String::from).collect(),
+            dependency_changes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_tombstones_are_compacted() {
+        let mut graph =
+            DependencyGraph::new(vec![package("core", &[]), package("api", &["core"])]).unwrap();
+
+        for round in 0..50 {
+            let name = format!("tmp-{}", round);
+            graph
+                .update_incremental(change(vec![package(&name, &["api"])], vec![]))
+                .unwrap();
+            graph.update_incremental(change(vec![], vec![&name])).unwrap();
+            assert!(graph.packages.len() <= 2 * graph.name_to_node.len() + 1);
+        }
+
+        assert_eq!(graph.topological_order(), ["core", "api"]);
+        assert_eq!(graph.dependency_levels(), [["core"], ["api"]]);
+        assert_eq!(graph.get_package("api").unwrap().deps[0], "core");
+        assert!(graph.get_package("tmp-49").is_none());
+    }
+
+    #[test]
+    fn test_new_dependency_reorders_packages() {
+        let mut graph = DependencyGraph::new(vec![
+            package("a", &[]),
+            package("b", &[]),
+            package("c", &["b"]),
+        ])
+        .unwrap();
+        let before = graph.topological_order();
+
+        // Whatever the initial order, `a` must end up before `b` and `c`.
+        let mut changes = change(vec![], vec![]);
+        changes.modified.push(package("b", &["a"]));
+        graph.update_incremental(changes).unwrap();
+
+        let order = graph.topological_order();
+        let position = |name: &str| order.iter().position(|n| n == name).unwrap();
+        assert!(position("a") < position("b"));
+        assert!(position("b") < position("c"));
+        assert_eq!(order.len(), before.len());
+        assert_eq!(graph.dependency_levels(), [["a"], ["b"], ["c"]]);
     }
 }
@@ -0,0 +1,344 @@
+//! Dependency graph export to DOT, Mermaid and JSON.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::graph::DependencyGraph;
+use crate::package::Language;
+
+/// A package in an exported graph.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportNode {
+    pub name: String,
+    pub language: Language,
+    pub public: bool,
+    pub version: Option<String>,
+    pub path: PathBuf,
+    /// Dependency level; packages on the same level can run in parallel.
+    pub level: usize,
+}
+
+/// A dependency edge, pointing from a package to its dependency.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ExportEdge {
+    pub from: String,
+    pub to: String,
+}
+
+/// A snapshot of the dependency graph, or of a subgraph around one package.
+///
+/// Nodes are in topological order, dependencies before dependents.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphExport {
+    pub nodes: Vec<ExportNode>,
+    pub edges: Vec<ExportEdge>,
+    pub levels: Vec<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus: Option<String>,
+}
+
+impl GraphExport {
+    /// Exports the whole graph.
+    pub fn new(graph: &DependencyGraph) -> Self {
+        let order = graph.topological_order();
+        let included: FxHashSet<&str> = order.iter().map(|name| name.as_str()).collect();
+        Self::build(graph, &order, &included, None)
+    }
+
+    /// Exports the named packages and the edges between them.
+    pub fn subset(graph: &DependencyGraph, names: &[String]) -> Self {
+        let order = graph.topological_order();
+        let included: FxHashSet<&str> = names.iter().map(|name| name.as_str()).collect();
+        Self::build(graph, &order, &included, None)
+    }
+
+    /// Exports the packages within `depth` edges of `focus`.
+    ///
+    /// Both dependencies and dependents are followed; `None` follows them
+    /// all the way.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `focus` is not in the graph.
+    pub fn focused(graph: &DependencyGraph, focus: &str, depth: Option<usize>) -> Result<Self> {
+        let mut included: FxHashSet<String> = FxHashSet::default();
+        included.insert(focus.to_string());
+
+        for dependents in [false, true] {
+            let mut queue = VecDeque::from([(focus.to_string(), 0usize)]);
+            let mut seen: FxHashSet<String> = FxHashSet::default();
+            while let Some((name, distance)) = queue.pop_front() {
+                if !seen.insert(name.clone()) || depth.is_some_and(|d| distance >= d) {
+                    continue;
+                }
+                let next = if dependents {
+                    graph.dependents(&name)?
+                } else {
+                    graph.dependencies(&name)?
+                };
+                for neighbour in next {
+                    included.insert(neighbour.clone());
+                    queue.push_back((neighbour, distance + 1));
+                }
+            }
+        }
+
+        let order = graph.topological_order();
+        let included: FxHashSet<&str> = included.iter().map(|name| name.as_str()).collect();
+        Ok(Self::build(
+            graph,
+            &order,
+            &included,
+            Some(focus.to_string()),
+        ))
+    }
+
+    fn build(
+        graph: &DependencyGraph,
+        order: &[String],
+        included: &FxHashSet<&str>,
+        focus: Option<String>,
+    ) -> Self {
+        let mut level_of: FxHashMap<&str, usize> = FxHashMap::default();
+        let levels: Vec<Vec<String>> = graph
+            .dependency_levels()
+            .into_iter()
+            .map(|level| {
+                level
+                    .into_iter()
+                    .filter(|name| included.contains(name.as_str()))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|level| !level.is_empty())
+            .collect();
+        for (index, level) in levels.iter().enumerate() {
+            for name in level {
+                level_of.insert(name, index);
+            }
+        }
+
+        let nodes: Vec<ExportNode> = order
+            .iter()
+            .filter(|name| included.contains(name.as_str()))
+            .filter_map(|name| graph.get_package(name))
+            .map(|package| ExportNode {
+                name: package.name.clone(),
+                language: package.language,
+                public: package.public,
+                version: package.version.clone(),
+                path: package.path.clone(),
+                level: level_of.get(package.name.as_str()).copied().unwrap_or(0),
+            })
+            .collect();
+
+        let edges: BTreeSet<ExportEdge> = nodes
+            .iter()
+            .flat_map(|node| {
+                graph
+                    .dependencies(&node.name)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|dep| included.contains(dep.as_str()))
+                    .map(|dep| ExportEdge {
+                        from: node.name.clone(),
+                        to: dep,
+                    })
+            })
+            .collect();
+
+        Self {
+            nodes,
+            edges: edges.into_iter().collect(),
+            levels,
+            focus,
+        }
+    }
+
+    /// Renders the graph in Graphviz DOT.
+    ///
+    /// Packages on the same dependency level share a rank.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph polykit {\n    rankdir=BT;\n    node [shape=box];\n");
+        for node in &self.nodes {
+            let style = if self.focus.as_deref() == Some(node.name.as_str()) {
+                ", style=bold"
+            } else if !node.public {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "    {} [label=\"{}\"{}];",
+                dot_id(&node.name),
+                escape(&self.label(node, "\\n")),
+                style
+            );
+        }
+        for level in &self.levels {
+            let ids: Vec<String> = level.iter().map(|name| dot_id(name)).collect();
+            let _ = writeln!(out, "    {{ rank=same; {}; }}", ids.join("; "));
+        }
+        for edge in &self.edges {
+            let _ = writeln!(out, "    {} -> {};", dot_id(&edge.from), dot_id(&edge.to));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Renders the graph as a Mermaid flowchart.
+    ///
+    /// Node ids are generated because package names may contain characters
+    /// Mermaid does not accept in ids.
+    pub fn to_mermaid(&self) -> String {
+        let ids: FxHashMap<&str, String> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.name.as_str(), format!("n{}", index)))
+            .collect();
+
+        let mut out = String::from("graph BT\n");
+        for node in &self.nodes {
+            let _ = writeln!(
+                out,
+                "    {}[\"{}\"]",
+                ids[node.name.as_str()],
+                self.label(node, "<br/>").replace('"', "#quot;")
+            );
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                out,
+                "    {} --> {}",
+                ids[edge.from.as_str()],
+                ids[edge.to.as_str()]
+            );
+        }
+        if let Some(id) = self.focus.as_deref().and_then(|focus| ids.get(focus)) {
+            let _ = writeln!(out, "    style {} stroke-width:3px", id);
+        }
+        out
+    }
+
+    /// Renders the graph as JSON.
+    ///
+    /// `packages` repeats the topological order for consumers of the
+    /// previous `polykit graph --json` output.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(object) = value.as_object_mut() {
+            let order: Vec<&str> = self.nodes.iter().map(|n| n.name.as_str()).collect();
+            object.insert("packages".to_string(), serde_json::json!(order));
+        }
+        value
+    }
+
+    fn label(&self, node: &ExportNode, separator: &str) -> String {
+        match node.version {
+            Some(ref version) => format!(
+                "{}{}{} {}",
+                node.name,
+                separator,
+                node.language.as_str(),
+                version
+            ),
+            None => format!("{}{}{}", node.name, separator, node.language.as_str()),
+        }
+    }
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", escape(name))
+}
+
+fn escape(value: &str) -> String {
+    value.replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::Package;
+
+    fn package(name: &str, deps: &[&str]) -> Package {
+        Package::new(
+            name.to_string(),
+            Language::Rust,
+            true,
+            PathBuf::from(name),
+            deps.iter().map(|d| d.to_string()).collect(),
+            vec![],
+        )
+    }
+
+    fn graph() -> DependencyGraph {
+        DependencyGraph::new(vec![
+            package("core", &[]),
+            package("utils", &["core"]),
+            package("api", &["utils"]),
+            package("web", &["api"]),
+            package("docs", &[]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_export_edges_and_levels() {
+        let export = GraphExport::new(&graph());
+
+        assert_eq!(export.nodes.len(), 5);
+        assert_eq!(export.edges.len(), 3);
+        assert!(export.edges.contains(&ExportEdge {
+            from: "api".to_string(),
+            to: "utils".to_string(),
+        }));
+        let web = export.nodes.iter().find(|n| n.name == "web").unwrap();
+        assert_eq!(web.level, 3);
+        assert_eq!(export.levels[0].len(), 2);
+    }
+
+    #[test]
+    fn test_focus_with_depth() {
+        let export = GraphExport::focused(&graph(), "api", Some(1)).unwrap();
+        let names: Vec<&str> = export.nodes.iter().map(|n| n.name.as_str()).collect();
+
+        assert_eq!(names, vec!["utils", "api", "web"]);
+        assert_eq!(export.edges.len(), 2);
+        assert_eq!(export.levels, vec![vec!["utils"], vec!["api"], vec!["web"]]);
+
+        let subset = GraphExport::subset(&graph(), &["web".to_string(), "utils".to_string()]);
+        assert_eq!(subset.nodes.len(), 2);
+        assert!(subset.edges.is_empty());
+
+        let all = GraphExport::focused(&graph(), "api", None).unwrap();
+        assert_eq!(all.nodes.len(), 4);
+        assert!(GraphExport::focused(&graph(), "missing", None).is_err());
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let export = GraphExport::focused(&graph(), "api", Some(1)).unwrap();
+
+        let dot = export.to_dot();
+        assert!(dot.starts_with("digraph polykit {"));
+        assert!(dot.contains("\"api\" -> \"utils\";"));
+        assert!(dot.contains("\"api\" [label=\"api\\nrust\", style=bold];"));
+
+        let mermaid = export.to_mermaid();
+        assert!(mermaid.contains("n1[\"api<br/>rust\"]"));
+        assert!(mermaid.contains("n1 --> n0"));
+        assert!(mermaid.contains("style n1 stroke-width:3px"));
+
+        let json = export.to_json();
+        assert_eq!(json["focus"], "api");
+        assert_eq!(json["edges"][0]["from"], "api");
+        assert_eq!(json["nodes"][2]["language"], "rust");
+        assert_eq!(json["packages"], serde_json::json!(["utils", "api", "web"]));
+    }
+}
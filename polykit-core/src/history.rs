@@ -0,0 +1,282 @@
+//! Local record of task durations across runs.
+//!
+//! Each run appends one JSON line to `.polykit/history.jsonl` in the
+//! workspace root. Only the most recent runs are kept.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::runner::TaskResult;
+
+/// History file, relative to the workspace root.
+pub const HISTORY_FILE: &str = ".polykit/history.jsonl";
+
+/// Number of runs kept in the history file.
+const MAX_RUNS: usize = 100;
+
+/// Number of recent runs averaged into a duration estimate.
+const ESTIMATE_RUNS: usize = 10;
+
+/// Duration of one package's task in a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskTiming {
+    pub package: String,
+    pub task: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    /// Restored from the local or remote cache, so `duration_ms` is only
+    /// the lookup time. Runs recorded before this field count as not cached.
+    #[serde(default)]
+    pub cached: bool,
+}
+
+/// One invocation of a task across packages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Seconds since the Unix epoch when the run finished.
+    pub timestamp: u64,
+    pub task: String,
+    /// The `-j` limit the run used, if any.
+    pub parallel: Option<usize>,
+    /// Wall time of the whole run.
+    pub duration_ms: u64,
+    pub tasks: Vec<TaskTiming>,
+}
+
+impl RunRecord {
+    /// Records the results of a run that took `duration`.
+    pub fn new(
+        task: &str,
+        parallel: Option<usize>,
+        duration: Duration,
+        results: &[TaskResult],
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            timestamp,
+            task: task.to_string(),
+            parallel,
+            duration_ms: duration.as_millis() as u64,
+            tasks: results
+                .iter()
+                .map(|result| TaskTiming {
+                    package: result.package_name.clone(),
+                    task: result.task_name.clone(),
+                    duration_ms: result.duration.as_millis() as u64,
+                    success: result.success,
+                    cached: result.cached,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns `true` if every task in the run was restored from cache, so
+    /// its wall time says nothing about how long the task takes.
+    pub fn is_cached(&self) -> bool {
+        !self.tasks.is_empty() && self.tasks.iter().all(|timing| timing.cached)
+    }
+}
+
+/// Append-only store of [`RunRecord`]s.
+#[derive(Debug, Clone)]
+pub struct RunHistory {
+    path: PathBuf,
+}
+
+impl RunHistory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Opens the history of the workspace at `root`.
+    pub fn in_workspace(root: &Path) -> Self {
+        Self::new(root.join(HISTORY_FILE))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends a run, dropping the oldest runs beyond the retention limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history file cannot be written.
+    pub fn record(&self, run: &RunRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", to_line(run)?)?;
+        drop(file);
+
+        let runs = self.load()?;
+        if runs.len() > MAX_RUNS {
+            let mut content = String::new();
+            for run in &runs[runs.len() - MAX_RUNS..] {
+                content.push_str(&to_line(run)?);
+                content.push('\n');
+            }
+            fs::write(&self.path, content)?;
+        }
+        Ok(())
+    }
+
+    /// Loads every recorded run, oldest first.
+    ///
+    /// A missing file is an empty history. Lines that do not parse, such as
+    /// ones written by another version, are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history file exists but cannot be read.
+    pub fn load(&self) -> Result<Vec<RunRecord>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Estimates how long `task` takes in each package.
+    ///
+    /// Each estimate is the mean over the most recent runs of `task` that
+    /// ran the package. Cache hits are skipped, since their durations are
+    /// lookup times. Packages that never ran are absent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history file cannot be read.
+    pub fn estimates(&self, task: &str) -> Result<FxHashMap<String, Duration>> {
+        let mut samples: FxHashMap<String, Vec<u64>> = FxHashMap::default();
+        for run in self.load()?.iter().rev().filter(|run| run.task == task) {
+            for timing in run.tasks.iter().filter(|timing| !timing.cached) {
+                let durations = samples.entry(timing.package.clone()).or_default();
+                if durations.len() < ESTIMATE_RUNS {
+                    durations.push(timing.duration_ms);
+                }
+            }
+        }
+        Ok(samples
+            .into_iter()
+            .map(|(package, durations)| {
+                let mean = durations.iter().sum::<u64>() / durations.len() as u64;
+                (package, Duration::from_millis(mean))
+            })
+            .collect())
+    }
+}
+
+fn to_line(run: &RunRecord) -> Result<String> {
+    serde_json::to_string(run).map_err(|e| Error::Adapter {
+        package: "history".to_string(),
+        message: format!("Failed to serialize run: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(package: &str, millis: u64) -> TaskResult {
+        TaskResult {
+            package_name: package.to_string(),
+            task_name: "build".to_string(),
+            success: true,
+            stdout: String::new(),
+            stderr: String::new(),
+            duration: Duration::from_millis(millis),
+            cached: false,
+        }
+    }
+
+    fn cached(package: &str) -> TaskResult {
+        TaskResult {
+            duration: Duration::from_millis(1),
+            cached: true,
+            ..result(package, 0)
+        }
+    }
+
+    #[test]
+    fn test_estimates_average_recent_runs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let history = RunHistory::in_workspace(temp_dir.path());
+        assert!(history.estimates("build").unwrap().is_empty());
+
+        for (api, core) in [(100, 40), (300, 60)] {
+            let results = [result("api", api), result("core", core)];
+            let run = RunRecord::new("build", Some(2), Duration::from_millis(api), &results);
+            history.record(&run).unwrap();
+        }
+        let run = RunRecord::new("test", None, Duration::ZERO, &[result("api", 9000)]);
+        history.record(&run).unwrap();
+
+        let estimates = history.estimates("build").unwrap();
+        assert_eq!(estimates["api"], Duration::from_millis(200));
+        assert_eq!(estimates["core"], Duration::from_millis(50));
+        assert_eq!(history.load().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_estimates_skip_cache_hits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let history = RunHistory::in_workspace(temp_dir.path());
+
+        let results = [result("api", 400), result("core", 60)];
+        let run = RunRecord::new("build", None, Duration::from_millis(400), &results);
+        assert!(!run.is_cached());
+        history.record(&run).unwrap();
+
+        let results = [cached("api"), result("core", 40)];
+        let run = RunRecord::new("build", None, Duration::from_millis(40), &results);
+        assert!(!run.is_cached());
+        history.record(&run).unwrap();
+
+        let results = [cached("api"), cached("core")];
+        let run = RunRecord::new("build", None, Duration::from_millis(2), &results);
+        assert!(run.is_cached());
+        history.record(&run).unwrap();
+
+        let estimates = history.estimates("build").unwrap();
+        assert_eq!(estimates["api"], Duration::from_millis(400));
+        assert_eq!(estimates["core"], Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_timings_without_cached_field_are_not_cached() {
+        let line = r#"{"timestamp":1,"task":"build","parallel":null,"duration_ms":5,"tasks":[{"package":"api","task":"build","duration_ms":5,"success":true}]}"#;
+        let run: RunRecord = serde_json::from_str(line).unwrap();
+        assert!(!run.tasks[0].cached);
+    }
+
+    #[test]
+    fn test_history_is_trimmed() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let history = RunHistory::in_workspace(temp_dir.path());
+        for millis in 0..(MAX_RUNS as u64 + 5) {
+            let run = RunRecord::new("build", None, Duration::ZERO, &[result("api", millis)]);
+            history.record(&run).unwrap();
+        }
+
+        let runs = history.load().unwrap();
+        assert_eq!(runs.len(), MAX_RUNS);
+        assert_eq!(runs[0].tasks[0].duration_ms, 5);
+    }
+}
@@ -0,0 +1,435 @@
+//! Package inference from native workspace manifests.
+//!
+//! Reads npm/pnpm/yarn `workspaces`, Cargo `[workspace] members`, `go.work`
+//! and Python projects so a repository can be scanned without a
+//! `polykit.toml` per package.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::adapter::LanguageAdapter;
+use crate::command_validator::CommandValidator;
+use crate::config::PackageOverride;
+use crate::error::{Error, Result};
+use crate::package::{Language, Package, Task};
+use crate::workspace::WorkspaceMembers;
+
+/// A package built from its native manifest.
+pub struct InferredPackage {
+    pub package: Package,
+    /// Whether the `polykit.toml` override declares `[deps]`, which then
+    /// replace the dependencies read from the native manifest.
+    pub declares_deps: bool,
+}
+
+/// Member globs declared by one native workspace manifest.
+struct NativeWorkspace {
+    members: Vec<String>,
+    exclude: Vec<String>,
+    manifest: &'static str,
+    language: Language,
+}
+
+/// Finds package directories declared by native workspace manifests at `root`.
+///
+/// Returns directories relative to `root`, mapped to the language of the
+/// manifest that declared them. A directory claimed by several ecosystems
+/// keeps the first one, in the order npm, Cargo, Go, Python.
+///
+/// # Errors
+///
+/// Returns an error if a root manifest cannot be parsed or declares an
+/// invalid glob.
+pub fn discover_package_dirs(root: &Path) -> Result<BTreeMap<PathBuf, Language>> {
+    let workspaces = [
+        js_workspace(root)?,
+        cargo_workspace(root)?,
+        go_workspace(root)?,
+        python_workspace(root)?,
+    ];
+
+    let mut dirs = BTreeMap::new();
+    for workspace in workspaces.into_iter().flatten() {
+        let members = WorkspaceMembers::new(root, &workspace.members, &workspace.exclude)?;
+        for manifest in members.find_manifests(workspace.manifest) {
+            let Some(dir) = manifest
+                .parent()
+                .and_then(|dir| dir.strip_prefix(root).ok())
+            else {
+                continue;
+            };
+            let language = if workspace.language == Language::Js
+                && root.join(dir).join("tsconfig.json").exists()
+            {
+                Language::Ts
+            } else {
+                workspace.language
+            };
+            dirs.entry(dir.to_path_buf()).or_insert(language);
+        }
+    }
+
+    Ok(dirs)
+}
+
+/// Builds a package from its native manifest and optional `polykit.toml`.
+///
+/// Returns `None` if the adapter does not recognise the directory.
+///
+/// # Arguments
+///
+/// * `root` - The workspace root
+/// * `relative_path` - The package directory, relative to `root`
+/// * `language` - The language of the manifest that declared the package
+/// * `adapter` - The adapter for `language`
+/// * `workspace_tasks` - Tasks added to packages that do not define them
+///
+/// # Errors
+///
+/// Returns an error if a manifest cannot be read or names are invalid.
+pub fn infer_package(
+    root: &Path,
+    relative_path: &Path,
+    language: Language,
+    adapter: &dyn LanguageAdapter,
+    workspace_tasks: &[Task],
+) -> Result<Option<InferredPackage>> {
+    let package_dir = root.join(relative_path);
+    if !adapter.detect(&package_dir) {
+        return Ok(None);
+    }
+
+    let metadata = adapter.read_metadata(&package_dir)?;
+    let overrides = read_override(&package_dir)?;
+
+    let name = match (&overrides.name, &metadata.name) {
+        (Some(name), _) => name.clone(),
+        (None, Some(native)) => package_identifier(native).to_string(),
+        (None, None) => package_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| Error::ConfigNotFound(package_dir.clone()))?,
+    };
+    CommandValidator::validate_identifier(&name, "Package name")?;
+
+    let deps = overrides
+        .deps
+        .as_ref()
+        .map(|deps| deps.internal.clone())
+        .unwrap_or_default();
+    for dep_name in &deps {
+        CommandValidator::validate_identifier(dep_name, "Dependency name")?;
+    }
+
+    let mut tasks = metadata.tasks;
+    for task in overrides.to_tasks() {
+        tasks.retain(|t| t.name != task.name);
+        tasks.push(task);
+    }
+    for workspace_task in workspace_tasks {
+        if !tasks.iter().any(|t| t.name == workspace_task.name) {
+            tasks.push(workspace_task.clone());
+        }
+    }
+    for task in &tasks {
+        CommandValidator::validate_identifier(&task.name, "Task name")?;
+    }
+    tasks.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+    let mut package = Package::new(
+        name,
+        overrides.parse_language()?.unwrap_or(language),
+        overrides.public.unwrap_or(!metadata.private),
+        relative_path.to_path_buf(),
+        deps,
+        tasks,
+    );
+    package.version = metadata.version;
+    if let Some(tags) = overrides.tags {
+        for tag in &tags {
+            CommandValidator::validate_tag(tag)?;
+        }
+        package.tags = tags;
+    }
+    if let Some(affected_ignore) = overrides.affected_ignore {
+        crate::path_utils::validate_affected_ignore(&package.name, &affected_ignore)?;
+        package.affected_ignore = affected_ignore;
+    }
+    Ok(Some(InferredPackage {
+        package,
+        declares_deps: overrides.deps.is_some(),
+    }))
+}
+
+/// Reads the optional `polykit.toml` override layer of a package.
+fn read_override(package_dir: &Path) -> Result<PackageOverride> {
+    let path = package_dir.join("polykit.toml");
+    if !path.exists() {
+        return Ok(PackageOverride::default());
+    }
+    let content = std::fs::read_to_string(&path)?;
+    toml::from_str(&content).map_err(|error| Error::Toml {
+        error,
+        context: path.display().to_string(),
+    })
+}
+
+fn read_toml(path: &Path) -> Result<toml::Value> {
+    let content = std::fs::read_to_string(path)?;
+    toml::from_str(&content).map_err(|error| Error::Toml {
+        error,
+        context: path.display().to_string(),
+    })
+}
+
+/// Turns a native package name into a Polykit identifier.
+///
+/// Scoped npm names and Go module paths keep their last segment, so
+/// `@acme/ui` becomes `ui` and `github.com/acme/api/v2` becomes `api`.
+fn package_identifier(native: &str) -> &str {
+    let mut segments = native.rsplit('/');
+    let last = segments.next().unwrap_or(native);
+    let is_major_suffix =
+        last.len() > 1 && last.starts_with('v') && last[1..].bytes().all(|b| b.is_ascii_digit());
+    if is_major_suffix {
+        segments.next().unwrap_or(last)
+    } else {
+        last
+    }
+}
+
+fn js_workspace(root: &Path) -> Result<Option<NativeWorkspace>> {
+    let mut patterns = Vec::new();
+
+    let package_json = root.join("package.json");
+    if package_json.exists() {
+        let content = std::fs::read_to_string(&package_json)?;
+        let json: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| inference_error(&package_json, e))?;
+        let workspaces = json.get("workspaces");
+        let globs = workspaces.and_then(|w| w.as_array()).or_else(|| {
+            workspaces
+                .and_then(|w| w.get("packages"))
+                .and_then(|p| p.as_array())
+        });
+        if let Some(globs) = globs {
+            patterns.extend(
+                globs
+                    .iter()
+                    .filter_map(|g| g.as_str().map(|s| s.to_string())),
+            );
+        }
+    }
+
+    let pnpm_workspace = root.join("pnpm-workspace.yaml");
+    if pnpm_workspace.exists() {
+        patterns.extend(parse_pnpm_packages(&std::fs::read_to_string(
+            &pnpm_workspace,
+        )?));
+    }
+
+    Ok(
+        split_negations(patterns).map(|(members, exclude)| NativeWorkspace {
+            members,
+            exclude,
+            manifest: "package.json",
+            language: Language::Js,
+        }),
+    )
+}
+
+fn cargo_workspace(root: &Path) -> Result<Option<NativeWorkspace>> {
+    let cargo_toml = root.join("Cargo.toml");
+    if !cargo_toml.exists() {
+        return Ok(None);
+    }
+    let value = read_toml(&cargo_toml)?;
+    let Some(workspace) = value.get("workspace") else {
+        return Ok(None);
+    };
+
+    Ok(Some(NativeWorkspace {
+        members: string_list(workspace.get("members")),
+        exclude: string_list(workspace.get("exclude")),
+        manifest: "Cargo.toml",
+        language: Language::Rust,
+    })
+    .filter(|w| !w.members.is_empty()))
+}
+
+fn go_workspace(root: &Path) -> Result<Option<NativeWorkspace>> {
+    let go_work = root.join("go.work");
+    if !go_work.exists() {
+        return Ok(None);
+    }
+    let members = parse_go_work_uses(&std::fs::read_to_string(&go_work)?);
+
+    Ok(Some(NativeWorkspace {
+        members,
+        exclude: Vec::new(),
+        manifest: "go.mod",
+        language: Language::Go,
+    })
+    .filter(|w| !w.members.is_empty()))
+}
+
+fn python_workspace(root: &Path) -> Result<Option<NativeWorkspace>> {
+    let pyproject = root.join("pyproject.toml");
+    let uv_workspace = if pyproject.exists() {
+        let value = read_toml(&pyproject)?;
+        value
+            .get("tool")
+            .and_then(|t| t.get("uv"))
+            .and_then(|uv| uv.get("workspace"))
+            .cloned()
+    } else {
+        None
+    };
+
+    // Without a uv workspace, every directory with a pyproject is a package
+    let (members, exclude) = match uv_workspace {
+        Some(workspace) => (
+            string_list(workspace.get("members")),
+            string_list(workspace.get("exclude")),
+        ),
+        None => (vec!["**".to_string()], Vec::new()),
+    };
+
+    Ok(Some(NativeWorkspace {
+        members,
+        exclude,
+        manifest: "pyproject.toml",
+        language: Language::Python,
+    }))
+}
+
+/// Reads the `packages` list of a `pnpm-workspace.yaml`.
+fn parse_pnpm_packages(content: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) && !trimmed.starts_with('-') {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if let Some(item) = trimmed.strip_prefix('-').filter(|_| in_packages) {
+            let item = item.split(" #").next().unwrap_or(item).trim();
+            patterns.push(item.trim_matches(|c| c == '"' || c == '\'').to_string());
+        }
+    }
+
+    patterns
+}
+
+/// Reads the directories of the `use` directives in a `go.work`.
+fn parse_go_work_uses(content: &str) -> Vec<String> {
+    let mut dirs = Vec::new();
+    let mut in_block = false;
+
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                dirs.push(line.trim_matches('"').to_string());
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("use") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+            } else if !rest.is_empty() && line.starts_with("use ") {
+                dirs.push(rest.trim_matches('"').to_string());
+            }
+        }
+    }
+
+    dirs.into_iter()
+        .map(|dir| dir.trim_start_matches("./").to_string())
+        .filter(|dir| !dir.is_empty() && dir != ".")
+        .collect()
+}
+
+/// Splits `!`-prefixed globs into excludes; `None` if nothing is included.
+fn split_negations(patterns: Vec<String>) -> Option<(Vec<String>, Vec<String>)> {
+    let (exclude, members): (Vec<String>, Vec<String>) =
+        patterns.into_iter().partition(|p| p.starts_with('!'));
+    let exclude = exclude
+        .into_iter()
+        .map(|p| p.trim_start_matches('!').to_string())
+        .collect();
+    (!members.is_empty()).then_some((members, exclude))
+}
+
+fn string_list(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn inference_error(path: &Path, error: impl std::fmt::Display) -> Error {
+    Error::Adapter {
+        package: "inference".to_string(),
+        message: format!("Failed to parse {}: {}", path.display(), error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_identifier() {
+        assert_eq!(package_identifier("@acme/ui"), "ui");
+        assert_eq!(package_identifier("github.com/acme/api/v2"), "api");
+        assert_eq!(package_identifier("github.com/acme/vault"), "vault");
+        assert_eq!(package_identifier("core"), "core");
+    }
+
+    #[test]
+    fn test_parse_pnpm_packages() {
+        let content = r#"
+packages:
+  - 'apps/*'
+  - "libs/**" # shared
+  - '!**/test/**'
+catalog:
+  react: ^18
+"#;
+        assert_eq!(
+            parse_pnpm_packages(content),
+            vec!["apps/*", "libs/**", "!**/test/**"]
+        );
+    }
+
+    #[test]
+    fn test_parse_go_work_uses() {
+        let content = r#"
+go 1.22
+
+use ./tools/gen // generators
+use (
+    ./services/api
+    "./services/worker"
+    .
+)
+"#;
+        assert_eq!(
+            parse_go_work_uses(content),
+            vec!["tools/gen", "services/api", "services/worker"]
+        );
+    }
+}
@@ -2,16 +2,25 @@
 
 pub mod adapter;
 pub mod adapter_registry;
+pub mod analysis;
 pub mod cache;
 pub mod change;
 pub mod command_validator;
 pub mod config;
+pub mod constraints;
+pub mod diagnostics;
 pub mod error;
 pub mod executor;
+pub mod filter;
 pub mod graph;
+pub mod graph_export;
+pub mod history;
+pub mod inference;
 pub mod metrics;
+pub mod native_deps;
 pub mod package;
 pub mod path_utils;
+pub mod query;
 pub mod release;
 pub mod release_reporter;
 pub mod remote_cache;
@@ -21,18 +30,23 @@ pub mod simd_utils;
 pub mod streaming;
 pub mod string_interner;
 pub mod task_cache;
+pub mod validation;
 pub mod watcher;
+pub mod workspace;
 
 pub use adapter::{LangMetadata, LanguageAdapter};
 pub use adapter_registry::AdapterRegistry;
 pub use cache::{Cache, CacheStats};
-pub use change::ChangeDetector;
+pub use change::{ChangeDetector, GitRange};
 pub use command_validator::CommandValidator;
-pub use config::{Config, WorkspaceConfig};
+pub use config::{Config, Constraints, WorkspaceConfig};
+pub use diagnostics::Diagnostic;
 pub use error::{Error, Result};
+pub use filter::PackageFilter;
 pub use graph::{DependencyGraph, GraphChange, GraphNode};
 pub use metrics::ExecutionMetrics;
 pub use package::{Language, Package, Task};
+pub use query::Query;
 pub use release::{BumpType, ReleaseEngine, ReleasePackage, ReleasePlan};
 pub use release_reporter::ReleaseReporter;
 pub use remote_cache::{
@@ -44,3 +58,4 @@ pub use scanner::Scanner;
 pub use streaming::StreamingTask;
 pub use task_cache::TaskCache;
 pub use watcher::{FileWatcher, WatcherConfig};
+pub use workspace::WorkspaceMembers;
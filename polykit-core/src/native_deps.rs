@@ -0,0 +1,209 @@
+//! Internal dependencies read from native manifests.
+
+use std::path::Path;
+
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::adapter::LanguageAdapter;
+use crate::error::Result;
+use crate::package::{Language, Package};
+
+/// Differences between declared and native internal dependencies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyDrift {
+    pub package: String,
+    /// Internal packages the manifest depends on but `[deps] internal` omits.
+    pub missing: Vec<String>,
+    /// Declared dependencies the manifest no longer depends on.
+    pub stale: Vec<String>,
+}
+
+/// Internal dependencies of each package, resolved from native manifests.
+///
+/// Native names are matched case-insensitively with `_` and `-` treated
+/// alike, so `ml_core` in a pyproject resolves to the `ml-core` package.
+#[derive(Debug, Default)]
+pub struct NativeDependencies {
+    deps: FxHashMap<String, Vec<String>>,
+}
+
+impl NativeDependencies {
+    /// Reads the native manifest of every package.
+    ///
+    /// Packages without a native manifest are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The directory package paths are relative to
+    /// * `packages` - The packages in the workspace
+    /// * `adapter_getter` - Returns the adapter for a language
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a native manifest cannot be read.
+    pub fn read<F>(root: &Path, packages: &[Package], adapter_getter: F) -> Result<Self>
+    where
+        F: Fn(&Language) -> Box<dyn LanguageAdapter> + Sync,
+    {
+        let manifests: Vec<(&Package, Option<String>, Vec<String>)> = packages
+            .par_iter()
+            .map(|package| {
+                let adapter = adapter_getter(&package.language);
+                let package_dir = root.join(&package.path);
+                if !adapter.detect(&package_dir) {
+                    return Ok(None);
+                }
+                let native_name = adapter
+                    .read_metadata(&package_dir)
+                    .ok()
+                    .and_then(|metadata| metadata.name);
+                let deps = adapter.read_dependencies(&package_dir)?;
+                Ok(Some((package, native_name, deps)))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let mut names: FxHashMap<String, &str> = FxHashMap::default();
+        for package in packages {
+            names.insert(normalize(&package.name), &package.name);
+        }
+        for (package, native_name, _) in &manifests {
+            if let Some(native_name) = native_name {
+                names.insert(normalize(native_name), &package.name);
+            }
+        }
+
+        let deps = manifests
+            .iter()
+            .map(|(package, _, native_deps)| {
+                let mut internal: Vec<String> = native_deps
+                    .iter()
+                    .filter_map(|dep| names.get(&normalize(dep)))
+                    .filter(|name| **name != package.name)
+                    .map(|name| name.to_string())
+                    .collect();
+                internal.sort_unstable();
+                internal.dedup();
+                (package.name.clone(), internal)
+            })
+            .collect();
+
+        Ok(Self { deps })
+    }
+
+    /// Returns the internal dependencies of a package with a native manifest.
+    pub fn get(&self, package: &str) -> Option<&[String]> {
+        self.deps.get(package).map(|deps| deps.as_slice())
+    }
+
+    /// Compares declared dependencies with the native manifests.
+    ///
+    /// Returns one entry per package whose declared dependencies differ,
+    /// sorted by package name.
+    pub fn drift(&self, packages: &[Package]) -> Vec<DependencyDrift> {
+        let mut drift: Vec<DependencyDrift> = packages
+            .iter()
+            .filter_map(|package| {
+                let native = self.get(&package.name)?;
+                let missing: Vec<String> = native
+                    .iter()
+                    .filter(|dep| !package.deps.contains(dep))
+                    .cloned()
+                    .collect();
+                let mut stale: Vec<String> = package
+                    .deps
+                    .iter()
+                    .filter(|dep| !native.contains(dep))
+                    .cloned()
+                    .collect();
+                stale.sort_unstable();
+                (!missing.is_empty() || !stale.is_empty()).then(|| DependencyDrift {
+                    package: package.name.clone(),
+                    missing,
+                    stale,
+                })
+            })
+            .collect();
+        drift.sort_unstable_by(|a, b| a.package.cmp(&b.package));
+        drift
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.to_lowercase().replace('_', "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct ListAdapter;
+
+    impl LanguageAdapter for ListAdapter {
+        fn language(&self) -> &'static str {
+            "python"
+        }
+
+        fn detect(&self, path: &Path) -> bool {
+            path.join("deps.txt").exists()
+        }
+
+        fn read_metadata(&self, _path: &Path) -> Result<crate::adapter::LangMetadata> {
+            Ok(Default::default())
+        }
+
+        fn bump_version(&self, _path: &Path, _new_version: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_dependencies(&self, path: &Path) -> Result<Vec<String>> {
+            let content = std::fs::read_to_string(path.join("deps.txt"))?;
+            Ok(content.lines().map(|line| line.to_string()).collect())
+        }
+    }
+
+    fn package(name: &str, deps: &[&str]) -> Package {
+        Package::new(
+            name.to_string(),
+            Language::Python,
+            false,
+            PathBuf::from(name),
+            deps.iter().map(|d| d.to_string()).collect(),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_drift_reports_missing_and_stale_edges() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for (name, deps) in [("api", "ML_Core\nrequests\napi"), ("ml-core", "numpy")] {
+            std::fs::create_dir_all(temp_dir.path().join(name)).unwrap();
+            std::fs::write(temp_dir.path().join(name).join("deps.txt"), deps).unwrap();
+        }
+        std::fs::create_dir_all(temp_dir.path().join("legacy")).unwrap();
+
+        let packages = vec![
+            package("api", &["legacy"]),
+            package("ml-core", &[]),
+            package("legacy", &[]),
+        ];
+        let native =
+            NativeDependencies::read(temp_dir.path(), &packages, |_| Box::new(ListAdapter))
+                .unwrap();
+
+        assert_eq!(native.get("api"), Some(&["ml-core".to_string()][..]));
+        assert_eq!(native.get("legacy"), None);
+        assert_eq!(
+            native.drift(&packages),
+            vec![DependencyDrift {
+                package: "api".to_string(),
+                missing: vec!["ml-core".to_string()],
+                stale: vec!["legacy".to_string()],
+            }]
+        );
+    }
+}
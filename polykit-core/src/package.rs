@@ -28,6 +28,16 @@ impl Language {
         }
     }
 
+    /// Returns the file name of the language's native manifest.
+    pub fn manifest(&self) -> &'static str {
+        match self {
+            Language::Js | Language::Ts => "package.json",
+            Language::Python => "pyproject.toml",
+            Language::Go => "go.mod",
+            Language::Rust => "Cargo.toml",
+        }
+    }
+
     /// Parses a language string into a `Language` variant.
     ///
     /// Supports aliases (e.g., "javascript" for "js", "typescript" for "ts").
@@ -54,7 +64,7 @@ pub struct Task {
 }
 
 /// Represents a package in the monorepo.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Package {
     pub name: String,
     pub language: Language,
@@ -67,6 +77,12 @@ pub struct Package {
     pub deps: SmallVec<[String; 4]>,
     pub tasks: Vec<Task>,
     pub version: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Globs of files, relative to `path`, whose changes don't affect the
+    /// package.
+    #[serde(default)]
+    pub affected_ignore: Vec<String>,
 }
 
 fn deserialize_deps<'de, D>(deserializer: D) -> Result<SmallVec<[String; 4]>, D::Error>
@@ -104,9 +120,16 @@ impl Package {
             deps: SmallVec::from_vec(deps),
             tasks,
             version: None,
+            tags: Vec::new(),
+            affected_ignore: Vec::new(),
         }
     }
 
+    #[inline]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
     #[inline]
     pub fn get_task(&self, name: &str) -> Option<&Task> {
         self.tasks.iter().find(|t| t.name == name)
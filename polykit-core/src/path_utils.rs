@@ -1,47 +1,341 @@
 //! Shared path utilities for package discovery.
 
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use rustc_hash::FxHashMap;
+
+use crate::config::GlobalInput;
+use crate::error::{Error, Result};
+use crate::filter::PackageFilter;
+use crate::graph::DependencyGraph;
+use crate::package::Package;
+
+/// Maps package directories to package names.
+///
+/// Directories are relative to the packages root, matching `Package::path`.
+#[derive(Debug, Clone, Default)]
+pub struct PackagePaths {
+    by_path: FxHashMap<PathBuf, String>,
+    /// Compiled `affected_ignore` globs by package name.
+    ignored: FxHashMap<String, GlobSet>,
+}
+
+impl PackagePaths {
+    /// Builds the map from scanned packages.
+    pub fn new<'a>(packages: impl IntoIterator<Item = &'a Package>) -> Self {
+        let mut by_path = FxHashMap::default();
+        let mut ignored = FxHashMap::default();
+        for package in packages {
+            by_path.insert(normalize(&package.path), package.name.clone());
+            if package.affected_ignore.is_empty() {
+                continue;
+            }
+            // Patterns are validated when the package is scanned.
+            let mut builder = GlobSetBuilder::new();
+            for pattern in &package.affected_ignore {
+                if let Ok(glob) = ignore_glob(pattern) {
+                    builder.add(glob);
+                }
+            }
+            if let Ok(set) = builder.build() {
+                ignored.insert(package.name.clone(), set);
+            }
+        }
+        Self { by_path, ignored }
+    }
+
+    /// Returns the deepest package whose directory contains `relative_path`.
+    ///
+    /// Nested packages take precedence over the packages enclosing them.
+    /// Returns `None` if the path matches one of that package's
+    /// `affected_ignore` globs.
+    pub fn package_for(&self, relative_path: &Path) -> Option<&str> {
+        let path = normalize(relative_path);
+        let (dir, name) = path
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .find_map(|ancestor| self.by_path.get_key_value(ancestor))?;
+        let inside = path.strip_prefix(dir).ok()?;
+        if self.is_ignored(name, inside) {
+            return None;
+        }
+        Some(name.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_path.is_empty()
+    }
+
+    /// Returns `true` if `inside`, or a directory containing it, matches an
+    /// `affected_ignore` glob of `package`.
+    fn is_ignored(&self, package: &str, inside: &Path) -> bool {
+        self.ignored.get(package).is_some_and(|set| {
+            inside
+                .ancestors()
+                .filter(|ancestor| !ancestor.as_os_str().is_empty())
+                .any(|ancestor| set.is_match(ancestor))
+        })
+    }
+}
+
+/// Checks that every `affected_ignore` pattern of `package` is a valid glob.
+///
+/// # Errors
+///
+/// Returns an error naming the first invalid pattern.
+pub(crate) fn validate_affected_ignore(package: &str, patterns: &[String]) -> Result<()> {
+    for pattern in patterns {
+        ignore_glob(pattern).map_err(|e| Error::Adapter {
+            package: package.to_string(),
+            message: format!("Invalid affected_ignore glob '{}': {}", pattern, e),
+        })?;
+    }
+    Ok(())
+}
+
+/// Compiles an `affected_ignore` pattern.
+///
+/// Like `.gitignore` entries, patterns without a `/` match a file or
+/// directory name at any depth, so `*.md` matches `docs/guide.md`, while
+/// other patterns are relative to the package directory.
+fn ignore_glob(pattern: &str) -> std::result::Result<Glob, globset::Error> {
+    let pattern = pattern.trim_end_matches('/');
+    let pattern = if pattern.contains('/') {
+        pattern.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+    GlobBuilder::new(&pattern).literal_separator(true).build()
+}
+
+/// Workspace files outside package directories whose changes affect packages,
+/// from `[workspace] global_inputs`.
+#[derive(Debug, Clone, Default)]
+pub struct GlobalInputs {
+    /// The directory the globs are relative to.
+    root: PathBuf,
+    inputs: Vec<(GlobMatcher, Vec<String>)>,
+}
+
+impl GlobalInputs {
+    /// Resolves global inputs against a graph.
+    ///
+    /// Globs are relative to `root`; path selectors in filters are relative
+    /// to `packages_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a glob or filter is invalid, or a filter names a
+    /// package that does not exist.
+    pub fn new(
+        root: impl Into<PathBuf>,
+        inputs: &[GlobalInput],
+        graph: &DependencyGraph,
+        packages_dir: &Path,
+    ) -> Result<Self> {
+        let inputs = inputs
+            .iter()
+            .map(|input| {
+                let glob = GlobBuilder::new(input.glob().trim_end_matches('/'))
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| Error::Adapter {
+                        package: "workspace".to_string(),
+                        message: format!("Invalid global input '{}': {}", input.glob(), e),
+                    })?;
+                let packages = PackageFilter::parse(input.filter())?.select(graph, packages_dir)?;
+                Ok((glob.compile_matcher(), packages))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            root: root.into(),
+            inputs,
+        })
+    }
+
+    /// Replaces the directory changed files are matched relative to, such as
+    /// the canonical workspace root for absolute watcher paths.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Returns the packages affected by a change to `file_path`.
+    pub fn packages_for(&self, file_path: &Path) -> Vec<&str> {
+        let file_path = normalize(file_path);
+        let root = normalize(&self.root);
+        let Ok(relative) = file_path.strip_prefix(&root) else {
+            return Vec::new();
+        };
+        self.inputs
+            .iter()
+            .filter(|(glob, _)| glob.is_match(relative))
+            .flat_map(|(_, packages)| packages.iter().map(|name| name.as_str()))
+            .collect()
+    }
+
+    /// Returns the directories holding the inputs and whether each must be
+    /// watched recursively.
+    pub fn watch_dirs(&self) -> Vec<(PathBuf, bool)> {
+        let mut dirs: Vec<(PathBuf, bool)> = Vec::new();
+        for (glob, _) in &self.inputs {
+            let parts: Vec<&str> = glob.glob().glob().split('/').collect();
+            let literal = parts
+                .iter()
+                .take_while(|part| !part.contains(['*', '?', '[', '{']))
+                .count();
+            let (base, recursive) = if literal == parts.len() {
+                (&parts[..literal - 1], false)
+            } else {
+                let rest = &parts[literal..];
+                (&parts[..literal], rest.len() > 1 || rest.contains(&"**"))
+            };
+            let dir = base
+                .iter()
+                .fold(self.root.clone(), |dir, part| dir.join(part));
+            match dirs.iter_mut().find(|(existing, _)| *existing == dir) {
+                Some((_, existing)) => *existing |= recursive,
+                None => dirs.push((dir, recursive)),
+            }
+        }
+        dirs
+    }
+}
 
 /// Converts a file path to its corresponding package name.
 ///
-/// This function extracts the package name from a file path relative to
-/// the packages directory. It handles both direct file paths and paths
-/// to polykit.toml configuration files.
+/// The file is mapped to the deepest enclosing package directory, so a file
+/// in `libs/ui/button/src` belongs to `libs/ui/button` rather than `libs/ui`.
 ///
 /// # Arguments
 ///
 /// * `file_path` - The file path to convert
-/// * `packages_dir` - The base packages directory
+/// * `packages_dir` - The base directory package paths are relative to
+/// * `package_paths` - The known package directories
 ///
 /// # Returns
 ///
 /// Returns `Some(package_name)` if a package can be determined, `None` otherwise.
-pub fn file_to_package(file_path: &Path, packages_dir: &Path) -> Option<String> {
-    let relative = file_path.strip_prefix(packages_dir).ok()?;
-
-    for component in relative.components() {
-        if let std::path::Component::Normal(name) = component {
-            let name_str = name.to_string_lossy();
-            if name_str == "polykit.toml" {
-                return relative
-                    .parent()
-                    .and_then(|p| p.components().next())
-                    .and_then(|c| {
-                        if let std::path::Component::Normal(n) = c {
-                            Some(n.to_string_lossy().to_string())
-                        } else {
-                            None
-                        }
-                    });
-            }
-        }
+pub fn file_to_package(
+    file_path: &Path,
+    packages_dir: &Path,
+    package_paths: &PackagePaths,
+) -> Option<String> {
+    let file_path = normalize(file_path);
+    let packages_dir = normalize(packages_dir);
+    let relative = file_path.strip_prefix(&packages_dir).ok()?;
+
+    package_paths
+        .package_for(relative)
+        .map(|name| name.to_string())
+}
+
+/// Drops `.` components so `./packages/a` and `packages/a` compare equal.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::Language;
+
+    fn package(name: &str, path: &str) -> Package {
+        Package::new(
+            name.to_string(),
+            Language::Rust,
+            false,
+            PathBuf::from(path),
+            vec![],
+            vec![],
+        )
     }
 
-    relative.components().next().and_then(|c| {
-        if let std::path::Component::Normal(n) = c {
-            Some(n.to_string_lossy().to_string())
-        } else {
-            None
-        }
-    })
+    #[test]
+    fn test_file_to_deepest_package() {
+        let packages = [
+            package("ui", "libs/ui"),
+            package("button", "libs/ui/button"),
+        ];
+        let paths = PackagePaths::new(&packages);
+        let root = Path::new(".");
+
+        assert_eq!(
+            file_to_package(Path::new("libs/ui/button/src/lib.rs"), root, &paths).as_deref(),
+            Some("button")
+        );
+        assert_eq!(
+            file_to_package(Path::new("./libs/ui/src/lib.rs"), root, &paths).as_deref(),
+            Some("ui")
+        );
+        assert_eq!(
+            file_to_package(Path::new("libs/ui/polykit.toml"), root, &paths).as_deref(),
+            Some("ui")
+        );
+        assert_eq!(file_to_package(Path::new("README.md"), root, &paths), None);
+    }
+
+    #[test]
+    fn test_affected_ignore() {
+        let mut ui = package("ui", "libs/ui");
+        ui.affected_ignore = vec![
+            "*.md".to_string(),
+            "docs/".to_string(),
+            "/CHANGELOG".to_string(),
+        ];
+        let packages = [ui, package("button", "libs/ui/button")];
+        let paths = PackagePaths::new(&packages);
+        let package_for = |path: &str| paths.package_for(Path::new(path));
+
+        assert_eq!(package_for("libs/ui/README.md"), None);
+        assert_eq!(package_for("libs/ui/src/notes.md"), None);
+        assert_eq!(package_for("libs/ui/docs/api/index.html"), None);
+        assert_eq!(package_for("libs/ui/CHANGELOG"), None);
+        assert_eq!(package_for("libs/ui/src/CHANGELOG"), Some("ui"));
+        assert_eq!(package_for("libs/ui/src/docs.rs"), Some("ui"));
+        // Ignore rules belong to the package that owns the file.
+        assert_eq!(package_for("libs/ui/button/README.md"), Some("button"));
+
+        assert!(validate_affected_ignore("ui", &["src/{a".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_global_input_watch_dirs() {
+        let graph = DependencyGraph::new(vec![package("ui", "libs/ui")]).unwrap();
+        let inputs = [
+            GlobalInput::Glob("Cargo.lock".to_string()),
+            GlobalInput::Glob(".github/**".to_string()),
+            GlobalInput::Glob("config/*.json".to_string()),
+            GlobalInput::Glob("config/nested/*/base.json".to_string()),
+        ];
+        let global_inputs =
+            GlobalInputs::new("/repo", &inputs, &graph, Path::new("/repo")).unwrap();
+
+        assert_eq!(
+            global_inputs.watch_dirs(),
+            vec![
+                (PathBuf::from("/repo"), false),
+                (PathBuf::from("/repo/.github"), true),
+                (PathBuf::from("/repo/config"), false),
+                (PathBuf::from("/repo/config/nested"), true),
+            ]
+        );
+        assert_eq!(
+            global_inputs.packages_for(Path::new("/repo/config/app.json")),
+            ["ui"]
+        );
+        assert!(global_inputs
+            .packages_for(Path::new("/repo/config/nested/app.json"))
+            .is_empty());
+        assert!(global_inputs
+            .packages_for(Path::new("/elsewhere/Cargo.lock"))
+            .is_empty());
+    }
 }
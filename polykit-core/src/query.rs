@@ -0,0 +1,630 @@
+//! Graph query language modeled on Bazel query.
+//!
+//! A query evaluates to a set of packages:
+//!
+//! | Expression | Result |
+//! |------------|--------|
+//! | `api` | The package `api` |
+//! | `//...` | Every package |
+//! | `deps(x)`, `deps(x, 2)` | `x` and its dependencies, optionally up to a depth |
+//! | `rdeps(x)`, `rdeps(x, 1)` | `x` and the packages depending on it |
+//! | `allpaths(a, b)` | Every package on a dependency path from `a` to `b` |
+//! | `somepath(a, b)` | One dependency path from `a` to `b` |
+//! | `attr(language, rust, x)` | Packages in `x` whose attribute matches a regex |
+//! | `attr(tag, 'team:.*', x)` | Packages in `x` with a tag matching a regex |
+//! | `x + y`, `x union y` | Union |
+//! | `x ^ y`, `x intersect y` | Intersection |
+//! | `x - y`, `x except y` | Difference |
+//!
+//! Binary operators share one precedence and associate to the left, so
+//! `a + b - c` is `(a + b) - c`. An operator `-` must be separated from the
+//! words around it, because `-` is also valid inside package names.
+
+use std::collections::VecDeque;
+
+use regex::Regex;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::error::{Error, Result};
+use crate::graph::DependencyGraph;
+use crate::package::Package;
+
+/// Word matching every package.
+const ALL_PACKAGES: &str = "//...";
+
+/// Attributes accepted by `attr`.
+const ATTRIBUTES: &[&str] = &["name", "language", "public", "version", "path", "tag"];
+
+/// A parsed query expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    All,
+    Package(String),
+    Deps {
+        expr: Box<Query>,
+        depth: Option<usize>,
+    },
+    Rdeps {
+        expr: Box<Query>,
+        depth: Option<usize>,
+    },
+    AllPaths(Box<Query>, Box<Query>),
+    SomePath(Box<Query>, Box<Query>),
+    Attr {
+        name: String,
+        pattern: String,
+        expr: Box<Query>,
+    },
+    Union(Box<Query>, Box<Query>),
+    Intersect(Box<Query>, Box<Query>),
+    Except(Box<Query>, Box<Query>),
+}
+
+impl Query {
+    /// Parses a query expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression is malformed, calls an unknown
+    /// function or passes the wrong number of arguments.
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let query = parser.expr()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(token) => Err(query_error(format!(
+                "unexpected '{}' at offset {}",
+                token.kind, token.offset
+            ))),
+        }
+    }
+
+    /// Evaluates the query against a graph.
+    ///
+    /// Returns the matching packages in topological order, dependencies
+    /// before dependents.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query names a package that is not in the
+    /// graph, or `attr` is given an unknown attribute or invalid regex.
+    pub fn evaluate(&self, graph: &DependencyGraph) -> Result<Vec<String>> {
+        let selected = self.eval(graph)?;
+        Ok(graph
+            .topological_order()
+            .into_iter()
+            .filter(|name| selected.contains(name))
+            .collect())
+    }
+
+    fn eval(&self, graph: &DependencyGraph) -> Result<FxHashSet<String>> {
+        match self {
+            Query::All => Ok(graph.topological_order().into_iter().collect()),
+            Query::Package(name) => {
+                if graph.get_package(name).is_none() {
+                    return Err(Error::PackageNotFound {
+                        name: name.clone(),
+                        available: format!("Package '{}' not found", name),
+                    });
+                }
+                Ok(FxHashSet::from_iter([name.clone()]))
+            }
+            Query::Deps { expr, depth } => reachable(graph, expr.eval(graph)?, *depth, false),
+            Query::Rdeps { expr, depth } => reachable(graph, expr.eval(graph)?, *depth, true),
+            Query::AllPaths(from, to) => {
+                let forward = reachable(graph, from.eval(graph)?, None, false)?;
+                let backward = reachable(graph, to.eval(graph)?, None, true)?;
+                Ok(forward.intersection(&backward).cloned().collect())
+            }
+            Query::SomePath(from, to) => some_path(graph, from.eval(graph)?, &to.eval(graph)?),
+            Query::Attr {
+                name,
+                pattern,
+                expr,
+            } => {
+                if !ATTRIBUTES.contains(&name.as_str()) {
+                    return Err(query_error(format!(
+                        "unknown attribute '{}', expected one of: {}",
+                        name,
+                        ATTRIBUTES.join(", ")
+                    )));
+                }
+                let regex = Regex::new(&format!("^(?:{})$", pattern))
+                    .map_err(|e| query_error(format!("invalid pattern '{}': {}", pattern, e)))?;
+                Ok(expr
+                    .eval(graph)?
+                    .into_iter()
+                    .filter(|package| {
+                        graph.get_package(package).is_some_and(|package| {
+                            if name == "tag" {
+                                package.tags.iter().any(|tag| regex.is_match(tag))
+                            } else {
+                                regex.is_match(&attribute(package, name))
+                            }
+                        })
+                    })
+                    .collect())
+            }
+            Query::Union(left, right) => {
+                let mut result = left.eval(graph)?;
+                result.extend(right.eval(graph)?);
+                Ok(result)
+            }
+            Query::Intersect(left, right) => {
+                let right = right.eval(graph)?;
+                Ok(left
+                    .eval(graph)?
+                    .into_iter()
+                    .filter(|name| right.contains(name))
+                    .collect())
+            }
+            Query::Except(left, right) => {
+                let right = right.eval(graph)?;
+                Ok(left
+                    .eval(graph)?
+                    .into_iter()
+                    .filter(|name| !right.contains(name))
+                    .collect())
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Query {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        Self::parse(input)
+    }
+}
+
+/// Returns `start` and every package within `depth` edges of it.
+pub(crate) fn reachable(
+    graph: &DependencyGraph,
+    start: FxHashSet<String>,
+    depth: Option<usize>,
+    dependents: bool,
+) -> Result<FxHashSet<String>> {
+    let mut queue: VecDeque<(String, usize)> = start.iter().map(|name| (name.clone(), 0)).collect();
+    let mut result = start;
+
+    while let Some((name, distance)) = queue.pop_front() {
+        if depth.is_some_and(|d| distance >= d) {
+            continue;
+        }
+        let next = if dependents {
+            graph.dependents(&name)?
+        } else {
+            graph.dependencies(&name)?
+        };
+        for neighbour in next {
+            if result.insert(neighbour.clone()) {
+                queue.push_back((neighbour, distance + 1));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Returns the packages on a shortest dependency path from `from` to `to`,
+/// or an empty set if there is none.
+fn some_path(
+    graph: &DependencyGraph,
+    from: FxHashSet<String>,
+    to: &FxHashSet<String>,
+) -> Result<FxHashSet<String>> {
+    let mut parents: FxHashMap<String, Option<String>> =
+        from.iter().map(|name| (name.clone(), None)).collect();
+    let mut queue: VecDeque<String> = from.into_iter().collect();
+
+    while let Some(name) = queue.pop_front() {
+        if to.contains(&name) {
+            let mut path = FxHashSet::default();
+            let mut current = Some(name);
+            while let Some(name) = current {
+                current = parents.get(&name).cloned().flatten();
+                path.insert(name);
+            }
+            return Ok(path);
+        }
+        for dep in graph.dependencies(&name)? {
+            if !parents.contains_key(&dep) {
+                parents.insert(dep.clone(), Some(name.clone()));
+                queue.push_back(dep);
+            }
+        }
+    }
+
+    Ok(FxHashSet::default())
+}
+
+fn attribute(package: &Package, name: &str) -> String {
+    match name {
+        "name" => package.name.clone(),
+        "language" => package.language.as_str().to_string(),
+        "public" => package.public.to_string(),
+        "version" => package.version.clone().unwrap_or_default(),
+        _ => package.path.to_string_lossy().into_owned(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Word(String),
+    LParen,
+    RParen,
+    Comma,
+    Plus,
+    Caret,
+    Minus,
+}
+
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Word(word) => write!(f, "{}", word),
+            TokenKind::LParen => write!(f, "("),
+            TokenKind::RParen => write!(f, ")"),
+            TokenKind::Comma => write!(f, ","),
+            TokenKind::Plus => write!(f, "+"),
+            TokenKind::Caret => write!(f, "^"),
+            TokenKind::Minus => write!(f, "-"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    offset: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | '/' | '*' | ':')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(offset, c)) = chars.peek() {
+        let kind = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            ',' => TokenKind::Comma,
+            '+' => TokenKind::Plus,
+            '^' => TokenKind::Caret,
+            '-' => TokenKind::Minus,
+            '"' | '\'' => {
+                chars.next();
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, end)) if end == c => break,
+                        Some((_, next)) => word.push(next),
+                        None => {
+                            return Err(query_error(format!(
+                                "unterminated string at offset {}",
+                                offset
+                            )))
+                        }
+                    }
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Word(word),
+                    offset,
+                });
+                continue;
+            }
+            c if is_word_char(c) => {
+                let mut word = String::new();
+                while let Some(&(_, next)) = chars.peek() {
+                    if !is_word_char(next) {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Word(word),
+                    offset,
+                });
+                continue;
+            }
+            c => {
+                return Err(query_error(format!(
+                    "unexpected '{}' at offset {}",
+                    c, offset
+                )))
+            }
+        };
+        chars.next();
+        tokens.push(Token { kind, offset });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| query_error("unexpected end of query".to_string()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<()> {
+        let token = self.next()?;
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Err(query_error(format!(
+                "expected '{}' but found '{}' at offset {}",
+                kind, token.kind, token.offset
+            )))
+        }
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        if self.peek().is_some_and(|token| token.kind == kind) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<Query> {
+        let mut left = self.term()?;
+        loop {
+            let operator = match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::Plus) => TokenKind::Plus,
+                Some(TokenKind::Caret) => TokenKind::Caret,
+                Some(TokenKind::Minus) => TokenKind::Minus,
+                Some(TokenKind::Word(word)) => match word.as_str() {
+                    "union" => TokenKind::Plus,
+                    "intersect" => TokenKind::Caret,
+                    "except" => TokenKind::Minus,
+                    _ => break,
+                },
+                _ => break,
+            };
+            self.position += 1;
+            let right = Box::new(self.term()?);
+            left = match operator {
+                TokenKind::Plus => Query::Union(Box::new(left), right),
+                TokenKind::Caret => Query::Intersect(Box::new(left), right),
+                _ => Query::Except(Box::new(left), right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Query> {
+        let token = self.next()?;
+        let word = match token.kind {
+            TokenKind::LParen => {
+                let query = self.expr()?;
+                self.expect(TokenKind::RParen)?;
+                return Ok(query);
+            }
+            TokenKind::Word(word) => word,
+            kind => {
+                return Err(query_error(format!(
+                    "unexpected '{}' at offset {}",
+                    kind, token.offset
+                )))
+            }
+        };
+
+        if !self.eat(TokenKind::LParen) {
+            return Ok(if word == ALL_PACKAGES {
+                Query::All
+            } else {
+                Query::Package(word)
+            });
+        }
+
+        let query = match word.as_str() {
+            "deps" | "rdeps" => {
+                let expr = Box::new(self.expr()?);
+                let depth = if self.eat(TokenKind::Comma) {
+                    Some(self.depth()?)
+                } else {
+                    None
+                };
+                if word == "deps" {
+                    Query::Deps { expr, depth }
+                } else {
+                    Query::Rdeps { expr, depth }
+                }
+            }
+            "allpaths" | "somepath" => {
+                let from = Box::new(self.expr()?);
+                self.expect(TokenKind::Comma)?;
+                let to = Box::new(self.expr()?);
+                if word == "allpaths" {
+                    Query::AllPaths(from, to)
+                } else {
+                    Query::SomePath(from, to)
+                }
+            }
+            "attr" => {
+                let name = self.word()?;
+                self.expect(TokenKind::Comma)?;
+                let pattern = self.word()?;
+                self.expect(TokenKind::Comma)?;
+                Query::Attr {
+                    name,
+                    pattern,
+                    expr: Box::new(self.expr()?),
+                }
+            }
+            _ => {
+                return Err(query_error(format!(
+                    "unknown function '{}' at offset {}, expected one of: deps, rdeps, allpaths, somepath, attr",
+                    word, token.offset
+                )))
+            }
+        };
+        self.expect(TokenKind::RParen)?;
+        Ok(query)
+    }
+
+    fn word(&mut self) -> Result<String> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Word(word) => Ok(word),
+            kind => Err(query_error(format!(
+                "expected a word but found '{}' at offset {}",
+                kind, token.offset
+            ))),
+        }
+    }
+
+    fn depth(&mut self) -> Result<usize> {
+        let offset = self.peek().map(|token| token.offset).unwrap_or_default();
+        let word = self.word()?;
+        word.parse().map_err(|_| {
+            query_error(format!(
+                "expected a depth but found '{}' at offset {}",
+                word, offset
+            ))
+        })
+    }
+}
+
+fn query_error(message: String) -> Error {
+    Error::Query(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::Language;
+    use std::path::PathBuf;
+
+    fn package(name: &str, language: Language, deps: &[&str]) -> Package {
+        Package::new(
+            name.to_string(),
+            language,
+            true,
+            PathBuf::from(name),
+            deps.iter().map(|d| d.to_string()).collect(),
+            vec![],
+        )
+    }
+
+    fn graph() -> DependencyGraph {
+        DependencyGraph::new(vec![
+            package("core", Language::Rust, &[]),
+            package("utils", Language::Rust, &["core"]),
+            package("ml-core", Language::Python, &["core"]),
+            package("api", Language::Rust, &["utils", "ml-core"]),
+            package("web", Language::Ts, &["api"]),
+        ])
+        .unwrap()
+    }
+
+    fn eval(query: &str) -> Vec<String> {
+        let mut result = Query::parse(query).unwrap().evaluate(&graph()).unwrap();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn test_parse_operators_associate_left() {
+        assert_eq!(
+            Query::parse("a + b - c").unwrap(),
+            Query::Except(
+                Box::new(Query::Union(
+                    Box::new(Query::Package("a".to_string())),
+                    Box::new(Query::Package("b".to_string())),
+                )),
+                Box::new(Query::Package("c".to_string())),
+            )
+        );
+        assert_eq!(
+            Query::parse("a-b intersect //...").unwrap(),
+            Query::Intersect(
+                Box::new(Query::Package("a-b".to_string())),
+                Box::new(Query::All),
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Query::parse("deps(api").is_err());
+        assert!(Query::parse("deps(api, two)").is_err());
+        assert!(Query::parse("owners(api)").is_err());
+        assert!(Query::parse("api +").is_err());
+        assert!(Query::parse("api web").is_err());
+        assert!(Query::parse("attr('language, rust, //...)").is_err());
+    }
+
+    #[test]
+    fn test_deps_and_rdeps() {
+        assert_eq!(eval("deps(api)"), ["api", "core", "ml-core", "utils"]);
+        assert_eq!(eval("deps(api, 1)"), ["api", "ml-core", "utils"]);
+        assert_eq!(eval("rdeps(utils)"), ["api", "utils", "web"]);
+        assert_eq!(eval("rdeps(core, 1) - core"), ["ml-core", "utils"]);
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(
+            eval("allpaths(web, core)"),
+            ["api", "core", "ml-core", "utils", "web"]
+        );
+        assert_eq!(eval("somepath(web, core)").len(), 4);
+        assert!(eval("somepath(core, web)").is_empty());
+    }
+
+    #[test]
+    fn test_attr_and_set_operators() {
+        assert_eq!(
+            eval("attr(language, rust, //...)"),
+            ["api", "core", "utils"]
+        );
+        assert_eq!(eval("attr(name, '.*core', //...) ^ deps(utils)"), ["core"]);
+        assert_eq!(eval("web union ml-core"), ["ml-core", "web"]);
+        assert!(Query::parse("attr(owner, x, //...)")
+            .unwrap()
+            .evaluate(&graph())
+            .is_err());
+    }
+
+    #[test]
+    fn test_evaluate_in_topological_order() {
+        let result = Query::parse("//... except web")
+            .unwrap()
+            .evaluate(&graph())
+            .unwrap();
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[0], "core");
+        assert_eq!(result[3], "api");
+        assert!(Query::parse("missing").unwrap().evaluate(&graph()).is_err());
+    }
+}
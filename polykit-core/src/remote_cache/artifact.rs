@@ -1,15 +1,27 @@
 //! Artifact format for cached task outputs.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::error::{Error, Result};
 
+use super::compression::{decompress, ArtifactCodec, Compressor, Dictionaries, ZSTD};
+use super::outputs::{validate_output_path, validate_symlink, OutputEntry};
+use super::signing::{ArtifactSignature, SigningKey};
+
+/// Tar entry holding the artifact signature.
+pub(crate) const SIGNATURE_ENTRY: &str = "signature.json";
+
+/// Format version written by this build.
+///
+/// Version 2 added file modes, symlinks, directories and mtimes.
+pub const ARTIFACT_VERSION: u32 = 2;
+
 /// Metadata about a cached artifact.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArtifactMetadata {
@@ -25,15 +37,33 @@ pub struct ArtifactMetadata {
     pub created_at: u64,
     /// Artifact format version.
     pub version: u32,
+    /// Compression used for the artifact.
+    #[serde(default)]
+    pub codec: ArtifactCodec,
 }
 
 /// Manifest of files contained in the artifact.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Everything needed to restore an output is part of the manifest, so it is
+/// covered by the artifact signature.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ArtifactManifest {
     /// Map of relative paths to SHA-256 hashes.
     pub files: BTreeMap<PathBuf, String>,
     /// Total size of all files (uncompressed).
     pub total_size: u64,
+    /// Unix permission bits of files and directories.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modes: BTreeMap<PathBuf, u32>,
+    /// Symlink targets, relative to the directory containing the link.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub symlinks: BTreeMap<PathBuf, PathBuf>,
+    /// Recorded directories, including empty ones.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub directories: BTreeSet<PathBuf>,
+    /// Modification times (Unix epoch seconds) of files and directories.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mtimes: BTreeMap<PathBuf, u64>,
 }
 
 /// Cached task artifact containing outputs and metadata.
@@ -42,16 +72,27 @@ pub struct ArtifactManifest {
 /// - Metadata (task info, timestamps, cache key hash)
 /// - Manifest (list of output files with hashes)
 /// - Compressed output files
+/// - Optionally, a signature over the metadata and manifest
 #[derive(Debug)]
 pub struct Artifact {
     metadata: ArtifactMetadata,
     manifest: ArtifactManifest,
+    signature: Option<ArtifactSignature>,
+    /// SHA-256 of the raw `metadata.json` entry.
+    metadata_digest: String,
+    /// SHA-256 of the raw `manifest.json` entry.
+    manifest_digest: String,
+    /// Settings the artifact was compressed with, reused when it is rewritten.
+    compressor: Compressor,
     compressed_data: Vec<u8>,
 }
 
 impl Artifact {
     /// Creates a new artifact from task outputs.
     ///
+    /// Every output is stored as a regular file with default permissions; use
+    /// [`Artifact::from_entries`] to keep modes, symlinks and directories.
+    ///
     /// # Arguments
     ///
     /// * `package_name` - Name of the package
@@ -69,6 +110,38 @@ impl Artifact {
         command: String,
         cache_key_hash: String,
         output_files: BTreeMap<PathBuf, Vec<u8>>,
+    ) -> Result<Self> {
+        let entries = output_files
+            .into_iter()
+            .map(|(path, content)| (path, OutputEntry::file(content)))
+            .collect();
+        Self::from_entries(package_name, task_name, command, cache_key_hash, entries)
+    }
+
+    /// Creates a new artifact from output files, symlinks and directories.
+    ///
+    /// The artifact is compressed with default settings; see
+    /// [`Artifact::compressed_with`].
+    ///
+    /// # Arguments
+    ///
+    /// * `package_name` - Name of the package
+    /// * `task_name` - Name of the task
+    /// * `command` - Command that was executed
+    /// * `cache_key_hash` - Hash of the cache key
+    /// * `entries` - Map of paths relative to the package to outputs, e.g.
+    ///   from [`collect_outputs`](super::collect_outputs)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a path or symlink points outside the package, or
+    /// compression or serialization fails.
+    pub fn from_entries(
+        package_name: String,
+        task_name: String,
+        command: String,
+        cache_key_hash: String,
+        entries: BTreeMap<PathBuf, OutputEntry>,
     ) -> Result<Self> {
         let created_at = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -78,22 +151,35 @@ impl Artifact {
             })?
             .as_secs();
 
-        let mut files = BTreeMap::new();
-        let mut total_size = 0u64;
+        let mut manifest = ArtifactManifest::default();
 
-        // Compute hashes for all files
-        for (path, content) in &output_files {
-            let mut hasher = Sha256::new();
-            hasher.update(content);
-            let hash = format!("{:x}", hasher.finalize());
-            files.insert(path.clone(), hash);
-            total_size += content.len() as u64;
+        // Compute hashes for all files and record everything needed to restore them
+        for (path, entry) in &entries {
+            validate_output_path(path)?;
+            match entry {
+                OutputEntry::File { content, mode, mtime } => {
+                    manifest.files.insert(path.clone(), sha256_hex(content));
+                    manifest.modes.insert(path.clone(), *mode);
+                    manifest.total_size += content.len() as u64;
+                    if let Some(mtime) = mtime {
+                        manifest.mtimes.insert(path.clone(), *mtime);
+                    }
+                }
+                OutputEntry::Symlink { target } => {
+                    manifest.symlinks.insert(path.clone(), target.clone());
+                }
+                OutputEntry::Directory { mode, mtime } => {
+                    manifest.directories.insert(path.clone());
+                    manifest.modes.insert(path.clone(), *mode);
+                    if let Some(mtime) = mtime {
+                        manifest.mtimes.insert(path.clone(), *mtime);
+                    }
+                }
+            }
+        }
+        for (link, target) in &manifest.symlinks {
+            validate_symlink(link, target, &manifest.symlinks)?;
         }
-
-        let manifest = ArtifactManifest {
-            files,
-            total_size,
-        };
 
         let metadata = ArtifactMetadata {
             package_name,
@@ -101,19 +187,25 @@ impl Artifact {
             command,
             cache_key_hash,
             created_at,
-            version: 1,
+            version: ARTIFACT_VERSION,
+            codec: ArtifactCodec::default(),
         };
 
+        let metadata_json = serde_json::to_string(&metadata).map_err(|e| Error::Adapter {
+            package: "artifact".to_string(),
+            message: format!("Failed to serialize metadata: {}", e),
+        })?;
+        let manifest_json = serde_json::to_string(&manifest).map_err(|e| Error::Adapter {
+            package: "artifact".to_string(),
+            message: format!("Failed to serialize manifest: {}", e),
+        })?;
+
         // Create tar archive in memory
         let mut tar_data = Vec::new();
         {
             let mut tar = tar::Builder::new(&mut tar_data);
 
             // Add metadata.json
-            let metadata_json = serde_json::to_string(&metadata).map_err(|e| Error::Adapter {
-                package: "artifact".to_string(),
-                message: format!("Failed to serialize metadata: {}", e),
-            })?;
             let mut metadata_header = tar::Header::new_gnu();
             metadata_header.set_path("metadata.json").map_err(|e| Error::Adapter {
                 package: "artifact".to_string(),
@@ -128,10 +220,6 @@ impl Artifact {
                 })?;
 
             // Add manifest.json
-            let manifest_json = serde_json::to_string(&manifest).map_err(|e| Error::Adapter {
-                package: "artifact".to_string(),
-                message: format!("Failed to serialize manifest: {}", e),
-            })?;
             let mut manifest_header = tar::Header::new_gnu();
             manifest_header.set_path("manifest.json").map_err(|e| Error::Adapter {
                 package: "artifact".to_string(),
@@ -145,19 +233,32 @@ impl Artifact {
                     message: format!("Failed to append manifest: {}", e),
                 })?;
 
-            // Add output files
-            for (path, content) in &output_files {
+            // Add outputs; modes and mtimes in the headers are informational,
+            // extraction uses the manifest
+            for (path, entry) in &entries {
                 let mut header = tar::Header::new_gnu();
                 let output_path = Path::new("outputs").join(path);
-                header.set_path(&output_path).map_err(|e| Error::Adapter {
-                    package: "artifact".to_string(),
-                    message: format!("Failed to set output path: {}", e),
-                })?;
+                let (entry_type, mode, mtime, content): (_, _, _, &[u8]) = match entry {
+                    OutputEntry::File { content, mode, mtime } => {
+                        (tar::EntryType::Regular, *mode, *mtime, content)
+                    }
+                    OutputEntry::Symlink { .. } => (tar::EntryType::Symlink, 0o777, None, &[]),
+                    OutputEntry::Directory { mode, mtime } => {
+                        (tar::EntryType::Directory, *mode, *mtime, &[])
+                    }
+                };
+                header.set_entry_type(entry_type);
+                header.set_mode(mode);
+                header.set_mtime(mtime.unwrap_or(0));
                 header.set_size(content.len() as u64);
-                header.set_cksum();
-                tar.append(&header, content.as_slice()).map_err(|e| Error::Adapter {
+
+                let result = match entry {
+                    OutputEntry::Symlink { target } => tar.append_link(&mut header, &output_path, target),
+                    _ => tar.append_data(&mut header, &output_path, content),
+                };
+                result.map_err(|e| Error::Adapter {
                     package: "artifact".to_string(),
-                    message: format!("Failed to append output file: {}", e),
+                    message: format!("Failed to append output {}: {}", path.display(), e),
                 })?;
             }
 
@@ -168,14 +269,16 @@ impl Artifact {
         }
 
         // Compress with zstd
-        let compressed_data = zstd::encode_all(&tar_data[..], 3).map_err(|e| Error::Adapter {
-            package: "artifact".to_string(),
-            message: format!("Failed to compress artifact: {}", e),
-        })?;
+        let compressor = Compressor::default();
+        let compressed_data = compressor.compress(&tar_data)?;
 
         Ok(Self {
             metadata,
             manifest,
+            signature: None,
+            metadata_digest: sha256_hex(metadata_json.as_bytes()),
+            manifest_digest: sha256_hex(manifest_json.as_bytes()),
+            compressor,
             compressed_data,
         })
     }
@@ -184,18 +287,27 @@ impl Artifact {
     ///
     /// # Errors
     ///
-    /// Returns an error if decompression, deserialization, or verification fails.
+    /// Returns an error if decompression, deserialization, or verification
+    /// fails, including for artifacts compressed with a dictionary.
     pub fn from_compressed(data: Vec<u8>) -> Result<Self> {
+        Self::from_compressed_with(data, &Dictionaries::new())
+    }
+
+    /// Reads an artifact that may be compressed with one of `dictionaries`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if decompression, deserialization, or verification
+    /// fails, or the artifact needs a dictionary that is not in the set.
+    pub fn from_compressed_with(data: Vec<u8>, dictionaries: &Dictionaries) -> Result<Self> {
         // Decompress
-        let tar_data = zstd::decode_all(&data[..]).map_err(|e| Error::Adapter {
-            package: "artifact".to_string(),
-            message: format!("Failed to decompress artifact: {}", e),
-        })?;
+        let (tar_data, dictionary) = decompress(&data, dictionaries)?;
 
         // Extract from tar
         let mut archive = tar::Archive::new(&tar_data[..]);
-        let mut metadata: Option<ArtifactMetadata> = None;
-        let mut manifest: Option<ArtifactManifest> = None;
+        let mut metadata: Option<(ArtifactMetadata, String)> = None;
+        let mut manifest: Option<(ArtifactManifest, String)> = None;
+        let mut signature: Option<ArtifactSignature> = None;
 
         for entry_result in archive.entries().map_err(|e| Error::Adapter {
             package: "artifact".to_string(),
@@ -217,29 +329,62 @@ impl Artifact {
                     package: "artifact".to_string(),
                     message: format!("Failed to read metadata: {}", e),
                 })?;
-                metadata = Some(serde_json::from_str(&content).map_err(|e| Error::Adapter {
+                let parsed = serde_json::from_str(&content).map_err(|e| Error::Adapter {
                     package: "artifact".to_string(),
                     message: format!("Failed to parse metadata: {}", e),
-                })?);
+                })?;
+                metadata = Some((parsed, sha256_hex(content.as_bytes())));
             } else if path == Path::new("manifest.json") {
                 let mut content = String::new();
                 entry.read_to_string(&mut content).map_err(|e| Error::Adapter {
                     package: "artifact".to_string(),
                     message: format!("Failed to read manifest: {}", e),
                 })?;
-                manifest = Some(serde_json::from_str(&content).map_err(|e| Error::Adapter {
+                let parsed = serde_json::from_str(&content).map_err(|e| Error::Adapter {
                     package: "artifact".to_string(),
                     message: format!("Failed to parse manifest: {}", e),
+                })?;
+                manifest = Some((parsed, sha256_hex(content.as_bytes())));
+            } else if path == Path::new(SIGNATURE_ENTRY) {
+                let mut content = String::new();
+                entry.read_to_string(&mut content).map_err(|e| Error::Adapter {
+                    package: "artifact".to_string(),
+                    message: format!("Failed to read signature: {}", e),
+                })?;
+                signature = Some(serde_json::from_str(&content).map_err(|e| Error::Adapter {
+                    package: "artifact".to_string(),
+                    message: format!("Failed to parse signature: {}", e),
                 })?);
             }
         }
 
-        let metadata = metadata.ok_or_else(|| Error::Adapter {
+        let (metadata, metadata_digest) = metadata.ok_or_else(|| Error::Adapter {
             package: "artifact".to_string(),
             message: "Missing metadata.json in artifact".to_string(),
         })?;
+        if metadata.version > ARTIFACT_VERSION {
+            return Err(Error::Adapter {
+                package: "artifact".to_string(),
+                message: format!(
+                    "Unsupported artifact format version {} (this build reads up to {})",
+                    metadata.version, ARTIFACT_VERSION
+                ),
+            });
+        }
+        if metadata.codec.algorithm != ZSTD {
+            return Err(Error::Adapter {
+                package: "artifact".to_string(),
+                message: format!("Unsupported artifact codec {}", metadata.codec.algorithm),
+            });
+        }
+
+        let mut compressor =
+            Compressor::new(metadata.codec.level).with_long_window(metadata.codec.long_window);
+        if let Some(dictionary) = dictionary {
+            compressor = compressor.with_dictionary(dictionary);
+        }
 
-        let manifest = manifest.ok_or_else(|| Error::Adapter {
+        let (manifest, manifest_digest) = manifest.ok_or_else(|| Error::Adapter {
             package: "artifact".to_string(),
             message: "Missing manifest.json in artifact".to_string(),
         })?;
@@ -247,10 +392,161 @@ impl Artifact {
         Ok(Self {
             metadata,
             manifest,
+            signature,
+            metadata_digest,
+            manifest_digest,
+            compressor,
             compressed_data: data,
         })
     }
 
+    /// Returns a copy of this artifact compressed with different settings.
+    ///
+    /// The codec is recorded in the metadata, so any signature is dropped;
+    /// sign the returned artifact instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be rewritten.
+    pub fn compressed_with(&self, compressor: &Compressor) -> Result<Self> {
+        let mut metadata = self.metadata.clone();
+        metadata.codec = compressor.codec();
+        let metadata_json = serde_json::to_string(&metadata).map_err(|e| Error::Adapter {
+            package: "artifact".to_string(),
+            message: format!("Failed to serialize metadata: {}", e),
+        })?;
+
+        let tar_data = self.tar_data()?;
+        let mut rewritten_tar = Vec::new();
+        {
+            let mut archive = tar::Archive::new(&tar_data[..]);
+            let mut tar = tar::Builder::new(&mut rewritten_tar);
+
+            for entry_result in archive.entries().map_err(|e| Error::Adapter {
+                package: "artifact".to_string(),
+                message: format!("Failed to read tar archive: {}", e),
+            })? {
+                let mut entry = entry_result.map_err(|e| Error::Adapter {
+                    package: "artifact".to_string(),
+                    message: format!("Failed to read tar entry: {}", e),
+                })?;
+
+                let path = entry
+                    .path()
+                    .map(|path| path.into_owned())
+                    .unwrap_or_default();
+                if path == Path::new(SIGNATURE_ENTRY) {
+                    continue;
+                }
+
+                let mut header = entry.header().clone();
+                let result = if path == Path::new("metadata.json") {
+                    header.set_size(metadata_json.len() as u64);
+                    header.set_cksum();
+                    tar.append(&header, metadata_json.as_bytes())
+                } else {
+                    tar.append(&header, &mut entry)
+                };
+                result.map_err(|e| Error::Adapter {
+                    package: "artifact".to_string(),
+                    message: format!("Failed to copy tar entry: {}", e),
+                })?;
+            }
+
+            tar.finish().map_err(|e| Error::Adapter {
+                package: "artifact".to_string(),
+                message: format!("Failed to finish tar archive: {}", e),
+            })?;
+        }
+
+        Ok(Self {
+            metadata,
+            manifest: self.manifest.clone(),
+            signature: None,
+            metadata_digest: sha256_hex(metadata_json.as_bytes()),
+            manifest_digest: self.manifest_digest.clone(),
+            compressor: compressor.clone(),
+            compressed_data: compressor.compress(&rewritten_tar)?,
+        })
+    }
+
+    /// Returns a copy of this artifact signed with the given key.
+    ///
+    /// Any existing signature is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be rewritten.
+    pub fn signed(&self, key: &SigningKey) -> Result<Self> {
+        let signature = key.sign(self);
+        let signature_json = serde_json::to_string(&signature).map_err(|e| Error::Adapter {
+            package: "artifact".to_string(),
+            message: format!("Failed to serialize signature: {}", e),
+        })?;
+
+        let tar_data = self.tar_data()?;
+
+        let mut signed_tar = Vec::new();
+        {
+            let mut archive = tar::Archive::new(&tar_data[..]);
+            let mut tar = tar::Builder::new(&mut signed_tar);
+
+            for entry_result in archive.entries().map_err(|e| Error::Adapter {
+                package: "artifact".to_string(),
+                message: format!("Failed to read tar archive: {}", e),
+            })? {
+                let mut entry = entry_result.map_err(|e| Error::Adapter {
+                    package: "artifact".to_string(),
+                    message: format!("Failed to read tar entry: {}", e),
+                })?;
+
+                let is_signature = entry
+                    .path()
+                    .map(|path| path == Path::new(SIGNATURE_ENTRY))
+                    .unwrap_or(false);
+                if is_signature {
+                    continue;
+                }
+
+                let header = entry.header().clone();
+                tar.append(&header, &mut entry).map_err(|e| Error::Adapter {
+                    package: "artifact".to_string(),
+                    message: format!("Failed to copy tar entry: {}", e),
+                })?;
+            }
+
+            let mut signature_header = tar::Header::new_gnu();
+            signature_header.set_path(SIGNATURE_ENTRY).map_err(|e| Error::Adapter {
+                package: "artifact".to_string(),
+                message: format!("Failed to set signature path: {}", e),
+            })?;
+            signature_header.set_size(signature_json.len() as u64);
+            signature_header.set_cksum();
+            tar.append(&signature_header, signature_json.as_bytes())
+                .map_err(|e| Error::Adapter {
+                    package: "artifact".to_string(),
+                    message: format!("Failed to append signature: {}", e),
+                })?;
+
+            tar.finish().map_err(|e| Error::Adapter {
+                package: "artifact".to_string(),
+                message: format!("Failed to finish tar archive: {}", e),
+            })?;
+        }
+
+        let compressed_data = self.compressor.compress(&signed_tar)?;
+
+        Ok(Self {
+            metadata: self.metadata.clone(),
+            manifest: self.manifest.clone(),
+            signature: Some(signature),
+            metadata_digest: self.metadata_digest.clone(),
+            manifest_digest: self.manifest_digest.clone(),
+            compressor: self.compressor.clone(),
+            compressed_data,
+        })
+    }
+
     /// Returns the artifact metadata.
     pub fn metadata(&self) -> &ArtifactMetadata {
         &self.metadata
@@ -261,6 +557,21 @@ impl Artifact {
         &self.manifest
     }
 
+    /// Returns the artifact signature, if it is signed.
+    pub fn signature(&self) -> Option<&ArtifactSignature> {
+        self.signature.as_ref()
+    }
+
+    /// Returns the SHA-256 of the raw metadata entry.
+    pub(crate) fn metadata_digest(&self) -> &str {
+        &self.metadata_digest
+    }
+
+    /// Returns the SHA-256 of the raw manifest entry.
+    pub(crate) fn manifest_digest(&self) -> &str {
+        &self.manifest_digest
+    }
+
     /// Returns the compressed artifact data.
     pub fn compressed_data(&self) -> &[u8] {
         &self.compressed_data
@@ -268,17 +579,33 @@ impl Artifact {
 
     /// Extracts output files from the artifact to the given directory.
     ///
+    /// File modes, symlinks, directories and mtimes recorded in the manifest
+    /// are restored. Paths and symlink targets that would land outside
+    /// `output_dir`, including through existing symlinks, are rejected.
+    ///
     /// # Errors
     ///
-    /// Returns an error if extraction fails.
+    /// Returns an error if extraction fails or an output escapes `output_dir`.
     pub fn extract_outputs(&self, output_dir: &Path) -> Result<()> {
         use std::fs;
 
         // Decompress
-        let tar_data = zstd::decode_all(&self.compressed_data[..]).map_err(|e| Error::Adapter {
-            package: "artifact".to_string(),
-            message: format!("Failed to decompress artifact: {}", e),
-        })?;
+        let tar_data = self.tar_data()?;
+
+        fs::create_dir_all(output_dir).map_err(Error::Io)?;
+        let root = output_dir.canonicalize().map_err(Error::Io)?;
+
+        // Directories first, so empty ones exist and get their mode. A
+        // previous restore may have left them read-only, so they are made
+        // writable until their recorded modes are applied at the end.
+        for dir in &self.manifest.directories {
+            let dest_path = safe_destination(&root, dir)?;
+            if dest_path.is_symlink() {
+                fs::remove_file(&dest_path).map_err(Error::Io)?;
+            }
+            fs::create_dir_all(&dest_path).map_err(Error::Io)?;
+            make_dir_writable(&dest_path)?;
+        }
 
         // Extract tar archive
         let mut archive = tar::Archive::new(&tar_data[..]);
@@ -293,40 +620,176 @@ impl Artifact {
                 message: format!("Failed to read tar entry: {}", e),
             })?;
 
-            let path = entry.path().map_err(|e| Error::Adapter {
+            // Symlinks and directories are restored from the manifest
+            if entry.header().entry_type() != tar::EntryType::Regular {
+                continue;
+            }
+
+            let path = entry
+                .path()
+                .map_err(|e| Error::Adapter {
+                    package: "artifact".to_string(),
+                    message: format!("Failed to get entry path: {}", e),
+                })?
+                .into_owned();
+
+            // Skip metadata, manifest and signature
+            let Ok(relative_path) = path.strip_prefix(outputs_dir) else {
+                continue;
+            };
+
+            // Replaced rather than overwritten, since a previous restore may
+            // have left the file read-only
+            let dest_path = safe_destination(&root, relative_path)?;
+            if fs::symlink_metadata(&dest_path).is_ok_and(|metadata| !metadata.is_dir()) {
+                fs::remove_file(&dest_path).map_err(Error::Io)?;
+            }
+
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).map_err(|e| Error::Adapter {
                 package: "artifact".to_string(),
-                message: format!("Failed to get entry path: {}", e),
+                message: format!("Failed to extract file: {}", e),
             })?;
+            fs::write(&dest_path, content).map_err(Error::Io)?;
 
-            // Skip metadata and manifest
-            if path == Path::new("metadata.json") || path == Path::new("manifest.json") {
-                continue;
+            if let Some(mode) = self.manifest.modes.get(relative_path) {
+                set_mode(&dest_path, *mode)?;
             }
+        }
 
-            // Extract output files
-            if let Ok(relative_path) = path.strip_prefix(outputs_dir) {
-                let dest_path = output_dir.join(relative_path);
-                if let Some(parent) = dest_path.parent() {
-                    fs::create_dir_all(parent).map_err(Error::Io)?;
+        for (link, target) in &self.manifest.symlinks {
+            validate_symlink(link, target, &self.manifest.symlinks)?;
+            let dest_path = safe_destination(&root, link)?;
+            // The parent may already be redirected by a symlink on disk, so
+            // check the target from where the link actually lands too
+            let on_disk = dest_path.strip_prefix(&root).unwrap_or(link);
+            validate_symlink(on_disk, target, &self.manifest.symlinks)?;
+            if fs::symlink_metadata(&dest_path).is_ok() {
+                if dest_path.is_dir() && !dest_path.is_symlink() {
+                    fs::remove_dir_all(&dest_path).map_err(Error::Io)?;
+                } else {
+                    fs::remove_file(&dest_path).map_err(Error::Io)?;
                 }
-                entry.unpack(&dest_path).map_err(|e| Error::Adapter {
-                    package: "artifact".to_string(),
-                    message: format!("Failed to extract file: {}", e),
-                })?;
+            }
+            create_symlink(target, &dest_path)?;
+        }
+
+        // Directory modes and mtimes last, since writing files changes them.
+        // Deepest directories first, so a read-only parent is applied last.
+        for (path, mtime) in &self.manifest.mtimes {
+            if self.manifest.files.contains_key(path) {
+                set_mtime(&root.join(path), *mtime)?;
+            }
+        }
+        for dir in self.manifest.directories.iter().rev() {
+            let dest_path = root.join(dir);
+            if let Some(mtime) = self.manifest.mtimes.get(dir) {
+                set_mtime(&dest_path, *mtime)?;
+            }
+            if let Some(mode) = self.manifest.modes.get(dir) {
+                set_mode(&dest_path, *mode)?;
             }
         }
 
         Ok(())
     }
 
+    /// Returns the uncompressed tar archive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if decompression fails.
+    pub fn tar_data(&self) -> Result<Vec<u8>> {
+        self.compressor.decompress(&self.compressed_data)
+    }
+
     /// Computes the SHA-256 hash of the compressed artifact.
     pub fn hash(&self) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(&self.compressed_data);
-        format!("{:x}", hasher.finalize())
+        sha256_hex(&self.compressed_data)
     }
 }
 
+fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Resolves `relative` below `root`, creating missing parent directories.
+///
+/// `root` must be canonical. The parent is canonicalized so symlinks already
+/// on disk cannot redirect the write outside `root`.
+fn safe_destination(root: &Path, relative: &Path) -> Result<PathBuf> {
+    validate_output_path(relative)?;
+
+    let dest_path = root.join(relative);
+    let parent = dest_path.parent().unwrap_or(root);
+    std::fs::create_dir_all(parent).map_err(Error::Io)?;
+    let parent = parent.canonicalize().map_err(Error::Io)?;
+    if !parent.starts_with(root) {
+        return Err(Error::Adapter {
+            package: "artifact".to_string(),
+            message: format!("Output path {} escapes the package", relative.display()),
+        });
+    }
+
+    let file_name = dest_path.file_name().ok_or_else(|| Error::Adapter {
+        package: "artifact".to_string(),
+        message: format!("Invalid output path {}", relative.display()),
+    })?;
+    Ok(parent.join(file_name))
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).map_err(Error::Io)
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Gives the owner full access to a directory so entries can be replaced.
+#[cfg(unix)]
+fn make_dir_writable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(path).map_err(Error::Io)?.permissions().mode();
+    if mode & 0o700 == 0o700 {
+        return Ok(());
+    }
+    set_mode(path, mode | 0o700)
+}
+
+#[cfg(not(unix))]
+fn make_dir_writable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link).map_err(Error::Io)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    Err(Error::Adapter {
+        package: "artifact".to_string(),
+        message: format!(
+            "Cannot restore symlink {} -> {} on this platform",
+            link.display(),
+            target.display()
+        ),
+    })
+}
+
+fn set_mtime(path: &Path, mtime: u64) -> Result<()> {
+    let file = std::fs::File::open(path).map_err(Error::Io)?;
+    file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime))
+        .map_err(Error::Io)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -358,4 +821,272 @@ mod tests {
         assert_eq!(artifact.metadata().task_name, artifact2.metadata().task_name);
         assert_eq!(artifact.manifest().files.len(), artifact2.manifest().files.len());
     }
+
+    #[test]
+    fn test_compressed_with_records_codec() {
+        let mut output_files = BTreeMap::new();
+        for i in 0..50 {
+            output_files.insert(
+                PathBuf::from(format!("dist/chunk-{}.js", i)),
+                format!("export const chunk{} = require('./chunk-{}.js');", i, i + 1).into_bytes(),
+            );
+        }
+        let artifact = Artifact::new(
+            "test-package".to_string(),
+            "build".to_string(),
+            "make".to_string(),
+            "abc123".to_string(),
+            output_files,
+        )
+        .unwrap();
+        assert_eq!(artifact.metadata().codec, ArtifactCodec::default());
+
+        let samples: Vec<Vec<u8>> = (0..200)
+            .map(|i| format!("export const chunk{} = require('./chunk-{}.js');", i, i * 7).into_bytes())
+            .collect();
+        let dictionary = super::super::Dictionary::train(&samples, 4096).unwrap();
+        let compressor = Compressor::new(19)
+            .with_long_window(true)
+            .with_dictionary(dictionary.clone());
+
+        let key = SigningKey::hmac("ci", b"secret");
+        let recompressed = artifact.compressed_with(&compressor).unwrap().signed(&key).unwrap();
+        assert_eq!(recompressed.metadata().codec, compressor.codec());
+
+        // Readers need the dictionary, and then see the same artifact
+        let data = recompressed.compressed_data().to_vec();
+        assert!(Artifact::from_compressed(data.clone()).is_err());
+        let mut dictionaries = Dictionaries::new();
+        dictionaries.add(dictionary);
+        let read = Artifact::from_compressed_with(data, &dictionaries).unwrap();
+        assert_eq!(read.metadata().codec, compressor.codec());
+        assert_eq!(read.manifest().files, artifact.manifest().files);
+
+        let mut trusted = super::super::TrustedKeys::new();
+        trusted.add(key.trusted_key());
+        assert!(trusted.verify(&read).is_ok());
+        assert!(super::super::ArtifactVerifier::verify(&read, None).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_restores_modes_symlinks_and_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut entries = BTreeMap::new();
+        entries.insert(
+            PathBuf::from("bin/tool"),
+            OutputEntry::File {
+                content: b"#!/bin/sh".to_vec(),
+                mode: 0o755,
+                mtime: Some(1_700_000_000),
+            },
+        );
+        entries.insert(
+            PathBuf::from("tool"),
+            OutputEntry::Symlink {
+                target: PathBuf::from("bin/tool"),
+            },
+        );
+        entries.insert(
+            PathBuf::from("cache"),
+            OutputEntry::Directory {
+                mode: 0o700,
+                mtime: None,
+            },
+        );
+
+        let artifact = Artifact::from_entries(
+            "test-package".to_string(),
+            "build".to_string(),
+            "make".to_string(),
+            "abc123".to_string(),
+            entries,
+        )
+        .unwrap();
+        assert_eq!(artifact.metadata().version, ARTIFACT_VERSION);
+
+        let artifact = Artifact::from_compressed(artifact.compressed_data().to_vec()).unwrap();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        artifact.extract_outputs(temp_dir.path()).unwrap();
+
+        let tool = temp_dir.path().join("bin/tool");
+        let metadata = std::fs::metadata(&tool).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o755);
+        assert_eq!(
+            metadata.modified().unwrap(),
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+        assert_eq!(
+            std::fs::read_link(temp_dir.path().join("tool")).unwrap(),
+            PathBuf::from("bin/tool")
+        );
+        let cache = std::fs::metadata(temp_dir.path().join("cache")).unwrap();
+        assert!(cache.is_dir());
+        assert_eq!(cache.permissions().mode() & 0o777, 0o700);
+    }
+
+    #[test]
+    fn test_rejects_escaping_outputs() {
+        let mut entries = BTreeMap::new();
+        entries.insert(
+            PathBuf::from("link"),
+            OutputEntry::Symlink {
+                target: PathBuf::from("../outside"),
+            },
+        );
+        assert!(Artifact::from_entries(
+            "test-package".to_string(),
+            "build".to_string(),
+            "make".to_string(),
+            "abc123".to_string(),
+            entries,
+        )
+        .is_err());
+
+        let mut output_files = BTreeMap::new();
+        output_files.insert(PathBuf::from("../outside.txt"), b"content".to_vec());
+        assert!(Artifact::new(
+            "test-package".to_string(),
+            "build".to_string(),
+            "make".to_string(),
+            "abc123".to_string(),
+            output_files,
+        )
+        .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_does_not_follow_existing_symlinks() {
+        let mut output_files = BTreeMap::new();
+        output_files.insert(PathBuf::from("dist/app.js"), b"app".to_vec());
+        let artifact = Artifact::new(
+            "test-package".to_string(),
+            "build".to_string(),
+            "make".to_string(),
+            "abc123".to_string(),
+            output_files,
+        )
+        .unwrap();
+
+        let outside = tempfile::TempDir::new().unwrap();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("dist")).unwrap();
+
+        assert!(artifact.extract_outputs(temp_dir.path()).is_err());
+        assert!(!outside.path().join("app.js").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_twice_over_read_only_outputs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let artifact = |content: &[u8]| {
+            let mut entries = BTreeMap::new();
+            entries.insert(
+                PathBuf::from("dist"),
+                OutputEntry::Directory {
+                    mode: 0o555,
+                    mtime: None,
+                },
+            );
+            entries.insert(
+                PathBuf::from("dist/app.js"),
+                OutputEntry::File {
+                    content: content.to_vec(),
+                    mode: 0o644,
+                    mtime: None,
+                },
+            );
+            entries.insert(
+                PathBuf::from("VERSION"),
+                OutputEntry::File {
+                    content: content.to_vec(),
+                    mode: 0o444,
+                    mtime: None,
+                },
+            );
+            Artifact::from_entries(
+                "test-package".to_string(),
+                "build".to_string(),
+                "make".to_string(),
+                "abc123".to_string(),
+                entries,
+            )
+            .unwrap()
+        };
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        artifact(b"v1").extract_outputs(temp_dir.path()).unwrap();
+        artifact(b"v2").extract_outputs(temp_dir.path()).unwrap();
+
+        let mode = |path: &str| {
+            std::fs::metadata(temp_dir.path().join(path))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777
+        };
+        assert_eq!(std::fs::read(temp_dir.path().join("VERSION")).unwrap(), b"v2");
+        assert_eq!(std::fs::read(temp_dir.path().join("dist/app.js")).unwrap(), b"v2");
+        assert_eq!(mode("VERSION"), 0o444);
+        assert_eq!(mode("dist"), 0o555);
+
+        // Lets the temporary directory be removed
+        set_mode(&temp_dir.path().join("dist"), 0o755).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_chained_symlink_escape() {
+        // `sub/up -> ..` alone stays inside, but `sub -> .` makes it the parent
+        let mut entries = BTreeMap::new();
+        entries.insert(
+            PathBuf::from("sub"),
+            OutputEntry::Symlink {
+                target: PathBuf::from("."),
+            },
+        );
+        entries.insert(
+            PathBuf::from("sub/up"),
+            OutputEntry::Symlink {
+                target: PathBuf::from(".."),
+            },
+        );
+        assert!(Artifact::from_entries(
+            "test-package".to_string(),
+            "build".to_string(),
+            "make".to_string(),
+            "abc123".to_string(),
+            entries,
+        )
+        .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_rejects_symlink_escaping_through_existing_symlink() {
+        let mut entries = BTreeMap::new();
+        entries.insert(
+            PathBuf::from("sub/up"),
+            OutputEntry::Symlink {
+                target: PathBuf::from(".."),
+            },
+        );
+        let artifact = Artifact::from_entries(
+            "test-package".to_string(),
+            "build".to_string(),
+            "make".to_string(),
+            "abc123".to_string(),
+            entries,
+        )
+        .unwrap();
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::os::unix::fs::symlink(".", temp_dir.path().join("sub")).unwrap();
+
+        assert!(artifact.extract_outputs(temp_dir.path()).is_err());
+        assert!(std::fs::symlink_metadata(temp_dir.path().join("up")).is_err());
+    }
 }
@@ -78,4 +78,29 @@ pub trait RemoteCacheBackend: Send + Sync {
     ///
     /// Returns an error only for unexpected failures. Cache misses should return `Ok(false)`.
     async fn has_artifact(&self, key: &CacheKey) -> Result<bool>;
+
+    /// Checks which of several artifacts exist in the remote cache.
+    ///
+    /// The default implementation calls [`has_artifact`](Self::has_artifact)
+    /// for each key. Backends that can answer in a single round-trip should
+    /// override it.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The cache keys to check
+    ///
+    /// # Returns
+    ///
+    /// Returns one flag per key, in the same order as `keys`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only for unexpected failures.
+    async fn has_artifacts(&self, keys: &[CacheKey]) -> Result<Vec<bool>> {
+        let mut found = Vec::with_capacity(keys.len());
+        for key in keys {
+            found.push(self.has_artifact(key).await?);
+        }
+        Ok(found)
+    }
 }
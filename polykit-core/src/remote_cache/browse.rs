@@ -0,0 +1,111 @@
+//! Types of the cache server's artifact browsing API.
+
+use serde::{Deserialize, Serialize};
+
+use super::artifact::{ArtifactManifest, ArtifactMetadata};
+
+/// Filter for listing stored artifacts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactFilter {
+    /// Only artifacts of this package.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// Only artifacts of this task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// Only artifacts created at or after this time (Unix epoch seconds).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    /// Maximum number of artifacts to return, newest first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl ArtifactFilter {
+    /// Returns `true` if an artifact with this metadata passes the filter.
+    ///
+    /// Artifacts without metadata only pass a filter without conditions.
+    pub fn matches(&self, metadata: Option<&ArtifactMetadata>) -> bool {
+        let Some(metadata) = metadata else {
+            return self.package.is_none() && self.task.is_none() && self.since.is_none();
+        };
+
+        self.package
+            .as_ref()
+            .is_none_or(|p| *p == metadata.package_name)
+            && self.task.as_ref().is_none_or(|t| *t == metadata.task_name)
+            && self.since.is_none_or(|since| metadata.created_at >= since)
+    }
+}
+
+/// A stored artifact as listed by the cache server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredArtifact {
+    /// Cache key of the artifact.
+    pub cache_key: String,
+    /// Size of the compressed artifact in bytes.
+    pub size: u64,
+    /// SHA-256 hash of the compressed artifact.
+    pub hash: String,
+    /// Time of the last download (Unix epoch seconds).
+    pub last_accessed: u64,
+    /// Artifact metadata, or `None` for artifacts stored by older servers.
+    pub metadata: Option<ArtifactMetadata>,
+}
+
+/// Response of the artifact listing endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactList {
+    /// Matching artifacts, newest first.
+    pub artifacts: Vec<StoredArtifact>,
+}
+
+/// File list of a stored artifact, returned without its payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredManifest {
+    /// Cache key of the artifact.
+    pub cache_key: String,
+    /// Artifact metadata.
+    pub metadata: ArtifactMetadata,
+    /// Files contained in the artifact.
+    #[serde(flatten)]
+    pub manifest: ArtifactManifest,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(package: &str, task: &str, created_at: u64) -> ArtifactMetadata {
+        ArtifactMetadata {
+            package_name: package.to_string(),
+            task_name: task.to_string(),
+            command: "echo".to_string(),
+            cache_key_hash: "abc".to_string(),
+            created_at,
+            version: 1,
+            codec: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let web_build = metadata("web", "build", 100);
+        let filter = ArtifactFilter {
+            package: Some("web".to_string()),
+            task: Some("build".to_string()),
+            since: Some(100),
+            limit: None,
+        };
+
+        assert!(filter.matches(Some(&web_build)));
+        assert!(!filter.matches(Some(&metadata("api", "build", 100))));
+        assert!(!filter.matches(Some(&metadata("web", "test", 100))));
+        assert!(!filter.matches(Some(&metadata("web", "build", 99))));
+        assert!(!filter.matches(None));
+
+        let unfiltered = ArtifactFilter::default();
+        assert!(unfiltered.matches(Some(&web_build)));
+        assert!(unfiltered.matches(None));
+    }
+}
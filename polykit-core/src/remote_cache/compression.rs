@@ -0,0 +1,446 @@
+//! Compression settings for artifacts.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Compression algorithm used by all artifacts.
+pub const ZSTD: &str = "zstd";
+
+/// zstd level used when none is configured.
+pub const DEFAULT_LEVEL: i32 = 3;
+
+/// Window size (log2) used for long-distance matching: 128 MiB.
+pub const LONG_WINDOW_LOG: u32 = 27;
+
+/// Largest window (log2) accepted when decoding, so any long-window
+/// artifact can be read regardless of the reader's own settings.
+const MAX_WINDOW_LOG: u32 = 31;
+
+/// Compression settings in [`RemoteCacheConfig`](super::RemoteCacheConfig).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionConfig {
+    /// zstd level, from 1 (fastest) to 22 (smallest). Negative levels trade
+    /// more ratio for speed.
+    pub level: i32,
+    /// Enable long-distance matching with a 128 MiB window.
+    ///
+    /// Helps large outputs with repeated content far apart.
+    pub long_window: bool,
+    /// Trained zstd dictionary, used to compress uploads and to decode
+    /// artifacts compressed with it.
+    pub dictionary: Option<PathBuf>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_LEVEL,
+            long_window: false,
+            dictionary: None,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Sets the zstd level.
+    pub fn with_level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets whether long-distance matching is enabled.
+    pub fn with_long_window(mut self, long_window: bool) -> Self {
+        self.long_window = long_window;
+        self
+    }
+
+    /// Sets the dictionary file.
+    pub fn with_dictionary(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dictionary = Some(path.into());
+        self
+    }
+}
+
+/// Codec recorded in [`ArtifactMetadata`](super::ArtifactMetadata).
+///
+/// Artifacts written before the codec was recorded used zstd level 3.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactCodec {
+    /// Compression algorithm, always `zstd`.
+    pub algorithm: String,
+    /// Compression level.
+    pub level: i32,
+    /// Whether long-distance matching was enabled.
+    #[serde(default)]
+    pub long_window: bool,
+    /// ID of the dictionary the artifact was compressed with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary_id: Option<u32>,
+}
+
+impl Default for ArtifactCodec {
+    fn default() -> Self {
+        Self {
+            algorithm: ZSTD.to_string(),
+            level: DEFAULT_LEVEL,
+            long_window: false,
+            dictionary_id: None,
+        }
+    }
+}
+
+impl std::fmt::Display for ArtifactCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.algorithm, self.level)?;
+        if self.long_window {
+            write!(f, "+long")?;
+        }
+        if let Some(id) = self.dictionary_id {
+            write!(f, "+dict:{}", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// A trained zstd dictionary.
+#[derive(Clone)]
+pub struct Dictionary {
+    id: u32,
+    data: Arc<Vec<u8>>,
+}
+
+impl std::fmt::Debug for Dictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dictionary")
+            .field("id", &self.id)
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+impl Dictionary {
+    /// Creates a dictionary from its serialized form.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not a trained zstd dictionary. Raw
+    /// content dictionaries carry no ID and cannot be matched when decoding.
+    pub fn new(data: Vec<u8>) -> Result<Self> {
+        let id = zstd::zstd_safe::get_dict_id_from_dict(&data).ok_or_else(|| {
+            compression_error("Dictionary has no ID; train it with `zstd --train`".to_string())
+        })?;
+        Ok(Self {
+            id: id.get(),
+            data: Arc::new(data),
+        })
+    }
+
+    /// Loads a dictionary file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a trained zstd
+    /// dictionary.
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).map_err(Error::Io)?;
+        Self::new(data)
+            .map_err(|e| compression_error(format!("Invalid dictionary {}: {}", path.display(), e)))
+    }
+
+    /// Trains a dictionary of at most `max_size` bytes from sample data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are too few samples to train on.
+    pub fn train(samples: &[Vec<u8>], max_size: usize) -> Result<Self> {
+        let data = zstd::dict::from_samples(samples, max_size)
+            .map_err(|e| compression_error(format!("Failed to train dictionary: {}", e)))?;
+        Self::new(data)
+    }
+
+    /// Returns the dictionary ID stored in compressed frames.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the serialized dictionary.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Dictionaries available for decoding, keyed by ID.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionaries {
+    by_id: BTreeMap<u32, Dictionary>,
+}
+
+impl Dictionaries {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads dictionary files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any file is not a trained zstd dictionary.
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let mut dictionaries = Self::new();
+        for path in paths {
+            dictionaries.add(Dictionary::load(path)?);
+        }
+        Ok(dictionaries)
+    }
+
+    /// Adds a dictionary, replacing any with the same ID.
+    pub fn add(&mut self, dictionary: Dictionary) {
+        self.by_id.insert(dictionary.id, dictionary);
+    }
+
+    /// Returns the dictionary with the given ID.
+    pub fn get(&self, id: u32) -> Option<&Dictionary> {
+        self.by_id.get(&id)
+    }
+
+    /// Returns `true` if no dictionaries are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+}
+
+/// Compresses and decompresses artifact archives.
+#[derive(Debug, Clone)]
+pub struct Compressor {
+    level: i32,
+    long_window: bool,
+    dictionary: Option<Dictionary>,
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new(DEFAULT_LEVEL)
+    }
+}
+
+impl Compressor {
+    /// Creates a compressor with the given zstd level.
+    pub fn new(level: i32) -> Self {
+        Self {
+            level,
+            long_window: false,
+            dictionary: None,
+        }
+    }
+
+    /// Creates a compressor from configuration, loading the dictionary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the level is out of range or the dictionary
+    /// cannot be loaded.
+    pub fn from_config(config: &CompressionConfig) -> Result<Self> {
+        let levels = zstd::compression_level_range();
+        if !levels.contains(&config.level) {
+            return Err(compression_error(format!(
+                "Compression level {} is outside {}..={}",
+                config.level,
+                levels.start(),
+                levels.end()
+            )));
+        }
+
+        let mut compressor = Self::new(config.level).with_long_window(config.long_window);
+        if let Some(ref path) = config.dictionary {
+            compressor = compressor.with_dictionary(Dictionary::load(path)?);
+        }
+        Ok(compressor)
+    }
+
+    /// Sets whether long-distance matching is enabled.
+    pub fn with_long_window(mut self, long_window: bool) -> Self {
+        self.long_window = long_window;
+        self
+    }
+
+    /// Compresses with the given dictionary.
+    pub fn with_dictionary(mut self, dictionary: Dictionary) -> Self {
+        self.dictionary = Some(dictionary);
+        self
+    }
+
+    /// Returns the dictionary used for compression, if any.
+    pub fn dictionary(&self) -> Option<&Dictionary> {
+        self.dictionary.as_ref()
+    }
+
+    /// Returns the dictionaries needed to decode this compressor's output.
+    pub fn dictionaries(&self) -> Dictionaries {
+        let mut dictionaries = Dictionaries::new();
+        if let Some(ref dictionary) = self.dictionary {
+            dictionaries.add(dictionary.clone());
+        }
+        dictionaries
+    }
+
+    /// Returns the codec recorded in artifacts written by this compressor.
+    pub fn codec(&self) -> ArtifactCodec {
+        ArtifactCodec {
+            algorithm: ZSTD.to_string(),
+            level: self.level,
+            long_window: self.long_window,
+            dictionary_id: self.dictionary.as_ref().map(Dictionary::id),
+        }
+    }
+
+    /// Compresses data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if compression fails.
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let dictionary = self
+            .dictionary
+            .as_ref()
+            .map(Dictionary::data)
+            .unwrap_or(&[]);
+        let to_error =
+            |e: std::io::Error| compression_error(format!("Failed to compress artifact: {}", e));
+
+        let mut encoder =
+            zstd::stream::Encoder::with_dictionary(Vec::new(), self.level, dictionary)
+                .map_err(to_error)?;
+        if self.long_window {
+            encoder.long_distance_matching(true).map_err(to_error)?;
+            encoder.window_log(LONG_WINDOW_LOG).map_err(to_error)?;
+        }
+        encoder.write_all(data).map_err(to_error)?;
+        encoder.finish().map_err(to_error)
+    }
+
+    /// Decompresses data written by any compressor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is corrupt or was compressed with a
+    /// dictionary that is not configured.
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        decompress(data, &self.dictionaries()).map(|(decoded, _)| decoded)
+    }
+}
+
+/// Decompresses an artifact, picking the dictionary by the ID in its frame.
+///
+/// Returns the decompressed data and the dictionary that was used.
+///
+/// # Errors
+///
+/// Returns an error if the data is corrupt or needs a dictionary that is not
+/// in `dictionaries`.
+pub(crate) fn decompress(
+    data: &[u8],
+    dictionaries: &Dictionaries,
+) -> Result<(Vec<u8>, Option<Dictionary>)> {
+    let dictionary = match zstd::zstd_safe::get_dict_id_from_frame(data) {
+        Some(id) => Some(dictionaries.get(id.get()).cloned().ok_or_else(|| {
+            compression_error(format!(
+                "Artifact was compressed with dictionary {}, which is not configured",
+                id
+            ))
+        })?),
+        None => None,
+    };
+    let to_error =
+        |e: std::io::Error| compression_error(format!("Failed to decompress artifact: {}", e));
+
+    let mut decoder = zstd::stream::Decoder::with_dictionary(
+        data,
+        dictionary.as_ref().map(Dictionary::data).unwrap_or(&[]),
+    )
+    .map_err(to_error)?;
+    decoder.window_log_max(MAX_WINDOW_LOG).map_err(to_error)?;
+
+    let mut decoded = Vec::new();
+    decoder.read_to_end(&mut decoded).map_err(to_error)?;
+    Ok((decoded, dictionary))
+}
+
+fn compression_error(message: String) -> Error {
+    Error::Adapter {
+        package: "artifact".to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<Vec<u8>> {
+        (0..200)
+            .map(|i| {
+                format!(
+                    "{{\"name\":\"package-{}\",\"version\":\"1.0.{}\",\"main\":\"dist/index.js\"}}",
+                    i % 17,
+                    i
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_with_settings() {
+        let data = b"polykit artifact ".repeat(1000);
+
+        for compressor in [
+            Compressor::new(1),
+            Compressor::new(19),
+            Compressor::new(3).with_long_window(true),
+        ] {
+            let compressed = compressor.compress(&data).unwrap();
+            assert!(compressed.len() < data.len());
+            // Readers need no settings to decode any level or window size
+            assert_eq!(Compressor::default().decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_dictionary_is_required_to_decode() {
+        let dictionary = Dictionary::train(&samples(), 4096).unwrap();
+        let compressor = Compressor::default().with_dictionary(dictionary.clone());
+        assert_eq!(compressor.codec().dictionary_id, Some(dictionary.id()));
+
+        let data = samples()[3].clone();
+        let compressed = compressor.compress(&data).unwrap();
+        assert_eq!(compressor.decompress(&compressed).unwrap(), data);
+        assert!(Compressor::default().decompress(&compressed).is_err());
+    }
+
+    #[test]
+    fn test_from_config_validates_level() {
+        assert!(Compressor::from_config(&CompressionConfig::default()).is_ok());
+        assert!(Compressor::from_config(&CompressionConfig::default().with_level(23)).is_err());
+    }
+
+    #[test]
+    fn test_codec_display() {
+        assert_eq!(ArtifactCodec::default().to_string(), "zstd-3");
+        let codec = ArtifactCodec {
+            level: 19,
+            long_window: true,
+            dictionary_id: Some(42),
+            ..ArtifactCodec::default()
+        };
+        assert_eq!(codec.to_string(), "zstd-19+long+dict:42");
+    }
+}
@@ -4,13 +4,21 @@ use std::collections::BTreeSet;
 
 use serde::{Deserialize, Serialize};
 
+use super::compression::CompressionConfig;
+use super::signing::KeyConfig;
+
 /// Configuration for remote cache.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct RemoteCacheConfig {
     /// Backend URL (HTTP URL or filesystem path).
     pub url: String,
     /// Authentication token (for HTTP backends).
     pub token: Option<String>,
+    /// Server-side namespace that keeps this workspace's artifacts apart (for HTTP backends).
+    ///
+    /// Artifacts go to the server's default namespace when unset.
+    pub namespace: Option<String>,
     /// Environment variables to include in cache keys.
     ///
     /// Only explicitly listed environment variables will be hashed into cache keys.
@@ -23,6 +31,14 @@ pub struct RemoteCacheConfig {
     pub max_artifact_size: Option<u64>,
     /// Read-only mode (disable uploads).
     pub read_only: bool,
+    /// Key used to sign uploaded artifacts.
+    pub signing_key: Option<KeyConfig>,
+    /// Keys whose signatures are accepted on fetched artifacts.
+    pub trusted_keys: Vec<KeyConfig>,
+    /// Reject fetched artifacts without a valid signature from a trusted key.
+    pub require_signatures: bool,
+    /// Compression applied to uploaded artifacts.
+    pub compression: CompressionConfig,
 }
 
 impl Default for RemoteCacheConfig {
@@ -30,10 +46,15 @@ impl Default for RemoteCacheConfig {
         Self {
             url: String::new(),
             token: None,
+            namespace: None,
             env_vars: BTreeSet::new(),
             input_files: Vec::new(),
             max_artifact_size: Some(1024 * 1024 * 1024), // 1GB
             read_only: false,
+            signing_key: None,
+            trusted_keys: Vec::new(),
+            require_signatures: false,
+            compression: CompressionConfig::default(),
         }
     }
 }
@@ -53,6 +74,12 @@ impl RemoteCacheConfig {
         self
     }
 
+    /// Sets the server-side namespace.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
     /// Adds an environment variable to track.
     pub fn add_env_var(mut self, var: impl Into<String>) -> Self {
         self.env_vars.insert(var.into());
@@ -86,6 +113,30 @@ impl RemoteCacheConfig {
         self
     }
 
+    /// Sets the key used to sign uploads.
+    pub fn with_signing_key(mut self, key: KeyConfig) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Adds a key trusted to sign fetched artifacts.
+    pub fn add_trusted_key(mut self, key: KeyConfig) -> Self {
+        self.trusted_keys.push(key);
+        self
+    }
+
+    /// Sets whether fetched artifacts must carry a trusted signature.
+    pub fn require_signatures(mut self, require: bool) -> Self {
+        self.require_signatures = require;
+        self
+    }
+
+    /// Sets the compression applied to uploads.
+    pub fn with_compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = compression;
+        self
+    }
+
     /// Checks if this is an HTTP backend.
     pub fn is_http(&self) -> bool {
         self.url.starts_with("http://") || self.url.starts_with("https://")
@@ -10,6 +10,7 @@ use crate::error::{Error, Result};
 use super::artifact::Artifact;
 use super::backend::RemoteCacheBackend;
 use super::cache_key::CacheKey;
+use super::compression::Dictionaries;
 
 /// Filesystem backend for remote cache.
 ///
@@ -17,6 +18,7 @@ use super::cache_key::CacheKey;
 /// Multiple worktrees share the same cache directory based on repository root.
 pub struct FilesystemBackend {
     cache_dir: PathBuf,
+    dictionaries: Dictionaries,
 }
 
 impl FilesystemBackend {
@@ -46,7 +48,16 @@ impl FilesystemBackend {
         // Create cache directory
         fs::create_dir_all(&cache_dir).map_err(Error::Io)?;
 
-        Ok(Self { cache_dir })
+        Ok(Self {
+            cache_dir,
+            dictionaries: Dictionaries::new(),
+        })
+    }
+
+    /// Sets the dictionaries used to decode fetched artifacts.
+    pub fn with_dictionaries(mut self, dictionaries: Dictionaries) -> Self {
+        self.dictionaries = dictionaries;
+        self
     }
 
     /// Finds the git repository root starting from the given path.
@@ -135,7 +146,7 @@ impl RemoteCacheBackend for FilesystemBackend {
             .map_err(Error::Io)?;
 
         // Parse artifact
-        let artifact = Artifact::from_compressed(data)?;
+        let artifact = Artifact::from_compressed_with(data, &self.dictionaries)?;
 
         Ok(Some(artifact))
     }
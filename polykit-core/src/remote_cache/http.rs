@@ -10,18 +10,24 @@ use crate::error::{Error, Result};
 
 use super::artifact::Artifact;
 use super::backend::RemoteCacheBackend;
+use super::browse::{ArtifactFilter, ArtifactList, StoredArtifact, StoredManifest};
 use super::cache_key::CacheKey;
+use super::compression::Dictionaries;
 use super::config::RemoteCacheConfig;
 
+/// Maximum number of keys sent in a single batch query.
+const QUERY_BATCH_SIZE: usize = 1000;
+
 /// HTTP backend for remote cache.
 ///
 /// Supports streaming upload/download, authentication, and retry logic.
 pub struct HttpBackend {
     client: Client,
-    base_url: String,
+    artifacts_url: String,
     token: Option<String>,
     max_retries: u32,
     retry_delay: Duration,
+    dictionaries: Dictionaries,
 }
 
 impl HttpBackend {
@@ -43,62 +49,175 @@ impl HttpBackend {
                 message: format!("Failed to create HTTP client: {}", e),
             })?;
 
+        let base_url = config.url.trim_end_matches('/');
+        let artifacts_url = match config.namespace {
+            Some(ref namespace) => format!("{}/v1/ns/{}/artifacts", base_url, namespace),
+            None => format!("{}/v1/artifacts", base_url),
+        };
+
         Ok(Self {
             client,
-            base_url: config.url.trim_end_matches('/').to_string(),
+            artifacts_url,
             token: config.token.clone(),
             max_retries: 3,
             retry_delay: Duration::from_millis(100),
+            dictionaries: Dictionaries::new(),
         })
     }
 
-    /// Gets the URL for an artifact.
-    fn artifact_url(&self, key: &CacheKey) -> String {
-        let key_str = key.as_string();
-        format!("{}/v1/artifacts/{}", self.base_url, key_str)
+    /// Sets the dictionaries used to decode fetched artifacts.
+    pub fn with_dictionaries(mut self, dictionaries: Dictionaries) -> Self {
+        self.dictionaries = dictionaries;
+        self
     }
 
+    /// Gets the URL for an artifact by its cache key hash.
+    fn key_url(&self, cache_key: &str) -> String {
+        format!("{}/{}", self.artifacts_url, cache_key)
+    }
 
-    /// Retries an operation with exponential backoff.
-    async fn retry<F, Fut, T>(&self, mut f: F) -> Result<T>
-    where
-        F: FnMut() -> Fut,
-        Fut: std::future::Future<Output = Result<T>> + Send,
-    {
-        let mut delay = self.retry_delay;
-        let mut last_error = None;
+    /// Gets the URL for batch existence queries.
+    fn query_url(&self) -> String {
+        format!("{}/query", self.artifacts_url)
+    }
 
-        for attempt in 0..=self.max_retries {
-            match f().await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    last_error = Some(e);
-                    if attempt < self.max_retries {
-                        sleep(delay).await;
-                        delay *= 2; // Exponential backoff
+    /// Queries existence of a batch of keys in one request.
+    ///
+    /// Returns `Ok(None)` if the server does not support batch queries.
+    async fn query_batch(&self, key_strings: &[String]) -> Result<Option<Vec<bool>>> {
+        let url = self.query_url();
+        let body = serde_json::json!({ "keys": key_strings }).to_string();
+
+        let client = self.client.clone();
+        let token = self.token.clone();
+        let found = self
+            .retry(move || {
+                let url = url.clone();
+                let body = body.clone();
+                let client = client.clone();
+                let token = token.clone();
+                async move {
+                    let mut builder = client
+                        .post(&url)
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .body(body);
+                    if let Some(ref token) = token {
+                        builder = builder.bearer_auth(token);
+                    }
+
+                    let response = builder.send().await.map_err(|e| Error::Adapter {
+                        package: "http-backend".to_string(),
+                        message: format!("Batch query failed: {}", e),
+                    })?;
+
+                    match response.status() {
+                        status if status.is_success() => {
+                            let text = response.text().await.map_err(|e| Error::Adapter {
+                                package: "http-backend".to_string(),
+                                message: format!("Failed to read response body: {}", e),
+                            })?;
+                            let parsed: QueryResponse =
+                                serde_json::from_str(&text).map_err(|e| Error::Adapter {
+                                    package: "http-backend".to_string(),
+                                    message: format!("Invalid batch query response: {}", e),
+                                })?;
+                            Ok(Some(parsed.found))
+                        }
+                        reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::METHOD_NOT_ALLOWED => {
+                            Ok(None)
+                        }
+                        status => {
+                            let text = response.text().await.unwrap_or_default();
+                            Err(Error::Adapter {
+                                package: "http-backend".to_string(),
+                                message: format!(
+                                    "Batch query failed with status {}: {}",
+                                    status, text
+                                ),
+                            })
+                        }
                     }
                 }
-            }
+            })
+            .await?;
+
+        Ok(found.map(|found| {
+            let found: std::collections::HashSet<String> = found.into_iter().collect();
+            key_strings.iter().map(|k| found.contains(k)).collect()
+        }))
+    }
+
+    /// Lists stored artifacts matching the filter, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the server does not support listing.
+    pub async fn list_artifacts(&self, filter: &ArtifactFilter) -> Result<Vec<StoredArtifact>> {
+        let list: Option<ArtifactList> = self.get_json(&self.artifacts_url, filter).await?;
+        list.map(|list| list.artifacts).ok_or_else(|| Error::Adapter {
+            package: "http-backend".to_string(),
+            message: "Cache server does not support listing artifacts".to_string(),
+        })
+    }
+
+    /// Fetches the metadata and file list of an artifact without its payload.
+    ///
+    /// Returns `Ok(None)` if the artifact does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub async fn fetch_manifest(&self, cache_key: &str) -> Result<Option<StoredManifest>> {
+        let url = format!("{}/{}/manifest", self.artifacts_url, cache_key);
+        self.get_json(&url, &ArtifactFilter::default()).await
+    }
+
+    /// Deletes an artifact from the server. Requires an admin token.
+    ///
+    /// Returns `Ok(false)` if the artifact did not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or is not authorized.
+    pub async fn delete_artifact(&self, cache_key: &str) -> Result<bool> {
+        let url = self.key_url(cache_key);
+
+        let mut builder = self.client.delete(&url);
+        if let Some(ref token) = self.token {
+            builder = builder.bearer_auth(token);
         }
 
-        Err(last_error.unwrap_or_else(|| Error::Adapter {
+        let response = builder.send().await.map_err(|e| Error::Adapter {
             package: "http-backend".to_string(),
-            message: "All retry attempts failed".to_string(),
-        }))
+            message: format!("Delete request failed: {}", e),
+        })?;
+
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            status => {
+                let text = response.text().await.unwrap_or_default();
+                Err(Error::Adapter {
+                    package: "http-backend".to_string(),
+                    message: format!("Delete failed with status {}: {}", status, text),
+                })
+            }
+        }
     }
-}
 
-#[async_trait]
-impl RemoteCacheBackend for HttpBackend {
-    async fn upload_artifact(&self, key: &CacheKey, artifact: &Artifact) -> Result<()> {
-        let url = self.artifact_url(key);
-        let compressed_data = artifact.compressed_data();
+    /// Uploads compressed artifact data under a cache key hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the upload fails after all retries.
+    pub async fn upload_compressed(&self, cache_key: &str, data: &[u8]) -> Result<()> {
+        let url = self.key_url(cache_key);
 
         let client = self.client.clone();
         let token = self.token.clone();
         self.retry(move || {
             let url = url.clone();
-            let data = compressed_data.to_vec();
+            let data = data.to_vec();
             let client = client.clone();
             let token = token.clone();
             async move {
@@ -127,8 +246,15 @@ impl RemoteCacheBackend for HttpBackend {
         .await
     }
 
-    async fn fetch_artifact(&self, key: &CacheKey) -> Result<Option<Artifact>> {
-        let url = self.artifact_url(key);
+    /// Downloads compressed artifact data by cache key hash.
+    ///
+    /// Returns `Ok(None)` if the artifact does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails after all retries.
+    pub async fn fetch_compressed(&self, cache_key: &str) -> Result<Option<Vec<u8>>> {
+        let url = self.key_url(cache_key);
 
         let client = self.client.clone();
         let token = self.token.clone();
@@ -153,8 +279,7 @@ impl RemoteCacheBackend for HttpBackend {
                             package: "http-backend".to_string(),
                             message: format!("Failed to read response body: {}", e),
                         })?;
-                        let artifact = Artifact::from_compressed(data.to_vec())?;
-                        Ok(Some(artifact))
+                        Ok(Some(data.to_vec()))
                     }
                     status if status == reqwest::StatusCode::NOT_FOUND => Ok(None),
                     status => {
@@ -170,8 +295,13 @@ impl RemoteCacheBackend for HttpBackend {
         .await
     }
 
-    async fn has_artifact(&self, key: &CacheKey) -> Result<bool> {
-        let url = self.artifact_url(key);
+    /// Checks whether an artifact exists by cache key hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails after all retries.
+    pub async fn has_key(&self, cache_key: &str) -> Result<bool> {
+        let url = self.key_url(cache_key);
 
         let client = self.client.clone();
         let token = self.token.clone();
@@ -205,6 +335,142 @@ impl RemoteCacheBackend for HttpBackend {
         })
         .await
     }
+
+    /// Checks which of several cache key hashes exist, in batches.
+    ///
+    /// Falls back to one `HEAD` request per key against servers without
+    /// the batch query endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails after all retries.
+    pub async fn has_keys(&self, cache_keys: &[String]) -> Result<Vec<bool>> {
+        let mut found = Vec::with_capacity(cache_keys.len());
+
+        for chunk in cache_keys.chunks(QUERY_BATCH_SIZE) {
+            match self.query_batch(chunk).await? {
+                Some(chunk_found) => found.extend(chunk_found),
+                None => {
+                    // Older servers without the batch endpoint
+                    for cache_key in chunk {
+                        found.push(self.has_key(cache_key).await?);
+                    }
+                }
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Sends a GET request and parses the JSON response, returning `Ok(None)` on 404.
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &ArtifactFilter,
+    ) -> Result<Option<T>> {
+        let client = self.client.clone();
+        let token = self.token.clone();
+        let text = self
+            .retry(move || {
+                let client = client.clone();
+                let token = token.clone();
+                async move {
+                    let mut builder = client.get(url).query(query);
+                    if let Some(ref token) = token {
+                        builder = builder.bearer_auth(token);
+                    }
+
+                    let response = builder.send().await.map_err(|e| Error::Adapter {
+                        package: "http-backend".to_string(),
+                        message: format!("Request to {} failed: {}", url, e),
+                    })?;
+
+                    match response.status() {
+                        status if status.is_success() => {
+                            response.text().await.map(Some).map_err(|e| Error::Adapter {
+                                package: "http-backend".to_string(),
+                                message: format!("Failed to read response body: {}", e),
+                            })
+                        }
+                        reqwest::StatusCode::NOT_FOUND => Ok(None),
+                        status => {
+                            let text = response.text().await.unwrap_or_default();
+                            Err(Error::Adapter {
+                                package: "http-backend".to_string(),
+                                message: format!("Request failed with status {}: {}", status, text),
+                            })
+                        }
+                    }
+                }
+            })
+            .await?;
+
+        text.map(|text| {
+            serde_json::from_str(&text).map_err(|e| Error::Adapter {
+                package: "http-backend".to_string(),
+                message: format!("Invalid response from {}: {}", url, e),
+            })
+        })
+        .transpose()
+    }
+
+    /// Retries an operation with exponential backoff.
+    async fn retry<F, Fut, T>(&self, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T>> + Send,
+    {
+        let mut delay = self.retry_delay;
+        let mut last_error = None;
+
+        for attempt in 0..=self.max_retries {
+            match f().await {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    last_error = Some(e);
+                    if attempt < self.max_retries {
+                        sleep(delay).await;
+                        delay *= 2; // Exponential backoff
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| Error::Adapter {
+            package: "http-backend".to_string(),
+            message: "All retry attempts failed".to_string(),
+        }))
+    }
+}
+
+/// Response body of `POST /v1/artifacts/query`.
+#[derive(serde::Deserialize)]
+struct QueryResponse {
+    found: Vec<String>,
+}
+
+#[async_trait]
+impl RemoteCacheBackend for HttpBackend {
+    async fn upload_artifact(&self, key: &CacheKey, artifact: &Artifact) -> Result<()> {
+        self.upload_compressed(&key.as_string(), artifact.compressed_data())
+            .await
+    }
+
+    async fn fetch_artifact(&self, key: &CacheKey) -> Result<Option<Artifact>> {
+        self.fetch_compressed(&key.as_string())
+            .await?
+            .map(|data| Artifact::from_compressed_with(data, &self.dictionaries))
+            .transpose()
+    }
+
+    async fn has_artifact(&self, key: &CacheKey) -> Result<bool> {
+        self.has_key(&key.as_string()).await
+    }
+
+    async fn has_artifacts(&self, keys: &[CacheKey]) -> Result<Vec<bool>> {
+        let cache_keys: Vec<String> = keys.iter().map(|k| k.as_string()).collect();
+        self.has_keys(&cache_keys).await
+    }
 }
 
 #[cfg(test)]
@@ -212,7 +478,7 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_artifact_url() {
+    fn test_key_url() {
         let config = RemoteCacheConfig::new("https://cache.example.com");
         let backend = HttpBackend::new(&config).unwrap();
 
@@ -225,7 +491,31 @@ mod tests {
             .build()
             .unwrap();
 
-        let url = backend.artifact_url(&key);
+        let url = backend.key_url(&key.as_string());
         assert!(url.starts_with("https://cache.example.com/v1/artifacts/"));
     }
+
+    #[test]
+    fn test_namespaced_urls() {
+        let config = RemoteCacheConfig::new("https://cache.example.com/").with_namespace("team-a");
+        let backend = HttpBackend::new(&config).unwrap();
+
+        let key = CacheKey::builder()
+            .package_id("test")
+            .task_name("build")
+            .command("echo")
+            .dependency_graph_hash("abc")
+            .toolchain_version("node-v20")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            backend.key_url(&key.as_string()),
+            format!("https://cache.example.com/v1/ns/team-a/artifacts/{}", key.as_string())
+        );
+        assert_eq!(
+            backend.query_url(),
+            "https://cache.example.com/v1/ns/team-a/artifacts/query"
+        );
+    }
 }
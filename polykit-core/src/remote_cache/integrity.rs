@@ -4,10 +4,12 @@ use std::io::Read;
 use std::path::Path;
 
 use sha2::{Digest, Sha256};
+use tar::EntryType;
 
 use crate::error::{Error, Result};
 
-use super::artifact::Artifact;
+use super::artifact::{Artifact, SIGNATURE_ENTRY};
+use super::outputs::{validate_output_path, validate_symlink};
 
 /// Verifies the integrity of an artifact.
 ///
@@ -58,10 +60,7 @@ impl ArtifactVerifier {
         use tar::Archive;
 
         // Decompress
-        let tar_data = zstd::decode_all(artifact.compressed_data()).map_err(|e| Error::Adapter {
-            package: "artifact-verification".to_string(),
-            message: format!("Failed to decompress artifact: {}", e),
-        })?;
+        let tar_data = artifact.tar_data()?;
 
         // Extract and verify files
         let mut archive = Archive::new(&tar_data[..]);
@@ -82,13 +81,59 @@ impl ArtifactVerifier {
                 message: format!("Failed to get entry path: {}", e),
             })?;
 
-            // Skip metadata and manifest
-            if path == Path::new("metadata.json") || path == Path::new("manifest.json") {
+            // Skip metadata, manifest and signature
+            if path == Path::new("metadata.json")
+                || path == Path::new("manifest.json")
+                || path == Path::new(SIGNATURE_ENTRY)
+            {
                 continue;
             }
 
             // Verify output files
             if let Ok(relative_path) = path.strip_prefix(outputs_dir) {
+                validate_output_path(relative_path)?;
+
+                match entry.header().entry_type() {
+                    EntryType::Directory => {
+                        if !manifest.directories.contains(relative_path) {
+                            return Err(Error::Adapter {
+                                package: "artifact-verification".to_string(),
+                                message: format!(
+                                    "Directory {} in artifact but not in manifest",
+                                    relative_path.display()
+                                ),
+                            });
+                        }
+                        continue;
+                    }
+                    EntryType::Symlink => {
+                        let relative_path = relative_path.to_path_buf();
+                        let target = entry.link_name().ok().flatten().map(|t| t.into_owned());
+                        let expected = manifest.symlinks.get(&relative_path);
+                        if target.is_none() || target.as_ref() != expected {
+                            return Err(Error::Adapter {
+                                package: "artifact-verification".to_string(),
+                                message: format!(
+                                    "Symlink {} does not match manifest",
+                                    relative_path.display()
+                                ),
+                            });
+                        }
+                        continue;
+                    }
+                    EntryType::Regular => {}
+                    other => {
+                        return Err(Error::Adapter {
+                            package: "artifact-verification".to_string(),
+                            message: format!(
+                                "Unsupported entry type {:?} for {}",
+                                other,
+                                relative_path.display()
+                            ),
+                        });
+                    }
+                }
+
                 let expected_hash = manifest.files.get(relative_path).ok_or_else(|| {
                     Error::Adapter {
                         package: "artifact-verification".to_string(),
@@ -126,7 +171,11 @@ impl ArtifactVerifier {
             }
         }
 
-        // All files have been verified during iteration above
+        // All files have been verified during iteration above; symlinks
+        // restored from the manifest must stay inside the package too
+        for (link, target) in &manifest.symlinks {
+            validate_symlink(link, target, &manifest.symlinks)?;
+        }
 
         Ok(())
     }
@@ -2,61 +2,171 @@
 
 mod artifact;
 mod backend;
+mod browse;
 mod cache_key;
+mod compression;
 mod config;
 mod filesystem;
 mod http;
 mod integrity;
+mod outputs;
+mod signing;
 
-pub use artifact::Artifact;
+pub use artifact::{Artifact, ArtifactManifest, ArtifactMetadata, ARTIFACT_VERSION};
 pub use backend::{BackendError, RemoteCacheBackend};
+pub use browse::{ArtifactFilter, ArtifactList, StoredArtifact, StoredManifest};
 pub use cache_key::{detect_toolchain_version, CacheKey, CacheKeyBuilder};
+pub use compression::{
+    ArtifactCodec, CompressionConfig, Compressor, Dictionaries, Dictionary, DEFAULT_LEVEL,
+    LONG_WINDOW_LOG,
+};
 pub use config::RemoteCacheConfig;
 pub use filesystem::FilesystemBackend;
 pub use http::HttpBackend;
 pub use integrity::ArtifactVerifier;
+pub use outputs::{collect_outputs, OutputEntry, DEFAULT_DIR_MODE, DEFAULT_FILE_MODE};
+pub use signing::{
+    ArtifactSignature, KeyConfig, SignatureAlgorithm, SigningKey, TrustedKey, TrustedKeys,
+};
 
-use crate::error::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tokio::sync::{OnceCell, Semaphore};
+use tokio::task::AbortHandle;
+
+use crate::error::{Error, Result};
 use crate::graph::DependencyGraph;
 use crate::package::Package;
 
+/// Maximum number of artifacts downloaded concurrently by [`RemoteCache::prefetch`].
+const PREFETCH_CONCURRENCY: usize = 8;
+
+/// Maximum compressed bytes of prefetched artifacts held in memory.
+///
+/// Downloads that would exceed it are dropped and fetched again on use.
+const PREFETCH_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Artifacts prefetched by [`RemoteCache::prefetch`] and not yet fetched.
+#[derive(Default)]
+struct Prefetched {
+    /// One slot per cache key, filled once its download finishes.
+    slots: HashMap<String, Arc<PrefetchSlot>>,
+    /// Compressed bytes held by filled slots still in `slots`.
+    bytes: u64,
+    /// Downloads still running, aborted by `clear_prefetched`.
+    downloads: Vec<AbortHandle>,
+}
+
+type PrefetchSlot = OnceCell<PrefetchResult>;
+
+/// A finished prefetch download.
+struct PrefetchResult {
+    /// The artifact, `None` for a miss. Taken by `fetch_artifact`.
+    artifact: Mutex<Option<Artifact>>,
+    /// Bytes counted against `PREFETCH_MAX_BYTES`.
+    charged: u64,
+}
+
+impl PrefetchResult {
+    fn new(artifact: Option<Artifact>, charged: u64) -> Self {
+        Self {
+            artifact: Mutex::new(artifact),
+            charged,
+        }
+    }
+}
+
 /// Remote cache orchestrator.
 ///
 /// Handles cache operations and integrates with task execution.
 pub struct RemoteCache {
-    backend: Box<dyn RemoteCacheBackend>,
+    backend: Arc<dyn RemoteCacheBackend>,
     config: RemoteCacheConfig,
+    /// Downloads started by [`prefetch`](Self::prefetch). Entries are
+    /// consumed by `fetch_artifact` and dropped by `clear_prefetched`.
+    prefetched: Arc<Mutex<Prefetched>>,
+    signing_key: Option<SigningKey>,
+    trusted_keys: TrustedKeys,
+    compressor: Compressor,
 }
 
 impl RemoteCache {
     /// Creates a new remote cache with the given backend and configuration.
     pub fn new(backend: Box<dyn RemoteCacheBackend>, config: RemoteCacheConfig) -> Self {
-        Self { backend, config }
+        Self {
+            backend: Arc::from(backend),
+            config,
+            prefetched: Arc::default(),
+            signing_key: None,
+            trusted_keys: TrustedKeys::new(),
+            compressor: Compressor::default(),
+        }
+    }
+
+    /// Signs uploaded artifacts with the given key.
+    pub fn with_signing_key(mut self, key: SigningKey) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Accepts signatures from the given keys on fetched artifacts.
+    pub fn with_trusted_keys(mut self, keys: TrustedKeys) -> Self {
+        self.trusted_keys = keys;
+        self
+    }
+
+    /// Compresses uploaded artifacts with the given settings.
+    pub fn with_compressor(mut self, compressor: Compressor) -> Self {
+        self.compressor = compressor;
+        self
     }
 
     /// Creates a remote cache from configuration.
     ///
-    /// Automatically selects the appropriate backend based on the URL.
+    /// Automatically selects the appropriate backend based on the URL, and
+    /// loads the signing and trusted keys and the compression dictionary.
     ///
     /// # Errors
     ///
-    /// Returns an error if backend creation fails.
+    /// Returns an error if backend creation fails, a key or dictionary cannot
+    /// be loaded, or signatures are required without any trusted keys.
     pub fn from_config(config: RemoteCacheConfig) -> Result<Self> {
+        let compressor = Compressor::from_config(&config.compression)?;
         let backend: Box<dyn RemoteCacheBackend> = if config.is_http() {
-            Box::new(HttpBackend::new(&config)?)
+            Box::new(HttpBackend::new(&config)?.with_dictionaries(compressor.dictionaries()))
         } else {
-            Box::new(FilesystemBackend::new(&config.url)?)
+            Box::new(
+                FilesystemBackend::new(&config.url)?.with_dictionaries(compressor.dictionaries()),
+            )
         };
 
-        Ok(Self::new(backend, config))
+        let signing_key = config
+            .signing_key
+            .as_ref()
+            .map(SigningKey::from_config)
+            .transpose()?;
+        let trusted_keys = TrustedKeys::from_configs(&config.trusted_keys)?;
+        if config.require_signatures && trusted_keys.is_empty() {
+            return Err(Error::Adapter {
+                package: "remote-cache".to_string(),
+                message: "require_signatures is set but no trusted_keys are configured"
+                    .to_string(),
+            });
+        }
+
+        let mut cache = Self::new(backend, config)
+            .with_trusted_keys(trusted_keys)
+            .with_compressor(compressor);
+        if let Some(key) = signing_key {
+            cache = cache.with_signing_key(key);
+        }
+        Ok(cache)
     }
 
     /// Creates a disabled remote cache (no-op).
     pub fn disabled() -> Self {
-        Self {
-            backend: Box::new(DisabledBackend),
-            config: RemoteCacheConfig::default(),
-        }
+        Self::new(Box::new(DisabledBackend), RemoteCacheConfig::default())
     }
 
     /// Checks if remote cache is enabled.
@@ -72,21 +182,70 @@ impl RemoteCache {
     ///
     /// # Returns
     ///
-    /// Returns `Some(artifact)` if found, `None` if not found.
+    /// Returns `Some(artifact)` if found, `None` if not found. Keys passed to
+    /// an earlier [`prefetch`](Self::prefetch) wait for its download instead
+    /// of contacting the backend again.
     ///
     /// # Errors
     ///
-    /// Returns an error only for unexpected failures. Cache misses return `Ok(None)`.
+    /// Returns an error for unexpected failures and for artifacts that fail
+    /// signature verification. Cache misses return `Ok(None)`.
     pub async fn fetch_artifact(&self, key: &CacheKey) -> Result<Option<Artifact>> {
         if !self.is_enabled() {
             return Ok(None);
         }
 
-        self.backend.fetch_artifact(key).await
+        let slot = lock(&self.prefetched).slots.remove(&key.as_string());
+        let artifact = match slot {
+            Some(slot) => {
+                // Downloads here if the prefetch has not started or failed
+                let result = slot
+                    .get_or_try_init(|| async {
+                        let artifact = self.backend.fetch_artifact(key).await?;
+                        Ok::<_, Error>(PrefetchResult::new(artifact, 0))
+                    })
+                    .await?;
+                let mut prefetched = lock(&self.prefetched);
+                prefetched.bytes = prefetched.bytes.saturating_sub(result.charged);
+                drop(prefetched);
+                result
+                    .artifact
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .take()
+            }
+            None => self.backend.fetch_artifact(key).await?,
+        };
+
+        if let Some(ref artifact) = artifact {
+            self.verify_signature(artifact)?;
+        }
+
+        Ok(artifact)
+    }
+
+    /// Checks an artifact's signature against the trusted keys.
+    ///
+    /// Unsigned artifacts are accepted unless `require_signatures` is set.
+    /// Signed artifacts are always checked when trusted keys are configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature is missing but required, or invalid.
+    pub fn verify_signature(&self, artifact: &Artifact) -> Result<()> {
+        if self.config.require_signatures
+            || (artifact.signature().is_some() && !self.trusted_keys.is_empty())
+        {
+            self.trusted_keys.verify(artifact)?;
+        }
+        Ok(())
     }
 
     /// Uploads an artifact to the remote cache.
     ///
+    /// The artifact is recompressed first if it was built with different
+    /// compression settings, then signed if a signing key is configured.
+    ///
     /// # Arguments
     ///
     /// * `key` - The cache key for this artifact
@@ -100,7 +259,21 @@ impl RemoteCache {
             return Ok(());
         }
 
-        self.backend.upload_artifact(key, artifact).await
+        let recompressed;
+        let artifact = if artifact.metadata().codec != self.compressor.codec() {
+            recompressed = artifact.compressed_with(&self.compressor)?;
+            &recompressed
+        } else {
+            artifact
+        };
+
+        match self.signing_key {
+            Some(ref signing_key) => {
+                let signed = artifact.signed(signing_key)?;
+                self.backend.upload_artifact(key, &signed).await
+            }
+            None => self.backend.upload_artifact(key, artifact).await,
+        }
     }
 
     /// Checks if an artifact exists in the remote cache.
@@ -124,6 +297,132 @@ impl RemoteCache {
         self.backend.has_artifact(key).await
     }
 
+    /// Checks which of several artifacts exist in the remote cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The cache keys to check
+    ///
+    /// # Returns
+    ///
+    /// Returns one flag per key, in the same order as `keys`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only for unexpected failures.
+    pub async fn has_artifacts(&self, keys: &[CacheKey]) -> Result<Vec<bool>> {
+        if !self.is_enabled() || keys.is_empty() {
+            return Ok(vec![false; keys.len()]);
+        }
+
+        self.backend.has_artifacts(keys).await
+    }
+
+    /// Queries all keys in one batch and starts downloading the hits in the
+    /// background.
+    ///
+    /// Returns once the downloads are started. A later
+    /// [`fetch_artifact`](Self::fetch_artifact) for one of the keys waits for
+    /// its download, and known misses are answered without a request.
+    /// Downloads that fail or would hold more than `PREFETCH_MAX_BYTES` are
+    /// dropped, leaving `fetch_artifact` to download them itself. Call
+    /// [`clear_prefetched`](Self::clear_prefetched) once the keys are no
+    /// longer expected, so unused results are not kept.
+    ///
+    /// Downloads run on the current tokio runtime and stop with it.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The cache keys expected to be fetched soon
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of downloads started.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch existence query fails.
+    pub async fn prefetch(&self, keys: &[CacheKey]) -> Result<usize> {
+        if !self.is_enabled() || keys.is_empty() {
+            return Ok(0);
+        }
+
+        let found = self.backend.has_artifacts(keys).await?;
+
+        let semaphore = Arc::new(Semaphore::new(PREFETCH_CONCURRENCY));
+        let mut prefetched = lock(&self.prefetched);
+        prefetched.downloads.retain(|download| !download.is_finished());
+
+        let mut started = 0;
+        for (key, exists) in keys.iter().zip(found) {
+            let name = key.as_string();
+            if !exists {
+                let miss = OnceCell::new_with(Some(PrefetchResult::new(None, 0)));
+                prefetched.slots.insert(name, Arc::new(miss));
+                continue;
+            }
+
+            let slot = Arc::new(PrefetchSlot::new());
+            prefetched.slots.insert(name.clone(), Arc::clone(&slot));
+
+            let backend = Arc::clone(&self.backend);
+            let state = Arc::clone(&self.prefetched);
+            let semaphore = Arc::clone(&semaphore);
+            let key = key.clone();
+            let download = tokio::spawn(async move {
+                let Ok(_permit) = semaphore.acquire_owned().await else {
+                    return;
+                };
+                let _ = slot
+                    .get_or_try_init(|| async {
+                        let artifact = backend.fetch_artifact(&key).await.map_err(|_| ())?;
+                        let size = artifact
+                            .as_ref()
+                            .map_or(0, |artifact| artifact.compressed_data().len() as u64);
+
+                        let mut state = lock(&state);
+                        let held = state
+                            .slots
+                            .get(&name)
+                            .is_some_and(|held| Arc::ptr_eq(held, &slot));
+                        if !held {
+                            // Already taken by `fetch_artifact` or cleared
+                            return Ok(PrefetchResult::new(artifact, 0));
+                        }
+                        if state.bytes.saturating_add(size) > PREFETCH_MAX_BYTES {
+                            state.slots.remove(&name);
+                            return Err(());
+                        }
+                        state.bytes += size;
+                        Ok(PrefetchResult::new(artifact, size))
+                    })
+                    .await;
+            });
+            prefetched.downloads.push(download.abort_handle());
+            started += 1;
+        }
+
+        Ok(started)
+    }
+
+    /// Drops the results of earlier [`prefetch`](Self::prefetch) calls and
+    /// stops their downloads.
+    ///
+    /// Unused results would otherwise hold memory, and a recorded miss would
+    /// hide an artifact uploaded later.
+    pub fn clear_prefetched(&self) {
+        let mut prefetched = lock(&self.prefetched);
+        for download in prefetched.downloads.drain(..) {
+            download.abort();
+        }
+        let charged: u64 = prefetched
+            .slots
+            .drain()
+            .filter_map(|(_, slot)| slot.get().map(|result| result.charged))
+            .sum();
+        prefetched.bytes = prefetched.bytes.saturating_sub(charged);
+    }
+
     /// Builds a cache key for a task execution.
     ///
     /// # Arguments
@@ -267,6 +566,10 @@ impl RemoteCache {
     }
 }
 
+fn lock(prefetched: &Mutex<Prefetched>) -> MutexGuard<'_, Prefetched> {
+    prefetched.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 /// Disabled backend that does nothing.
 struct DisabledBackend;
 
@@ -0,0 +1,347 @@
+//! Task outputs recorded in artifacts.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::error::{Error, Result};
+
+/// Permission bits used for regular files when no mode is known.
+pub const DEFAULT_FILE_MODE: u32 = 0o644;
+
+/// Permission bits used for directories when no mode is known.
+pub const DEFAULT_DIR_MODE: u32 = 0o755;
+
+/// A single output file, symlink or directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputEntry {
+    /// A regular file.
+    File {
+        /// File contents.
+        content: Vec<u8>,
+        /// Unix permission bits.
+        mode: u32,
+        /// Modification time (Unix epoch seconds), if recorded.
+        mtime: Option<u64>,
+    },
+    /// A symbolic link.
+    Symlink {
+        /// Link target, relative to the directory containing the link.
+        target: PathBuf,
+    },
+    /// A directory, recorded so empty directories are restored.
+    Directory {
+        /// Unix permission bits.
+        mode: u32,
+        /// Modification time (Unix epoch seconds), if recorded.
+        mtime: Option<u64>,
+    },
+}
+
+impl OutputEntry {
+    /// Creates a regular file entry with default permissions.
+    pub fn file(content: Vec<u8>) -> Self {
+        Self::File {
+            content,
+            mode: DEFAULT_FILE_MODE,
+            mtime: None,
+        }
+    }
+
+    /// Reads an entry from the filesystem without following symlinks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path cannot be read or is not a regular file,
+    /// symlink or directory.
+    pub fn read(path: &Path, preserve_mtime: bool) -> Result<Self> {
+        let metadata = fs::symlink_metadata(path).map_err(Error::Io)?;
+        let mtime = if preserve_mtime {
+            metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+        } else {
+            None
+        };
+
+        let file_type = metadata.file_type();
+        if file_type.is_symlink() {
+            Ok(Self::Symlink {
+                target: fs::read_link(path).map_err(Error::Io)?,
+            })
+        } else if file_type.is_dir() {
+            Ok(Self::Directory {
+                mode: unix_mode(&metadata).unwrap_or(DEFAULT_DIR_MODE),
+                mtime,
+            })
+        } else if file_type.is_file() {
+            Ok(Self::File {
+                content: fs::read(path).map_err(Error::Io)?,
+                mode: unix_mode(&metadata).unwrap_or(DEFAULT_FILE_MODE),
+                mtime,
+            })
+        } else {
+            Err(Error::Adapter {
+                package: "artifact".to_string(),
+                message: format!("Unsupported output type: {}", path.display()),
+            })
+        }
+    }
+}
+
+#[cfg(unix)]
+fn unix_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// Reads outputs below `root` into artifact entries.
+///
+/// Each path is relative to `root`. Directories are walked recursively and
+/// recorded themselves, so empty directories survive a round trip.
+/// Symlinks are recorded as links and never followed.
+///
+/// # Errors
+///
+/// Returns an error if a path escapes `root`, cannot be read, or is a
+/// symlink pointing outside `root`.
+pub fn collect_outputs(
+    root: &Path,
+    paths: &[PathBuf],
+    preserve_mtimes: bool,
+) -> Result<BTreeMap<PathBuf, OutputEntry>> {
+    let mut entries = BTreeMap::new();
+
+    for path in paths {
+        validate_output_path(path)?;
+
+        for dir_entry in walkdir::WalkDir::new(root.join(path)).follow_links(false) {
+            let dir_entry = dir_entry.map_err(|e| Error::Adapter {
+                package: "artifact".to_string(),
+                message: format!("Failed to read output {}: {}", path.display(), e),
+            })?;
+            let relative = dir_entry
+                .path()
+                .strip_prefix(root)
+                .map_err(|_| Error::Adapter {
+                    package: "artifact".to_string(),
+                    message: format!("Output {} is outside the package", path.display()),
+                })?
+                .to_path_buf();
+            if relative.as_os_str().is_empty() {
+                continue;
+            }
+
+            let entry = OutputEntry::read(dir_entry.path(), preserve_mtimes)?;
+            entries.insert(relative, entry);
+        }
+    }
+
+    // Symlinks are checked once all are known, since they may chain
+    let symlinks: BTreeMap<PathBuf, PathBuf> = entries
+        .iter()
+        .filter_map(|(path, entry)| match entry {
+            OutputEntry::Symlink { target } => Some((path.clone(), target.clone())),
+            _ => None,
+        })
+        .collect();
+    for (link, target) in &symlinks {
+        validate_symlink(link, target, &symlinks)?;
+    }
+
+    Ok(entries)
+}
+
+/// Checks that an output path is relative and stays inside the package.
+///
+/// # Errors
+///
+/// Returns an error for empty or absolute paths and paths with `..`.
+pub(crate) fn validate_output_path(path: &Path) -> Result<()> {
+    let is_safe = path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+    if is_safe {
+        Ok(())
+    } else {
+        Err(Error::Adapter {
+            package: "artifact".to_string(),
+            message: format!("Output path {} escapes the package", path.display()),
+        })
+    }
+}
+
+/// Maximum number of symlinks followed while resolving a symlink target.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Checks that a symlink at `link` (relative to the package) points inside
+/// the package.
+///
+/// `symlinks` are the other symlinks recorded alongside `link`. They are
+/// followed while resolving, so a chain such as `sub -> .` and
+/// `sub/up -> ..` cannot step outside the package.
+///
+/// # Errors
+///
+/// Returns an error for absolute targets, targets that leave the package,
+/// and chains of more than `MAX_SYMLINK_HOPS` symlinks.
+pub(crate) fn validate_symlink(
+    link: &Path,
+    target: &Path,
+    symlinks: &BTreeMap<PathBuf, PathBuf>,
+) -> Result<()> {
+    let escapes = || Error::Adapter {
+        package: "artifact".to_string(),
+        message: format!(
+            "Symlink {} -> {} points outside the package",
+            link.display(),
+            target.display()
+        ),
+    };
+
+    // Components still to resolve, in reverse so the next one is popped
+    let parent = link.parent().unwrap_or_else(|| Path::new(""));
+    let mut pending: Vec<Component<'_>> = target.components().rev().collect();
+    pending.extend(parent.components().rev());
+
+    let mut resolved = PathBuf::new();
+    let mut hops = 0;
+    while let Some(component) = pending.pop() {
+        match component {
+            Component::Normal(name) => {
+                resolved.push(name);
+                if let Some(next) = symlinks.get(&resolved) {
+                    hops += 1;
+                    if hops > MAX_SYMLINK_HOPS {
+                        return Err(Error::Adapter {
+                            package: "artifact".to_string(),
+                            message: format!(
+                                "Symlink {} -> {} passes through too many symlinks",
+                                link.display(),
+                                target.display()
+                            ),
+                        });
+                    }
+                    resolved.pop();
+                    pending.extend(next.components().rev());
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return Err(escapes());
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return Err(escapes()),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_output_path() {
+        assert!(validate_output_path(Path::new("dist/app.js")).is_ok());
+        assert!(validate_output_path(Path::new("./bin")).is_ok());
+        assert!(validate_output_path(Path::new("../secret")).is_err());
+        assert!(validate_output_path(Path::new("dist/../../secret")).is_err());
+        assert!(validate_output_path(Path::new("/etc/passwd")).is_err());
+        assert!(validate_output_path(Path::new("")).is_err());
+    }
+
+    #[test]
+    fn test_validate_symlink() {
+        let none = BTreeMap::new();
+        assert!(
+            validate_symlink(Path::new("bin/tool"), Path::new("../dist/tool.js"), &none).is_ok()
+        );
+        assert!(validate_symlink(Path::new("latest"), Path::new("dist"), &none).is_ok());
+        assert!(
+            validate_symlink(Path::new("bin/tool"), Path::new("../../other/tool"), &none).is_err()
+        );
+        assert!(validate_symlink(Path::new("latest"), Path::new(".."), &none).is_err());
+        assert!(validate_symlink(Path::new("bin/sh"), Path::new("/bin/sh"), &none).is_err());
+    }
+
+    #[test]
+    fn test_validate_symlink_follows_chains() {
+        let symlinks = BTreeMap::from([
+            (PathBuf::from("sub"), PathBuf::from(".")),
+            (PathBuf::from("pkg"), PathBuf::from("store/pkg@1")),
+            (PathBuf::from("loop"), PathBuf::from("loop")),
+        ]);
+
+        // Each is fine on its own, but `sub` resolves to the package root
+        assert!(validate_symlink(Path::new("sub/up"), Path::new(".."), &BTreeMap::new()).is_ok());
+        assert!(validate_symlink(Path::new("sub/up"), Path::new(".."), &symlinks).is_err());
+        assert!(validate_symlink(Path::new("up"), Path::new("sub/.."), &symlinks).is_err());
+
+        assert!(validate_symlink(
+            Path::new("bin/tool"),
+            Path::new("../pkg/bin/tool"),
+            &symlinks
+        )
+        .is_ok());
+        assert!(validate_symlink(Path::new("up"), Path::new("pkg/../.."), &symlinks).is_ok());
+        assert!(validate_symlink(Path::new("up"), Path::new("pkg/../../.."), &symlinks).is_err());
+        assert!(validate_symlink(Path::new("spin"), Path::new("loop/x"), &symlinks).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_outputs() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("dist/empty")).unwrap();
+        fs::write(root.join("dist/tool.js"), b"#!/usr/bin/env node").unwrap();
+        fs::set_permissions(root.join("dist/tool.js"), fs::Permissions::from_mode(0o755)).unwrap();
+        symlink("tool.js", root.join("dist/tool")).unwrap();
+
+        let entries = collect_outputs(root, &[PathBuf::from("dist")], false).unwrap();
+        assert!(matches!(
+            entries[Path::new("dist/tool.js")],
+            OutputEntry::File {
+                mode: 0o755,
+                mtime: None,
+                ..
+            }
+        ));
+        assert_eq!(
+            entries[Path::new("dist/tool")],
+            OutputEntry::Symlink {
+                target: PathBuf::from("tool.js")
+            }
+        );
+        assert!(matches!(
+            entries[Path::new("dist/empty")],
+            OutputEntry::Directory { .. }
+        ));
+
+        symlink("/etc/passwd", root.join("dist/passwd")).unwrap();
+        assert!(collect_outputs(root, &[PathBuf::from("dist")], false).is_err());
+        fs::remove_file(root.join("dist/passwd")).unwrap();
+
+        symlink(".", root.join("dist/sub")).unwrap();
+        symlink("..", root.join("dist/up")).unwrap();
+        assert!(collect_outputs(root, &[PathBuf::from("dist")], false).is_ok());
+        fs::remove_file(root.join("dist/up")).unwrap();
+        symlink("sub/../..", root.join("dist/up")).unwrap();
+        assert!(collect_outputs(root, &[PathBuf::from("dist")], false).is_err());
+    }
+}
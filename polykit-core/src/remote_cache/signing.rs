@@ -0,0 +1,464 @@
+//! Artifact signing and signature verification.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+use super::artifact::Artifact;
+
+/// Signature algorithm used for an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignatureAlgorithm {
+    /// Ed25519 public-key signature.
+    Ed25519,
+    /// HMAC-SHA256 with a shared secret.
+    HmacSha256,
+}
+
+impl SignatureAlgorithm {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SignatureAlgorithm::Ed25519 => "ed25519",
+            SignatureAlgorithm::HmacSha256 => "hmac-sha256",
+        }
+    }
+}
+
+/// Signature stored in an artifact as `signature.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactSignature {
+    /// Algorithm that produced the signature.
+    pub algorithm: SignatureAlgorithm,
+    /// Identifier of the signing key.
+    pub key_id: String,
+    /// Hex-encoded signature.
+    pub signature: String,
+}
+
+/// Key configuration as written in `polykit.toml` or a trusted keys file.
+///
+/// Key material is hex-encoded and read from exactly one of `key`, `key_env`
+/// or `key_file`. For Ed25519 signing keys it is the 32-byte seed, for
+/// trusted Ed25519 keys the 32-byte public key, and for HMAC the shared secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyConfig {
+    /// Identifier recorded in signatures.
+    pub key_id: String,
+    /// Signature algorithm.
+    pub algorithm: SignatureAlgorithm,
+    /// Inline key material.
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Environment variable holding the key material.
+    #[serde(default)]
+    pub key_env: Option<String>,
+    /// File holding the key material.
+    #[serde(default)]
+    pub key_file: Option<PathBuf>,
+}
+
+impl KeyConfig {
+    /// Loads and decodes the key material.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no source or more than one source is set, the
+    /// source cannot be read, or the material is not valid hex.
+    pub fn load_material(&self) -> Result<Vec<u8>> {
+        let sources =
+            self.key.is_some() as u8 + self.key_env.is_some() as u8 + self.key_file.is_some() as u8;
+        if sources != 1 {
+            return Err(signing_error(format!(
+                "Key '{}' must set exactly one of key, key_env or key_file",
+                self.key_id
+            )));
+        }
+
+        let encoded = if let Some(ref key) = self.key {
+            key.clone()
+        } else if let Some(ref var) = self.key_env {
+            std::env::var(var).map_err(|_| {
+                signing_error(format!(
+                    "Environment variable {} for key '{}' is not set",
+                    var, self.key_id
+                ))
+            })?
+        } else if let Some(ref path) = self.key_file {
+            std::fs::read_to_string(path).map_err(|e| {
+                signing_error(format!(
+                    "Failed to read key file {} for key '{}': {}",
+                    path.display(),
+                    self.key_id,
+                    e
+                ))
+            })?
+        } else {
+            unreachable!()
+        };
+
+        decode_hex(encoded.trim())
+            .ok_or_else(|| signing_error(format!("Key '{}' is not valid hex", self.key_id)))
+    }
+}
+
+/// Key used to sign artifacts at upload time.
+pub struct SigningKey {
+    key_id: String,
+    inner: SigningKeyInner,
+}
+
+enum SigningKeyInner {
+    Ed25519(Ed25519KeyPair),
+    Hmac(ring::hmac::Key, Vec<u8>),
+}
+
+impl SigningKey {
+    /// Creates an Ed25519 signing key from a 32-byte seed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the seed is not a valid Ed25519 seed.
+    pub fn ed25519(key_id: impl Into<String>, seed: &[u8]) -> Result<Self> {
+        let key_id = key_id.into();
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(seed).map_err(|e| {
+            signing_error(format!("Invalid Ed25519 seed for key '{}': {}", key_id, e))
+        })?;
+        Ok(Self {
+            key_id,
+            inner: SigningKeyInner::Ed25519(key_pair),
+        })
+    }
+
+    /// Creates an HMAC-SHA256 signing key from a shared secret.
+    pub fn hmac(key_id: impl Into<String>, secret: &[u8]) -> Self {
+        Self {
+            key_id: key_id.into(),
+            inner: SigningKeyInner::Hmac(
+                ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret),
+                secret.to_vec(),
+            ),
+        }
+    }
+
+    /// Creates a signing key from configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key material cannot be loaded.
+    pub fn from_config(config: &KeyConfig) -> Result<Self> {
+        let material = config.load_material()?;
+        match config.algorithm {
+            SignatureAlgorithm::Ed25519 => Self::ed25519(config.key_id.clone(), &material),
+            SignatureAlgorithm::HmacSha256 => Ok(Self::hmac(config.key_id.clone(), &material)),
+        }
+    }
+
+    /// Returns the key identifier.
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Returns the algorithm of this key.
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        match self.inner {
+            SigningKeyInner::Ed25519(_) => SignatureAlgorithm::Ed25519,
+            SigningKeyInner::Hmac(..) => SignatureAlgorithm::HmacSha256,
+        }
+    }
+
+    /// Returns the key that verifies signatures made with this key.
+    ///
+    /// For Ed25519 this is the public key; for HMAC it is the shared secret.
+    pub fn trusted_key(&self) -> TrustedKey {
+        let material = match self.inner {
+            SigningKeyInner::Ed25519(ref key_pair) => key_pair.public_key().as_ref().to_vec(),
+            SigningKeyInner::Hmac(_, ref secret) => secret.clone(),
+        };
+        TrustedKey {
+            key_id: self.key_id.clone(),
+            algorithm: self.algorithm(),
+            material,
+        }
+    }
+
+    /// Signs an artifact's metadata, manifest and cache key hash.
+    pub fn sign(&self, artifact: &Artifact) -> ArtifactSignature {
+        let payload = signed_payload(artifact, self.algorithm(), &self.key_id);
+        let signature = match self.inner {
+            SigningKeyInner::Ed25519(ref key_pair) => key_pair.sign(&payload).as_ref().to_vec(),
+            SigningKeyInner::Hmac(ref key, _) => ring::hmac::sign(key, &payload).as_ref().to_vec(),
+        };
+
+        ArtifactSignature {
+            algorithm: self.algorithm(),
+            key_id: self.key_id.clone(),
+            signature: encode_hex(&signature),
+        }
+    }
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("key_id", &self.key_id)
+            .field("algorithm", &self.algorithm())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Key trusted to verify artifact signatures.
+#[derive(Clone)]
+pub struct TrustedKey {
+    key_id: String,
+    algorithm: SignatureAlgorithm,
+    material: Vec<u8>,
+}
+
+impl TrustedKey {
+    /// Creates a trusted key from raw key material.
+    pub fn new(key_id: impl Into<String>, algorithm: SignatureAlgorithm, material: Vec<u8>) -> Self {
+        Self {
+            key_id: key_id.into(),
+            algorithm,
+            material,
+        }
+    }
+
+    /// Creates a trusted key from configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key material cannot be loaded.
+    pub fn from_config(config: &KeyConfig) -> Result<Self> {
+        Ok(Self::new(
+            config.key_id.clone(),
+            config.algorithm,
+            config.load_material()?,
+        ))
+    }
+
+    /// Returns the key identifier.
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn verify(&self, payload: &[u8], signature: &[u8]) -> bool {
+        match self.algorithm {
+            SignatureAlgorithm::Ed25519 => UnparsedPublicKey::new(&ED25519, &self.material)
+                .verify(payload, signature)
+                .is_ok(),
+            SignatureAlgorithm::HmacSha256 => {
+                let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, &self.material);
+                ring::hmac::verify(&key, payload, signature).is_ok()
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for TrustedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrustedKey")
+            .field("key_id", &self.key_id)
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Set of keys whose artifact signatures are accepted.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedKeys {
+    keys: BTreeMap<String, TrustedKey>,
+}
+
+#[derive(Deserialize)]
+struct TrustedKeysFile {
+    #[serde(default)]
+    keys: Vec<KeyConfig>,
+}
+
+impl TrustedKeys {
+    /// Creates an empty key set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key, replacing any key with the same identifier.
+    pub fn add(&mut self, key: TrustedKey) {
+        self.keys.insert(key.key_id.clone(), key);
+    }
+
+    /// Creates a key set from configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any key material cannot be loaded.
+    pub fn from_configs(configs: &[KeyConfig]) -> Result<Self> {
+        let mut keys = Self::new();
+        for config in configs {
+            keys.add(TrustedKey::from_config(config)?);
+        }
+        Ok(keys)
+    }
+
+    /// Loads a key set from a TOML file with `[[keys]]` entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or any key
+    /// material cannot be loaded.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let file: TrustedKeysFile = toml::from_str(&content).map_err(|e| Error::Toml {
+            error: e,
+            context: path.display().to_string(),
+        })?;
+        Self::from_configs(&file.keys)
+    }
+
+    /// Returns `true` if the set contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the number of keys in the set.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Verifies that an artifact carries a valid signature from a trusted key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the artifact is unsigned, signed by an unknown key,
+    /// or the signature does not match.
+    pub fn verify(&self, artifact: &Artifact) -> Result<()> {
+        let signature = artifact
+            .signature()
+            .ok_or_else(|| signing_error("Artifact is not signed".to_string()))?;
+
+        let key = self.keys.get(&signature.key_id).ok_or_else(|| {
+            signing_error(format!(
+                "Artifact is signed by untrusted key '{}'",
+                signature.key_id
+            ))
+        })?;
+
+        if key.algorithm != signature.algorithm {
+            return Err(signing_error(format!(
+                "Signature algorithm {} does not match trusted key '{}' ({})",
+                signature.algorithm.as_str(),
+                key.key_id,
+                key.algorithm.as_str()
+            )));
+        }
+
+        let raw = decode_hex(&signature.signature)
+            .ok_or_else(|| signing_error("Signature is not valid hex".to_string()))?;
+        let payload = signed_payload(artifact, signature.algorithm, &signature.key_id);
+
+        if !key.verify(&payload, &raw) {
+            return Err(signing_error(format!(
+                "Invalid signature from key '{}'",
+                signature.key_id
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the bytes covered by a signature.
+///
+/// The digests are taken over the raw `metadata.json` and `manifest.json`
+/// entries, so signatures stay valid across releases that add fields.
+fn signed_payload(artifact: &Artifact, algorithm: SignatureAlgorithm, key_id: &str) -> Vec<u8> {
+    format!(
+        "polykit-artifact-signature-v1\n{}\n{}\n{}\n{}\n{}",
+        artifact.metadata_digest(),
+        artifact.manifest_digest(),
+        artifact.metadata().cache_key_hash,
+        algorithm.as_str(),
+        key_id
+    )
+    .into_bytes()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn signing_error(message: String) -> Error {
+    Error::Adapter {
+        package: "artifact-signing".to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn artifact() -> Artifact {
+        let mut output_files = BTreeMap::new();
+        output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+        Artifact::new(
+            "test".to_string(),
+            "build".to_string(),
+            "echo".to_string(),
+            "hash123".to_string(),
+            output_files,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = vec![0x00, 0x7f, 0xab, 0xff];
+        assert_eq!(decode_hex(&encode_hex(&bytes)), Some(bytes));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+
+    #[test]
+    fn test_key_config_requires_single_source() {
+        let config = KeyConfig {
+            key_id: "ci".to_string(),
+            algorithm: SignatureAlgorithm::HmacSha256,
+            key: Some("00ff".to_string()),
+            key_env: Some("POLYKIT_TEST_UNUSED".to_string()),
+            key_file: None,
+        };
+        assert!(config.load_material().is_err());
+    }
+
+    #[test]
+    fn test_hmac_signature_round_trip() {
+        let key = SigningKey::hmac("ci", b"secret");
+        let signed = artifact().signed(&key).unwrap();
+
+        let mut trusted = TrustedKeys::new();
+        trusted.add(key.trusted_key());
+        assert!(trusted.verify(&signed).is_ok());
+
+        let mut wrong = TrustedKeys::new();
+        wrong.add(SigningKey::hmac("ci", b"other").trusted_key());
+        assert!(wrong.verify(&signed).is_err());
+    }
+}
@@ -1,13 +1,18 @@
 //! Task execution engine and orchestration.
 
+use std::cmp::Reverse;
 use std::collections::HashSet;
+use std::future::Future;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-use rayon::prelude::*;
 use crossbeam::channel;
+use rustc_hash::FxHashMap;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use crate::analysis;
 use crate::command_validator::CommandValidator;
 use crate::error::{Error, Result};
 use crate::executor::TaskExecutor;
@@ -27,15 +32,12 @@ pub struct TaskRunner {
     remote_cache: Option<Arc<RemoteCache>>,
     thread_pool: Arc<rayon::ThreadPool>,
     executor: TaskExecutor,
+    priorities: FxHashMap<String, Duration>,
 }
 
 impl TaskRunner {
     pub fn new(packages_dir: impl Into<PathBuf>, graph: DependencyGraph) -> Self {
-        let pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(rayon::current_num_threads())
-            .thread_name(|i| format!("polykit-worker-{}", i))
-            .build()
-            .unwrap_or_else(|_| rayon::ThreadPoolBuilder::new().build().unwrap());
+        let pool = worker_pool(rayon::current_num_threads());
 
         let packages_dir_path = packages_dir.into();
         let executor = TaskExecutor::new(
@@ -55,6 +57,7 @@ impl TaskRunner {
             remote_cache: None,
             thread_pool: Arc::new(pool),
             executor,
+            priorities: FxHashMap::default(),
         }
     }
 
@@ -82,11 +85,25 @@ impl TaskRunner {
         self
     }
 
+    /// Limits how many packages run at once.
+    ///
+    /// Tasks mostly wait on child processes, so the limit may exceed the
+    /// number of CPUs.
     pub fn with_max_parallel(mut self, max_parallel: Option<usize>) -> Self {
+        if let Some(n) = max_parallel.filter(|&n| n > self.thread_pool.current_num_threads()) {
+            self.thread_pool = Arc::new(worker_pool(n));
+        }
         self.max_parallel = max_parallel;
         self
     }
 
+    /// Starts packages on long dependency chains first, using estimated
+    /// task durations such as those from [`RunHistory`](crate::history::RunHistory).
+    pub fn with_estimates(mut self, estimates: &FxHashMap<String, Duration>) -> Self {
+        self.priorities = analysis::priorities(&self.graph, estimates);
+        self
+    }
+
     pub fn with_remote_cache(mut self, remote_cache: Arc<RemoteCache>) -> Self {
         self.remote_cache = Some(remote_cache.clone());
         self.executor = TaskExecutor::new(
@@ -99,34 +116,90 @@ impl TaskRunner {
         self
     }
 
+    /// Looks up the named packages, or returns every package for `None`.
+    fn resolve_packages(&self, package_names: Option<&[String]>) -> Result<Vec<&Package>> {
+        let Some(names) = package_names else {
+            return Ok(self.graph.all_packages());
+        };
+        names
+            .iter()
+            .map(|name| {
+                self.graph
+                    .get_package(name)
+                    .ok_or_else(|| Error::PackageNotFound {
+                        name: name.clone(),
+                        available: format!("Package '{}' not found", name),
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the packages of a level that should run, highest priority
+    /// first.
+    fn level_packages<'a>(
+        &'a self,
+        level: &[String],
+        selected: &HashSet<&str>,
+    ) -> Vec<&'a Package> {
+        let mut packages: Vec<&Package> = level
+            .iter()
+            .filter(|name| selected.contains(name.as_str()))
+            .filter_map(|name| self.graph.get_package(name))
+            .collect();
+        packages.sort_by_key(|package| {
+            Reverse(self.priorities.get(&package.name).copied().unwrap_or_default())
+        });
+        packages
+    }
+
+    /// Returns how many packages of a level may run at once.
+    fn workers(&self, level_size: usize) -> usize {
+        self.max_parallel
+            .unwrap_or_else(|| self.thread_pool.current_num_threads())
+            .clamp(1, level_size.max(1))
+    }
+
+    /// Runs a task in the named packages, or in every package for `None`.
+    ///
+    /// Packages run one dependency level at a time, at most `max_parallel`
+    /// at once, starting with the highest priority.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a named package is not in the graph or a task
+    /// cannot be started.
     pub fn run_task(
         &self,
         task_name: &str,
         package_names: Option<&[String]>,
     ) -> Result<Vec<TaskResult>> {
-        if let Some(names) = package_names {
-            if names.is_empty() {
-                return Ok(Vec::new());
-            }
-            if names.len() == 1 {
-                if let Some(package) = self.graph.get_package(&names[0]) {
-                    let result = self.executor.execute_task(package, task_name)?;
-                    return Ok(vec![result]);
-                }
-            }
+        let packages_to_run = self.resolve_packages(package_names)?;
+        if packages_to_run.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let packages_to_run = if let Some(names) = package_names {
-            names
-                .iter()
-                .filter_map(|name| self.graph.get_package(name))
-                .collect::<Vec<_>>()
-        } else {
-            self.graph.all_packages()
+        // Prefetch downloads run in the background on a tokio runtime, so
+        // one must outlive them when called from synchronous code
+        let runtime = match (&self.remote_cache, tokio::runtime::Handle::try_current()) {
+            (Some(_), Err(_)) => tokio::runtime::Runtime::new().ok(),
+            _ => None,
         };
+        let _runtime_guard = runtime.as_ref().map(|runtime| runtime.enter());
+
+        self.prefetch_remote_cache(task_name, &packages_to_run);
+        let results = self.run_packages(task_name, &packages_to_run);
+        // Results the run did not use, such as those of failed or skipped
+        // tasks, must not outlive it
+        if let Some(ref remote_cache) = self.remote_cache {
+            remote_cache.clear_prefetched();
+        }
+        results
+    }
 
-        if packages_to_run.is_empty() {
-            return Ok(Vec::new());
+    fn run_packages(&self, task_name: &str, packages_to_run: &[&Package]) -> Result<Vec<TaskResult>> {
+        if let [package] = packages_to_run[..] {
+            let result = self.executor.execute_task(package, task_name)?;
+            return Ok(vec![result]);
         }
 
         let packages_set: HashSet<&str> = packages_to_run.iter().map(|p| p.name.as_str()).collect();
@@ -135,11 +208,7 @@ impl TaskRunner {
         let mut results = Vec::with_capacity(packages_to_run.len());
 
         for level in levels {
-            let level_packages: Vec<&Package> = level
-                .iter()
-                .filter(|name| packages_set.contains(name.as_str()))
-                .filter_map(|name| self.graph.get_package(name))
-                .collect();
+            let level_packages = self.level_packages(&level, &packages_set);
 
             if level_packages.is_empty() {
                 continue;
@@ -147,13 +216,19 @@ impl TaskRunner {
 
             let (tx, rx) = channel::unbounded();
             let executor = &self.executor;
-            self.thread_pool.install(|| {
-                level_packages
-                    .into_par_iter()
-                    .for_each(|package| {
-                        let result = executor.execute_task(package, task_name);
-                        let _ = tx.send(result);
+            let next = AtomicUsize::new(0);
+            self.thread_pool.scope(|scope| {
+                for _ in 0..self.workers(level_packages.len()) {
+                    let tx = tx.clone();
+                    let (next, level_packages) = (&next, &level_packages);
+                    scope.spawn(move |_| {
+                        while let Some(package) =
+                            level_packages.get(next.fetch_add(1, Ordering::Relaxed))
+                        {
+                            let _ = tx.send(executor.execute_task(package, task_name));
+                        }
                     });
+                }
             });
             drop(tx);
 
@@ -173,6 +248,45 @@ impl TaskRunner {
         Ok(results)
     }
 
+    /// Queries the remote cache for every task `run_task` may execute and
+    /// starts downloading the hits before scheduling starts.
+    ///
+    /// Only the batch query is waited for; tasks wait for their own download
+    /// when they need it. Failures are ignored; tasks then fall back to
+    /// fetching one at a time.
+    fn prefetch_remote_cache(&self, task_name: &str, packages: &[&Package]) {
+        let Some(ref remote_cache) = self.remote_cache else {
+            return;
+        };
+        if !remote_cache.is_enabled() {
+            return;
+        }
+
+        let prefetch = async {
+            let mut keys = Vec::new();
+            for package in packages {
+                let Ok(order) = self.executor.build_task_dependency_order(package, task_name) else {
+                    continue;
+                };
+                let package_path = self.packages_dir.join(&package.path);
+                for name in &order {
+                    let Some(task) = package.get_task(name) else {
+                        continue;
+                    };
+                    if let Ok(key) = remote_cache
+                        .build_cache_key(package, name, &task.command, &self.graph, &package_path)
+                        .await
+                    {
+                        keys.push(key);
+                    }
+                }
+            }
+            remote_cache.prefetch(&keys).await
+        };
+
+        let _ = block_on(prefetch);
+    }
+
     pub async fn run_task_streaming<F>(
         &self,
         task_name: &str,
@@ -182,15 +296,11 @@ impl TaskRunner {
     where
         F: Fn(&str, &str, bool) + Send + Sync + 'static,
     {
-        let packages_to_run: Vec<Package> = if let Some(names) = package_names {
-            names
-                .iter()
-                .filter_map(|name| self.graph.get_package(name))
-                .cloned()
-                .collect()
-        } else {
-            self.graph.all_packages().into_iter().cloned().collect()
-        };
+        let packages_to_run: Vec<Package> = self
+            .resolve_packages(package_names)?
+            .into_iter()
+            .cloned()
+            .collect();
 
         if packages_to_run.is_empty() {
             return Ok(Vec::new());
@@ -201,15 +311,14 @@ impl TaskRunner {
         let levels = self.graph.dependency_levels();
         let mut results = Vec::new();
         use std::sync::{Arc, Mutex};
-        use tokio::sync::mpsc;
+        use tokio::sync::{mpsc, Semaphore};
 
         let output_handler = Arc::new(Mutex::new(on_output));
 
         for level in levels {
-            let level_packages: Vec<Package> = level
-                .iter()
-                .filter(|name| packages_set.contains(name.as_str()))
-                .filter_map(|name| self.graph.get_package(name))
+            let level_packages: Vec<Package> = self
+                .level_packages(&level, &packages_set)
+                .into_iter()
                 .cloned()
                 .collect();
 
@@ -217,6 +326,10 @@ impl TaskRunner {
                 continue;
             }
 
+            // Permits are taken before spawning, so the highest priority
+            // packages start first.
+            let permits = Arc::new(Semaphore::new(self.workers(level_packages.len())));
+
             let (tx, mut rx) = mpsc::unbounded_channel::<(String, String, bool)>();
             let output_handler_clone = Arc::clone(&output_handler);
 
@@ -235,8 +348,14 @@ impl TaskRunner {
                 let package_path = packages_dir.join(&package.path);
                 let task_name = task_name.to_string();
                 let tx_clone = tx.clone();
+                let permit = Arc::clone(&permits)
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| Error::MutexLock(format!("Failed to acquire permit: {}", e)))?;
 
                 let handle = tokio::spawn(async move {
+                    let _permit = permit;
+                    let start = Instant::now();
                     let streaming_task =
                         match StreamingTask::spawn(&package, &task_name, &package_path).await {
                             Ok(task) => task,
@@ -288,6 +407,8 @@ impl TaskRunner {
                         success,
                         stdout: stdout_result,
                         stderr: stderr_result,
+                        duration: start.elapsed(),
+                        cached: false,
                     })
                 });
                 handles.push(handle);
@@ -330,4 +451,34 @@ pub struct TaskResult {
     pub stdout: String,
     /// Standard error from the task.
     pub stderr: String,
+    /// Wall time of the task, including `depends_on` tasks run before it in
+    /// the same package. Restored cache hits take the lookup time.
+    pub duration: Duration,
+    /// Whether the result was restored from the local or remote cache
+    /// instead of running the task.
+    pub cached: bool,
+}
+
+fn worker_pool(threads: usize) -> rayon::ThreadPool {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("polykit-worker-{}", i))
+        .build()
+        .unwrap_or_else(|_| rayon::ThreadPoolBuilder::new().build().unwrap())
+}
+
+/// Runs a future to completion from synchronous code.
+///
+/// Returns `None` when called from a current-thread runtime, which cannot be
+/// blocked without deadlocking.
+fn block_on<F: Future>(future: F) -> Option<F::Output> {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => match handle.runtime_flavor() {
+            tokio::runtime::RuntimeFlavor::CurrentThread => None,
+            _ => Some(tokio::task::block_in_place(|| handle.block_on(future))),
+        },
+        Err(_) => tokio::runtime::Runtime::new()
+            .ok()
+            .map(|runtime| runtime.block_on(future)),
+    }
 }
@@ -9,11 +9,17 @@ use jwalk::WalkDir as JWalkDir;
 use memmap2::Mmap;
 use std::fs::File;
 
+use crate::adapter::LanguageAdapter;
 use crate::cache::Cache;
 use crate::config::{Config, WorkspaceConfig};
 use crate::error::Result;
-use crate::package::Package;
+use crate::graph::DependencyGraph;
+use crate::inference::InferredPackage;
+use crate::native_deps::NativeDependencies;
+use crate::package::{Language, Package};
+use crate::path_utils::GlobalInputs;
 use crate::simd_utils;
+use crate::workspace::WorkspaceMembers;
 
 fn get_default_cache_dir() -> std::path::PathBuf {
     dirs::cache_dir()
@@ -29,18 +35,81 @@ pub struct Scanner {
     packages_dir: PathBuf,
     cache: Option<Cache>,
     workspace_config: Option<WorkspaceConfig>,
+    /// Why the workspace `polykit.toml` could not be loaded.
+    workspace_error: Option<InvalidWorkspace>,
+    adapter_getter: Option<Arc<AdapterGetter>>,
 }
 
+/// A workspace `polykit.toml` with an invalid section.
+#[derive(Debug, Clone)]
+struct InvalidWorkspace {
+    error: toml::de::Error,
+    path: PathBuf,
+}
+
+impl InvalidWorkspace {
+    fn to_error(&self) -> crate::Error {
+        crate::Error::Toml {
+            error: self.error.clone(),
+            context: self.path.display().to_string(),
+        }
+    }
+}
+
+/// Top-level tables of the workspace `polykit.toml` that are read with serde.
+#[derive(Debug, Default, serde::Deserialize)]
+struct WorkspaceSections {
+    #[serde(default)]
+    remote_cache: Option<crate::remote_cache::RemoteCacheConfig>,
+    #[serde(default)]
+    constraints: crate::config::Constraints,
+    #[serde(default)]
+    workspace: WorkspaceSection,
+}
+
+/// Keys of the `[workspace]` table that are read with serde.
+#[derive(Debug, Default, serde::Deserialize)]
+struct WorkspaceSection {
+    #[serde(default)]
+    global_inputs: Vec<crate::config::GlobalInput>,
+}
+
+type AdapterGetter = dyn Fn(&Language) -> Box<dyn LanguageAdapter> + Send + Sync;
+
 impl Scanner {
-    fn load_workspace_config(packages_dir: &Path) -> Option<WorkspaceConfig> {
-        let mut current_dir = packages_dir.parent()?;
+    /// Finds and reads the workspace `polykit.toml`.
+    ///
+    /// Returns an error if a section read with serde, such as `[remote_cache]`,
+    /// `[constraints]` or `global_inputs`, is invalid: ignoring it would
+    /// silently drop settings like signature enforcement, module-boundary
+    /// rules or the files that mark every package affected.
+    fn load_workspace_config(
+        packages_dir: &Path,
+    ) -> std::result::Result<Option<WorkspaceConfig>, InvalidWorkspace> {
+        let Some(mut current_dir) = packages_dir.parent() else {
+            return Ok(None);
+        };
 
         loop {
             let workspace_toml = current_dir.join("polykit.toml");
             if workspace_toml.exists() {
-                let content = std::fs::read_to_string(&workspace_toml).ok()?;
-                let mut table: toml::Value = toml::from_str(&content).ok()?;
-                let workspace_table = table.get_mut("workspace")?.as_table_mut()?;
+                let Ok(content) = std::fs::read_to_string(&workspace_toml) else {
+                    return Ok(None);
+                };
+                let sections: WorkspaceSections = toml::from_str(&content)
+                    .map_err(|error| InvalidWorkspace {
+                        error,
+                        path: workspace_toml.clone(),
+                    })?;
+                let Ok(mut table) = toml::from_str::<toml::Value>(&content) else {
+                    return Ok(None);
+                };
+                let Some(workspace_table) = table
+                    .get_mut("workspace")
+                    .and_then(|workspace| workspace.as_table_mut())
+                else {
+                    return Ok(None);
+                };
 
                 let mut config = WorkspaceConfig {
                     cache_dir: workspace_table
@@ -53,14 +122,19 @@ impl Scanner {
                         .map(|i| i as usize),
                     workspace_config_path: Some(workspace_toml),
                     tasks: FxHashMap::default(),
-                    remote_cache: None,
+                    remote_cache: sections.remote_cache,
+                    members: string_array(workspace_table.get("members")),
+                    exclude: string_array(workspace_table.get("exclude")),
+                    infer: workspace_table.get("infer").and_then(|v| v.as_bool()),
+                    global_inputs: sections.workspace.global_inputs,
+                    constraints: sections.constraints,
                 };
 
                 if let Some(tasks_table) = workspace_table.get("tasks").and_then(|v| v.as_table()) {
                     config.tasks = crate::config::parse_tasks_from_toml_map(tasks_table);
                 }
 
-                return Some(config);
+                return Ok(Some(config));
             }
 
             if current_dir.join(".git").exists() {
@@ -78,22 +152,33 @@ impl Scanner {
             }
         }
 
-        None
+        Ok(None)
+    }
+
+    /// Loads the workspace configuration, keeping the error for
+    /// [`Scanner::workspace_config`] and [`Scanner::scan`] to report.
+    fn load_workspace(packages_dir: &Path) -> (Option<WorkspaceConfig>, Option<InvalidWorkspace>) {
+        match Self::load_workspace_config(packages_dir) {
+            Ok(workspace_config) => (workspace_config, None),
+            Err(invalid) => (None, Some(invalid)),
+        }
     }
 
     pub fn new(packages_dir: impl AsRef<Path>) -> Self {
         let packages_dir = packages_dir.as_ref().to_path_buf();
-        let workspace_config = Self::load_workspace_config(&packages_dir);
+        let (workspace_config, workspace_error) = Self::load_workspace(&packages_dir);
         Self {
             packages_dir,
             cache: None,
             workspace_config,
+            workspace_error,
+            adapter_getter: None,
         }
     }
 
     pub fn with_default_cache(packages_dir: impl AsRef<Path>) -> Self {
         let packages_dir = packages_dir.as_ref().to_path_buf();
-        let workspace_config = Self::load_workspace_config(&packages_dir);
+        let (workspace_config, workspace_error) = Self::load_workspace(&packages_dir);
         let cache_dir = workspace_config
             .as_ref()
             .and_then(|wc| {
@@ -115,28 +200,144 @@ impl Scanner {
             packages_dir,
             cache: Some(Cache::new(cache_dir)),
             workspace_config,
+            workspace_error,
+            adapter_getter: None,
         }
     }
 
     pub fn with_cache(packages_dir: impl AsRef<Path>, cache_dir: impl AsRef<Path>) -> Self {
         let packages_dir = packages_dir.as_ref().to_path_buf();
-        let workspace_config = Self::load_workspace_config(&packages_dir);
+        let (workspace_config, workspace_error) = Self::load_workspace(&packages_dir);
         Self {
             packages_dir,
             cache: Some(Cache::new(cache_dir)),
             workspace_config,
+            workspace_error,
+            adapter_getter: None,
         }
     }
 
-    pub fn workspace_config(&self) -> Option<&WorkspaceConfig> {
-        self.workspace_config.as_ref()
+    /// Enables inferring packages from native workspace manifests.
+    ///
+    /// Inference runs when `[workspace] infer = true`, or when neither
+    /// `members` nor the packages directory exists. A `polykit.toml` in an
+    /// inferred package overrides the values read from its native manifest.
+    ///
+    /// The `adapter_getter` function is used to obtain language adapters for
+    /// reading package metadata.
+    pub fn with_inference<F>(mut self, adapter_getter: F) -> Self
+    where
+        F: Fn(&Language) -> Box<dyn LanguageAdapter> + Send + Sync + 'static,
+    {
+        self.adapter_getter = Some(Arc::new(adapter_getter));
+        self
+    }
+
+    /// Returns whether packages are inferred from native manifests.
+    pub fn infers_packages(&self) -> bool {
+        if self.adapter_getter.is_none() {
+            return false;
+        }
+        match self.workspace_config {
+            Some(ref wc) => match wc.infer {
+                Some(infer) => infer,
+                None => wc.members.is_empty() && !self.packages_dir.is_dir(),
+            },
+            None => !self.packages_dir.is_dir(),
+        }
+    }
+
+    /// Returns the workspace configuration, if there is a workspace
+    /// `polykit.toml`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a section of it, such as `[remote_cache]`, is
+    /// invalid.
+    pub fn workspace_config(&self) -> Result<Option<&WorkspaceConfig>> {
+        match self.workspace_error {
+            Some(ref invalid) => Err(invalid.to_error()),
+            None => Ok(self.workspace_config.as_ref()),
+        }
     }
 
     pub fn cache_stats(&self) -> Option<&crate::cache::CacheStats> {
         self.cache.as_ref().map(|c| c.stats())
     }
 
+    /// Returns the directory package paths are relative to.
+    ///
+    /// This is the workspace root when `[workspace] members` is configured,
+    /// otherwise the packages directory.
+    pub fn packages_root(&self) -> PathBuf {
+        if self.infers_packages() {
+            return self.workspace_root();
+        }
+        match self.workspace_config {
+            Some(ref wc) if !wc.members.is_empty() => workspace_root(wc),
+            _ => self.packages_dir.clone(),
+        }
+    }
+
+    /// Returns the directory holding the workspace `polykit.toml`, or the
+    /// parent of the packages directory if there is none.
+    pub fn workspace_root(&self) -> PathBuf {
+        match self.workspace_config {
+            Some(ref wc) => workspace_root(wc),
+            None => self
+                .packages_dir
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(|dir| dir.to_path_buf())
+                .unwrap_or_else(|| PathBuf::from(".")),
+        }
+    }
+
+    /// Returns the compiled `[workspace] members` globs, if configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a member or exclude glob is invalid.
+    pub fn workspace_members(&self) -> Result<Option<WorkspaceMembers>> {
+        match self.workspace_config {
+            Some(ref wc) if !wc.members.is_empty() => Ok(Some(WorkspaceMembers::new(
+                workspace_root(wc),
+                &wc.members,
+                &wc.exclude,
+            )?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolves `[workspace] global_inputs` against `graph`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a glob or filter is invalid.
+    pub fn global_inputs(&self, graph: &DependencyGraph) -> Result<GlobalInputs> {
+        match self.workspace_config {
+            Some(ref wc) if !wc.global_inputs.is_empty() => GlobalInputs::new(
+                workspace_root(wc),
+                &wc.global_inputs,
+                graph,
+                &self.packages_root(),
+            ),
+            _ => Ok(GlobalInputs::default()),
+        }
+    }
+
     pub fn scan(&mut self) -> Result<Vec<Package>> {
+        if let Some(ref invalid) = self.workspace_error {
+            return Err(invalid.to_error());
+        }
+        if self.infers_packages() {
+            return self.scan_inferred();
+        }
+
+        if let Some(members) = self.workspace_members()? {
+            return self.scan_members(&members);
+        }
+
         if let Some(ref mut cache) = self.cache {
             if let Some(cached) = cache.load(&self.packages_dir)? {
                 return Ok(cached);
@@ -152,11 +353,83 @@ impl Scanner {
         Ok(packages)
     }
 
+    fn scan_members(&mut self, members: &WorkspaceMembers) -> Result<Vec<Package>> {
+        let config_files = members.find_config_files();
+
+        if let Some(ref mut cache) = self.cache {
+            if let Some(cached) = cache.load_members(members, &config_files)? {
+                return Ok(cached);
+            }
+        }
+
+        let packages = self.parse_packages(members.root(), config_files.clone())?;
+
+        if let Some(ref mut cache) = self.cache {
+            cache.save_members(members, &config_files, &packages)?;
+        }
+
+        Ok(packages)
+    }
+
+    /// Scans native workspace manifests.
+    ///
+    /// Internal dependencies come from the native manifests unless the
+    /// package's `polykit.toml` declares `[deps]`. Inferred scans are not
+    /// cached because native manifests change independently of `polykit.toml`.
+    fn scan_inferred(&self) -> Result<Vec<Package>> {
+        let root = self.workspace_root();
+        let Some(ref adapter_getter) = self.adapter_getter else {
+            return Ok(Vec::new());
+        };
+        let workspace_tasks = self
+            .workspace_config
+            .as_ref()
+            .map(|wc| wc.to_tasks())
+            .unwrap_or_default();
+        for task in &workspace_tasks {
+            crate::command_validator::CommandValidator::validate_identifier(
+                &task.name,
+                "Workspace task name",
+            )?;
+        }
+
+        let dirs: Vec<(PathBuf, Language)> =
+            crate::inference::discover_package_dirs(&root)?.into_iter().collect();
+        let inferred: Result<Vec<Option<InferredPackage>>> = dirs
+            .into_par_iter()
+            .map(|(relative_path, language)| {
+                let adapter = adapter_getter(&language);
+                crate::inference::infer_package(
+                    &root,
+                    &relative_path,
+                    language,
+                    adapter.as_ref(),
+                    &workspace_tasks,
+                )
+            })
+            .collect();
+
+        let inferred: Vec<InferredPackage> = inferred?.into_iter().flatten().collect();
+        let mut packages: Vec<Package> = inferred.iter().map(|i| i.package.clone()).collect();
+
+        // Build the graph from the manifests unless the override declares deps
+        let native_deps =
+            NativeDependencies::read(&root, &packages, |language| adapter_getter(language))?;
+        for (package, inferred) in packages.iter_mut().zip(&inferred) {
+            if inferred.declares_deps {
+                continue;
+            }
+            if let Some(deps) = native_deps.get(&package.name) {
+                package.deps = deps.iter().cloned().collect();
+            }
+        }
+
+        packages.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(packages)
+    }
+
     #[inline]
     fn scan_internal(&self) -> Result<Vec<Package>> {
-        let workspace_config = Arc::new(self.workspace_config.clone());
-        let packages_dir = Arc::new(self.packages_dir.clone());
-
         let config_files: Vec<PathBuf> = JWalkDir::new(&self.packages_dir)
             .max_depth(2)
             .follow_links(false)
@@ -176,6 +449,13 @@ impl Scanner {
             })
             .collect();
 
+        self.parse_packages(&self.packages_dir, config_files)
+    }
+
+    fn parse_packages(&self, base_dir: &Path, config_files: Vec<PathBuf>) -> Result<Vec<Package>> {
+        let workspace_config = Arc::new(self.workspace_config.clone());
+        let packages_dir = Arc::new(base_dir.to_path_buf());
+
         let packages: Result<Vec<Package>> = config_files
             .into_par_iter()
             .map(|config_path| {
@@ -225,14 +505,22 @@ impl Scanner {
                     }
                 }
 
-                Ok(Package::new(
+                for tag in &config.tags {
+                    crate::command_validator::CommandValidator::validate_tag(tag)?;
+                }
+                crate::path_utils::validate_affected_ignore(&config.name, &config.affected_ignore)?;
+
+                let mut package = Package::new(
                     config.name,
                     language,
                     config.public,
                     relative_path,
                     config.deps.internal,
                     package_tasks,
-                ))
+                );
+                package.tags = config.tags;
+                package.affected_ignore = config.affected_ignore;
+                Ok(package)
             })
             .collect();
 
@@ -279,6 +567,84 @@ impl Scanner {
         let change = detect_graph_changes(old_packages, &new_packages);
         Ok((new_packages.values().cloned().collect(), change))
     }
+
+    /// Scans packages and builds their dependency graph.
+    ///
+    /// With a cache, the graph is saved next to the scan cache, keyed by the
+    /// mtimes the scan cache recorded. The next call restores the saved graph
+    /// and applies only the packages that changed since, instead of sorting
+    /// the whole graph again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if scanning fails, the packages do not form a valid
+    /// graph, or the snapshot cannot be written.
+    pub fn scan_graph(&mut self) -> Result<DependencyGraph> {
+        let cache_id = self.cache_id()?;
+        let (Some(cache), Some(cache_id)) = (&self.cache, cache_id) else {
+            return DependencyGraph::new(self.scan()?);
+        };
+        let snapshot_path = cache.graph_path(&cache_id);
+
+        // An unreadable snapshot, such as one from another version, is rebuilt.
+        let (graph, saved) = match DependencyGraph::load_snapshot(&snapshot_path) {
+            Ok((mut graph, fingerprint)) => {
+                let old_packages: FxHashMap<String, Package> = graph
+                    .all_packages()
+                    .into_iter()
+                    .map(|package| (package.name.clone(), package.clone()))
+                    .collect();
+                let (packages, changes) = self.scan_with_changes(&old_packages)?;
+                if !changes.is_empty() && graph.update_incremental(changes).is_err() {
+                    graph = DependencyGraph::new(packages)?;
+                }
+                (graph, Some(fingerprint))
+            }
+            Err(_) => (DependencyGraph::new(self.scan()?)?, None),
+        };
+
+        if let Some(ref cache) = self.cache {
+            let fingerprint = cache.fingerprint(&cache_id)?.unwrap_or_default();
+            if saved != Some(fingerprint) {
+                graph.save_snapshot(&snapshot_path, fingerprint)?;
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Returns the id the scan cache stores this scanner's packages under,
+    /// or `None` for inferred scans, which are not cached.
+    fn cache_id(&self) -> Result<Option<PathBuf>> {
+        if self.infers_packages() {
+            return Ok(None);
+        }
+        Ok(Some(match self.workspace_members()? {
+            Some(members) => crate::cache::members_cache_id(&members),
+            None => self.packages_dir.clone(),
+        }))
+    }
+}
+
+fn workspace_root(config: &WorkspaceConfig) -> PathBuf {
+    config
+        .workspace_config_path
+        .as_ref()
+        .and_then(|path| path.parent())
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn string_array(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 /// Detects changes between old and new package sets.
@@ -296,7 +662,7 @@ pub fn detect_graph_changes(
     for (name, new_pkg) in new_packages {
         match old_packages.get(name) {
             Some(old_pkg) => {
-                if old_pkg.deps != new_pkg.deps || old_pkg.tasks != new_pkg.tasks {
+                if old_pkg != new_pkg {
                     change.modified.push(new_pkg.clone());
                     if old_pkg.deps != new_pkg.deps {
                         change.dependency_changes.push((
@@ -305,7 +305,7 @@ unsafe fn find_byte_simd_x86(haystack: &[u8], needle: u8) -> Option<usize> {
         offset += 16;
     }
 
-    #[allow(clippy::needless_range_loop)]
+    #[allow(clippy::needless_range_loop, clippy::manual_find)]
     for i in offset..len {
         if haystack[i] == needle {
             return Some(i);
@@ -2,6 +2,7 @@
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use bincode;
 use serde::{Deserialize, Serialize};
@@ -127,6 +128,8 @@ impl TaskCache {
             success: entry.success,
             stdout: entry.stdout,
             stderr: entry.stderr,
+            duration: Duration::ZERO,
+            cached: true,
         }))
     }
 
@@ -0,0 +1,606 @@
+//! Workspace checks behind `polykit validate`.
+//!
+//! | Code | Severity | Problem |
+//! |------|----------|---------|
+//! | `duplicate-package` | error | Two directories declare the same package name |
+//! | `unknown-dependency` | error | A dependency on a package that does not exist |
+//! | `dependency-cycle` | error | Packages that depend on each other |
+//! | `constraint-violation` | error | A dependency that breaks a `[constraints]` rule |
+//! | `unknown-task` | error | A `depends_on` entry naming a task the package lacks |
+//! | `task-cycle` | error | Tasks that wait on each other, within or across packages |
+//! | `language-mismatch` | error | The directory holds another language's manifest |
+//! | `invalid-manifest` | error | The native manifest cannot be read by its adapter |
+//! | `missing-manifest` | warning | The directory has no manifest for its language |
+//! | `missing-version` | warning | A public package whose manifest has no version |
+//! | `invalid-version` | warning | A version that is not semver |
+//! | `shadowed-task` | warning | A package task that replaces a different workspace task |
+//! | `dependency-drift` | warning | `[deps] internal` differs from the native manifest |
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use petgraph::graph::{DiGraph, NodeIndex};
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::adapter::LanguageAdapter;
+use crate::config::WorkspaceConfig;
+use crate::diagnostics::{cycle_paths, diagnose, suggest, Diagnostic};
+use crate::graph::DependencyGraph;
+use crate::native_deps::NativeDependencies;
+use crate::package::{Language, Package};
+
+/// How serious an [`Issue`] is. Errors fail validation; warnings do not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    /// A stable, kebab-case identifier such as `task-cycle`.
+    pub code: &'static str,
+    pub package: Option<String>,
+    /// The file to fix: the package's `polykit.toml` or native manifest.
+    pub file: Option<PathBuf>,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+impl Issue {
+    fn error(code: &'static str, package: &Package, root: &Path, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            package: Some(package.name.clone()),
+            file: Some(config_file(root, package)),
+            message,
+        }
+    }
+
+    fn warning(code: &'static str, package: &Package, file: PathBuf, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            code,
+            package: Some(package.name.clone()),
+            file: Some(file),
+            message,
+        }
+    }
+}
+
+/// Runs every workspace check.
+///
+/// Issues are returned errors first, each group in check order. Constraints
+/// are only checked once the dependency graph itself is valid.
+///
+/// # Arguments
+///
+/// * `root` - Directory the package paths are relative to
+/// * `packages` - Scanned packages
+/// * `workspace` - The workspace configuration, if any
+/// * `adapter_getter` - Returns the adapter for a language
+pub fn validate<F>(
+    root: &Path,
+    packages: &[Package],
+    workspace: Option<&WorkspaceConfig>,
+    adapter_getter: F,
+) -> Vec<Issue>
+where
+    F: Fn(&Language) -> Box<dyn LanguageAdapter> + Sync,
+{
+    let by_name = package_map(packages);
+    let mut issues = duplicate_packages(root, packages);
+    let graph_issues = graph_issues(root, packages, &by_name);
+
+    if issues.is_empty() && graph_issues.is_empty() {
+        if let Some(workspace) = workspace.filter(|wc| !wc.constraints.is_empty()) {
+            if let Ok(graph) = DependencyGraph::new(packages.to_vec()) {
+                issues.extend(
+                    workspace
+                        .constraints
+                        .check(&graph)
+                        .into_iter()
+                        .map(|violation| {
+                            let package = by_name[violation.package.as_str()];
+                            Issue::error(
+                                "constraint-violation",
+                                package,
+                                root,
+                                violation.to_string(),
+                            )
+                        }),
+                );
+            }
+        }
+    }
+    issues.extend(graph_issues);
+    issues.extend(task_issues(root, packages, &by_name));
+    if let Some(workspace) = workspace {
+        issues.extend(shadowed_tasks(root, packages, workspace));
+    }
+    issues.extend(manifest_issues(root, packages, &adapter_getter));
+    if let Ok(native) = NativeDependencies::read(root, packages, &adapter_getter) {
+        issues.extend(native.drift(packages).into_iter().map(|drift| {
+            let package = by_name[drift.package.as_str()];
+            let mut changes = Vec::new();
+            if !drift.missing.is_empty() {
+                changes.push(format!(
+                    "missing {} (in manifest, not in [deps] internal)",
+                    drift.missing.join(", ")
+                ));
+            }
+            if !drift.stale.is_empty() {
+                changes.push(format!(
+                    "stale {} (in [deps] internal, not in manifest)",
+                    drift.stale.join(", ")
+                ));
+            }
+            Issue::warning(
+                "dependency-drift",
+                package,
+                config_file(root, package),
+                format!(
+                    "Declared dependencies differ from the native manifest: {}",
+                    changes.join("; ")
+                ),
+            )
+        }));
+    }
+
+    issues.sort_by_key(|issue| issue.severity);
+    issues
+}
+
+/// Maps names to packages, keeping the first package of a duplicated name.
+fn package_map(packages: &[Package]) -> FxHashMap<&str, &Package> {
+    let mut by_name = FxHashMap::default();
+    for package in packages {
+        by_name.entry(package.name.as_str()).or_insert(package);
+    }
+    by_name
+}
+
+/// Returns the package's `polykit.toml`, or its native manifest if it was
+/// inferred without one.
+fn config_file(root: &Path, package: &Package) -> PathBuf {
+    let dir = root.join(&package.path);
+    let config = dir.join("polykit.toml");
+    if config.exists() {
+        config
+    } else {
+        dir.join(package.language.manifest())
+    }
+}
+
+fn duplicate_packages(root: &Path, packages: &[Package]) -> Vec<Issue> {
+    let mut paths: FxHashMap<&str, Vec<&Path>> = FxHashMap::default();
+    for package in packages {
+        paths
+            .entry(package.name.as_str())
+            .or_default()
+            .push(&package.path);
+    }
+
+    packages
+        .iter()
+        .filter_map(|package| {
+            let others: Vec<String> = paths[package.name.as_str()]
+                .iter()
+                .filter(|path| **path != package.path)
+                .map(|path| path.display().to_string())
+                .collect();
+            (!others.is_empty()).then(|| {
+                Issue::error(
+                    "duplicate-package",
+                    package,
+                    root,
+                    format!(
+                        "Package '{}' is also declared in {}",
+                        package.name,
+                        others.join(", ")
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+fn graph_issues(
+    root: &Path,
+    packages: &[Package],
+    by_name: &FxHashMap<&str, &Package>,
+) -> Vec<Issue> {
+    diagnose(packages)
+        .into_iter()
+        .map(|diagnostic| {
+            let (code, package) = match diagnostic {
+                Diagnostic::Cycle { ref path } => ("dependency-cycle", path[0].as_str()),
+                Diagnostic::UnknownDependency { ref package, .. } => {
+                    ("unknown-dependency", package.as_str())
+                }
+            };
+            Issue::error(code, by_name[package], root, diagnostic.to_string())
+        })
+        .collect()
+}
+
+/// Checks `depends_on` entries and cycles in the task graph.
+///
+/// A task waits for its `depends_on` tasks in the same package and for the
+/// task of the same name in each package it depends on.
+fn task_issues(
+    root: &Path,
+    packages: &[Package],
+    by_name: &FxHashMap<&str, &Package>,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut graph: DiGraph<(&str, &str), ()> = DiGraph::new();
+    let mut nodes: FxHashMap<(&str, &str), NodeIndex> = FxHashMap::default();
+    for package in by_name.values() {
+        for task in &package.tasks {
+            let key = (package.name.as_str(), task.name.as_str());
+            nodes.insert(key, graph.add_node(key));
+        }
+    }
+
+    for package in packages {
+        if by_name[package.name.as_str()].path != package.path {
+            continue;
+        }
+        for task in &package.tasks {
+            let from = nodes[&(package.name.as_str(), task.name.as_str())];
+            for dep in &task.depends_on {
+                match nodes.get(&(package.name.as_str(), dep.as_str())) {
+                    Some(&to) => {
+                        graph.add_edge(from, to, ());
+                    }
+                    None => {
+                        let tasks = package.tasks.iter().map(|t| t.name.as_str());
+                        let mut message =
+                            format!("Task '{}' depends on unknown task '{}'", task.name, dep);
+                        if let Some(suggestion) = suggest(dep, tasks) {
+                            message.push_str(&format!(" (did you mean '{}'?)", suggestion));
+                        }
+                        issues.push(Issue::error("unknown-task", package, root, message));
+                    }
+                }
+            }
+            for dep in &package.deps {
+                if let Some(&to) = nodes.get(&(dep.as_str(), task.name.as_str())) {
+                    graph.add_edge(from, to, ());
+                }
+            }
+        }
+    }
+
+    for path in cycle_paths(&graph, |node| {
+        format!("{}:{}", graph[node].0, graph[node].1)
+    }) {
+        let package = path[0].split(':').next().unwrap_or_default();
+        issues.push(Issue::error(
+            "task-cycle",
+            by_name[package],
+            root,
+            format!("Circular task dependency: {}", path.join(" -> ")),
+        ));
+    }
+    issues
+}
+
+/// Finds package tasks that replace a workspace task with a different one.
+fn shadowed_tasks(root: &Path, packages: &[Package], workspace: &WorkspaceConfig) -> Vec<Issue> {
+    let workspace_tasks = workspace.to_tasks();
+    let mut issues = Vec::new();
+    for package in packages {
+        for workspace_task in &workspace_tasks {
+            let Some(task) = package.get_task(&workspace_task.name) else {
+                continue;
+            };
+            if task != workspace_task {
+                issues.push(Issue::warning(
+                    "shadowed-task",
+                    package,
+                    config_file(root, package),
+                    format!(
+                        "Task '{}' overrides the workspace task '{}'",
+                        task.name, workspace_task.command
+                    ),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// Checks that each package has a readable manifest for its language.
+fn manifest_issues<F>(root: &Path, packages: &[Package], adapter_getter: &F) -> Vec<Issue>
+where
+    F: Fn(&Language) -> Box<dyn LanguageAdapter> + Sync,
+{
+    packages
+        .par_iter()
+        .flat_map_iter(|package| {
+            let adapter = adapter_getter(&package.language);
+            let dir = root.join(&package.path);
+            let manifest = dir.join(package.language.manifest());
+            if !adapter.detect(&dir) {
+                let other = [Language::Js, Language::Python, Language::Go, Language::Rust]
+                    .into_iter()
+                    .filter(|language| adapter_getter(language).language() != adapter.language())
+                    .find(|language| adapter_getter(language).detect(&dir));
+                let issue = match other {
+                    Some(other) => Issue::error(
+                        "language-mismatch",
+                        package,
+                        root,
+                        format!(
+                            "Package is declared as {} but has a {} and no {}",
+                            package.language.as_str(),
+                            other.manifest(),
+                            package.language.manifest()
+                        ),
+                    ),
+                    None => Issue::warning(
+                        "missing-manifest",
+                        package,
+                        config_file(root, package),
+                        format!(
+                            "No {} found for {} package",
+                            package.language.manifest(),
+                            package.language.as_str()
+                        ),
+                    ),
+                };
+                return vec![issue];
+            }
+
+            match adapter.read_metadata(&dir) {
+                Err(e) => vec![Issue {
+                    severity: Severity::Error,
+                    code: "invalid-manifest",
+                    package: Some(package.name.clone()),
+                    file: Some(manifest),
+                    message: e.to_string(),
+                }],
+                Ok(metadata) => match metadata.version {
+                    None if package.public => vec![Issue::warning(
+                        "missing-version",
+                        package,
+                        manifest,
+                        "Public package has no version".to_string(),
+                    )],
+                    Some(version)
+                        if semver::Version::parse(version.trim_start_matches('v')).is_err() =>
+                    {
+                        vec![Issue::warning(
+                            "invalid-version",
+                            package,
+                            manifest,
+                            format!("Version '{}' is not semver", version),
+                        )]
+                    }
+                    _ => Vec::new(),
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::LangMetadata;
+    use crate::config::{ConstraintRule, Constraints};
+    use crate::error::Result;
+    use crate::package::Task;
+
+    /// Reads the version from a `Cargo.toml` holding only `version = "..."`.
+    struct CargoAdapter;
+
+    impl LanguageAdapter for CargoAdapter {
+        fn language(&self) -> &'static str {
+            "rust"
+        }
+
+        fn detect(&self, path: &Path) -> bool {
+            path.join("Cargo.toml").exists()
+        }
+
+        fn read_metadata(&self, path: &Path) -> Result<LangMetadata> {
+            let content = std::fs::read_to_string(path.join("Cargo.toml"))?;
+            let version = content
+                .strip_prefix("version = ")
+                .map(|v| v.trim().trim_matches('"').to_string());
+            Ok(LangMetadata {
+                version,
+                ..Default::default()
+            })
+        }
+
+        fn bump_version(&self, _path: &Path, _new_version: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct JsonAdapter;
+
+    impl LanguageAdapter for JsonAdapter {
+        fn language(&self) -> &'static str {
+            "js"
+        }
+
+        fn detect(&self, path: &Path) -> bool {
+            path.join("package.json").exists()
+        }
+
+        fn read_metadata(&self, _path: &Path) -> Result<LangMetadata> {
+            Ok(Default::default())
+        }
+
+        fn bump_version(&self, _path: &Path, _new_version: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn adapter(language: &Language) -> Box<dyn LanguageAdapter> {
+        match language {
+            Language::Rust => Box::new(CargoAdapter),
+            _ => Box::new(JsonAdapter),
+        }
+    }
+
+    fn task(name: &str, depends_on: &[&str]) -> Task {
+        Task {
+            name: name.to_string(),
+            command: format!("echo {}", name),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    fn package(name: &str, path: &str, deps: &[&str], tasks: Vec<Task>) -> Package {
+        Package::new(
+            name.to_string(),
+            Language::Rust,
+            false,
+            PathBuf::from(path),
+            deps.iter().map(|d| d.to_string()).collect(),
+            tasks,
+        )
+    }
+
+    fn codes(issues: &[Issue], package: &str) -> Vec<&'static str> {
+        issues
+            .iter()
+            .filter(|issue| issue.package.as_deref() == Some(package))
+            .map(|issue| issue.code)
+            .collect()
+    }
+
+    #[test]
+    fn test_task_and_package_issues() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for dir in ["core", "api", "web", "copy"] {
+            std::fs::create_dir_all(temp_dir.path().join(dir)).unwrap();
+        }
+        std::fs::write(
+            temp_dir.path().join("core/Cargo.toml"),
+            "version = \"1.0.0\"",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("api/Cargo.toml"),
+            "version = \"latest\"",
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("web/package.json"), "{}").unwrap();
+
+        let mut core = package("core", "core", &[], vec![task("build", &["gen"])]);
+        core.public = true;
+        let packages = vec![
+            core,
+            package("api", "api", &["core"], vec![task("build", &["biuld"])]),
+            package(
+                "web",
+                "web",
+                &[],
+                vec![task("lint", &["test"]), task("test", &["lint"])],
+            ),
+            package("web", "copy", &[], vec![]),
+        ];
+
+        let issues = validate(temp_dir.path(), &packages, None, adapter);
+
+        assert_eq!(codes(&issues, "core"), ["unknown-task"]);
+        assert_eq!(
+            codes(&issues, "api"),
+            ["unknown-task", "invalid-version", "dependency-drift"]
+        );
+        assert_eq!(
+            codes(&issues, "web"),
+            [
+                "duplicate-package",
+                "duplicate-package",
+                "task-cycle",
+                "language-mismatch",
+                "missing-manifest",
+            ]
+        );
+        let api = issues
+            .iter()
+            .find(|i| i.package.as_deref() == Some("api"))
+            .unwrap();
+        assert_eq!(
+            api.to_string(),
+            "error[unknown-task]: Task 'build' depends on unknown task 'biuld' (did you mean 'build'?)"
+        );
+        assert_eq!(
+            api.file.as_deref(),
+            Some(temp_dir.path().join("api/Cargo.toml").as_path())
+        );
+        assert!(issues
+            .iter()
+            .any(|i| i.message == "Circular task dependency: web:lint -> web:test -> web:lint"));
+    }
+
+    #[test]
+    fn test_cross_package_task_cycle_and_workspace_checks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let packages = vec![
+            package("a", "a", &["b"], vec![task("build", &[])]),
+            package("b", "b", &["a"], vec![task("build", &[])]),
+        ];
+        let workspace = WorkspaceConfig {
+            tasks: [(
+                "build".to_string(),
+                crate::config::TaskValue::Simple("make".to_string()),
+            )]
+            .into_iter()
+            .collect(),
+            constraints: Constraints {
+                deny_public_to_private: false,
+                rules: vec![ConstraintRule {
+                    tag: "x".to_string(),
+                    allow: None,
+                    deny: Vec::new(),
+                }],
+            },
+            ..Default::default()
+        };
+
+        let issues = validate(temp_dir.path(), &packages, Some(&workspace), adapter);
+        let messages: Vec<String> = issues.iter().map(|i| i.message.clone()).collect();
+
+        assert!(messages.contains(&"Circular dependency: a -> b -> a".to_string()));
+        assert!(messages
+            .contains(&"Circular task dependency: a:build -> b:build -> a:build".to_string()));
+        assert_eq!(
+            issues.iter().filter(|i| i.code == "shadowed-task").count(),
+            2
+        );
+        assert!(issues.iter().all(|i| i.code != "constraint-violation"));
+        assert_eq!(
+            serde_json::to_value(&issues[0]).unwrap()["severity"],
+            "error"
+        );
+    }
+}
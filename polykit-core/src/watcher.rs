@@ -4,14 +4,18 @@ use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 use notify::Config as NotifyConfig;
-use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 
 use crate::error::{Error, Result};
-use crate::path_utils;
+use crate::path_utils::{self, GlobalInputs, PackagePaths};
 
 pub struct WatcherConfig {
     pub debounce_ms: u64,
     pub packages_dir: PathBuf,
+    /// Package directories used to map changed files to packages.
+    pub package_paths: PackagePaths,
+    /// Files outside the packages directory that affect packages.
+    pub global_inputs: GlobalInputs,
 }
 
 impl Default for WatcherConfig {
@@ -19,6 +23,8 @@ impl Default for WatcherConfig {
         Self {
             debounce_ms: 300,
             packages_dir: PathBuf::from("./packages"),
+            package_paths: PackagePaths::default(),
+            global_inputs: GlobalInputs::default(),
         }
     }
 }
@@ -65,6 +71,21 @@ impl FileWatcher {
                 package: "watcher".to_string(),
                 message: format!("Failed to watch directory: {}", e),
             })?;
+
+        for (dir, recursive) in self.config.global_inputs.watch_dirs() {
+            if !dir.is_dir() || (recursive && dir.starts_with(&self.config.packages_dir)) {
+                continue;
+            }
+            let mode = if recursive {
+                RecursiveMode::Recursive
+            } else {
+                RecursiveMode::NonRecursive
+            };
+            self.watcher.watch(&dir, mode).map_err(|e| Error::Adapter {
+                package: "watcher".to_string(),
+                message: format!("Failed to watch {}: {}", dir.display(), e),
+            })?;
+        }
         Ok(())
     }
 
@@ -99,31 +120,34 @@ impl FileWatcher {
     pub fn get_affected_packages(&self, event: &Event) -> HashSet<String> {
         let mut affected = HashSet::new();
 
-        match &event.kind {
-            EventKind::Any | EventKind::Other => {
-                for path in &event.paths {
-                    if let Some(package_name) =
-                        Self::file_to_package(path, &self.config.packages_dir)
-                    {
-                        affected.insert(package_name);
-                    }
-                }
-            }
-            _ => {
-                for path in &event.paths {
-                    if let Some(package_name) =
-                        Self::file_to_package(path, &self.config.packages_dir)
-                    {
-                        affected.insert(package_name);
-                    }
-                }
+        for path in &event.paths {
+            if let Some(package_name) = Self::file_to_package(path, &self.config) {
+                affected.insert(package_name);
             }
+            affected.extend(
+                self.config
+                    .global_inputs
+                    .packages_for(path)
+                    .into_iter()
+                    .map(|name| name.to_string()),
+            );
         }
 
         affected
     }
 
-    fn file_to_package(file_path: &Path, packages_dir: &Path) -> Option<String> {
-        path_utils::file_to_package(file_path, packages_dir)
+    /// Replaces the package directories after a rescan.
+    pub fn set_package_paths(&mut self, package_paths: PackagePaths) {
+        self.config.package_paths = package_paths;
+    }
+
+    /// Replaces the global inputs after a rescan, keeping the watched
+    /// directories.
+    pub fn set_global_inputs(&mut self, global_inputs: GlobalInputs) {
+        self.config.global_inputs = global_inputs;
+    }
+
+    fn file_to_package(file_path: &Path, config: &WatcherConfig) -> Option<String> {
+        path_utils::file_to_package(file_path, &config.packages_dir, &config.package_paths)
     }
 }
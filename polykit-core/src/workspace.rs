@@ -0,0 +1,259 @@
+//! Workspace member globs for package discovery.
+
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+
+use crate::error::{Error, Result};
+
+/// Directories never searched for packages.
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "venv",
+    "__pycache__",
+];
+
+/// A `members` glob and the directory walk it needs.
+struct MemberPattern {
+    matcher: GlobMatcher,
+    /// Longest literal prefix of the glob, where the walk starts.
+    base: PathBuf,
+    /// Maximum walk depth below `base`, `None` for `**` globs.
+    max_depth: Option<usize>,
+}
+
+/// Package directories selected by `[workspace] members` and `exclude`.
+///
+/// Globs are relative to the workspace root. `*` matches within one path
+/// component and `**` matches any number of components, so `libs/**` selects
+/// packages at any depth below `libs`.
+pub struct WorkspaceMembers {
+    root: PathBuf,
+    members: Vec<MemberPattern>,
+    exclude: GlobSet,
+    fingerprint: String,
+}
+
+impl WorkspaceMembers {
+    /// Compiles the member and exclude globs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a glob is invalid or not relative to the root.
+    pub fn new(root: impl Into<PathBuf>, members: &[String], exclude: &[String]) -> Result<Self> {
+        let fingerprint = format!("{}\0{}", members.join("\n"), exclude.join("\n"));
+        let members = members
+            .iter()
+            .map(|pattern| {
+                let (base, max_depth) = walk_plan(pattern)?;
+                Ok(MemberPattern {
+                    matcher: compile(pattern)?.compile_matcher(),
+                    base,
+                    max_depth,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut exclude_set = GlobSetBuilder::new();
+        for pattern in exclude {
+            walk_plan(pattern)?;
+            exclude_set.add(compile(pattern)?);
+        }
+        let exclude = exclude_set
+            .build()
+            .map_err(|e| workspace_error(e.to_string()))?;
+
+        Ok(Self {
+            root: root.into(),
+            members,
+            exclude,
+            fingerprint,
+        })
+    }
+
+    /// Returns the workspace root the globs are resolved from.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns a string identifying the member and exclude globs.
+    pub(crate) fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Checks whether a directory, relative to the root, is a member.
+    ///
+    /// Directories inside an excluded directory are excluded too.
+    pub fn is_member(&self, relative_dir: &Path) -> bool {
+        if relative_dir.as_os_str().is_empty() {
+            return false;
+        }
+        if self.is_excluded(relative_dir) {
+            return false;
+        }
+        self.members
+            .iter()
+            .any(|member| member.matcher.is_match(relative_dir))
+    }
+
+    fn is_excluded(&self, relative_dir: &Path) -> bool {
+        relative_dir
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| self.exclude.is_match(ancestor))
+    }
+
+    /// Finds the `polykit.toml` of every member package, sorted by path.
+    ///
+    /// Hidden directories, excluded directories and build output directories
+    /// such as `node_modules` and `target` are not searched.
+    pub fn find_config_files(&self) -> Vec<PathBuf> {
+        self.find_manifests("polykit.toml")
+    }
+
+    /// Finds `file_name` in every member directory, sorted by path.
+    ///
+    /// Used to resolve native workspaces, such as `package.json` files
+    /// matched by npm `workspaces` globs.
+    pub fn find_manifests(&self, file_name: &str) -> Vec<PathBuf> {
+        let mut manifests: Vec<PathBuf> = self
+            .members
+            .par_iter()
+            .flat_map_iter(|member| self.walk_member(member, file_name))
+            .collect();
+        manifests.sort();
+        manifests.dedup();
+        manifests
+    }
+
+    fn walk_member(&self, member: &MemberPattern, file_name: &str) -> Vec<PathBuf> {
+        let base = self.root.join(&member.base);
+        if !base.is_dir() {
+            return Vec::new();
+        }
+
+        let mut walker = walkdir::WalkDir::new(&base).follow_links(false);
+        if let Some(depth) = member.max_depth {
+            // One more level for the manifest inside the package directory
+            walker = walker.max_depth(depth + 1);
+        }
+
+        walker
+            .into_iter()
+            .filter_entry(|entry| {
+                if entry.depth() == 0 || !entry.file_type().is_dir() {
+                    return true;
+                }
+                let name = entry.file_name().to_string_lossy();
+                if name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()) {
+                    return false;
+                }
+                entry
+                    .path()
+                    .strip_prefix(&self.root)
+                    .map(|relative| !self.exclude.is_match(relative))
+                    .unwrap_or(false)
+            })
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file() && entry.file_name() == file_name)
+            .filter(|entry| {
+                entry
+                    .path()
+                    .parent()
+                    .and_then(|dir| dir.strip_prefix(&self.root).ok())
+                    .is_some_and(|relative| self.is_member(relative))
+            })
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+}
+
+fn compile(pattern: &str) -> Result<Glob> {
+    GlobBuilder::new(pattern.trim_end_matches('/'))
+        .literal_separator(true)
+        .build()
+        .map_err(|e| workspace_error(format!("Invalid glob '{}': {}", pattern, e)))
+}
+
+/// Splits a glob into its literal base directory and the walk depth below it.
+fn walk_plan(pattern: &str) -> Result<(PathBuf, Option<usize>)> {
+    let path = Path::new(pattern.trim_end_matches('/'));
+    if path.as_os_str().is_empty()
+        || !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(workspace_error(format!(
+            "Glob '{}' must be relative to the workspace root",
+            pattern
+        )));
+    }
+
+    let mut base = PathBuf::new();
+    let mut depth = 0;
+    let mut recursive = false;
+    for component in path.components() {
+        let part = component.as_os_str().to_string_lossy();
+        let is_glob = part.contains(['*', '?', '[', '{']);
+        if part == "**" {
+            recursive = true;
+        }
+        if is_glob || depth > 0 {
+            depth += 1;
+        } else {
+            base.push(component);
+        }
+    }
+
+    Ok((base, (!recursive).then_some(depth)))
+}
+
+fn workspace_error(message: String) -> Error {
+    Error::Adapter {
+        package: "workspace".to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_plan() {
+        assert_eq!(
+            walk_plan("apps/*").unwrap(),
+            (PathBuf::from("apps"), Some(1))
+        );
+        assert_eq!(walk_plan("libs/**").unwrap(), (PathBuf::from("libs"), None));
+        assert_eq!(
+            walk_plan("tools/cli").unwrap(),
+            (PathBuf::from("tools/cli"), Some(0))
+        );
+        assert_eq!(walk_plan("*/pkg").unwrap(), (PathBuf::new(), Some(2)));
+        assert!(walk_plan("../other/*").is_err());
+        assert!(walk_plan("/abs/*").is_err());
+    }
+
+    #[test]
+    fn test_is_member() {
+        let members = WorkspaceMembers::new(
+            "/repo",
+            &["apps/*".to_string(), "libs/**".to_string()],
+            &["libs/legacy".to_string()],
+        )
+        .unwrap();
+
+        assert!(members.is_member(Path::new("apps/web")));
+        assert!(!members.is_member(Path::new("apps/web/nested")));
+        assert!(members.is_member(Path::new("libs/ui/button")));
+        assert!(!members.is_member(Path::new("libs/legacy")));
+        assert!(!members.is_member(Path::new("libs/legacy/old")));
+        assert!(!members.is_member(Path::new("tools/cli")));
+        assert!(!members.is_member(Path::new("")));
+    }
+}
@@ -1,9 +1,13 @@
 use std::fs;
+use std::path::Path;
 use tempfile::TempDir;
 
-use polykit_core::change::ChangeDetector;
+use polykit_core::change::{ChangeDetector, GitRange};
+use polykit_core::config::GlobalInput;
 use polykit_core::graph::DependencyGraph;
 use polykit_core::package::{Language, Package};
+use polykit_core::path_utils::GlobalInputs;
+use polykit_core::PackageFilter;
 
 fn create_test_packages() -> Vec<Package> {
     vec![
@@ -63,3 +67,212 @@ fn test_detect_single_package() {
     assert_eq!(affected.len(), 1);
     assert!(affected.contains("pkg-b"));
 }
+
+#[test]
+fn test_detect_deepest_nested_package() {
+    let root = std::path::Path::new("/repo");
+    let packages = vec![
+        Package::new(
+            "ui".to_string(),
+            Language::Js,
+            true,
+            "libs/ui".into(),
+            vec![],
+            vec![],
+        ),
+        Package::new(
+            "button".to_string(),
+            Language::Js,
+            true,
+            "libs/ui/button".into(),
+            vec![],
+            vec![],
+        ),
+    ];
+    let graph = DependencyGraph::new(packages).unwrap();
+
+    let changed_files = vec![root.join("libs/ui/button/src/index.ts")];
+    let affected = ChangeDetector::detect_affected_packages(&graph, &changed_files, root).unwrap();
+
+    assert_eq!(affected.len(), 1);
+    assert!(affected.contains("button"));
+}
+
+fn commit_all(repo: &git2::Repository, message: &str) -> git2::Oid {
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("test", "test@example.com").unwrap();
+    let parents: Vec<git2::Commit> = repo
+        .head()
+        .ok()
+        .and_then(|head| head.peel_to_commit().ok())
+        .into_iter()
+        .collect();
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+        .unwrap()
+}
+
+fn sorted(mut files: Vec<std::path::PathBuf>) -> Vec<String> {
+    files.sort();
+    files
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[test]
+fn test_git_range_uncommitted_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = git2::Repository::init(temp_dir.path()).unwrap();
+    let root = temp_dir.path();
+    for name in ["pkg-a", "pkg-b", "pkg-c"] {
+        fs::create_dir_all(root.join(name)).unwrap();
+        fs::write(root.join(name).join("lib.rs"), "// v1\n").unwrap();
+    }
+    commit_all(&repo, "initial");
+
+    // Staged, unstaged and untracked changes.
+    fs::write(root.join("pkg-a/lib.rs"), "// v2\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(std::path::Path::new("pkg-a/lib.rs")).unwrap();
+    index.write().unwrap();
+    fs::write(root.join("pkg-b/lib.rs"), "// v2\n").unwrap();
+    fs::create_dir_all(root.join("pkg-c/src")).unwrap();
+    fs::write(root.join("pkg-c/src/new.rs"), "").unwrap();
+
+    let committed = GitRange::new("HEAD").changed_files(&repo).unwrap();
+    assert!(committed.is_empty());
+
+    let uncommitted = GitRange::new("HEAD")
+        .with_uncommitted(true)
+        .changed_files(&repo)
+        .unwrap();
+    assert_eq!(
+        sorted(uncommitted),
+        ["pkg-a/lib.rs", "pkg-b/lib.rs", "pkg-c/src/new.rs"]
+    );
+}
+
+#[test]
+fn test_git_range_with_head() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = git2::Repository::init(temp_dir.path()).unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("pkg-a")).unwrap();
+    fs::create_dir_all(root.join("pkg-b")).unwrap();
+    fs::write(root.join("pkg-a/lib.rs"), "// v1\n").unwrap();
+    let first = commit_all(&repo, "first").to_string();
+    fs::write(root.join("pkg-a/lib.rs"), "// v2\n").unwrap();
+    let second = commit_all(&repo, "second").to_string();
+    fs::write(root.join("pkg-b/lib.rs"), "// v1\n").unwrap();
+    commit_all(&repo, "third");
+
+    let range = GitRange::new(first.as_str()).with_head(second.as_str());
+    assert_eq!(sorted(range.changed_files(&repo).unwrap()), ["pkg-a/lib.rs"]);
+
+    let to_head = GitRange::new(first.as_str());
+    assert_eq!(
+        sorted(to_head.changed_files(&repo).unwrap()),
+        ["pkg-a/lib.rs", "pkg-b/lib.rs"]
+    );
+}
+
+/// Builds `main: c0 - m1` and `feature: c0 - f1`, with HEAD on `feature`.
+fn diverged_repo(root: &std::path::Path) -> git2::Repository {
+    let repo = git2::Repository::init(root).unwrap();
+    for name in ["pkg-a", "pkg-b"] {
+        fs::create_dir_all(root.join(name)).unwrap();
+        fs::write(root.join(name).join("lib.rs"), "// v1\n").unwrap();
+    }
+    let c0 = commit_all(&repo, "c0");
+    for branch in ["main", "feature"] {
+        repo.branch(branch, &repo.find_commit(c0).unwrap(), true)
+            .unwrap();
+    }
+
+    repo.set_head("refs/heads/main").unwrap();
+    fs::write(root.join("pkg-b/lib.rs"), "// main\n").unwrap();
+    commit_all(&repo, "m1");
+
+    repo.set_head("refs/heads/feature").unwrap();
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+        .unwrap();
+    fs::write(root.join("pkg-a/lib.rs"), "// feature\n").unwrap();
+    commit_all(&repo, "f1");
+    repo
+}
+
+#[test]
+fn test_git_range_uses_merge_base() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = diverged_repo(temp_dir.path());
+
+    let since_branch = GitRange::new("main").changed_files(&repo).unwrap();
+    assert_eq!(sorted(since_branch), ["pkg-a/lib.rs"]);
+
+    let exact = GitRange::new("main")
+        .with_exact_base(true)
+        .changed_files(&repo)
+        .unwrap();
+    assert_eq!(sorted(exact), ["pkg-a/lib.rs", "pkg-b/lib.rs"]);
+}
+
+#[test]
+fn test_changed_selector_includes_global_inputs() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let repo = diverged_repo(root);
+    fs::create_dir_all(root.join("config")).unwrap();
+    fs::write(root.join("config/app.json"), "{}\n").unwrap();
+    commit_all(&repo, "f2");
+
+    // `[ref]` selectors open the repository from the environment; no other
+    // test in this file does.
+    std::env::set_var("GIT_DIR", repo.path());
+
+    let graph = DependencyGraph::new(create_test_packages()).unwrap();
+    let inputs = [GlobalInput::Filtered {
+        glob: "config/**".to_string(),
+        filter: vec!["pkg-b".to_string()],
+    }];
+    let global_inputs = GlobalInputs::new("", &inputs, &graph, Path::new("")).unwrap();
+    let filter = PackageFilter::parse(&["[main]".to_string()]).unwrap();
+
+    assert_eq!(filter.select(&graph, Path::new("")).unwrap(), ["pkg-a"]);
+    assert_eq!(
+        filter
+            .select_with_global_inputs(&graph, Path::new(""), &global_inputs)
+            .unwrap(),
+        ["pkg-a", "pkg-b"]
+    );
+}
+
+#[test]
+fn test_git_range_shallow_clone_without_merge_base() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = diverged_repo(temp_dir.path());
+
+    // Cut both branches off below their tips, as `--depth 1` would.
+    let tips: Vec<String> = ["main", "feature"]
+        .iter()
+        .map(|branch| repo.revparse_single(branch).unwrap().id().to_string())
+        .collect();
+    fs::write(repo.path().join("shallow"), tips.join("\n") + "\n").unwrap();
+    let repo = git2::Repository::open(temp_dir.path()).unwrap();
+    assert!(repo.is_shallow());
+
+    let error = GitRange::new("main").changed_files(&repo).unwrap_err();
+    let message = error.to_string();
+    assert!(message.contains("shallow clone"), "{}", message);
+    assert!(message.contains("--deepen=1"), "{}", message);
+
+    assert!(GitRange::new("main")
+        .with_exact_base(true)
+        .changed_files(&repo)
+        .is_ok());
+}
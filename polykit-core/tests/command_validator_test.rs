@@ -89,3 +89,12 @@ fn test_validate_identifier_rejects_invalid_chars() {
     assert!(CommandValidator::validate_identifier("package;name", "Package").is_err());
     assert!(CommandValidator::validate_identifier("package\0name", "Package").is_err());
 }
+
+#[test]
+fn test_validate_tag() {
+    assert!(CommandValidator::validate_tag("team:payments").is_ok());
+    assert!(CommandValidator::validate_tag("scope/ui-kit").is_ok());
+    assert!(CommandValidator::validate_tag("").is_err());
+    assert!(CommandValidator::validate_tag("layer domain").is_err());
+    assert!(CommandValidator::validate_tag("layer;rm").is_err());
+}
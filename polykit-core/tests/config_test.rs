@@ -42,6 +42,8 @@ fn test_parse_language() {
         name: "test".to_string(),
         language: "rust".to_string(),
         public: true,
+        tags: Vec::new(),
+        affected_ignore: Vec::new(),
         deps: Default::default(),
         tasks: Default::default(),
     };
@@ -56,6 +58,8 @@ fn test_invalid_language() {
         name: "test".to_string(),
         language: "invalid".to_string(),
         public: true,
+        tags: Vec::new(),
+        affected_ignore: Vec::new(),
         deps: Default::default(),
         tasks: Default::default(),
     };
@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use polykit_core::graph::DependencyGraph;
 use polykit_core::package::{Language, Package};
+use polykit_core::scanner::detect_graph_changes;
 use proptest::prelude::*;
+use rustc_hash::FxHashMap;
 
 fn gen_package_name() -> impl Strategy<Value = String> {
     "[a-z]{3,8}"
@@ -55,3 +59,71 @@ proptest! {
         }
     }
 }
+
+/// Packages `p0`..`p7`, each present or not, depending on any present
+/// package ranked below it so that every step is acyclic.
+fn gen_step() -> impl Strategy<Value = Vec<Package>> {
+    (
+        prop::collection::vec(any::<bool>(), 8),
+        Just((0..8).collect::<Vec<usize>>()).prop_shuffle(),
+        prop::collection::vec(any::<bool>(), 64),
+    )
+        .prop_map(|(present, rank, edges)| {
+            (0..8)
+                .filter(|&i| present[i])
+                .map(|i| {
+                    let deps = (0..8)
+                        .filter(|&j| present[j] && rank[j] < rank[i] && edges[i * 8 + j])
+                        .map(|j| format!("p{}", j))
+                        .collect();
+                    Package::new(
+                        format!("p{}", i),
+                        Language::Rust,
+                        true,
+                        format!("pkg-p{}", i).into(),
+                        deps,
+                        vec![],
+                    )
+                })
+                .collect()
+        })
+}
+
+fn levels_by_name(graph: &DependencyGraph) -> HashMap<String, usize> {
+    graph
+        .dependency_levels()
+        .into_iter()
+        .enumerate()
+        .flat_map(|(level, names)| names.into_iter().map(move |name| (name, level)))
+        .collect()
+}
+
+fn as_map(packages: &[Package]) -> FxHashMap<String, Package> {
+    packages.iter().map(|p| (p.name.clone(), p.clone())).collect()
+}
+
+proptest! {
+    #[test]
+    fn test_incremental_updates_match_rebuild(steps in prop::collection::vec(gen_step(), 1..8)) {
+        let mut graph = DependencyGraph::new(Vec::new()).unwrap();
+        let mut previous = Vec::new();
+
+        for packages in steps {
+            let changes = detect_graph_changes(&as_map(&previous), &as_map(&packages));
+            graph.update_incremental(changes).unwrap();
+            let rebuilt = DependencyGraph::new(packages.clone()).unwrap();
+
+            let order = graph.topological_order();
+            prop_assert_eq!(order.len(), packages.len());
+            let position: HashMap<&str, usize> =
+                order.iter().enumerate().map(|(i, name)| (name.as_str(), i)).collect();
+            for package in &packages {
+                for dep in &package.deps {
+                    prop_assert!(position[dep.as_str()] < position[package.name.as_str()]);
+                }
+            }
+            prop_assert_eq!(levels_by_name(&graph), levels_by_name(&rebuilt));
+            previous = packages;
+        }
+    }
+}
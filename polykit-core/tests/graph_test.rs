@@ -1,3 +1,5 @@
+use polykit_core::diagnostics::Diagnostic;
+use polykit_core::error::Error;
 use polykit_core::graph::DependencyGraph;
 use polykit_core::package::{Language, Package};
 
@@ -108,6 +110,49 @@ fn test_circular_dependency() {
         .contains("Circular dependency"));
 }
 
+#[test]
+fn test_invalid_graph_reports_every_problem() {
+    let packages = vec![
+        Package::new(
+            "pkg-a".to_string(),
+            Language::Rust,
+            true,
+            "pkg-a".into(),
+            vec!["pkg-b".to_string(), "pkg-x".to_string()],
+            vec![],
+        ),
+        Package::new(
+            "pkg-b".to_string(),
+            Language::Rust,
+            true,
+            "pkg-b".into(),
+            vec!["pkg-a".to_string()],
+            vec![],
+        ),
+    ];
+
+    let Err(Error::InvalidGraph(diagnostics)) = DependencyGraph::new(packages) else {
+        panic!("expected an invalid graph");
+    };
+    assert_eq!(
+        diagnostics,
+        vec![
+            Diagnostic::UnknownDependency {
+                package: "pkg-a".to_string(),
+                dependency: "pkg-x".to_string(),
+                suggestion: Some("pkg-a".to_string()),
+            },
+            Diagnostic::Cycle {
+                path: vec![
+                    "pkg-a".to_string(),
+                    "pkg-b".to_string(),
+                    "pkg-a".to_string()
+                ],
+            },
+        ]
+    );
+}
+
 #[test]
 fn test_affected_packages() {
     let packages = create_test_packages();
@@ -119,3 +164,22 @@ fn test_affected_packages() {
     assert!(affected.contains("pkg-b"));
     assert!(affected.contains("pkg-c"));
 }
+
+#[test]
+fn test_snapshot_round_trip() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("graph.bin");
+    let graph = DependencyGraph::new(create_test_packages()).unwrap();
+
+    graph.save_snapshot(&path, 42).unwrap();
+    let (restored, fingerprint) = DependencyGraph::load_snapshot(&path).unwrap();
+
+    assert_eq!(fingerprint, 42);
+    assert_eq!(restored.topological_order(), graph.topological_order());
+    assert_eq!(restored.dependency_levels(), graph.dependency_levels());
+    assert_eq!(restored.dependents("pkg-a").unwrap(), ["pkg-b"]);
+
+    graph.save_to_file(&path).unwrap();
+    let loaded = DependencyGraph::load_from_file(&path).unwrap();
+    assert_eq!(loaded.all_packages().len(), 3);
+}
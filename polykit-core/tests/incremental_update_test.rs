@@ -169,3 +169,80 @@ fn test_incremental_update_detects_circular_dependency() {
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("Cycle"));
 }
+
+#[test]
+fn test_incremental_remove_keeps_other_lookups() {
+    let packages = generate_test_packages();
+    let mut graph = DependencyGraph::new(packages).unwrap();
+
+    // Removing the first node moves another into its slot.
+    let modified = Package::new(
+        "middle".to_string(),
+        Language::Rust,
+        false,
+        PathBuf::from("packages/middle"),
+        Vec::new(),
+        Vec::new(),
+    );
+    let change = GraphChange {
+        added: Vec::new(),
+        modified: vec![modified],
+        removed: vec!["base".to_string()],
+        dependency_changes: vec![("middle".to_string(), Vec::new())],
+    };
+
+    graph.update_incremental(change).unwrap();
+
+    assert_eq!(graph.topological_order(), ["middle", "top"]);
+    assert_eq!(graph.dependencies("top").unwrap(), ["middle"]);
+    assert_eq!(graph.dependents("middle").unwrap(), ["top"]);
+}
+
+#[test]
+fn test_incremental_rejects_dangling_dependencies() {
+    let packages = generate_test_packages();
+    let mut graph = DependencyGraph::new(packages).unwrap();
+
+    let change = GraphChange {
+        added: Vec::new(),
+        modified: Vec::new(),
+        removed: vec!["base".to_string()],
+        dependency_changes: Vec::new(),
+    };
+
+    assert!(graph.update_incremental(change).is_err());
+}
+
+#[test]
+fn test_incremental_add_packages_depending_on_each_other() {
+    let packages = generate_test_packages();
+    let mut graph = DependencyGraph::new(packages).unwrap();
+
+    let package = |name: &str, deps: Vec<String>| {
+        Package::new(
+            name.to_string(),
+            Language::Rust,
+            false,
+            PathBuf::from(format!("packages/{}", name)),
+            deps,
+            Vec::new(),
+        )
+    };
+    let change = GraphChange {
+        added: vec![
+            package("app", vec!["lib".to_string()]),
+            package("lib", vec!["top".to_string()]),
+        ],
+        modified: Vec::new(),
+        removed: Vec::new(),
+        dependency_changes: Vec::new(),
+    };
+
+    graph.update_incremental(change).unwrap();
+
+    assert_eq!(
+        graph.topological_order(),
+        ["base", "middle", "top", "lib", "app"]
+    );
+    assert_eq!(graph.dependencies("app").unwrap(), ["lib"]);
+}
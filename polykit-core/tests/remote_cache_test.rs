@@ -243,3 +243,187 @@ fn test_artifact_hash() {
     assert!(!hash.is_empty());
     assert_eq!(hash.len(), 64); // SHA-256 hex string length
 }
+
+#[tokio::test]
+async fn test_remote_cache_has_artifacts_and_prefetch() {
+    use polykit_core::remote_cache::RemoteCacheConfig;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config = RemoteCacheConfig::new(temp_dir.path().to_string_lossy());
+    let cache = RemoteCache::from_config(config).unwrap();
+
+    let keys: Vec<CacheKey> = ["a", "b", "c"]
+        .iter()
+        .map(|name| {
+            CacheKey::builder()
+                .package_id(*name)
+                .task_name("build")
+                .command("echo")
+                .dependency_graph_hash("abc")
+                .toolchain_version("node-v20")
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    let mut output_files = BTreeMap::new();
+    output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+    let artifact = Artifact::new(
+        "b".to_string(),
+        "build".to_string(),
+        "echo".to_string(),
+        keys[1].as_string(),
+        output_files,
+    )
+    .unwrap();
+    cache.upload_artifact(&keys[1], &artifact).await.unwrap();
+
+    assert_eq!(cache.has_artifacts(&keys).await.unwrap(), vec![false, true, false]);
+    assert_eq!(cache.prefetch(&keys).await.unwrap(), 1);
+    assert!(cache.fetch_artifact(&keys[1]).await.unwrap().is_some());
+    assert!(cache.fetch_artifact(&keys[0]).await.unwrap().is_none());
+
+    let disabled = RemoteCache::disabled();
+    assert_eq!(disabled.has_artifacts(&keys).await.unwrap(), vec![false; 3]);
+    assert_eq!(disabled.prefetch(&keys).await.unwrap(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_remote_cache_clear_prefetched_forgets_misses() {
+    use polykit_core::remote_cache::RemoteCacheConfig;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config = RemoteCacheConfig::new(temp_dir.path().to_string_lossy());
+    let cache = RemoteCache::from_config(config).unwrap();
+
+    let key = CacheKey::builder()
+        .package_id("a")
+        .task_name("build")
+        .command("echo")
+        .dependency_graph_hash("abc")
+        .toolchain_version("node-v20")
+        .build()
+        .unwrap();
+    let mut output_files = BTreeMap::new();
+    output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+    let artifact = Artifact::new(
+        "a".to_string(),
+        "build".to_string(),
+        "echo".to_string(),
+        key.as_string(),
+        output_files,
+    )
+    .unwrap();
+
+    // A miss recorded before the upload hides it until cleared
+    assert_eq!(cache.prefetch(std::slice::from_ref(&key)).await.unwrap(), 0);
+    cache.upload_artifact(&key, &artifact).await.unwrap();
+    cache.clear_prefetched();
+    assert!(cache.fetch_artifact(&key).await.unwrap().is_some());
+
+    // Prefetched hits are handed over once, then fetched from the backend
+    assert_eq!(cache.prefetch(std::slice::from_ref(&key)).await.unwrap(), 1);
+    assert!(cache.fetch_artifact(&key).await.unwrap().is_some());
+    assert!(cache.fetch_artifact(&key).await.unwrap().is_some());
+
+    // Clearing stops downloads that were never used
+    assert_eq!(cache.prefetch(std::slice::from_ref(&key)).await.unwrap(), 1);
+    cache.clear_prefetched();
+    assert!(cache.fetch_artifact(&key).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_remote_cache_signed_artifacts() {
+    use polykit_core::remote_cache::{KeyConfig, RemoteCacheConfig, SignatureAlgorithm};
+
+    let temp_dir = TempDir::new().unwrap();
+    let key = KeyConfig {
+        key_id: "ci".to_string(),
+        algorithm: SignatureAlgorithm::HmacSha256,
+        key: Some("00112233445566778899aabbccddeeff".to_string()),
+        key_env: None,
+        key_file: None,
+    };
+    let url = temp_dir.path().to_string_lossy().to_string();
+
+    let signing_cache = RemoteCache::from_config(
+        RemoteCacheConfig::new(url.clone()).with_signing_key(key.clone()),
+    )
+    .unwrap();
+    let unsigned_cache = RemoteCache::from_config(RemoteCacheConfig::new(url.clone())).unwrap();
+    let verifying_cache = RemoteCache::from_config(
+        RemoteCacheConfig::new(url)
+            .add_trusted_key(key)
+            .require_signatures(true),
+    )
+    .unwrap();
+
+    let make = |name: &str| {
+        let key = CacheKey::builder()
+            .package_id(name)
+            .task_name("build")
+            .command("echo")
+            .dependency_graph_hash("abc")
+            .toolchain_version("node-v20")
+            .build()
+            .unwrap();
+        let mut output_files = BTreeMap::new();
+        output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+        let artifact = Artifact::new(
+            name.to_string(),
+            "build".to_string(),
+            "echo".to_string(),
+            key.as_string(),
+            output_files,
+        )
+        .unwrap();
+        (key, artifact)
+    };
+
+    let (signed_key, signed_artifact) = make("signed");
+    signing_cache
+        .upload_artifact(&signed_key, &signed_artifact)
+        .await
+        .unwrap();
+    let fetched = verifying_cache.fetch_artifact(&signed_key).await.unwrap().unwrap();
+    assert_eq!(fetched.signature().unwrap().key_id, "ci");
+
+    let (unsigned_key, unsigned_artifact) = make("unsigned");
+    unsigned_cache
+        .upload_artifact(&unsigned_key, &unsigned_artifact)
+        .await
+        .unwrap();
+    assert!(verifying_cache.fetch_artifact(&unsigned_key).await.is_err());
+}
+
+#[test]
+fn test_ed25519_signature_verification() {
+    use polykit_core::remote_cache::{SigningKey, TrustedKeys};
+
+    let mut output_files = BTreeMap::new();
+    output_files.insert(PathBuf::from("file.txt"), b"content".to_vec());
+    let artifact = Artifact::new(
+        "test".to_string(),
+        "build".to_string(),
+        "echo".to_string(),
+        "hash123".to_string(),
+        output_files,
+    )
+    .unwrap();
+
+    let key = SigningKey::ed25519("release", &[7u8; 32]).unwrap();
+    let signed = artifact.signed(&key).unwrap();
+
+    // Signature survives a round-trip through the compressed form
+    let reloaded = Artifact::from_compressed(signed.compressed_data().to_vec()).unwrap();
+    assert!(ArtifactVerifier::verify(&reloaded, None).is_ok());
+
+    let mut trusted = TrustedKeys::new();
+    trusted.add(key.trusted_key());
+    assert!(trusted.verify(&reloaded).is_ok());
+    assert!(trusted.verify(&artifact).is_err());
+
+    let mut other = TrustedKeys::new();
+    other.add(SigningKey::ed25519("release", &[8u8; 32]).unwrap().trusted_key());
+    assert!(other.verify(&reloaded).is_err());
+}
@@ -1,6 +1,7 @@
 use std::fs;
 use tempfile::TempDir;
 
+use polykit_core::change::ChangeDetector;
 use polykit_core::scanner::Scanner;
 
 fn create_test_package(dir: &std::path::Path, name: &str, language: &str, deps: &[&str]) {
@@ -64,3 +65,340 @@ fn test_scan_as_map() {
     assert_eq!(map.len(), 1);
     assert!(map.contains_key("pkg-a"));
 }
+
+#[test]
+fn test_workspace_remote_cache_config() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join(".git")).unwrap();
+    let packages_dir = temp_dir.path().join("packages");
+    fs::create_dir_all(&packages_dir).unwrap();
+
+    fs::write(
+        temp_dir.path().join("polykit.toml"),
+        r#"
+[workspace]
+default_parallel = 4
+
+[remote_cache]
+url = "http://localhost:8080"
+require_signatures = true
+
+[[remote_cache.trusted_keys]]
+key_id = "ci"
+algorithm = "ed25519"
+key = "00112233"
+"#,
+    )
+    .unwrap();
+
+    let scanner = Scanner::new(&packages_dir);
+    let remote_cache = scanner
+        .workspace_config()
+        .unwrap()
+        .and_then(|wc| wc.remote_cache.as_ref())
+        .unwrap();
+
+    assert_eq!(remote_cache.url, "http://localhost:8080");
+    assert!(remote_cache.require_signatures);
+    assert_eq!(remote_cache.trusted_keys[0].key_id, "ci");
+}
+
+#[test]
+fn test_invalid_remote_cache_config_is_an_error() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join(".git")).unwrap();
+    let packages_dir = temp_dir.path().join("packages");
+    create_test_package(&packages_dir, "core", "rust", &[]);
+
+    for remote_cache in [
+        "url = \"http://localhost:8080\"\nrequire_signatures = \"yes\"\n",
+        "url = \"http://localhost:8080\"\nrequire_signature = true\n",
+        "url = \"http://localhost:8080\"\n[[remote_cache.trusted_keys]]\nkey_id = \"ci\"\n",
+    ] {
+        fs::write(
+            temp_dir.path().join("polykit.toml"),
+            format!("[workspace]\n\n[remote_cache]\n{}", remote_cache),
+        )
+        .unwrap();
+
+        let mut scanner = Scanner::new(&packages_dir);
+        let error = scanner.workspace_config().unwrap_err();
+        let source = std::error::Error::source(&error).unwrap().to_string();
+        assert!(source.contains("require_signature") || source.contains("algorithm"), "{}", source);
+        assert!(scanner.scan().is_err());
+    }
+}
+
+#[test]
+fn test_invalid_constraints_are_an_error() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join(".git")).unwrap();
+    let packages_dir = temp_dir.path().join("packages");
+    create_test_package(&packages_dir, "core", "rust", &[]);
+
+    fs::write(
+        temp_dir.path().join("polykit.toml"),
+        "[workspace]\n\n[constraints]\ndeny_public_to_private = true\n",
+    )
+    .unwrap();
+    let scanner = Scanner::new(&packages_dir);
+    let constraints = &scanner.workspace_config().unwrap().unwrap().constraints;
+    assert!(constraints.deny_public_to_private);
+
+    for constraints in [
+        "deny_public_to_private = \"yes\"\n",
+        "deny_public_to_privat = true\n",
+        "[[constraints.rules]]\nallow = [\"layer:shared\"]\n",
+        "[[constraints.rules]]\ntag = \"layer:ui\"\nalow = [\"layer:shared\"]\n",
+    ] {
+        fs::write(
+            temp_dir.path().join("polykit.toml"),
+            format!("[workspace]\n\n[constraints]\n{}", constraints),
+        )
+        .unwrap();
+
+        let mut scanner = Scanner::new(&packages_dir);
+        let error = scanner.workspace_config().unwrap_err();
+        let source = std::error::Error::source(&error).unwrap().to_string();
+        assert!(
+            source.contains("deny_public_to_priv") || source.contains("tag") || source.contains("alow"),
+            "{}",
+            source
+        );
+        assert!(scanner.scan().is_err());
+    }
+}
+
+#[test]
+fn test_workspace_members_nested_packages() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+
+    fs::write(
+        root.join("polykit.toml"),
+        r#"
+[workspace]
+members = ["apps/*", "libs/**"]
+exclude = ["libs/legacy"]
+"#,
+    )
+    .unwrap();
+
+    create_test_package(&root.join("apps"), "web", "js", &["button"]);
+    create_test_package(&root.join("apps/web"), "too-deep", "js", &[]);
+    create_test_package(&root.join("libs"), "ui", "js", &[]);
+    create_test_package(&root.join("libs/ui/components"), "button", "js", &["ui"]);
+    create_test_package(&root.join("libs/legacy"), "old", "js", &[]);
+    create_test_package(&root.join("libs/ui/node_modules"), "vendored", "js", &[]);
+    create_test_package(&root.join("tools"), "cli", "rust", &[]);
+
+    let mut scanner = Scanner::with_cache(root.join("packages"), root.join(".cache"));
+    assert_eq!(scanner.packages_root(), root);
+
+    let map = scanner.scan_as_map().unwrap();
+    let mut names: Vec<&str> = map.keys().map(|k| k.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["button", "ui", "web"]);
+    assert_eq!(
+        map["button"].path,
+        std::path::PathBuf::from("libs/ui/components/button")
+    );
+
+    create_test_package(&root.join("apps"), "docs", "js", &[]);
+    let map = scanner.scan_as_map().unwrap();
+    assert!(map.contains_key("docs"));
+}
+
+#[test]
+fn test_workspace_members_invalid_glob() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join(".git")).unwrap();
+    fs::write(
+        temp_dir.path().join("polykit.toml"),
+        "[workspace]\nmembers = [\"../outside/*\"]\n",
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(temp_dir.path().join("packages"));
+    assert!(scanner.scan().is_err());
+}
+
+#[test]
+fn test_global_inputs_and_affected_ignore() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+    fs::write(
+        root.join("polykit.toml"),
+        r#"
+[workspace]
+global_inputs = [
+    "Cargo.lock",
+    ".github/**",
+    { glob = "tsconfig.base.json", filter = ["lang:js"] },
+]
+"#,
+    )
+    .unwrap();
+
+    let packages_dir = root.join("packages");
+    create_test_package(&packages_dir, "core", "rust", &[]);
+    create_test_package(&packages_dir, "web", "js", &[]);
+    let config = fs::read_to_string(packages_dir.join("core/polykit.toml")).unwrap();
+    fs::write(
+        packages_dir.join("core/polykit.toml"),
+        format!("affected_ignore = [\"docs\", \"*.md\"]\n{}", config),
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&packages_dir);
+    let graph = scanner.scan_graph().unwrap();
+    let global_inputs = scanner.global_inputs(&graph).unwrap();
+    let affected = |files: &[&str]| {
+        let files: Vec<_> = files.iter().map(|file| root.join(file)).collect();
+        let mut names: Vec<String> = ChangeDetector::detect_with_global_inputs(
+            &graph,
+            &files,
+            &packages_dir,
+            &global_inputs,
+        )
+        .unwrap()
+        .into_iter()
+        .collect();
+        names.sort_unstable();
+        names
+    };
+
+    assert_eq!(affected(&["Cargo.lock"]), ["core", "web"]);
+    assert_eq!(affected(&[".github/workflows/ci.yml"]), ["core", "web"]);
+    assert_eq!(affected(&["tsconfig.base.json"]), ["web"]);
+    assert!(affected(&["README.md", "packages/Cargo.lock"]).is_empty());
+    assert!(affected(&[
+        "packages/core/README.md",
+        "packages/core/src/notes.md",
+        "packages/core/docs/guide.txt",
+    ])
+    .is_empty());
+    assert_eq!(affected(&["packages/core/src/lib.rs"]), ["core"]);
+}
+
+#[test]
+fn test_invalid_global_inputs_are_an_error() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join(".git")).unwrap();
+    let packages_dir = temp_dir.path().join("packages");
+    create_test_package(&packages_dir, "core", "rust", &[]);
+
+    for global_inputs in [
+        "\"Cargo.lock\"",
+        "[\"Cargo.lock\", 3]",
+        "[{ glob = \"tsconfig.json\", filters = [\"lang:js\"] }]",
+        "[{ filter = [\"lang:js\"] }]",
+    ] {
+        fs::write(
+            temp_dir.path().join("polykit.toml"),
+            format!("[workspace]\nglobal_inputs = {}\n", global_inputs),
+        )
+        .unwrap();
+
+        let mut scanner = Scanner::new(&packages_dir);
+        let error = scanner.workspace_config().unwrap_err();
+        let source = std::error::Error::source(&error).unwrap().to_string();
+        assert!(source.contains("global_inputs"), "{}", source);
+        assert!(scanner.scan().is_err());
+    }
+}
+
+#[test]
+fn test_affected_ignore_invalid_glob() {
+    let temp_dir = TempDir::new().unwrap();
+    let packages_dir = temp_dir.path().join("packages");
+    create_test_package(&packages_dir, "core", "rust", &[]);
+    let config = fs::read_to_string(packages_dir.join("core/polykit.toml")).unwrap();
+    fs::write(
+        packages_dir.join("core/polykit.toml"),
+        format!("affected_ignore = [\"docs/[\"]\n{}", config),
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&packages_dir);
+    let error = scanner.scan().unwrap_err().to_string();
+    assert!(error.contains("docs/["), "{}", error);
+}
+
+fn graph_snapshots(cache_dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    fs::read_dir(cache_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("graph_"))
+        })
+        .collect()
+}
+
+#[test]
+fn test_scan_graph_reuses_snapshot() {
+    let temp_dir = TempDir::new().unwrap();
+    let packages_dir = temp_dir.path().join("packages");
+    let cache_dir = temp_dir.path().join("cache");
+    fs::create_dir_all(&packages_dir).unwrap();
+
+    create_test_package(&packages_dir, "pkg-a", "rust", &[]);
+    create_test_package(&packages_dir, "pkg-b", "rust", &["pkg-a"]);
+    create_test_package(&packages_dir, "pkg-c", "rust", &["pkg-b"]);
+
+    let graph = Scanner::with_cache(&packages_dir, &cache_dir)
+        .scan_graph()
+        .unwrap();
+    assert_eq!(graph.topological_order(), ["pkg-a", "pkg-b", "pkg-c"]);
+    assert_eq!(graph_snapshots(&cache_dir).len(), 1);
+
+    let restored = Scanner::with_cache(&packages_dir, &cache_dir)
+        .scan_graph()
+        .unwrap();
+    assert_eq!(restored.topological_order(), graph.topological_order());
+    assert_eq!(restored.dependency_levels(), graph.dependency_levels());
+
+    // New packages, including one depending on another new one, and a
+    // removed package are applied to the snapshot.
+    fs::remove_dir_all(packages_dir.join("pkg-c")).unwrap();
+    create_test_package(&packages_dir, "pkg-d", "rust", &["pkg-e"]);
+    create_test_package(&packages_dir, "pkg-e", "rust", &["pkg-b"]);
+
+    let updated = Scanner::with_cache(&packages_dir, &cache_dir)
+        .scan_graph()
+        .unwrap();
+    assert!(updated.get_package("pkg-c").is_none());
+    assert_eq!(updated.all_packages().len(), 4);
+    assert_eq!(
+        updated.dependency_levels(),
+        [["pkg-a"], ["pkg-b"], ["pkg-e"], ["pkg-d"]]
+    );
+    assert_eq!(updated.dependents("pkg-b").unwrap(), ["pkg-e"]);
+}
+
+#[test]
+fn test_scan_graph_rebuilds_unreadable_snapshot() {
+    let temp_dir = TempDir::new().unwrap();
+    let packages_dir = temp_dir.path().join("packages");
+    let cache_dir = temp_dir.path().join("cache");
+    fs::create_dir_all(&packages_dir).unwrap();
+
+    create_test_package(&packages_dir, "pkg-a", "rust", &[]);
+    create_test_package(&packages_dir, "pkg-b", "rust", &["pkg-a"]);
+
+    Scanner::with_cache(&packages_dir, &cache_dir)
+        .scan_graph()
+        .unwrap();
+    for snapshot in graph_snapshots(&cache_dir) {
+        fs::write(snapshot, b"not a graph").unwrap();
+    }
+
+    let graph = Scanner::with_cache(&packages_dir, &cache_dir)
+        .scan_graph()
+        .unwrap();
+    assert_eq!(graph.topological_order(), ["pkg-a", "pkg-b"]);
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use polykit_core::runner::TaskResult;
 use polykit_core::task_cache::TaskCache;
 use tempfile::TempDir;
@@ -13,6 +15,8 @@ fn test_task_cache_put_and_get() {
         success: true,
         stdout: "output".to_string(),
         stderr: "".to_string(),
+        duration: Duration::from_millis(5),
+        cached: false,
     };
 
     cache.put("test-pkg", "test", "echo test", &result).unwrap();
@@ -23,6 +27,7 @@ fn test_task_cache_put_and_get() {
     assert_eq!(cached_result.package_name, "test-pkg");
     assert_eq!(cached_result.task_name, "test");
     assert_eq!(cached_result.stdout, "output");
+    assert!(cached_result.cached);
 }
 
 #[test]
@@ -45,6 +50,8 @@ fn test_task_cache_failed_task_not_cached() {
         success: false,
         stdout: "".to_string(),
         stderr: "error".to_string(),
+        duration: Duration::from_millis(5),
+        cached: false,
     };
 
     cache.put("test-pkg", "test", "false", &result).unwrap();
@@ -0,0 +1,234 @@
+//! Critical-path analysis of recorded runs.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use serde_json::json;
+
+use polykit_core::analysis::{simulate, Analysis};
+use polykit_core::history::RunHistory;
+
+use crate::formatting::{
+    format_duration, print_custom_table, print_key_value, print_section_header, print_warning,
+    SectionStyle,
+};
+
+use super::{create_scanner, print_cache_stats};
+
+/// `-j` values compared when none are given.
+const DEFAULT_JOBS: [usize; 5] = [1, 2, 4, 8, 16];
+
+/// Most packages listed as bottlenecks.
+const MAX_BOTTLENECKS: usize = 10;
+
+/// Most parallelism segments printed.
+const MAX_SEGMENTS: usize = 20;
+
+fn ms(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+fn human(duration: Duration) -> String {
+    format_duration(duration.as_secs_f64())
+}
+
+pub fn cmd_analyze(
+    packages_dir: PathBuf,
+    task: String,
+    parallel: Option<usize>,
+    jobs: Vec<usize>,
+    json: bool,
+    no_cache: bool,
+    show_cache_stats: bool,
+) -> Result<()> {
+    let mut scanner = create_scanner(&packages_dir, no_cache);
+    let graph = scanner.scan_graph()?;
+    let history = RunHistory::in_workspace(&scanner.workspace_root());
+    let runs: Vec<_> = history
+        .load()?
+        .into_iter()
+        // Fully cached runs only measure cache lookups
+        .filter(|run| run.task == task && !run.is_cached())
+        .collect();
+
+    let Some(last_run) = runs.last() else {
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&json!({ "task": task, "runs": 0 }))?
+            );
+        } else {
+            print_warning(&format!(
+                "No recorded runs of '{}' in {}; run `polykit {}` first",
+                task,
+                history.path().display(),
+                task
+            ));
+        }
+        return Ok(());
+    };
+
+    let estimates = history.estimates(&task)?;
+    let analysis = Analysis::new(&graph, &estimates);
+    let current_jobs = parallel
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1);
+    let schedule = simulate(&graph, &estimates, current_jobs);
+
+    let mut compared = if jobs.is_empty() {
+        DEFAULT_JOBS.to_vec()
+    } else {
+        jobs
+    };
+    compared.push(current_jobs);
+    compared.sort_unstable();
+    compared.dedup();
+    let serial = simulate(&graph, &estimates, 1).duration;
+    let speedups: Vec<(usize, Duration, f64)> = compared
+        .into_iter()
+        .map(|jobs| {
+            let duration = simulate(&graph, &estimates, jobs).duration;
+            let speedup = if duration.is_zero() {
+                1.0
+            } else {
+                serial.as_secs_f64() / duration.as_secs_f64()
+            };
+            (jobs, duration, speedup)
+        })
+        .collect();
+
+    let mut bottlenecks: Vec<_> = analysis.packages.iter().collect();
+    bottlenecks.sort_by(|a, b| b.duration.cmp(&a.duration).then(a.package.cmp(&b.package)));
+    bottlenecks.truncate(MAX_BOTTLENECKS);
+
+    if json {
+        let report = json!({
+            "task": task,
+            "runs": runs.len(),
+            "last_run_ms": last_run.duration_ms,
+            "critical_path": analysis.critical_path,
+            "critical_path_ms": ms(analysis.duration),
+            "total_work_ms": ms(analysis.total_work),
+            "packages": analysis.packages.iter().map(|p| json!({
+                "package": p.package,
+                "duration_ms": ms(p.duration),
+                "earliest_start_ms": ms(p.earliest_start),
+                "slack_ms": ms(p.slack),
+                "critical": analysis.critical_path.contains(&p.package),
+            })).collect::<Vec<_>>(),
+            "parallelism": {
+                "jobs": schedule.jobs,
+                "duration_ms": ms(schedule.duration),
+                "segments": schedule.parallelism.iter().map(|s| json!({
+                    "start_ms": ms(s.start),
+                    "end_ms": ms(s.end),
+                    "running": s.running,
+                })).collect::<Vec<_>>(),
+            },
+            "estimates": speedups.iter().map(|(jobs, duration, speedup)| json!({
+                "jobs": jobs,
+                "duration_ms": ms(*duration),
+                "speedup": speedup,
+            })).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_section_header(&format!("Analysis of '{}'", task), SectionStyle::Primary);
+    print_key_value("Recorded runs", &runs.len().to_string());
+    print_key_value(
+        "Last run",
+        &human(Duration::from_millis(last_run.duration_ms)),
+    );
+    print_key_value(
+        "Packages with timings",
+        &format!("{} of {}", estimates.len(), analysis.packages.len()),
+    );
+    println!();
+
+    print_section_header("Critical Path", SectionStyle::Secondary);
+    let path: Vec<String> = analysis
+        .critical_path
+        .iter()
+        .map(|name| {
+            format!(
+                "{} ({})",
+                name,
+                human(estimates.get(name).copied().unwrap_or_default())
+            )
+        })
+        .collect();
+    println!("  {}", path.join(&format!(" {} ", "→".cyan())));
+    println!();
+    print_key_value("Critical path", &human(analysis.duration));
+    print_key_value("Total work", &human(analysis.total_work));
+    println!();
+
+    print_section_header("Bottlenecks", SectionStyle::Secondary);
+    print_custom_table(
+        vec!["Package", "Duration", "Earliest start", "Slack", "Critical"],
+        bottlenecks
+            .iter()
+            .map(|p| {
+                vec![
+                    p.package.clone(),
+                    human(p.duration),
+                    human(p.earliest_start),
+                    human(p.slack),
+                    if analysis.critical_path.contains(&p.package) {
+                        "✓".to_string()
+                    } else {
+                        String::new()
+                    },
+                ]
+            })
+            .collect(),
+    );
+    println!();
+
+    print_section_header(
+        &format!("Parallelism at -j {}", schedule.jobs),
+        SectionStyle::Secondary,
+    );
+    for segment in schedule.parallelism.iter().take(MAX_SEGMENTS) {
+        println!(
+            "  {:>10} – {:<10} {} {}",
+            human(segment.start),
+            human(segment.end),
+            "█".repeat(segment.running.min(40)).cyan(),
+            segment.running
+        );
+    }
+    if schedule.parallelism.len() > MAX_SEGMENTS {
+        println!(
+            "  {}",
+            format!("… {} more", schedule.parallelism.len() - MAX_SEGMENTS).bright_black()
+        );
+    }
+    println!();
+
+    print_section_header("Estimated Duration by -j", SectionStyle::Secondary);
+    print_custom_table(
+        vec!["-j", "Duration", "Speedup"],
+        speedups
+            .iter()
+            .map(|(jobs, duration, speedup)| {
+                vec![
+                    jobs.to_string(),
+                    human(*duration),
+                    format!("{:.2}x", speedup),
+                ]
+            })
+            .collect(),
+    );
+    println!();
+
+    if show_cache_stats {
+        print_cache_stats(&scanner);
+    }
+
+    Ok(())
+}
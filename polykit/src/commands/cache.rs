@@ -0,0 +1,415 @@
+//! Remote cache browsing and compression benchmark commands.
+
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+
+use polykit_core::remote_cache::{
+    collect_outputs, Artifact, ArtifactFilter, CompressionConfig, Compressor, Dictionary,
+    HttpBackend, OutputEntry,
+};
+
+use crate::formatting::{
+    format_bytes, print_custom_table, print_key_value, print_section_header, print_success,
+    print_warning, SectionStyle,
+};
+
+use super::{create_scanner, remote_cache_config};
+
+/// Creates an HTTP client for the configured remote cache.
+fn create_http_backend(
+    packages_dir: &PathBuf,
+    url: Option<String>,
+    no_cache: bool,
+) -> Result<HttpBackend> {
+    let scanner = create_scanner(packages_dir, no_cache);
+    let workspace_config = scanner
+        .workspace_config()?
+        .and_then(|wc| wc.remote_cache.clone());
+
+    let config = remote_cache_config(workspace_config, url).context(
+        "No remote cache configured. Set [remote_cache] url in polykit.toml or pass --remote-cache-url",
+    )?;
+    if !config.is_http() {
+        anyhow::bail!("Browsing needs an HTTP remote cache, got '{}'", config.url);
+    }
+
+    Ok(HttpBackend::new(&config)?)
+}
+
+fn block_on<F: std::future::Future>(future: F) -> Result<F::Output> {
+    let rt = tokio::runtime::Runtime::new().context("Failed to create async runtime")?;
+    Ok(rt.block_on(future))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Parses `--since` as a relative age (`30m`, `12h`, `7d`) or Unix epoch seconds.
+fn parse_since(value: &str, now: u64) -> Result<u64> {
+    let value = value.trim();
+    let digits = value.len() - value.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (number, unit) = value.split_at(digits);
+    let amount: u64 = number.parse().with_context(|| {
+        format!(
+            "Invalid --since '{}': expected e.g. 12h, 7d or a Unix timestamp",
+            value
+        )
+    })?;
+
+    let seconds = match unit {
+        "" => return Ok(amount),
+        "s" => amount,
+        "m" => amount * 60,
+        "h" => amount * 60 * 60,
+        "d" => amount * 60 * 60 * 24,
+        "w" => amount * 60 * 60 * 24 * 7,
+        _ => anyhow::bail!("Invalid --since unit '{}': use s, m, h, d or w", unit),
+    };
+    Ok(now.saturating_sub(seconds))
+}
+
+/// Formats a timestamp as a coarse age such as `5m ago`.
+fn format_age(timestamp: u64, now: u64) -> String {
+    let age = now.saturating_sub(timestamp);
+    match age {
+        0..=59 => format!("{}s ago", age),
+        60..=3599 => format!("{}m ago", age / 60),
+        3600..=86399 => format!("{}h ago", age / 3600),
+        _ => format!("{}d ago", age / 86400),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn cmd_remote_ls(
+    packages_dir: PathBuf,
+    url: Option<String>,
+    package: Option<String>,
+    task: Option<String>,
+    since: Option<String>,
+    limit: Option<usize>,
+    json: bool,
+    no_cache: bool,
+) -> Result<()> {
+    let backend = create_http_backend(&packages_dir, url, no_cache)?;
+    let now = now_secs();
+    let filter = ArtifactFilter {
+        package,
+        task,
+        since: since.map(|s| parse_since(&s, now)).transpose()?,
+        limit,
+    };
+
+    let artifacts = block_on(backend.list_artifacts(&filter))??;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&artifacts)?);
+        return Ok(());
+    }
+
+    print_section_header("Remote Artifacts", SectionStyle::Primary);
+    println!();
+
+    if artifacts.is_empty() {
+        print_warning("No artifacts found");
+        println!();
+        return Ok(());
+    }
+
+    let rows = artifacts
+        .iter()
+        .map(|artifact| {
+            let (package, task, created) = match artifact.metadata {
+                Some(ref m) => (
+                    m.package_name.clone(),
+                    m.task_name.clone(),
+                    format_age(m.created_at, now),
+                ),
+                None => ("?".to_string(), "?".to_string(), "?".to_string()),
+            };
+            vec![
+                artifact.cache_key[..16.min(artifact.cache_key.len())].to_string(),
+                package,
+                task,
+                format_bytes(artifact.size),
+                created,
+                format_age(artifact.last_accessed, now),
+            ]
+        })
+        .collect();
+
+    print_custom_table(
+        vec!["Key", "Package", "Task", "Size", "Created", "Last Used"],
+        rows,
+    );
+    println!();
+    println!(
+        "  {} {}",
+        artifacts.len().to_string().bold(),
+        "artifacts".bright_black()
+    );
+    println!();
+
+    Ok(())
+}
+
+pub fn cmd_remote_show(
+    packages_dir: PathBuf,
+    url: Option<String>,
+    cache_key: String,
+    json: bool,
+    no_cache: bool,
+) -> Result<()> {
+    let backend = create_http_backend(&packages_dir, url, no_cache)?;
+
+    let Some(manifest) = block_on(backend.fetch_manifest(&cache_key))?? else {
+        anyhow::bail!("Artifact {} not found", cache_key);
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&manifest)?);
+        return Ok(());
+    }
+
+    let now = now_secs();
+    print_section_header("Remote Artifact", SectionStyle::Primary);
+    println!();
+    print_key_value("Key:", &manifest.cache_key);
+    print_key_value("Package:", &manifest.metadata.package_name);
+    print_key_value("Task:", &manifest.metadata.task_name);
+    print_key_value("Command:", &manifest.metadata.command);
+    print_key_value("Created:", &format_age(manifest.metadata.created_at, now));
+    print_key_value("Size:", &format_bytes(manifest.manifest.total_size));
+    print_key_value("Codec:", &manifest.metadata.codec.to_string());
+    println!();
+
+    let rows = manifest
+        .manifest
+        .files
+        .iter()
+        .map(|(path, hash)| {
+            vec![
+                path.display().to_string(),
+                hash[..16.min(hash.len())].to_string(),
+            ]
+        })
+        .collect();
+    print_custom_table(vec!["File", "SHA-256"], rows);
+    println!();
+
+    Ok(())
+}
+
+pub fn cmd_remote_rm(
+    packages_dir: PathBuf,
+    url: Option<String>,
+    cache_keys: Vec<String>,
+    no_cache: bool,
+) -> Result<()> {
+    let backend = create_http_backend(&packages_dir, url, no_cache)?;
+
+    let mut missing = 0;
+    for cache_key in &cache_keys {
+        if block_on(backend.delete_artifact(cache_key))?? {
+            print_success(&format!("Deleted {}", cache_key));
+        } else {
+            print_warning(&format!("Artifact {} not found", cache_key));
+            missing += 1;
+        }
+    }
+
+    if missing > 0 {
+        anyhow::bail!("{} of {} artifacts not found", missing, cache_keys.len());
+    }
+
+    Ok(())
+}
+
+/// Maximum size of dictionaries trained by `polykit cache bench --train`.
+const TRAINED_DICTIONARY_SIZE: usize = 112_640;
+
+/// Result of compressing the benchmark input with one setting.
+struct BenchResult {
+    codec: String,
+    size: u64,
+    ratio: f64,
+    compress_ms: f64,
+    decompress_ms: f64,
+}
+
+/// Compresses `tar_data` with `compressor` and times a round trip.
+fn bench_compressor(compressor: &Compressor, tar_data: &[u8]) -> Result<BenchResult> {
+    let started = Instant::now();
+    let compressed = compressor.compress(tar_data)?;
+    let compress_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    let started = Instant::now();
+    let decompressed = compressor.decompress(&compressed)?;
+    let decompress_ms = started.elapsed().as_secs_f64() * 1000.0;
+    if decompressed != tar_data {
+        anyhow::bail!("Round trip with {} changed the data", compressor.codec());
+    }
+
+    Ok(BenchResult {
+        codec: compressor.codec().to_string(),
+        size: compressed.len() as u64,
+        ratio: tar_data.len() as f64 / compressed.len().max(1) as f64,
+        compress_ms,
+        decompress_ms,
+    })
+}
+
+/// Makes `path` relative to `root` for [`collect_outputs`].
+fn relative_output_path(root: &Path, path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        path.strip_prefix(root)
+            .map(Path::to_path_buf)
+            .with_context(|| format!("{} is outside {}", path.display(), root.display()))
+    } else {
+        Ok(path.to_path_buf())
+    }
+}
+
+pub fn cmd_cache_bench(
+    paths: Vec<PathBuf>,
+    levels: Vec<i32>,
+    long_window: bool,
+    dictionary: Option<PathBuf>,
+    train: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    let root = std::env::current_dir().context("Failed to get current directory")?;
+    let paths = paths
+        .iter()
+        .map(|path| relative_output_path(&root, path))
+        .collect::<Result<Vec<_>>>()?;
+
+    let entries = collect_outputs(&root, &paths, false)?;
+    let samples: Vec<Vec<u8>> = entries
+        .values()
+        .filter_map(|entry| match entry {
+            OutputEntry::File { content, .. } => Some(content.clone()),
+            _ => None,
+        })
+        .collect();
+    if samples.is_empty() {
+        anyhow::bail!("No output files found to benchmark");
+    }
+    let file_count = samples.len();
+
+    // Benchmark the archive the cache would upload, not the raw files
+    let artifact = Artifact::from_entries(
+        "bench".to_string(),
+        "bench".to_string(),
+        String::new(),
+        String::new(),
+        entries,
+    )?;
+    let tar_data = artifact.tar_data()?;
+
+    let mut dictionaries = Vec::new();
+    if let Some(ref path) = dictionary {
+        dictionaries.push(Dictionary::load(path)?);
+    }
+    if let Some(ref path) = train {
+        let trained = Dictionary::train(&samples, TRAINED_DICTIONARY_SIZE)?;
+        std::fs::write(path, trained.data())
+            .with_context(|| format!("Failed to write dictionary to {}", path.display()))?;
+        dictionaries.push(trained);
+    }
+
+    let windows: &[bool] = if long_window { &[false, true] } else { &[false] };
+    let mut results = Vec::new();
+    for &level in &levels {
+        for &long_window in windows {
+            let config = CompressionConfig::default()
+                .with_level(level)
+                .with_long_window(long_window);
+            let compressor = Compressor::from_config(&config)?;
+            results.push(bench_compressor(&compressor, &tar_data)?);
+            for dictionary in &dictionaries {
+                let compressor = compressor.clone().with_dictionary(dictionary.clone());
+                results.push(bench_compressor(&compressor, &tar_data)?);
+            }
+        }
+    }
+
+    if json {
+        let results: Vec<_> = results
+            .iter()
+            .map(|result| {
+                serde_json::json!({
+                    "codec": result.codec,
+                    "size": result.size,
+                    "ratio": result.ratio,
+                    "compress_ms": result.compress_ms,
+                    "decompress_ms": result.decompress_ms,
+                })
+            })
+            .collect();
+        let output = serde_json::json!({
+            "files": file_count,
+            "uncompressed_size": tar_data.len(),
+            "results": results,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    print_section_header("Compression Benchmark", SectionStyle::Primary);
+    println!();
+    print_key_value("Files:", &file_count.to_string());
+    print_key_value("Uncompressed:", &format_bytes(tar_data.len() as u64));
+    if let Some(ref path) = train {
+        print_key_value("Trained:", &path.display().to_string());
+    }
+    println!();
+
+    let rows = results
+        .iter()
+        .map(|result| {
+            vec![
+                result.codec.clone(),
+                format_bytes(result.size),
+                format!("{:.2}x", result.ratio),
+                format!("{:.1} ms", result.compress_ms),
+                format!("{:.1} ms", result.decompress_ms),
+            ]
+        })
+        .collect();
+    print_custom_table(
+        vec!["Settings", "Size", "Ratio", "Compress", "Decompress"],
+        rows,
+    );
+    println!();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since() {
+        let now = 1_000_000;
+        assert_eq!(parse_since("30m", now).unwrap(), now - 1800);
+        assert_eq!(parse_since("2d", now).unwrap(), now - 2 * 86400);
+        assert_eq!(parse_since("1700000000", now).unwrap(), 1_700_000_000);
+        assert!(parse_since("2y", now).is_err());
+        assert!(parse_since("h", now).is_err());
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(100, 130), "30s ago");
+        assert_eq!(format_age(0, 7200), "2h ago");
+        assert_eq!(format_age(200, 100), "0s ago");
+    }
+}
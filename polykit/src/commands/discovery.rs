@@ -4,11 +4,12 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use comfy_table::{Cell, Table};
-use polykit_core::{ChangeDetector, DependencyGraph};
+use polykit_core::graph_export::GraphExport;
+use polykit_core::{ChangeDetector, GitRange};
 
 use crate::formatting::{print_key_value, print_package_list, print_package_table, print_section_header, print_success, print_warning, SectionStyle};
 
-use super::{create_scanner, print_cache_stats};
+use super::{create_scanner, print_cache_stats, select_filtered};
 
 pub fn cmd_scan(
     packages_dir: PathBuf,
@@ -48,56 +49,40 @@ pub fn cmd_scan(
     Ok(())
 }
 
+/// Output formats for `polykit graph`.
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Table,
+    Dot,
+    Mermaid,
+    Json,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn cmd_graph(
     packages_dir: PathBuf,
-    json: bool,
+    format: GraphFormat,
+    focus: Option<String>,
+    depth: Option<usize>,
+    filters: Vec<String>,
     no_cache: bool,
     show_cache_stats: bool,
 ) -> Result<()> {
     let mut scanner = create_scanner(&packages_dir, no_cache);
-    let packages = scanner.scan()?;
-    let graph = DependencyGraph::new(packages)?;
+    let graph = scanner.scan_graph()?;
+    let selected = select_filtered(&graph, &scanner, &filters)?;
 
-    let order = graph.topological_order();
-
-    if json {
-        let graph_data = serde_json::json!({
-            "packages": order,
-            "edges": {
-                // Could expand this to show actual edges
-            }
-        });
-        println!("{}", serde_json::to_string_pretty(&graph_data)?);
-    } else {
-        print_section_header("Dependency Graph", SectionStyle::Primary);
+    let export = match (focus, selected) {
+        (Some(ref focus), _) => GraphExport::focused(&graph, focus, depth)?,
+        (None, Some(ref selected)) => GraphExport::subset(&graph, selected),
+        (None, None) => GraphExport::new(&graph),
+    };
 
-        if order.is_empty() {
-            print_warning("No packages found");
-        } else {
-            print_key_value(
-                "Topological order",
-                &format!("{} packages", order.len()),
-            );
-            println!();
-            let mut table = Table::new();
-            table
-                .set_header(vec![
-                    Cell::new("#").add_attribute(comfy_table::Attribute::Bold),
-                    Cell::new("Package").add_attribute(comfy_table::Attribute::Bold),
-                ])
-                .load_preset(comfy_table::presets::UTF8_FULL)
-                .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
-                .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
-
-            for (idx, pkg) in order.iter().enumerate() {
-                table.add_row(vec![
-                    Cell::new((idx + 1).to_string()).fg(comfy_table::Color::DarkGrey),
-                    Cell::new(pkg).fg(comfy_table::Color::White),
-                ]);
-            }
-            println!("{}", table);
-        }
-        println!();
+    match format {
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&export.to_json())?),
+        GraphFormat::Dot => print!("{}", export.to_dot()),
+        GraphFormat::Mermaid => print!("{}", export.to_mermaid()),
+        GraphFormat::Table => print_graph_table(&export),
     }
 
     if show_cache_stats {
@@ -107,27 +92,91 @@ pub fn cmd_graph(
     Ok(())
 }
 
+fn print_graph_table(export: &GraphExport) {
+    print_section_header("Dependency Graph", SectionStyle::Primary);
+
+    if export.nodes.is_empty() {
+        print_warning("No packages found");
+    } else {
+        print_key_value(
+            "Topological order",
+            &format!("{} packages", export.nodes.len()),
+        );
+        if let Some(ref focus) = export.focus {
+            print_key_value("Focus", focus);
+        }
+        println!();
+        let mut table = Table::new();
+        table
+            .set_header(vec![
+                Cell::new("#").add_attribute(comfy_table::Attribute::Bold),
+                Cell::new("Package").add_attribute(comfy_table::Attribute::Bold),
+                Cell::new("Level").add_attribute(comfy_table::Attribute::Bold),
+                Cell::new("Depends on").add_attribute(comfy_table::Attribute::Bold),
+            ])
+            .load_preset(comfy_table::presets::UTF8_FULL)
+            .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS)
+            .set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+
+        for (idx, node) in export.nodes.iter().enumerate() {
+            let deps: Vec<&str> = export
+                .edges
+                .iter()
+                .filter(|edge| edge.from == node.name)
+                .map(|edge| edge.to.as_str())
+                .collect();
+            table.add_row(vec![
+                Cell::new((idx + 1).to_string()).fg(comfy_table::Color::DarkGrey),
+                Cell::new(&node.name).fg(comfy_table::Color::White),
+                Cell::new(node.level.to_string()).fg(comfy_table::Color::DarkGrey),
+                Cell::new(deps.join(", ")).fg(comfy_table::Color::DarkGrey),
+            ]);
+        }
+        println!("{}", table);
+    }
+    println!();
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn cmd_affected(
     packages_dir: PathBuf,
     files: Vec<String>,
     git: bool,
     base: Option<String>,
+    exact_base: bool,
+    head: Option<String>,
+    include_uncommitted: Option<bool>,
     no_cache: bool,
     show_cache_stats: bool,
 ) -> Result<()> {
     let mut scanner = create_scanner(&packages_dir, no_cache);
-    let packages = scanner.scan()?;
-    let graph = DependencyGraph::new(packages)?;
+    let graph = scanner.scan_graph()?;
+    let packages_root = scanner.packages_root();
+    let global_inputs = scanner.global_inputs(&graph)?;
 
     let affected = if git {
-        ChangeDetector::detect_from_git(&graph, &packages_dir, base.as_deref())?
+        // Local runs see work in progress; CI only sees what was committed.
+        let include_uncommitted =
+            include_uncommitted.unwrap_or_else(|| std::env::var_os("CI").is_none());
+        let mut range = GitRange::new(base.unwrap_or_else(|| "HEAD".to_string()))
+            .with_uncommitted(include_uncommitted && head.is_none())
+            .with_exact_base(exact_base);
+        if let Some(head) = head {
+            range = range.with_head(head);
+        }
+        ChangeDetector::detect_from_git(&graph, &packages_root, &range, &global_inputs)?
     } else if files.is_empty() {
         return Err(anyhow::anyhow!(
             "No files specified. Use --git to detect from git or provide file paths."
         ));
     } else {
         let file_paths: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
-        ChangeDetector::detect_affected_packages(&graph, &file_paths, &packages_dir)?
+        ChangeDetector::detect_with_global_inputs(
+            &graph,
+            &file_paths,
+            &packages_root,
+            &global_inputs,
+        )?
     };
 
     print_section_header("Affected Packages", SectionStyle::Primary);
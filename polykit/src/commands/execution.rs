@@ -2,39 +2,79 @@
 
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use comfy_table::{Cell, Table};
 use owo_colors::OwoColorize;
 
-use polykit_core::{DependencyGraph, RemoteCache, RemoteCacheConfig, TaskRunner};
+use polykit_core::history::{RunHistory, RunRecord};
+use polykit_core::{DependencyGraph, Query, RemoteCache, RemoteCacheConfig, Scanner, TaskRunner};
 
 use crate::formatting::{create_progress_bar, format_duration, print_section_header, print_separator_with_spacing, print_summary_box, print_success, print_warning, SectionStyle, Status};
 
-use super::create_scanner;
+use super::{create_scanner, remote_cache_config, select_filtered};
 
 fn create_remote_cache(
+    workspace_config: Option<RemoteCacheConfig>,
     url: Option<String>,
     read_only: bool,
     disabled: bool,
 ) -> Result<Option<Arc<RemoteCache>>> {
-    if disabled || url.is_none() {
+    if disabled {
         return Ok(None);
     }
 
-    let url = url.unwrap();
-    if url.is_empty() {
+    let Some(config) = remote_cache_config(workspace_config, url) else {
         return Ok(None);
-    }
+    };
 
-    let config = RemoteCacheConfig::new(url)
-        .read_only(read_only);
+    let config = if read_only { config.read_only(true) } else { config };
 
     let remote_cache = RemoteCache::from_config(config)?;
     Ok(Some(Arc::new(remote_cache)))
 }
 
+/// Resolves the packages to run from names, a query or `--filter` selectors.
+///
+/// Returns `None` to run every package.
+fn select_packages(
+    graph: &DependencyGraph,
+    scanner: &Scanner,
+    packages: Vec<String>,
+    query: Option<String>,
+    filters: &[String],
+) -> Result<Option<Vec<String>>> {
+    let selected = match query {
+        Some(query) => Some(query.parse::<Query>()?.evaluate(graph)?),
+        None if !filters.is_empty() => select_filtered(graph, scanner, filters)?,
+        None if !packages.is_empty() => Some(packages),
+        None => None,
+    };
+    if selected.as_ref().is_some_and(|selected| selected.is_empty()) {
+        print_warning("No packages matched the selection");
+    }
+    Ok(selected)
+}
+
+/// Appends a run to the workspace history, warning instead of failing.
+fn record_run(
+    history: &RunHistory,
+    task_name: &str,
+    parallel: Option<usize>,
+    duration: Duration,
+    results: &[polykit_core::TaskResult],
+) {
+    let run = RunRecord::new(task_name, parallel, duration, results);
+    if let Err(e) = history.record(&run) {
+        print_warning(&format!(
+            "Could not record run history in {}: {}",
+            history.path().display(),
+            e
+        ));
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn run_task_with_progress(
     packages_dir: PathBuf,
@@ -45,6 +85,7 @@ fn run_task_with_progress(
     graph: DependencyGraph,
     progress_msg: &str,
     remote_cache: Option<Arc<RemoteCache>>,
+    history: &RunHistory,
 ) -> Result<Vec<polykit_core::TaskResult>> {
     let packages_to_run = if let Some(names) = packages_opt {
         names.len()
@@ -55,7 +96,9 @@ fn run_task_with_progress(
     let pb = create_progress_bar(packages_to_run as u64);
     pb.set_message(progress_msg.to_string());
 
-    let mut runner = TaskRunner::new(&packages_dir, graph).with_max_parallel(parallel);
+    let mut runner = TaskRunner::new(&packages_dir, graph)
+        .with_max_parallel(parallel)
+        .with_estimates(&history.estimates(task_name).unwrap_or_default());
     if let Some(ref rc) = remote_cache {
         runner = runner.with_remote_cache(Arc::clone(rc));
     }
@@ -157,6 +200,8 @@ fn print_task_results(
 pub fn cmd_build(
     packages_dir: PathBuf,
     packages: Vec<String>,
+    query: Option<String>,
+    filters: Vec<String>,
     parallel: Option<usize>,
     continue_on_error: bool,
     no_cache: bool,
@@ -168,14 +213,9 @@ pub fn cmd_build(
 ) -> Result<()> {
     let start = Instant::now();
     let mut scanner = create_scanner(&packages_dir, no_cache);
-    let scanned = scanner.scan()?;
-    let graph = DependencyGraph::new(scanned)?;
-
-    let packages_opt = if packages.is_empty() {
-        None
-    } else {
-        Some(packages.as_slice())
-    };
+    let graph = scanner.scan_graph()?;
+    let selected = select_packages(&graph, &scanner, packages, query, &filters)?;
+    let packages_opt = selected.as_deref();
 
     let packages_to_run = if let Some(names) = packages_opt {
         names.len()
@@ -185,10 +225,17 @@ pub fn cmd_build(
 
     print_section_header("Building packages", SectionStyle::Primary);
 
-    let remote_cache = create_remote_cache(remote_cache_url, remote_cache_readonly, no_remote_cache)?;
+    let remote_cache = create_remote_cache(
+        scanner.workspace_config()?.and_then(|wc| wc.remote_cache.clone()),
+        remote_cache_url,
+        remote_cache_readonly,
+        no_remote_cache,
+    )?;
 
+    let history = RunHistory::in_workspace(&scanner.workspace_root());
+    let run_start = Instant::now();
     let results = run_task_with_progress(
-        packages_dir,
+        scanner.packages_root(),
         "build",
         packages_opt,
         parallel,
@@ -196,7 +243,9 @@ pub fn cmd_build(
         graph,
         "Building...",
         remote_cache,
+        &history,
     )?;
+    record_run(&history, "build", parallel, run_start.elapsed(), &results);
 
     let failed = print_task_results(
         results,
@@ -236,6 +285,8 @@ pub fn cmd_build(
 pub fn cmd_test(
     packages_dir: PathBuf,
     packages: Vec<String>,
+    query: Option<String>,
+    filters: Vec<String>,
     parallel: Option<usize>,
     continue_on_error: bool,
     no_cache: bool,
@@ -247,14 +298,9 @@ pub fn cmd_test(
 ) -> Result<()> {
     let start = Instant::now();
     let mut scanner = create_scanner(&packages_dir, no_cache);
-    let scanned = scanner.scan()?;
-    let graph = DependencyGraph::new(scanned)?;
-
-    let packages_opt = if packages.is_empty() {
-        None
-    } else {
-        Some(packages.as_slice())
-    };
+    let graph = scanner.scan_graph()?;
+    let selected = select_packages(&graph, &scanner, packages, query, &filters)?;
+    let packages_opt = selected.as_deref();
 
     let packages_to_run = if let Some(names) = packages_opt {
         names.len()
@@ -264,10 +310,17 @@ pub fn cmd_test(
 
     print_section_header("Running tests", SectionStyle::Primary);
 
-    let remote_cache = create_remote_cache(remote_cache_url, remote_cache_readonly, no_remote_cache)?;
+    let remote_cache = create_remote_cache(
+        scanner.workspace_config()?.and_then(|wc| wc.remote_cache.clone()),
+        remote_cache_url,
+        remote_cache_readonly,
+        no_remote_cache,
+    )?;
 
+    let history = RunHistory::in_workspace(&scanner.workspace_root());
+    let run_start = Instant::now();
     let results = run_task_with_progress(
-        packages_dir,
+        scanner.packages_root(),
         "test",
         packages_opt,
         parallel,
@@ -275,7 +328,9 @@ pub fn cmd_test(
         graph,
         "Testing...",
         remote_cache,
+        &history,
     )?;
+    record_run(&history, "test", parallel, run_start.elapsed(), &results);
 
     let failed = print_task_results(
         results,
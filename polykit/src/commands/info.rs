@@ -4,14 +4,17 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use comfy_table::{Cell, Table};
+use owo_colors::OwoColorize;
 use polykit_adapters::get_adapter;
 use polykit_core::release::BumpType;
-use polykit_core::{DependencyGraph, ReleaseEngine};
+use polykit_core::validation::{validate, Severity};
+use serde_json::json;
+use polykit_core::{DependencyGraph, Query, ReleaseEngine};
 
-use crate::formatting::{print_key_value, print_package_list, print_section_header, print_separator_with_spacing, print_success, SectionStyle};
+use crate::formatting::{print_error, print_key_value, print_package_list, print_section_header, print_separator_with_spacing, print_success, print_warning, SectionStyle};
 
 use super::release_reporter::CliReleaseReporter;
-use super::{create_scanner, print_cache_stats};
+use super::{create_scanner, print_cache_stats, select_filtered};
 
 pub fn cmd_release(
     packages_dir: PathBuf,
@@ -22,11 +25,10 @@ pub fn cmd_release(
     show_cache_stats: bool,
 ) -> Result<()> {
     let mut scanner = create_scanner(&packages_dir, no_cache);
-    let scanned = scanner.scan()?;
-    let graph = DependencyGraph::new(scanned)?;
+    let graph = scanner.scan_graph()?;
 
     let engine = ReleaseEngine::new(
-        &packages_dir,
+        scanner.packages_root(),
         graph,
         dry_run,
         get_adapter,
@@ -102,8 +104,7 @@ pub fn cmd_why(
     show_cache_stats: bool,
 ) -> Result<()> {
     let mut scanner = create_scanner(&packages_dir, no_cache);
-    let scanned = scanner.scan()?;
-    let graph = DependencyGraph::new(scanned)?;
+    let graph = scanner.scan_graph()?;
 
     let deps = graph.dependencies(&package)?;
     let dependents = graph.dependents(&package)?;
@@ -133,6 +134,37 @@ pub fn cmd_why(
     Ok(())
 }
 
+pub fn cmd_query(
+    packages_dir: PathBuf,
+    expr: String,
+    json: bool,
+    no_cache: bool,
+    show_cache_stats: bool,
+) -> Result<()> {
+    let query: Query = expr.parse()?;
+    let mut scanner = create_scanner(&packages_dir, no_cache);
+    let graph = scanner.scan_graph()?;
+    let packages = query.evaluate(&graph)?;
+
+    if json {
+        let report = json!({ "query": expr, "packages": packages });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_section_header("Query Results", SectionStyle::Primary);
+        print_key_value("Query", &expr);
+        print_key_value("Matched", &format!("{} packages", packages.len()));
+        println!();
+        print_package_list(&packages, "");
+        println!();
+    }
+
+    if show_cache_stats {
+        print_cache_stats(&scanner);
+    }
+
+    Ok(())
+}
+
 pub fn cmd_validate(
     packages_dir: PathBuf,
     json: bool,
@@ -141,15 +173,61 @@ pub fn cmd_validate(
 ) -> Result<()> {
     let mut scanner = create_scanner(&packages_dir, no_cache);
     let packages = scanner.scan()?;
-    let _ = DependencyGraph::new(packages)?;
+    let issues = validate(
+        &scanner.packages_root(),
+        &packages,
+        scanner.workspace_config()?,
+        get_adapter,
+    );
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    let warnings = issues.len() - errors;
 
     if json {
-        println!("{{\"valid\": true}}");
-    } else {
+        let report = json!({
+            "valid": errors == 0,
+            "errors": errors,
+            "warnings": warnings,
+            "diagnostics": issues,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if issues.is_empty() {
         print_section_header("Validation", SectionStyle::Success);
         print_success("All packages are valid");
         print_success("No circular dependencies detected");
         print_success("Dependency graph is valid");
+        print_success("Declared dependencies match native manifests");
+        println!();
+    } else {
+        let style = if errors > 0 {
+            SectionStyle::Error
+        } else {
+            SectionStyle::Warning
+        };
+        print_section_header("Validation", style);
+        for issue in &issues {
+            let label = format!("{}[{}]", issue.severity, issue.code);
+            match issue.severity {
+                Severity::Error => println!("  {}: {}", label.red().bold(), issue.message),
+                Severity::Warning => println!("  {}: {}", label.yellow().bold(), issue.message),
+            }
+            let location = match (&issue.file, &issue.package) {
+                (Some(file), Some(package)) => format!("{} ({})", file.display(), package),
+                (Some(file), None) => file.display().to_string(),
+                (None, Some(package)) => package.clone(),
+                (None, None) => continue,
+            };
+            println!("    {} {}", "-->".bright_black(), location);
+        }
+        println!();
+        let summary = format!("{} errors, {} warnings", errors, warnings);
+        if errors > 0 {
+            print_error(&summary);
+        } else {
+            print_warning(&summary);
+        }
         println!();
     }
 
@@ -157,17 +235,28 @@ pub fn cmd_validate(
         print_cache_stats(&scanner);
     }
 
+    if errors > 0 {
+        std::process::exit(1);
+    }
+
     Ok(())
 }
 
 pub fn cmd_list(
     packages_dir: PathBuf,
     json: bool,
+    filters: Vec<String>,
     no_cache: bool,
     show_cache_stats: bool,
 ) -> Result<()> {
     let mut scanner = create_scanner(&packages_dir, no_cache);
-    let packages = scanner.scan()?;
+    let mut packages = scanner.scan()?;
+    if !filters.is_empty() {
+        let graph = DependencyGraph::new(packages.clone())?;
+        if let Some(selected) = select_filtered(&graph, &scanner, &filters)? {
+            packages.retain(|package| selected.contains(&package.name));
+        }
+    }
 
     if json {
         let tasks: std::collections::HashMap<String, Vec<String>> = packages
@@ -183,7 +272,6 @@ pub fn cmd_list(
         println!();
 
         if packages.is_empty() {
-            use crate::formatting::print_warning;
             print_warning("No packages found");
         } else {
             let package_tasks: Vec<(String, Vec<String>)> = packages
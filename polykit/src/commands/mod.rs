@@ -1,5 +1,7 @@
 //! Command implementations for the CLI.
 
+mod analyze;
+mod cache;
 mod discovery;
 mod execution;
 mod info;
@@ -8,21 +10,61 @@ mod watch;
 
 use std::path::PathBuf;
 
-use polykit_core::Scanner;
+use polykit_core::{DependencyGraph, PackageFilter, RemoteCacheConfig, Scanner};
 
 use crate::formatting::print_summary_box;
 
-pub use discovery::{cmd_affected, cmd_graph, cmd_scan};
+pub use analyze::cmd_analyze;
+pub use cache::{cmd_cache_bench, cmd_remote_ls, cmd_remote_rm, cmd_remote_show};
+pub use discovery::{cmd_affected, cmd_graph, cmd_scan, GraphFormat};
 pub use execution::{cmd_build, cmd_test};
-pub use info::{cmd_list, cmd_release, cmd_validate, cmd_why};
+pub use info::{cmd_list, cmd_query, cmd_release, cmd_validate, cmd_why};
 pub use watch::cmd_watch;
 
 fn create_scanner(packages_dir: &PathBuf, no_cache: bool) -> Scanner {
-    if no_cache {
+    let scanner = if no_cache {
         Scanner::new(packages_dir)
     } else {
         Scanner::with_default_cache(packages_dir)
+    };
+    scanner.with_inference(polykit_adapters::get_adapter)
+}
+
+/// Resolves `--filter` selectors, or returns `None` when there are none.
+///
+/// Changes to the workspace's global inputs count in `[ref]` selectors.
+fn select_filtered(
+    graph: &DependencyGraph,
+    scanner: &Scanner,
+    filters: &[String],
+) -> anyhow::Result<Option<Vec<String>>> {
+    let filter = PackageFilter::parse(filters)?;
+    if filter.is_empty() {
+        return Ok(None);
     }
+    let global_inputs = scanner.global_inputs(graph)?;
+    Ok(Some(filter.select_with_global_inputs(
+        graph,
+        &scanner.packages_root(),
+        &global_inputs,
+    )?))
+}
+
+/// Resolves the remote cache configuration.
+///
+/// The CLI URL overrides the workspace `[remote_cache]` table but keeps its
+/// other settings.
+fn remote_cache_config(
+    workspace_config: Option<RemoteCacheConfig>,
+    url: Option<String>,
+) -> Option<RemoteCacheConfig> {
+    let config = match (url, workspace_config) {
+        (Some(url), Some(config)) => RemoteCacheConfig { url, ..config },
+        (Some(url), None) => RemoteCacheConfig::new(url),
+        (None, Some(config)) => config,
+        (None, None) => return None,
+    };
+    (!config.url.is_empty()).then_some(config)
 }
 
 fn print_cache_stats(scanner: &Scanner) {
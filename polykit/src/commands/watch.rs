@@ -6,6 +6,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use polykit_core::path_utils::PackagePaths;
 use polykit_core::{DependencyGraph, FileWatcher, TaskRunner, WatcherConfig};
 
 use crate::formatting::{print_key_value, print_section_header, print_success, print_warning, SectionStyle};
@@ -28,11 +29,18 @@ pub fn cmd_watch(
     .map_err(|e| anyhow::anyhow!("Failed to set signal handler: {}", e))?;
 
     let mut scanner = create_scanner(&packages_dir, no_cache);
+    let packages_root = scanner.packages_root();
+    let watch_root = packages_root.canonicalize().unwrap_or_else(|_| packages_root.clone());
+    let workspace_root = scanner.workspace_root();
+    let workspace_root = workspace_root.canonicalize().unwrap_or(workspace_root);
+    let graph = DependencyGraph::new(scanner.scan()?)?;
+    let package_paths = PackagePaths::new(graph.all_packages());
+    let global_inputs = scanner.global_inputs(&graph)?.with_root(&workspace_root);
 
     let debounce_duration = Duration::from_millis(debounce_ms.unwrap_or(300));
 
     print_section_header("Watch Mode", SectionStyle::Primary);
-    print_key_value("Watching", &packages_dir.display().to_string());
+    print_key_value("Watching", &packages_root.display().to_string());
     print_key_value("Task", &task);
     if !packages.is_empty() {
         print_key_value("Packages", &packages.join(", "));
@@ -41,8 +49,10 @@ pub fn cmd_watch(
     println!();
 
     let watcher_config = WatcherConfig {
-        packages_dir: packages_dir.clone(),
+        packages_dir: watch_root,
         debounce_ms: debounce_ms.unwrap_or(300),
+        package_paths,
+        global_inputs,
     };
 
     let mut watcher = FileWatcher::new(watcher_config)?;
@@ -68,7 +78,11 @@ pub fn cmd_watch(
                 if !affected_packages.is_empty() && last_event_time.elapsed() >= debounce_duration {
                     print_warning("Change detected, rebuilding...");
                     let scanned = scanner.scan()?;
+                    watcher.set_package_paths(PackagePaths::new(&scanned));
                     let graph = DependencyGraph::new(scanned)?;
+                    watcher.set_global_inputs(
+                        scanner.global_inputs(&graph)?.with_root(&workspace_root),
+                    );
 
                     let mut packages_to_rebuild = affected_packages.clone();
                     for pkg_name in &affected_packages {
@@ -87,7 +101,7 @@ pub fn cmd_watch(
                     };
 
                     if !packages_to_run.is_empty() {
-                        let runner = TaskRunner::new(&packages_dir, graph);
+                        let runner = TaskRunner::new(&packages_root, graph);
                         let results = runner.run_task(&task, Some(&packages_to_run))?;
 
                         let mut failed = false;
@@ -65,7 +65,6 @@ pub fn print_task_table(packages: &[(String, Vec<String>)]) {
 }
 
 /// Prints a table with custom headers and rows.
-#[allow(dead_code)]
 pub fn print_custom_table(headers: Vec<&str>, rows: Vec<Vec<String>>) {
     let mut table = Table::new();
     let header_cells: Vec<Cell> = headers
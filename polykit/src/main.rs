@@ -57,8 +57,20 @@ enum Commands {
         json: bool,
     },
     Graph {
-        #[arg(long, action)]
+        #[arg(long, action, conflicts_with = "format")]
         json: bool,
+        /// Output format
+        #[arg(long, value_enum)]
+        format: Option<commands::GraphFormat>,
+        /// Only show this package and the packages around it
+        #[arg(long)]
+        focus: Option<String>,
+        /// With --focus, how many edges to follow in each direction
+        #[arg(long, requires = "focus")]
+        depth: Option<usize>,
+        /// Only show the selected packages, see `build --filter`
+        #[arg(short = 'F', long = "filter", conflicts_with = "focus")]
+        filters: Vec<String>,
     },
     Affected {
         files: Vec<String>,
@@ -66,9 +78,30 @@ enum Commands {
         git: bool,
         #[arg(long)]
         base: Option<String>,
+        /// With --git, compare against --base itself instead of where HEAD branched from it
+        #[arg(long, requires = "git")]
+        exact_base: bool,
+        /// With --git, compare up to this reference instead of HEAD
+        #[arg(long, requires = "git")]
+        head: Option<String>,
+        /// With --git, also count staged, unstaged and untracked files (default: on outside CI)
+        #[arg(
+            long,
+            requires = "git",
+            conflicts_with = "head",
+            num_args = 0..=1,
+            default_missing_value = "true"
+        )]
+        include_uncommitted: Option<bool>,
     },
     Build {
         packages: Vec<String>,
+        /// Select packages with a query expression instead of names
+        #[arg(long, conflicts_with = "packages")]
+        query: Option<String>,
+        /// Select packages, e.g. `api...`, `./apps/*`, `lang:rust`, `!docs`, `[origin/main]`
+        #[arg(short = 'F', long = "filter", conflicts_with_all = ["packages", "query"])]
+        filters: Vec<String>,
         #[arg(short = 'j', long)]
         parallel: Option<usize>,
         #[arg(long, action)]
@@ -76,6 +109,12 @@ enum Commands {
     },
     Test {
         packages: Vec<String>,
+        /// Select packages with a query expression instead of names
+        #[arg(long, conflicts_with = "packages")]
+        query: Option<String>,
+        /// Select packages, e.g. `api...`, `./apps/*`, `lang:rust`, `!docs`, `[origin/main]`
+        #[arg(short = 'F', long = "filter", conflicts_with_all = ["packages", "query"])]
+        filters: Vec<String>,
         #[arg(short = 'j', long)]
         parallel: Option<usize>,
         #[arg(long, action)]
@@ -91,13 +130,35 @@ enum Commands {
     Why {
         package: String,
     },
+    /// Evaluate a query such as `deps(api) - attr(language, rust, //...)`
+    Query {
+        expr: String,
+        #[arg(long, action)]
+        json: bool,
+    },
     Validate {
         #[arg(long, action)]
         json: bool,
     },
+    /// Find the critical path and bottlenecks from recorded runs of a task
+    Analyze {
+        #[arg(default_value = "build")]
+        task: String,
+        /// Job count to show parallelism for (defaults to `default_parallel` or the CPU count)
+        #[arg(short = 'j', long)]
+        parallel: Option<usize>,
+        /// Job counts to estimate run time for, e.g. `--jobs 1,4,16`
+        #[arg(long, value_delimiter = ',')]
+        jobs: Vec<usize>,
+        #[arg(long, action)]
+        json: bool,
+    },
     List {
         #[arg(long, action)]
         json: bool,
+        /// Only list the selected packages, see `build --filter`
+        #[arg(short = 'F', long = "filter")]
+        filters: Vec<String>,
     },
     Watch {
         task: String,
@@ -105,6 +166,68 @@ enum Commands {
         #[arg(long)]
         debounce: Option<u64>,
     },
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Browse and manage the remote cache
+    Remote {
+        #[command(subcommand)]
+        command: RemoteCacheCommand,
+    },
+    /// Compare compression settings on real task outputs
+    Bench {
+        /// Output files or directories, relative to the current directory
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// zstd levels to compare
+        #[arg(long, value_delimiter = ',', default_values_t = [1, 3, 9, 19])]
+        levels: Vec<i32>,
+        /// Also compare with long-distance matching
+        #[arg(long, action)]
+        long: bool,
+        /// Also compare with this dictionary
+        #[arg(long)]
+        dictionary: Option<PathBuf>,
+        /// Train a dictionary on the outputs, write it here and compare with it
+        #[arg(long)]
+        train: Option<PathBuf>,
+        #[arg(long, action)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum RemoteCacheCommand {
+    /// List stored artifacts, newest first
+    Ls {
+        #[arg(long)]
+        package: Option<String>,
+        #[arg(long)]
+        task: Option<String>,
+        /// Only artifacts created within this age (e.g. 12h, 7d) or after a Unix timestamp
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        limit: Option<usize>,
+        #[arg(long, action)]
+        json: bool,
+    },
+    /// Show an artifact's metadata and file manifest
+    Show {
+        cache_key: String,
+        #[arg(long, action)]
+        json: bool,
+    },
+    /// Delete artifacts (requires an admin token)
+    Rm {
+        #[arg(required = true)]
+        cache_keys: Vec<String>,
+    },
 }
 
 #[derive(clap::ValueEnum, Clone, Copy)]
@@ -144,25 +267,56 @@ fn main() -> Result<()> {
     } else {
         Scanner::with_default_cache(&cli.packages_dir)
     };
-    let workspace_config = scanner.workspace_config();
+    let workspace_config = scanner.workspace_config()?;
 
     match cli.command {
         Commands::Scan { json } => {
             commands::cmd_scan(cli.packages_dir, json, cli.no_cache, cli.show_cache_stats)?
         }
-        Commands::Graph { json } => {
-            commands::cmd_graph(cli.packages_dir, json, cli.no_cache, cli.show_cache_stats)?
+        Commands::Graph {
+            json,
+            format,
+            focus,
+            depth,
+            filters,
+        } => {
+            let format = if json {
+                commands::GraphFormat::Json
+            } else {
+                format.unwrap_or(commands::GraphFormat::Table)
+            };
+            commands::cmd_graph(
+                cli.packages_dir,
+                format,
+                focus,
+                depth,
+                filters,
+                cli.no_cache,
+                cli.show_cache_stats,
+            )?
         }
-        Commands::Affected { files, git, base } => commands::cmd_affected(
+        Commands::Affected {
+            files,
+            git,
+            base,
+            exact_base,
+            head,
+            include_uncommitted,
+        } => commands::cmd_affected(
             cli.packages_dir,
             files,
             git,
             base,
+            exact_base,
+            head,
+            include_uncommitted,
             cli.no_cache,
             cli.show_cache_stats,
         )?,
         Commands::Build {
             packages,
+            query,
+            filters,
             parallel,
             continue_on_error,
         } => {
@@ -170,6 +324,8 @@ fn main() -> Result<()> {
             commands::cmd_build(
                 cli.packages_dir,
                 packages,
+                query,
+                filters,
                 parallel,
                 continue_on_error,
                 cli.no_cache,
@@ -182,6 +338,8 @@ fn main() -> Result<()> {
         }
         Commands::Test {
             packages,
+            query,
+            filters,
             parallel,
             continue_on_error,
         } => {
@@ -189,6 +347,8 @@ fn main() -> Result<()> {
             commands::cmd_test(
                 cli.packages_dir,
                 packages,
+                query,
+                filters,
                 parallel,
                 continue_on_error,
                 cli.no_cache,
@@ -217,17 +377,89 @@ fn main() -> Result<()> {
             cli.no_cache,
             cli.show_cache_stats,
         )?,
+        Commands::Query { expr, json } => commands::cmd_query(
+            cli.packages_dir,
+            expr,
+            json,
+            cli.no_cache,
+            cli.show_cache_stats,
+        )?,
         Commands::Validate { json } => {
             commands::cmd_validate(cli.packages_dir, json, cli.no_cache, cli.show_cache_stats)?
         }
-        Commands::List { json } => {
-            commands::cmd_list(cli.packages_dir, json, cli.no_cache, cli.show_cache_stats)?
+        Commands::Analyze {
+            task,
+            parallel,
+            jobs,
+            json,
+        } => {
+            let parallel = parallel.or_else(|| workspace_config.and_then(|wc| wc.default_parallel));
+            commands::cmd_analyze(
+                cli.packages_dir,
+                task,
+                parallel,
+                jobs,
+                json,
+                cli.no_cache,
+                cli.show_cache_stats,
+            )?
         }
+        Commands::List { json, filters } => commands::cmd_list(
+            cli.packages_dir,
+            json,
+            filters,
+            cli.no_cache,
+            cli.show_cache_stats,
+        )?,
         Commands::Watch {
             task,
             packages,
             debounce,
         } => commands::cmd_watch(cli.packages_dir, task, packages, debounce, cli.no_cache)?,
+        Commands::Cache {
+            command:
+                CacheCommand::Bench {
+                    paths,
+                    levels,
+                    long,
+                    dictionary,
+                    train,
+                    json,
+                },
+        } => commands::cmd_cache_bench(paths, levels, long, dictionary, train, json)?,
+        Commands::Cache {
+            command: CacheCommand::Remote { command },
+        } => match command {
+            RemoteCacheCommand::Ls {
+                package,
+                task,
+                since,
+                limit,
+                json,
+            } => commands::cmd_remote_ls(
+                cli.packages_dir,
+                cli.remote_cache_url,
+                package,
+                task,
+                since,
+                limit,
+                json,
+                cli.no_cache,
+            )?,
+            RemoteCacheCommand::Show { cache_key, json } => commands::cmd_remote_show(
+                cli.packages_dir,
+                cli.remote_cache_url,
+                cache_key,
+                json,
+                cli.no_cache,
+            )?,
+            RemoteCacheCommand::Rm { cache_keys } => commands::cmd_remote_rm(
+                cli.packages_dir,
+                cli.remote_cache_url,
+                cache_keys,
+                cli.no_cache,
+            )?,
+        },
     }
 
     Ok(())